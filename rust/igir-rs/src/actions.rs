@@ -9,30 +9,40 @@ use std::thread;
 use std::time::{Duration, Instant};
 
 use anyhow::{Context, anyhow};
+use globset::{Glob, GlobMatcher};
 use serde::Serialize;
+use sha2::{Digest, Sha256};
 use zip::write::FileOptions;
 
 use tempfile::tempdir;
 use walkdir::WalkDir;
 use which::which;
 
+use crate::archives::{is_archive_path, looks_like_external_archive};
 use crate::cache;
+use crate::candidates::{
+    DatRomTuple, MatchOptions, build_write_candidates, dat_game, dat_rom_tuples, dat_sets,
+    group_sets, input_precedence, near_misses, split_disc_tag,
+};
 use crate::config::Config;
 use crate::dat::{
-    DatIndex, load_dat_roms, online_lookup, partition_dat_matches,
-    records_without_dat_match_with_index,
+    DatIndex, check_dat_checksum_coverage, effective_scan_config, find_dat_for_record_with_index,
+    load_dat_roms, online_lookup, parse_dat_priority, partition_dat_matches,
+    records_without_dat_match_with_index, select_best_dat_matches,
 };
 use crate::game_console::record_is_cartridge_based;
 use crate::progress::{BackgroundTask, ProgressReporter};
 use crate::records::{
-    collect_files, ensure_parent, populate_locale_tokens, resolve_output_path,
-    resolve_output_path_with_dats,
+    MAX_OUTPUT_PATH, collect_files, ensure_parent, filter_by_metadata, populate_locale_tokens,
+    resolve_output_path, resolve_output_path_with_dats,
 };
 use crate::types::{
-    Action, ActionOutcome, Checksum, ChecksumSet, ExecutionPlan, FileRecord, FilterSummary,
-    IgdbLookupMode, LinkMode, RunSummary, SkipReason, SkipSummary, SkippedFile, ZipFormat,
+    Action, ActionOutcome, Checksum, ChecksumSet, CollisionPolicy, DuplicateFile, DuplicateGroup,
+    ExecFailure, ExecutionPlan, FileRecord, FilterSummary, FixdatFormat, IgdbLookupMode, LinkMode,
+    LogStyle, MtimeTiebreak, RunSummary, SkipReason, SkipSummary, SkippedFile, VerifyReport,
+    ZipFormat,
 };
-use crate::utils::build_globset;
+use crate::utils::{available_space, build_globset, write_atomically};
 
 enum ActionProgress {
     ItemBytes {
@@ -71,14 +81,16 @@ fn log_diag_step(progress: Option<&ProgressReporter>, enabled: bool, message: im
     }
 }
 
+/// Keep a phase's duration when `--diag` or `--profile` is set; only `--diag` logs it.
 fn record_diag_duration(
     phase: &str,
     elapsed: Duration,
     progress: Option<&ProgressReporter>,
     enabled: bool,
+    profile: bool,
     timings: &mut Vec<(String, Duration)>,
 ) {
-    if !enabled {
+    if !enabled && !profile {
         return;
     }
     timings.push((phase.to_string(), elapsed));
@@ -97,6 +109,7 @@ fn with_diag_timing<T, F>(
     phase: &str,
     progress: Option<&ProgressReporter>,
     enabled: bool,
+    profile: bool,
     timings: &mut Vec<(String, Duration)>,
     work: F,
 ) -> anyhow::Result<T>
@@ -117,7 +130,7 @@ where
             p.finish_diag_phase(phase, Some(summary));
         }
     }
-    record_diag_duration(phase, elapsed, progress, enabled, timings);
+    record_diag_duration(phase, elapsed, progress, enabled, profile, timings);
     result
 }
 
@@ -822,16 +835,22 @@ fn is_ambiguous_extension_token(token: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::{
-        ensure_genres_from_igdb_sources, extract_platform_from_hasheous,
-        extract_platform_from_igdb, extract_record, log_diag_step,
-        record_diag_duration, record_is_extractable_archive, resolve_igdb_platform_token,
-        should_accept_platform_override,
+        apply_patches_to_outputs, clean_backup_run_key, clean_output, converted_files_to_remove,
+        copy_record, ensure_genres_from_igdb_sources, extract_platform_from_hasheous,
+        extract_platform_from_igdb, extract_record, link_record, log_diag_step, move_by_copy,
+        move_record, output_collisions, preflight_output_dir, preflight_output_path_lengths,
+        preflight_output_space, quarantine_record, record_diag_duration,
+        record_is_extractable_archive, remove_empty_input_dirs, rename_record_to_dat,
+        report_checksums, resolve_igdb_platform_token, resolve_output_collisions, romvault_report,
+        should_accept_platform_override, write_fixdat, write_report, zip_member_filter, zip_record,
+        zip_record_hashed,
     };
     use crate::cache;
     use crate::config::Config;
-    use crate::progress::ProgressReporter;
-    use crate::types::ChecksumSet;
+    use crate::progress::{BackgroundTask, ProgressReporter};
+    use crate::types::{Action, ChecksumSet, CollisionPolicy, FixdatFormat, LinkMode, ZipFormat};
     use serde_json::json;
+    use std::collections::HashSet;
     use std::io::Write;
     use std::path::{Path, PathBuf};
     use std::time::Duration;
@@ -971,6 +990,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         }
     }
 
@@ -1008,6 +1033,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         }
     }
 
@@ -1082,6 +1113,406 @@ mod tests {
         cfg
     }
 
+    #[test]
+    fn quarantine_reports_when_the_target_already_exists() {
+        let tmp = tempdir().unwrap();
+        let quarantine = tmp.path().join("quarantine");
+        let source = tmp.path().join("Stray.bin");
+        std::fs::write(&source, b"stray").unwrap();
+        std::fs::create_dir_all(&quarantine).unwrap();
+        std::fs::write(quarantine.join("Stray.bin"), b"earlier").unwrap();
+
+        let (target, moved) = quarantine_record(&record_for_source(&source), &quarantine).unwrap();
+        assert!(!moved);
+        assert_eq!(target, quarantine.join("Stray.bin"));
+        assert!(source.exists());
+
+        std::fs::remove_file(&target).unwrap();
+        let (_, moved) = quarantine_record(&record_for_source(&source), &quarantine).unwrap();
+        assert!(moved);
+        assert!(!source.exists());
+    }
+
+    #[test]
+    fn outputs_differing_only_in_case_collide() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        let records = vec![
+            record_for_source(&tmp.path().join("a").join("Game.bin")),
+            record_for_source(&tmp.path().join("b").join("Other.bin")),
+            record_for_source(&tmp.path().join("c").join("game.bin")),
+        ];
+
+        let cfg = config_with_output(&out);
+        assert_eq!(output_collisions(&records, &cfg, None), vec![vec![0, 2]]);
+
+        let cfg = Config {
+            on_collision: CollisionPolicy::Error,
+            ..config_with_output(&out)
+        };
+        assert!(resolve_output_collisions(records.clone(), &cfg, &[], None).is_err());
+
+        let cfg = Config {
+            on_collision: CollisionPolicy::Rename,
+            ..config_with_output(&out)
+        };
+        let renamed = resolve_output_collisions(records.clone(), &cfg, &[], None).unwrap();
+        assert_eq!(renamed[2].output_name.as_deref(), Some("game (1).bin"));
+        assert!(output_collisions(&renamed, &cfg, None).is_empty());
+
+        let cfg = Config {
+            on_collision: CollisionPolicy::Skip,
+            ..config_with_output(&out)
+        };
+        let skipped = resolve_output_collisions(records, &cfg, &[], None).unwrap();
+        assert_eq!(skipped.len(), 2);
+    }
+
+    #[test]
+    fn identical_inputs_sharing_an_output_are_written_once() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        let copy = |dir: &str, sha1: &str| {
+            let mut record = record_for_source(&tmp.path().join(dir).join("Game.bin"));
+            record.size = 4;
+            record.checksums.sha1 = Some(sha1.to_string());
+            record
+        };
+        let records = vec![copy("a", "aa"), copy("b", "aa"), copy("c", "cc")];
+
+        let cfg = config_with_output(&out);
+        assert_eq!(cfg.on_collision, CollisionPolicy::Rename);
+        let resolved = resolve_output_collisions(records, &cfg, &[], None).unwrap();
+        let sources: Vec<_> = resolved.iter().map(|r| r.source.clone()).collect();
+        assert_eq!(
+            sources,
+            vec![
+                tmp.path().join("a").join("Game.bin"),
+                tmp.path().join("c").join("Game.bin"),
+            ]
+        );
+        assert_eq!(resolved[0].output_name, None);
+        assert_eq!(resolved[1].output_name.as_deref(), Some("Game (1).bin"));
+    }
+
+    #[test]
+    fn clean_backup_uses_dated_run_folders_and_prunes_old_runs() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        let backup = tmp.path().join("backup");
+        let mut cfg = config_with_output(&out);
+        cfg.clean_backup = Some(backup.clone());
+
+        let run_folders = || {
+            let mut names: Vec<String> = std::fs::read_dir(&backup)
+                .unwrap()
+                .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        std::fs::create_dir_all(out.join("sub")).unwrap();
+        std::fs::write(out.join("sub").join("stale.bin"), b"first").unwrap();
+        let cleaned = clean_output(&[], &cfg, None).unwrap();
+        assert_eq!(cleaned.len(), 1);
+        assert!(cleaned[0].ends_with("sub/stale.bin"));
+
+        std::fs::write(out.join("sub").join("stale.bin"), b"second").unwrap();
+        clean_output(&[], &cfg, None).unwrap();
+
+        let folders = run_folders();
+        assert_eq!(folders.len(), 2, "{folders:?}");
+        assert!(folders.iter().all(|f| clean_backup_run_key(f).is_some()));
+        let contents: Vec<Vec<u8>> = folders
+            .iter()
+            .map(|f| std::fs::read(backup.join(f).join("sub").join("stale.bin")).unwrap())
+            .collect();
+        assert!(contents.contains(&b"first".to_vec()));
+        assert!(contents.contains(&b"second".to_vec()));
+
+        std::fs::write(out.join("third.bin"), b"third").unwrap();
+        cfg.clean_backup_keep = Some(2);
+        clean_output(&[], &cfg, None).unwrap();
+        let remaining = run_folders();
+        assert_eq!(remaining.len(), 2, "{remaining:?}");
+        assert!(!remaining.contains(&folders[0]));
+        assert!(
+            remaining
+                .iter()
+                .any(|f| backup.join(f).join("third.bin").exists())
+        );
+    }
+
+    #[test]
+    fn move_delete_dirs_stops_at_the_input_root() {
+        let tmp = tempdir().unwrap();
+        let above = tmp.path().join("above");
+        let input = above.join("input");
+        let deep = input.join("sub").join("deep");
+        std::fs::create_dir_all(&deep).unwrap();
+        std::fs::write(deep.join("game.bin"), b"rom").unwrap();
+
+        let mut cfg = config_with_output(&tmp.path().join("out"));
+        cfg.input = vec![input.clone()];
+        let records = crate::records::collect_files(&cfg, None).unwrap().records;
+        assert_eq!(records[0].input_root.as_deref(), Some(input.as_path()));
+
+        move_record(&records[0], &cfg).unwrap();
+        assert!(!input.join("sub").exists());
+        assert!(input.is_dir(), "input root must survive even when emptied");
+        assert!(
+            above.is_dir(),
+            "directories above the input root are never removed"
+        );
+
+        // A file passed directly as an input only treats its own directory as the root.
+        std::fs::write(input.join("loose.bin"), b"loose").unwrap();
+        cfg.input = vec![input.join("loose.bin")];
+        let records = crate::records::collect_files(&cfg, None).unwrap().records;
+        move_record(&records[0], &cfg).unwrap();
+        assert!(input.is_dir());
+    }
+
+    #[test]
+    fn space_preflight_errors_when_the_plan_exceeds_free_space() {
+        let tmp = tempdir().unwrap();
+        let records: Vec<_> = ["a.bin", "b.bin"]
+            .iter()
+            .map(|name| {
+                let mut record = record_for_source(&tmp.path().join(name));
+                record.size = 600;
+                record
+            })
+            .collect();
+        let mut cfg = config_with_output(&tmp.path().join("out"));
+        cfg.commands = vec![Action::Copy];
+
+        let err = preflight_output_space(&records, &cfg, &[], Some(1000)).unwrap_err();
+        assert!(err.to_string().contains("need 1200 bytes"), "{err}");
+        assert!(preflight_output_space(&records, &cfg, &[], Some(1200)).is_ok());
+        assert!(preflight_output_space(&records, &cfg, &[], None).is_ok());
+
+        // Links take no space on the output volume.
+        cfg.commands = vec![Action::Link];
+        cfg.link_mode = LinkMode::Reflink;
+        assert!(preflight_output_space(&records, &cfg, &[], Some(0)).is_ok());
+    }
+
+    #[test]
+    fn space_preflight_counts_each_new_output_once() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        let mut cfg = config_with_output(&out);
+        cfg.commands = vec![Action::Copy];
+        let record = |dir: &str, name: &str| {
+            let mut record = record_for_source(&tmp.path().join(dir).join(name));
+            record.size = 600;
+            record
+        };
+        // Two inputs sharing an output path, and one whose output is already there.
+        let records = vec![
+            record("a", "game.bin"),
+            record("b", "game.bin"),
+            record("a", "kept.bin"),
+        ];
+        std::fs::create_dir_all(&out).unwrap();
+        std::fs::write(out.join("kept.bin"), b"old").unwrap();
+
+        assert!(preflight_output_space(&records, &cfg, &[], Some(600)).is_ok());
+        cfg.overwrite = true;
+        let err = preflight_output_space(&records, &cfg, &[], Some(600)).unwrap_err();
+        assert!(err.to_string().contains("need 1200 bytes"), "{err}");
+    }
+
+    #[test]
+    fn preflight_output_dir_rejects_a_file_and_creates_a_missing_dir() {
+        let tmp = tempdir().unwrap();
+        let file = tmp.path().join("out.txt");
+        std::fs::write(&file, b"not a directory").unwrap();
+        for output in [file.clone(), PathBuf::from(format!("{}/", file.display()))] {
+            let mut cfg = config_with_output(&output);
+            cfg.commands = vec![Action::Copy];
+            let err = preflight_output_dir(&cfg).unwrap_err().to_string();
+            assert!(err.contains("is not a directory"), "{err}");
+        }
+
+        let out = tmp.path().join("nested").join("out");
+        let mut cfg = config_with_output(&out);
+        cfg.commands = vec![Action::Copy];
+        cfg.plan_diff = true;
+        preflight_output_dir(&cfg).unwrap();
+        assert!(!out.exists(), "--plan-diff must not create the output");
+        cfg.plan_diff = false;
+        preflight_output_dir(&cfg).unwrap();
+        assert!(out.is_dir());
+
+        let templated = tmp
+            .path()
+            .join("templated")
+            .join("{platform}")
+            .join("{region}");
+        let mut cfg = config_with_output(&templated);
+        cfg.commands = vec![Action::Copy];
+        preflight_output_dir(&cfg).unwrap();
+        assert!(tmp.path().join("templated").is_dir());
+        assert!(!tmp.path().join("templated").join("{platform}").exists());
+    }
+
+    #[test]
+    fn converted_cue_only_removes_tracks_written_under_the_output() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        std::fs::create_dir_all(&out).unwrap();
+        let cue = out.join("Game.cue");
+        let track = out.join("Game.bin");
+        let outside = tmp.path().join("outside.bin");
+        let unwritten = out.join("stale.bin");
+        for path in [&cue, &track, &outside, &unwritten] {
+            std::fs::write(path, b"data").unwrap();
+        }
+        let written: HashSet<PathBuf> = [&cue, &track]
+            .iter()
+            .map(|path| path.canonicalize().unwrap())
+            .collect();
+        let tracks = vec![
+            track.clone(),
+            out.join("..").join("outside.bin"),
+            outside.canonicalize().unwrap(),
+            unwritten.clone(),
+            out.join("missing.bin"),
+        ];
+
+        let root = out.canonicalize().unwrap();
+        let removed = converted_files_to_remove(&cue, &tracks, Some(&root), &written);
+        assert_eq!(
+            removed,
+            vec![track.canonicalize().unwrap(), cue.canonicalize().unwrap()]
+        );
+        assert!(converted_files_to_remove(&cue, &tracks, None, &written).is_empty());
+    }
+
+    #[test]
+    fn clean_output_returns_sorted_paths() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        let mut cfg = config_with_output(&out);
+        let names = [
+            "zeta.bin",
+            "b/inner.bin",
+            "alpha.bin",
+            "a.txt",
+            "a/nested.bin",
+            "Mid.bin",
+        ];
+        for name in names {
+            let path = out.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, name.as_bytes()).unwrap();
+        }
+        let mut expected: Vec<PathBuf> = names.iter().map(|n| out.join(n)).collect();
+        expected.sort();
+
+        cfg.clean_dry_run = true;
+        assert_eq!(clean_output(&[], &cfg, None).unwrap(), expected);
+
+        cfg.clean_dry_run = false;
+        assert_eq!(clean_output(&[], &cfg, None).unwrap(), expected);
+        assert!(expected.iter().all(|p| !p.exists()));
+    }
+
+    #[test]
+    fn clean_output_keeps_chds_converted_from_written_cues() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        let mut cfg = config_with_output(&out);
+        cfg.clean_dry_run = true;
+        std::fs::create_dir_all(&out).unwrap();
+        std::fs::write(out.join("Game.chd"), b"chd").unwrap();
+        let records = [record_for_source(&tmp.path().join("Game.cue"))];
+
+        assert_eq!(
+            clean_output(&records, &cfg, None).unwrap(),
+            vec![out.join("Game.chd")]
+        );
+        cfg.to_chd = true;
+        assert!(clean_output(&records, &cfg, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn clean_empty_dirs_removes_nested_empty_trees_only() {
+        let tmp = tempdir().unwrap();
+        let input = tmp.path().join("input");
+        std::fs::create_dir_all(input.join("empty/deeper/deepest")).unwrap();
+        std::fs::create_dir_all(input.join("kept/empty-child")).unwrap();
+        std::fs::write(input.join("kept/game.bin"), b"rom").unwrap();
+        let mut cfg = Config {
+            input: vec![input.clone()],
+            ..Config::default()
+        };
+
+        let expected = vec![
+            input.join("empty"),
+            input.join("empty/deeper"),
+            input.join("empty/deeper/deepest"),
+            input.join("kept/empty-child"),
+        ];
+        cfg.clean_dry_run = true;
+        assert_eq!(remove_empty_input_dirs(&cfg).unwrap(), expected);
+        assert!(input.join("empty/deeper/deepest").exists());
+
+        cfg.clean_dry_run = false;
+        assert_eq!(remove_empty_input_dirs(&cfg).unwrap(), expected);
+        assert!(!input.join("empty").exists());
+        assert!(!input.join("kept/empty-child").exists());
+        assert!(input.join("kept/game.bin").exists());
+        assert!(input.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failed_copy_leaves_no_partial_destination() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        let cfg = config_with_output(&out);
+
+        // A directory opens fine on unix but fails on the first read, after the
+        // destination would already have been created.
+        let source = tmp.path().join("game.bin");
+        std::fs::create_dir(&source).unwrap();
+        let record = record_for_source(&source);
+
+        assert!(copy_record(&record, &cfg, None).is_err());
+        assert!(!out.join("game.bin").exists());
+        assert!(!out.join("game.bin.tmp").exists());
+
+        std::fs::remove_dir(&source).unwrap();
+        std::fs::write(&source, b"rom").unwrap();
+        let target = copy_record(&record, &cfg, None).unwrap();
+        assert_eq!(std::fs::read(&target).unwrap(), b"rom");
+        assert!(!out.join("game.bin.tmp").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn move_copy_fallback_keeps_the_source_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempdir().unwrap();
+        let source = tmp.path().join("game.bin");
+        std::fs::write(&source, b"rom").unwrap();
+        std::fs::set_permissions(&source, std::fs::Permissions::from_mode(0o640)).unwrap();
+        let target = tmp.path().join("out").join("game.bin");
+        std::fs::create_dir_all(target.parent().unwrap()).unwrap();
+
+        move_by_copy(&source, &target, None).unwrap();
+
+        assert!(!source.exists());
+        assert_eq!(std::fs::read(&target).unwrap(), b"rom");
+        let mode = std::fs::metadata(&target).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
+
     #[test]
     fn extract_record_only_unzips_valid_archives() {
         let tmp = tempdir().unwrap();
@@ -1106,165 +1537,982 @@ mod tests {
     }
 
     #[test]
-    fn extract_record_falls_back_when_zip_invalid() {
+    fn tar_members_are_written_on_their_own() {
         let tmp = tempdir().unwrap();
+        let archive = tmp.path().join("set.tar");
+        let mut builder = tar::Builder::new(std::fs::File::create(&archive).unwrap());
+        for (name, data) in [("a.bin", &b"first"[..]), ("dir/b.bin", &b"second"[..])] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, name, data).unwrap();
+        }
+        builder.finish().unwrap();
+        drop(builder);
+        let members =
+            crate::archives::scan_tar_entries(&archive, &Config::default(), None).unwrap();
+        assert_eq!(members.len(), 2);
+
         let out = tmp.path().join("out");
         let cfg = config_with_output(&out);
+        let copied = copy_record(&members[0], &cfg, None).unwrap();
+        assert_eq!(copied, out.join("a.bin"));
+        assert_eq!(std::fs::read(&copied).unwrap(), b"first");
+
+        let moved = move_record(&members[1], &cfg).unwrap();
+        assert_eq!(std::fs::read(&moved).unwrap(), b"second");
+        assert!(
+            archive.exists(),
+            "moving a member must leave its archive in place"
+        );
+    }
 
-        let fake_path = tmp.path().join("fake.zip");
-        std::fs::write(&fake_path, b"not a zip").unwrap();
+    #[test]
+    fn archive_member_inputs_copy_only_that_member() {
+        let tmp = tempdir().unwrap();
+        let archive = tmp.path().join("collection.zip");
+        let mut inner = Vec::new();
+        {
+            let mut zipw = zip::ZipWriter::new(std::io::Cursor::new(&mut inner));
+            zipw.start_file::<_, ()>("deep.bin", FileOptions::default())
+                .unwrap();
+            zipw.write_all(b"nested").unwrap();
+            zipw.finish().unwrap();
+        }
+        {
+            let mut zipw = zip::ZipWriter::new(std::fs::File::create(&archive).unwrap());
+            for (name, bytes) in [
+                ("roms/game.sfc", &b"game"[..]),
+                ("roms/else.sfc", b"else"),
+                ("inner.zip", &inner),
+            ] {
+                zipw.start_file::<_, ()>(name, FileOptions::default())
+                    .unwrap();
+                zipw.write_all(bytes).unwrap();
+            }
+            zipw.finish().unwrap();
+        }
 
-        let record = record_for_source(&fake_path);
-        let written = extract_record(&record, &cfg).unwrap();
-        let expected = out.join("fake.zip");
-        assert_eq!(written, vec![expected.clone()]);
-        assert_eq!(std::fs::read(expected).unwrap(), b"not a zip");
+        let out = tmp.path().join("out");
+        let cfg = config_with_output(&out);
+        let member =
+            crate::archives::scan_zip_member(&archive, "roms/game.sfc", &Config::default())
+                .unwrap();
+        let written = link_record(&member, &cfg).unwrap();
+        assert_eq!(std::fs::read(&written).unwrap(), b"game");
+
+        let mut nested = record_for_source(&archive);
+        nested.relative = PathBuf::from("inner.zip/deep.bin");
+        let written = copy_record(&nested, &cfg, None).unwrap();
+        assert_eq!(std::fs::read(&written).unwrap(), b"nested");
     }
 
     #[test]
-    fn log_diag_step_only_runs_when_enabled() {
-        crate::progress::force_progress_tty_for_tests(Some(true));
-        let cfg = Config::default();
-        let progress = ProgressReporter::maybe_new(&cfg).expect("progress reporter");
-        log_diag_step(Some(&progress), false, "skip");
-        assert!(progress.diag_last_hint_for_tests().is_none());
-        log_diag_step(Some(&progress), true, "emit");
-        assert_eq!(progress.diag_last_hint_for_tests().as_deref(), Some("emit"));
-        drop(progress);
-        crate::progress::force_progress_tty_for_tests(None);
+    fn extract_exclude_skips_matching_archive_members() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        let cfg = Config {
+            extract_exclude: Some("*.txt".to_string()),
+            ..config_with_output(&out)
+        };
+
+        let archive_path = tmp.path().join("mixed.zip");
+        {
+            let file = std::fs::File::create(&archive_path).unwrap();
+            let mut zipw = zip::ZipWriter::new(file);
+            zipw.start_file::<_, ()>("game.sfc", FileOptions::default())
+                .unwrap();
+            zipw.write_all(b"rom").unwrap();
+            zipw.start_file::<_, ()>("readme.txt", FileOptions::default())
+                .unwrap();
+            zipw.write_all(b"notes").unwrap();
+            zipw.finish().unwrap();
+        }
+
+        let written = extract_record(&record_for_source(&archive_path), &cfg).unwrap();
+        assert_eq!(written, vec![out.join("game.sfc")]);
+        assert!(!out.join("readme.txt").exists());
     }
 
     #[test]
-    fn record_diag_duration_tracks_timings_when_enabled() {
-        crate::progress::force_progress_tty_for_tests(Some(true));
-        let cfg = Config::default();
-        let progress = ProgressReporter::maybe_new(&cfg).expect("progress reporter");
-        let mut timings = Vec::new();
-        record_diag_duration(
-            "phase1",
-            Duration::from_millis(5),
-            Some(&progress),
-            false,
-            &mut timings,
-        );
-        assert!(timings.is_empty());
-        assert!(progress.diag_last_hint_for_tests().is_none());
-        record_diag_duration(
-            "phase2",
-            Duration::from_millis(10),
-            Some(&progress),
-            true,
-            &mut timings,
-        );
-        assert_eq!(timings.len(), 1);
-        assert!(progress
-            .diag_last_hint_for_tests()
-            .unwrap()
-            .contains("phase=phase2"));
-        drop(progress);
-        crate::progress::force_progress_tty_for_tests(None);
+    fn zip_exclude_matches_the_archive_relative_path() {
+        let tmp = tempdir().unwrap();
+        let archive = tmp.path().join("set.zip");
+        let mut readme = record_for_source(&archive);
+        readme.relative = PathBuf::from("docs").join("readme.txt");
+        let mut game = record_for_source(&archive);
+        game.relative = PathBuf::from("game.bin");
+        let mut loose_readme = record_for_source(&tmp.path().join("readme.txt"));
+        loose_readme.relative = PathBuf::from("readme.txt");
+
+        let cfg = Config {
+            zip_exclude: vec!["docs/*".to_string(), "*.nfo".to_string()],
+            ..config_with_output(&tmp.path().join("out"))
+        };
+        let keep = zip_member_filter(&cfg).unwrap();
+        assert!(!keep(&readme));
+        assert!(keep(&game));
+        assert!(keep(&loose_readme), "only the docs/ path is excluded");
     }
-}
 
-fn copy_file_with_progress(
-    src: &Path,
-    dest: &Path,
-    progress: Option<&ActionProgressHandle>,
-) -> anyhow::Result<()> {
-    let mut reader = fs::File::open(src).with_context(|| format!("opening {src:?} for copy"))?;
-    let mut writer = fs::File::create(dest).with_context(|| format!("creating {dest:?} for copy"))?;
-    let total = reader
-        .metadata()
-        .map(|m| m.len())
-        .with_context(|| format!("reading metadata for {src:?}"))?;
-    let mut buf = vec![0u8; 1 << 20];
-    let mut written = 0u64;
-    loop {
-        let n = reader.read(&mut buf)?;
-        if n == 0 {
-            break;
-        }
-        writer.write_all(&buf[..n])?;
-        written = written.saturating_add(n as u64);
-        if let Some(handle) = progress {
-            handle.report_bytes(written, Some(total));
-        }
-    }
-    writer.flush()?;
-    Ok(())
-}
+    #[test]
+    fn overlong_output_paths_fail_the_preflight_unless_shortened() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        let mut record = record_for_source(&tmp.path().join("Game.bin"));
+        record.relative = PathBuf::from("A".repeat(120))
+            .join("B".repeat(120))
+            .join("Game.bin");
+
+        let cfg = Config {
+            dir_mirror: true,
+            ..config_with_output(&out)
+        };
+        let err = preflight_output_path_lengths(std::slice::from_ref(&record), &cfg, &[], 260)
+            .unwrap_err();
+        assert!(err.to_string().contains("--shorten-paths"));
 
-pub fn copy_record(
-    record: &FileRecord,
-    config: &Config,
-    dats: Option<&[crate::dat::DatRom]>,
-) -> anyhow::Result<PathBuf> {
-    let target = resolve_output_path_with_dats(record, config, dats);
-    ensure_parent(&target)?;
+        let cfg = Config {
+            shorten_paths: true,
+            ..cfg
+        };
+        let target = crate::records::resolve_output_path(&record, &cfg);
+        assert!(target.to_string_lossy().chars().count() <= 260);
+        assert!(target.starts_with(&out));
+        assert_eq!(target.file_name().unwrap(), "Game.bin");
+        assert_eq!(target, crate::records::resolve_output_path(&record, &cfg));
+        preflight_output_path_lengths(std::slice::from_ref(&record), &cfg, &[], 260).unwrap();
+
+        let mut sibling = record.clone();
+        sibling.relative = PathBuf::from(format!("{}2", "A".repeat(119)))
+            .join("B".repeat(120))
+            .join("Game.bin");
+        assert_ne!(crate::records::resolve_output_path(&sibling, &cfg), target);
+    }
 
-    if target.exists() {
-        if !config.overwrite && !config.overwrite_invalid {
-            return Ok(target);
-        }
+    #[test]
+    fn output_path_preflight_measures_relative_outputs_as_absolute() {
+        let tmp = tempdir().unwrap();
+        let mut record = record_for_source(&tmp.path().join("Game.bin"));
+        record.relative = PathBuf::from("A".repeat(120))
+            .join("B".repeat(115))
+            .join("Game.bin");
+        let cfg = Config {
+            dir_mirror: true,
+            ..config_with_output(Path::new("out"))
+        };
+        let relative = crate::records::resolve_output_path(&record, &cfg);
+        assert!(relative.is_relative());
+        assert!(relative.to_string_lossy().chars().count() <= 260);
+        preflight_output_path_lengths(std::slice::from_ref(&record), &cfg, &[], 260).unwrap_err();
+
+        let cfg = Config {
+            shorten_paths: true,
+            ..cfg
+        };
+        preflight_output_path_lengths(std::slice::from_ref(&record), &cfg, &[], 260).unwrap();
     }
 
-    copy_file_with_progress(&record.source, &target, None)
-        .with_context(|| format!("copying {:?} to {:?}", record.source, target))?;
-    Ok(target)
-}
+    #[test]
+    fn zip_record_streams_large_roms_in_bounded_chunks() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        let rom = tmp.path().join("Big.bin");
+        let payload: Vec<u8> = (0..5u32 << 20).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&rom, &payload).unwrap();
+        let cfg = Config {
+            zip_format: ZipFormat::Torrentzip,
+            ..config_with_output(&out)
+        };
 
-// New wrappers that accept dats and perform actions using DAT-aware path resolution.
-pub fn copy_record_with_dats(
-    record: &FileRecord,
-    config: &Config,
-    dats: Option<&[crate::dat::DatRom]>,
-    progress: Option<&ActionProgressHandle>,
-) -> anyhow::Result<PathBuf> {
-    let target = resolve_output_path_with_dats(record, config, dats);
-    ensure_parent(&target)?;
+        let (tx, rx) = std::sync::mpsc::channel();
+        let handle = super::ActionProgressHandle::new(tx, rom.clone());
+        let target = zip_record(&record_for_source(&rom), &cfg, None, Some(&handle)).unwrap();
+        drop(handle);
 
-    if target.exists() {
-        if !config.overwrite && !config.overwrite_invalid {
-            return Ok(target);
+        let reported: Vec<u64> = rx
+            .iter()
+            .map(|event| match event {
+                super::ActionProgress::ItemBytes { bytes_done, .. } => bytes_done,
+            })
+            .collect();
+        assert_eq!(reported.last(), Some(&(payload.len() as u64)));
+        let mut previous = 0;
+        for done in &reported {
+            assert!(
+                done - previous <= 1 << 20,
+                "chunk of {} bytes",
+                done - previous
+            );
+            previous = *done;
         }
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&target).unwrap()).unwrap();
+        assert!(
+            String::from_utf8_lossy(archive.comment()).starts_with("TORRENTZIPPED-"),
+            "comment patched"
+        );
+        let mut entry = archive.by_name("Big.bin").unwrap();
+        let mut unpacked = Vec::new();
+        std::io::Read::read_to_end(&mut entry, &mut unpacked).unwrap();
+        assert!(unpacked == payload, "round-tripped contents differ");
     }
 
-    copy_file_with_progress(&record.source, &target, progress)
+    #[test]
+    fn zip_streams_the_sha256_of_the_written_entry() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        let rom = tmp.path().join("Game.bin");
+        let payload: Vec<u8> = (0..300_000u32).map(|i| (i % 253) as u8).collect();
+        std::fs::write(&rom, &payload).unwrap();
+        let cfg = Config {
+            zip_format: ZipFormat::Torrentzip,
+            input_checksum_max: Some(crate::types::Checksum::Sha256),
+            ..config_with_output(&out)
+        };
+
+        let (target, sha256) =
+            zip_record_hashed(&record_for_source(&rom), &cfg, None, None).unwrap();
+        let independent = crate::checksum::compute_all_checksums(&rom).unwrap().sha256;
+        assert_eq!(sha256, independent);
+        assert!(target.exists());
+
+        // A scanned SHA256 that disagrees with what was written fails verification.
+        std::fs::remove_file(&target).unwrap();
+        let mut record = record_for_source(&rom);
+        record.checksums.sha256 = Some("0".repeat(64));
+        let err = zip_record_hashed(&record, &cfg, None, None).unwrap_err();
+        assert!(err.to_string().contains("zip verification failed"), "{err}");
+        assert!(!target.exists());
+
+        // A scan that dropped a header hashed only the rest, so it is not compared.
+        let mut headered = record_for_source(&rom);
+        headered.checksums.sha256 = Some(hex::encode(<sha2::Sha256 as sha2::Digest>::digest(
+            &payload[16..],
+        )));
+        headered.scan_info = Some(crate::roms::rom_scanner::RomInfo {
+            path: rom.to_string_lossy().into_owned(),
+            header_size: Some(16),
+            header_name: None,
+            is_chd: false,
+            is_nkit: false,
+            is_iso: false,
+            is_pbp: false,
+            is_psx_exe: false,
+            is_cue: false,
+            trimmed_size: payload.len() as u64 - 16,
+        });
+        zip_record_hashed(&headered, &cfg, None, None).unwrap();
+        std::fs::remove_file(&target).unwrap();
+
+        // Without SHA256 in the checksum range nothing extra is hashed.
+        let cfg = Config {
+            zip_format: ZipFormat::Torrentzip,
+            ..config_with_output(&out)
+        };
+        let (_, sha256) = zip_record_hashed(&record_for_source(&rom), &cfg, None, None).unwrap();
+        assert_eq!(sha256, None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn relative_symlinks_from_deeply_nested_targets_resolve_to_the_source() {
+        let tmp = tempdir().unwrap();
+        let roms = tmp.path().join("in/roms");
+        std::fs::create_dir_all(&roms).unwrap();
+        std::fs::create_dir_all(tmp.path().join("in/other")).unwrap();
+        std::fs::write(roms.join("Game.bin"), b"the game").unwrap();
+        let cfg = Config {
+            link_mode: LinkMode::Symlink,
+            symlink_relative: true,
+            ..config_with_output(&tmp.path().join("out/a/b/c/d"))
+        };
+
+        let cwd = std::env::current_dir().unwrap();
+        let sources = [
+            // `..` segments in the input path
+            tmp.path().join("in/other/../roms/Game.bin"),
+            // an input given relative to the working directory
+            pathdiff::diff_paths(roms.join("Game.bin"), &cwd).unwrap(),
+        ];
+        for source in sources {
+            let target = link_record(&record_for_source(&source), &cfg).unwrap();
+            let link = std::fs::read_link(&target).unwrap();
+            assert!(link.is_relative(), "{link:?}");
+            assert_eq!(std::fs::read(&target).unwrap(), b"the game", "{link:?}");
+        }
+    }
+
+    #[test]
+    fn zip_comment_is_written_to_plain_zips() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        let rom = tmp.path().join("Game.bin");
+        std::fs::write(&rom, b"rom data").unwrap();
+        let cfg = Config {
+            zip_format: ZipFormat::Deflate,
+            zip_comment: Some("dumped by me".to_string()),
+            ..config_with_output(&out)
+        };
+
+        let target = zip_record(&record_for_source(&rom), &cfg, None, None).unwrap();
+        let archive = zip::ZipArchive::new(std::fs::File::open(target).unwrap()).unwrap();
+        assert_eq!(archive.comment(), b"dumped by me");
+    }
+
+    #[test]
+    fn extract_record_falls_back_when_zip_invalid() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        let cfg = config_with_output(&out);
+
+        let fake_path = tmp.path().join("fake.zip");
+        std::fs::write(&fake_path, b"not a zip").unwrap();
+
+        let record = record_for_source(&fake_path);
+        let written = extract_record(&record, &cfg).unwrap();
+        let expected = out.join("fake.zip");
+        assert_eq!(written, vec![expected.clone()]);
+        assert_eq!(std::fs::read(expected).unwrap(), b"not a zip");
+    }
+
+    #[test]
+    fn rename_record_to_dat_renames_in_place_and_guards_collisions() {
+        let tmp = tempdir().unwrap();
+        let misnamed = tmp.path().join("smw.sfc");
+        std::fs::write(&misnamed, b"rom").unwrap();
+        let dat = crate::dat::DatRom {
+            name: "Super Mario World (USA).sfc".to_string(),
+            description: Some("Super Mario World (USA)".to_string()),
+            source_dat: tmp.path().join("snes.dat"),
+            size: Some(3),
+            crc32: None,
+            md5: None,
+            sha1: None,
+            sha256: None,
+            match_reasons: None,
+            serial: None,
+            version: None,
+            region: None,
+            alternates: Vec::new(),
+            dat_name: None,
+            dat_description: None,
+            set_info: None,
+        };
+
+        let renamed = rename_record_to_dat(&record_for_source(&misnamed), &dat)
+            .unwrap()
+            .expect("file renamed");
+        assert_eq!(renamed, tmp.path().join("Super Mario World (USA).sfc"));
+        assert!(renamed.exists());
+        assert!(!misnamed.exists());
+
+        // already correctly named: no-op
+        assert!(
+            rename_record_to_dat(&record_for_source(&renamed), &dat)
+                .unwrap()
+                .is_none()
+        );
+
+        // another file already holds the DAT name: left untouched
+        let duplicate = tmp.path().join("smw copy.sfc");
+        std::fs::write(&duplicate, b"rom").unwrap();
+        assert!(
+            rename_record_to_dat(&record_for_source(&duplicate), &dat)
+                .unwrap()
+                .is_none()
+        );
+        assert!(duplicate.exists());
+    }
+
+    #[test]
+    fn romvault_log_style_reports_dats_and_missing_and_fixed_roms() {
+        let rom = |name: &str, dat: &str| crate::dat::DatRom {
+            name: name.to_string(),
+            description: None,
+            source_dat: PathBuf::from(format!("{dat}.dat")),
+            size: None,
+            crc32: None,
+            md5: None,
+            sha1: None,
+            sha256: None,
+            match_reasons: None,
+            serial: None,
+            version: None,
+            region: None,
+            alternates: Vec::new(),
+            dat_name: None,
+            dat_description: None,
+            set_info: None,
+        };
+        let matched = vec![rom("Alpha.sfc", "snes"), rom("Sonic.md", "genesis")];
+        let unmatched = vec![rom("Beta.sfc", "snes"), rom("Pong.a26", "atari")];
+        let fixed = vec![rom("Sonic.md", "genesis")];
+
+        let report = romvault_report(&matched, &unmatched, &fixed);
+        assert!(report.starts_with("RomVault Report\n"));
+        for line in [
+            "Complete DATs (1)\n  genesis  1/1\n",
+            "Partial DATs (1)\n  snes  1/2\n",
+            "Empty DATs (1)\n  atari  0/1\n",
+            "Missing ROMs (2)\n  snes\\Beta.sfc\n  atari\\Pong.a26\n",
+            "Fixed ROMs (1)\n  genesis\\Sonic.md\n",
+            "Have: 2  Missing: 2  Fixed: 1\n",
+        ] {
+            assert!(report.contains(line), "{line:?} missing from:\n{report}");
+        }
+
+        let report = romvault_report(&matched, &unmatched, &[]);
+        assert!(report.contains("Fixed ROMs (0)\n"));
+    }
+
+    #[test]
+    fn clrmamepro_fixdat_reparses_to_missing_games() {
+        let tmp = tempdir().unwrap();
+        let mut cfg = config_with_output(&tmp.path().join("out"));
+        cfg.fixdat_format = Some(FixdatFormat::Clrmamepro);
+
+        let mut first = record_for_source(&tmp.path().join("Alpha (USA).sfc"));
+        first.size = 4;
+        first.checksums.crc32 = Some("deadbeef".to_string());
+        first.checksums.sha1 = Some("a".repeat(40));
+        let mut second = record_for_source(&tmp.path().join("Beta (Europe).md"));
+        second.size = 8;
+        second.checksums.crc32 = Some("0badf00d".to_string());
+
+        let target = write_fixdat(&[second, first], &cfg, None).unwrap();
+        assert_eq!(target.file_name().unwrap(), "fixdat.dat");
+
+        let text = std::fs::read_to_string(&target).unwrap();
+        let parsed = crate::dat::parse_clrmamepro(&text, &target);
+
+        let summary: Vec<_> = parsed
+            .iter()
+            .map(|rom| {
+                (
+                    rom.description.as_deref(),
+                    rom.name.as_str(),
+                    rom.size,
+                    rom.crc32.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    Some("Alpha (USA)"),
+                    "Alpha (USA).sfc",
+                    Some(4),
+                    Some("DEADBEEF")
+                ),
+                (
+                    Some("Beta (Europe)"),
+                    "Beta (Europe).md",
+                    Some(8),
+                    Some("0BADF00D")
+                ),
+            ]
+        );
+        assert_eq!(parsed[0].sha1.as_deref(), Some("a".repeat(40).as_str()));
+        assert!(parsed[1].sha1.is_none());
+    }
+
+    #[test]
+    fn second_report_run_reuses_cached_checksums() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        let mut cfg = config_with_output(&out);
+        cfg.skip_existing_checksum = true;
+        let cache_path = tmp.path().join("report-cache.sqlite");
+        let cache = cache::Cache::open(Some(&cache_path), None).expect("cache opened");
+
+        let rom = tmp.path().join("game.bin");
+        std::fs::write(&rom, b"report payload").unwrap();
+        let record = record_for_source(&rom);
+
+        write_report(std::slice::from_ref(&record), &cfg, Some(&cache), &[]).unwrap();
+
+        let (cached, hashed) = report_checksums(&rom, &cfg, Some(&cache)).unwrap();
+        assert!(!hashed, "unchanged output should not be rehashed");
+        let fresh = crate::checksum::compute_all_checksums(&rom).unwrap();
+        assert_eq!(cached.crc32, fresh.crc32);
+        assert_eq!(cached.sha256, fresh.sha256);
+
+        cfg.skip_existing_checksum = false;
+        let (_, hashed) = report_checksums(&rom, &cfg, Some(&cache)).unwrap();
+        assert!(
+            hashed,
+            "cache is only consulted with --skip-existing-checksum"
+        );
+    }
+
+    #[test]
+    fn report_rehashes_files_touched_since_they_were_cached() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        let mut cfg = config_with_output(&out);
+        cfg.skip_existing_checksum = true;
+        let cache_path = tmp.path().join("report-cache.sqlite");
+        let cache = cache::Cache::open(Some(&cache_path), None).expect("cache opened");
+
+        let rom = tmp.path().join("game.bin");
+        std::fs::write(&rom, b"first payload").unwrap();
+        let record = record_for_source(&rom);
+        let read_row = |cfg: &Config| {
+            let report =
+                write_report(std::slice::from_ref(&record), cfg, Some(&cache), &[]).unwrap();
+            let rows: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(report).unwrap()).unwrap();
+            rows[0].clone()
+        };
+
+        assert_eq!(read_row(&cfg)["checksum_source"], "computed");
+        assert_eq!(read_row(&cfg)["checksum_source"], "cache");
+
+        // Same size, new content and a new mtime: the cached entry must not be reused.
+        std::fs::write(&rom, b"other payload").unwrap();
+        let touched = std::time::SystemTime::now() + Duration::from_secs(60);
+        std::fs::File::options()
+            .write(true)
+            .open(&rom)
+            .unwrap()
+            .set_modified(touched)
+            .unwrap();
+        let row = read_row(&cfg);
+        assert_eq!(row["checksum_source"], "computed");
+        let fresh = crate::checksum::compute_all_checksums(&rom).unwrap();
+        assert_eq!(row["checksums"]["sha256"].as_str(), fresh.sha256.as_deref());
+        assert_eq!(row["checksums"]["crc32"].as_str(), fresh.crc32.as_deref());
+    }
+
+    #[test]
+    fn report_rows_name_the_archive_containing_a_member() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        let cfg = config_with_output(&out);
+
+        let loose = tmp.path().join("rom.bin");
+        std::fs::write(&loose, b"loose").unwrap();
+        let archive = tmp.path().join("coll.zip");
+        std::fs::write(&archive, b"not really a zip").unwrap();
+        let mut member = record_for_source(&archive);
+        member.relative = PathBuf::from("rom.bin");
+
+        write_report(&[record_for_source(&loose), member], &cfg, None, &[]).unwrap();
+
+        let report: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(out.join("report.json")).unwrap())
+                .unwrap();
+        let paths: Vec<&str> = report
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|row| row["path"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                loose.display().to_string(),
+                format!("{}#rom.bin", archive.display())
+            ]
+        );
+        assert_eq!(report[0]["checksum_source"], "computed");
+        // The archive's own hashes must not be reported as the member's.
+        assert!(report[1].get("checksum_source").is_none());
+        assert!(report[1]["checksums"]["sha256"].is_null());
+    }
+
+    #[test]
+    fn report_rows_carry_the_serial_version_and_region_of_the_matched_dat_game() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        let cfg = config_with_output(&out);
+
+        let rom = tmp.path().join("crash.bin");
+        std::fs::write(&rom, b"crash").unwrap();
+        let mut record = record_for_source(&rom);
+        record.size = 5;
+        record.checksums = crate::checksum::compute_all_checksums(&rom).unwrap();
+        let dat = crate::dat::DatRom {
+            name: "Crash Bandicoot (USA).bin".to_string(),
+            description: Some("Crash Bandicoot (USA)".to_string()),
+            source_dat: tmp.path().join("psx.dat"),
+            size: Some(5),
+            crc32: record.checksums.crc32.clone(),
+            md5: None,
+            sha1: None,
+            sha256: None,
+            match_reasons: None,
+            serial: Some("SCUS-94900".to_string()),
+            version: Some("1.1".to_string()),
+            region: Some("USA".to_string()),
+            alternates: Vec::new(),
+            dat_name: None,
+            dat_description: None,
+            set_info: None,
+        };
+
+        write_report(std::slice::from_ref(&record), &cfg, None, &[dat]).unwrap();
+
+        let report: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(out.join("report.json")).unwrap())
+                .unwrap();
+        assert_eq!(report[0]["serial"], "SCUS-94900");
+        assert_eq!(report[0]["version"], "1.1");
+        assert_eq!(report[0]["region"], "USA");
+    }
+
+    #[test]
+    fn report_duplicates_lists_only_groups_with_several_copies() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        let mut cfg = config_with_output(&out);
+        cfg.report_duplicates = true;
+
+        let mut records = Vec::new();
+        for (name, bytes) in [
+            ("a.bin", &b"same rom"[..]),
+            ("b.bin", &b"same rom"[..]),
+            ("c.bin", &b"other"[..]),
+        ] {
+            let rom = tmp.path().join(name);
+            std::fs::write(&rom, bytes).unwrap();
+            let mut record = record_for_source(&rom);
+            record.size = bytes.len() as u64;
+            record.checksums = crate::checksum::compute_all_checksums(&rom).unwrap();
+            records.push(record);
+        }
+
+        write_report(&records, &cfg, None, &[]).unwrap();
+
+        let report: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(out.join("duplicates.json")).unwrap())
+                .unwrap();
+        let groups = report.as_array().unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            groups[0]["checksum"],
+            json!(records[0].checksums.sha256.clone().unwrap())
+        );
+        let files: Vec<(&str, u64)> = groups[0]["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| (f["relative"].as_str().unwrap(), f["size"].as_u64().unwrap()))
+            .collect();
+        assert_eq!(files, vec![("a.bin", 8), ("b.bin", 8)]);
+    }
+
+    #[test]
+    fn log_diag_step_only_runs_when_enabled() {
+        crate::progress::force_progress_tty_for_tests(Some(true));
+        let cfg = Config::default();
+        let progress = ProgressReporter::maybe_new(&cfg).expect("progress reporter");
+        log_diag_step(Some(&progress), false, "skip");
+        assert!(progress.diag_last_hint_for_tests().is_none());
+        log_diag_step(Some(&progress), true, "emit");
+        assert_eq!(progress.diag_last_hint_for_tests().as_deref(), Some("emit"));
+        drop(progress);
+        crate::progress::force_progress_tty_for_tests(None);
+    }
+
+    #[test]
+    fn record_diag_duration_tracks_timings_when_enabled() {
+        crate::progress::force_progress_tty_for_tests(Some(true));
+        let cfg = Config::default();
+        let progress = ProgressReporter::maybe_new(&cfg).expect("progress reporter");
+        let mut timings = Vec::new();
+        record_diag_duration(
+            "phase1",
+            Duration::from_millis(5),
+            Some(&progress),
+            false,
+            false,
+            &mut timings,
+        );
+        assert!(timings.is_empty());
+        assert!(progress.diag_last_hint_for_tests().is_none());
+        record_diag_duration(
+            "phase2",
+            Duration::from_millis(10),
+            Some(&progress),
+            true,
+            false,
+            &mut timings,
+        );
+        assert_eq!(timings.len(), 1);
+        assert!(progress
+            .diag_last_hint_for_tests()
+            .unwrap()
+            .contains("phase=phase2"));
+        drop(progress);
+        crate::progress::force_progress_tty_for_tests(None);
+    }
+
+    #[test]
+    fn patching_outputs_runs_a_patch_phase() {
+        let tmp = tempdir().unwrap();
+        let out = tmp.path().join("out");
+        std::fs::create_dir_all(&out).unwrap();
+        std::fs::write(out.join("Game.bin"), [0u8; 4]).unwrap();
+        let patch = tmp.path().join("Game (Fixed) 0a0b0c0d.ips");
+        let mut ips = b"PATCH".to_vec();
+        ips.extend_from_slice(&[0x00, 0x00, 0x01, 0x00, 0x02, 0xAA, 0xBB]);
+        ips.extend_from_slice(b"EOF");
+        std::fs::write(&patch, ips).unwrap();
+
+        let mut record = record_for_source(&tmp.path().join("in").join("Game.bin"));
+        record.checksums.crc32 = Some("0A0B0C0D".to_string());
+        let mut cfg = config_with_output(&out);
+        cfg.patch = vec![patch];
+
+        crate::progress::force_progress_tty_for_tests(Some(true));
+        let progress = ProgressReporter::maybe_new(&cfg).expect("progress reporter");
+        let written = apply_patches_to_outputs(&[record], &cfg, &[], Some(&progress)).unwrap();
+        assert_eq!(
+            progress.task_events_for_tests(),
+            vec![
+                (BackgroundTask::Patch, "begin"),
+                (BackgroundTask::Patch, "finish"),
+            ]
+        );
+        drop(progress);
+        crate::progress::force_progress_tty_for_tests(None);
+
+        assert_eq!(written, vec![out.join("Game (Fixed).bin")]);
+        assert_eq!(
+            std::fs::read(&written[0]).unwrap(),
+            [0x00, 0xAA, 0xBB, 0x00]
+        );
+    }
+}
+
+fn copy_file_with_progress(
+    src: &Path,
+    dest: &Path,
+    progress: Option<&ActionProgressHandle>,
+) -> anyhow::Result<()> {
+    let mut reader = fs::File::open(src).with_context(|| format!("opening {src:?} for copy"))?;
+    let total = reader
+        .metadata()
+        .map(|m| m.len())
+        .with_context(|| format!("reading metadata for {src:?}"))?;
+    write_atomically(dest, |tmp| {
+        let mut writer =
+            fs::File::create(tmp).with_context(|| format!("creating {tmp:?} for copy"))?;
+        let mut buf = vec![0u8; 1 << 20];
+        let mut written = 0u64;
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            writer.write_all(&buf[..n])?;
+            written = written.saturating_add(n as u64);
+            if let Some(handle) = progress {
+                handle.report_bytes(written, Some(total));
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    })
+}
+
+/// Whether `record` was read from inside an archive that can hand out that member on its
+/// own. Such records are written by extracting the member, never by copying the archive.
+fn is_copyable_member(record: &FileRecord) -> bool {
+    !record.source.ends_with(&record.relative)
+        && crate::archives::copies_archive_members(&record.source)
+}
+
+/// Write the bytes `record` stands for to `dest`: the source file, or the single member it
+/// names inside an archive.
+fn copy_record_contents(
+    record: &FileRecord,
+    dest: &Path,
+    progress: Option<&ActionProgressHandle>,
+) -> anyhow::Result<()> {
+    if !is_copyable_member(record) {
+        return copy_file_with_progress(&record.source, dest, progress);
+    }
+    write_atomically(dest, |tmp| {
+        let mut writer = std::io::BufWriter::new(
+            fs::File::create(tmp).with_context(|| format!("creating {tmp:?} for copy"))?,
+        );
+        let written =
+            crate::archives::copy_archive_member(&record.source, &record.relative, &mut writer)?;
+        writer.flush()?;
+        if let Some(handle) = progress {
+            handle.report_bytes(written, Some(record.size.max(written)));
+        }
+        Ok(())
+    })
+}
+
+pub fn copy_record(
+    record: &FileRecord,
+    config: &Config,
+    dats: Option<&[crate::dat::DatRom]>,
+) -> anyhow::Result<PathBuf> {
+    let target = resolve_output_path_with_dats(record, config, dats);
+    ensure_parent(&target)?;
+
+    if target.exists() {
+        if !config.overwrite && !config.overwrite_invalid {
+            return Ok(target);
+        }
+    }
+
+    copy_record_contents(record, &target, None)
         .with_context(|| format!("copying {:?} to {:?}", record.source, target))?;
     Ok(target)
 }
 
+// New wrappers that accept dats and perform actions using DAT-aware path resolution.
+// Each returns the destination and whether it was written: an existing file kept for
+// want of `--overwrite` is not.
+pub fn copy_record_with_dats(
+    record: &FileRecord,
+    config: &Config,
+    dats: Option<&[crate::dat::DatRom]>,
+    progress: Option<&ActionProgressHandle>,
+) -> anyhow::Result<(PathBuf, bool)> {
+    let target = resolve_output_path_with_dats(record, config, dats);
+    ensure_parent(&target)?;
+
+    if target.exists() {
+        if !config.overwrite && !config.overwrite_invalid {
+            return Ok((target, false));
+        }
+    }
+
+    copy_record_contents(record, &target, progress)
+        .with_context(|| format!("copying {:?} to {:?}", record.source, target))?;
+    Ok((target, true))
+}
+
+/// The cross-device half of a move: copy `source` to `target`, carry over its permissions
+/// and (best effort, Unix only) its owner, then remove `source`.
+fn move_by_copy(
+    source: &Path,
+    target: &Path,
+    progress: Option<&ActionProgressHandle>,
+) -> anyhow::Result<()> {
+    copy_file_with_progress(source, target, progress)
+        .with_context(|| format!("copying {source:?} to {target:?}"))?;
+    let metadata = fs::metadata(source).with_context(|| format!("reading {source:?}"))?;
+    // Best effort like the chown below: some filesystems (e.g. SMB/FAT mounts) reject
+    // permission changes, and the data has already been copied.
+    let _ = fs::set_permissions(target, metadata.permissions());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        // Only root (or the owner, for the group) may chown; failing leaves the mover as owner.
+        let _ = std::os::unix::fs::chown(target, Some(metadata.uid()), Some(metadata.gid()));
+    }
+    fs::remove_file(source)
+        .with_context(|| format!("removing source after move fallback: {source:?}"))
+}
+
+/// Remove directories emptied by moving `record`, walking up from its parent but
+/// never removing the record's input root or anything above it. Without a known
+/// root only the immediate parent is tried.
+fn remove_empty_source_dirs(record: &FileRecord) {
+    let Some(parent) = record.source.parent() else {
+        return;
+    };
+    let Some(root) = record.input_root.as_deref() else {
+        let _ = fs::remove_dir(parent);
+        return;
+    };
+    for dir in parent.ancestors() {
+        if dir == root || !dir.starts_with(root) || fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
+
 pub fn move_record_with_dats(
     record: &FileRecord,
     config: &Config,
     dats: Option<&[crate::dat::DatRom]>,
     progress: Option<&ActionProgressHandle>,
-) -> anyhow::Result<PathBuf> {
+) -> anyhow::Result<(PathBuf, bool)> {
+    // The archive may hold other members still to be written, so a member is copied out
+    // and the archive left in place.
+    if is_copyable_member(record) {
+        return copy_record_with_dats(record, config, dats, progress);
+    }
     let target = resolve_output_path_with_dats(record, config, dats);
     ensure_parent(&target)?;
 
     if target.exists() && !config.overwrite {
-        return Ok(target);
+        return Ok((target, false));
     }
 
     if fs::rename(&record.source, &target).is_err() {
         // On cross-device moves fall back to copy + delete.
-        copy_file_with_progress(&record.source, &target, progress)
-            .with_context(|| format!("copying {:?} to {:?}", record.source, target))?;
-        fs::remove_file(&record.source)
-            .with_context(|| format!("removing source after move fallback: {:?}", record.source))?
+        move_by_copy(&record.source, &target, progress)?;
     }
 
     if matches!(
         config.move_delete_dirs,
         crate::types::MoveDeleteDirsMode::Always | crate::types::MoveDeleteDirsMode::Auto
     ) {
-        if let Some(parent) = record.source.parent() {
-            let _ = fs::remove_dir(parent);
-        }
+        remove_empty_source_dirs(record);
     }
 
-    Ok(target)
+    Ok((target, true))
+}
+
+/// Rename a loose input file in place to the file name of its matching DAT entry.
+/// Returns the new path, or `None` when the file already has that name, lives inside an
+/// archive, or another file already occupies the target name.
+pub fn rename_record_to_dat(
+    record: &FileRecord,
+    dat: &crate::dat::DatRom,
+) -> anyhow::Result<Option<PathBuf>> {
+    if !record.source.ends_with(&record.relative) {
+        return Ok(None);
+    }
+    let Some(dat_file_name) = Path::new(&dat.name).file_name() else {
+        return Ok(None);
+    };
+    let target = record
+        .source
+        .parent()
+        .map(|parent| parent.join(dat_file_name))
+        .unwrap_or_else(|| PathBuf::from(dat_file_name));
+
+    if target == record.source {
+        return Ok(None);
+    }
+
+    // case-only renames on case-insensitive filesystems resolve to the same file
+    let same_file = fs::canonicalize(&target).ok() == fs::canonicalize(&record.source).ok();
+    if target.exists() && !same_file {
+        vwarn!(
+            "not renaming {:?} to {:?}: target already exists",
+            record.source, target
+        );
+        return Ok(None);
+    }
+
+    fs::rename(&record.source, &target)
+        .with_context(|| format!("renaming {:?} to {:?}", record.source, target))?;
+    Ok(Some(target))
+}
+
+/// `--symlink-relative` link text for a symlink at `target` pointing at `source`. Both
+/// ends are canonicalized before diffing, so a relative `-i` path, `..` segments or a
+/// symlinked parent cannot skew how many levels the link climbs from a nested target.
+#[cfg(unix)]
+fn relative_link_source(source: &Path, target: &Path) -> PathBuf {
+    let source = fs::canonicalize(source).unwrap_or_else(|_| source.to_path_buf());
+    let parent = target.parent().unwrap_or(Path::new("."));
+    let parent = fs::canonicalize(parent).unwrap_or_else(|_| parent.to_path_buf());
+    pathdiff::diff_paths(&source, &parent).unwrap_or(source)
 }
 
 pub fn link_record_with_dats(
@@ -1272,7 +2520,11 @@ pub fn link_record_with_dats(
     config: &Config,
     dats: Option<&[crate::dat::DatRom]>,
     progress: Option<&ActionProgressHandle>,
-) -> anyhow::Result<PathBuf> {
+) -> anyhow::Result<(PathBuf, bool)> {
+    // A member has no file of its own to link to; it is copied out instead.
+    if is_copyable_member(record) {
+        return copy_record_with_dats(record, config, dats, progress);
+    }
     let target = resolve_output_path_with_dats(record, config, dats);
     ensure_parent(&target)?;
 
@@ -1291,11 +2543,7 @@ pub fn link_record_with_dats(
             {
                 use std::os::unix::fs::symlink;
                 let src = if config.symlink_relative {
-                    pathdiff::diff_paths(
-                        &record.source,
-                        target.parent().unwrap_or_else(|| std::path::Path::new(".")),
-                    )
-                    .unwrap_or(record.source.clone())
+                    relative_link_source(&record.source, &target)
                 } else {
                     record.source.clone()
                 };
@@ -1325,17 +2573,42 @@ pub fn link_record_with_dats(
         }
     }
 
-    Ok(target)
+    Ok((target, true))
 }
 
 fn record_is_extractable_archive(record: &FileRecord) -> bool {
-    let extension = record
-        .source
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .map(|s| s.to_ascii_lowercase());
+    is_archive_path(&record.source)
+}
+
+/// Check the stored CRCs of every zip/7z input archive (each archive once, however many
+/// records it contributed). Returns the number of archives checked and the corrupt ones.
+pub fn verify_archives(records: &[FileRecord]) -> (usize, Vec<(PathBuf, String)>) {
+    use rayon::prelude::*;
 
-    matches!(extension.as_deref(), Some("zip")) || looks_like_external_archive(&record.source)
+    let mut sources: Vec<&Path> = records
+        .iter()
+        .map(|record| record.source.as_path())
+        .filter(|source| {
+            source
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| {
+                    ext.eq_ignore_ascii_case("zip") || ext.eq_ignore_ascii_case("7z")
+                })
+        })
+        .collect();
+    sources.sort();
+    sources.dedup();
+
+    let corrupt = sources
+        .par_iter()
+        .filter_map(|source| match crate::archives::verify_archive(source) {
+            Ok(None) => None,
+            Ok(Some(problem)) => Some((source.to_path_buf(), problem)),
+            Err(e) => Some((source.to_path_buf(), format!("could not verify: {e}"))),
+        })
+        .collect();
+    (sources.len(), corrupt)
 }
 
 fn record_should_zip(record: &FileRecord, dats: Option<&[crate::dat::DatRom]>) -> bool {
@@ -1356,10 +2629,14 @@ pub fn extract_record_with_dats(
         return Ok(extracted);
     }
 
-    Ok(vec![copy_record_with_dats(record, config, dats, progress)?])
+    let (target, written) = copy_record_with_dats(record, config, dats, progress)?;
+    Ok(if written { vec![target] } else { Vec::new() })
 }
 
 pub fn move_record(record: &FileRecord, config: &Config) -> anyhow::Result<PathBuf> {
+    if is_copyable_member(record) {
+        return copy_record(record, config, None);
+    }
     let target = resolve_output_path(record, config);
     ensure_parent(&target)?;
 
@@ -1367,24 +2644,24 @@ pub fn move_record(record: &FileRecord, config: &Config) -> anyhow::Result<PathB
         return Ok(target);
     }
 
-    fs::rename(&record.source, &target).or_else(|_| {
-        fs::copy(&record.source, &target)?;
-        fs::remove_file(&record.source)
-    })?;
+    if fs::rename(&record.source, &target).is_err() {
+        move_by_copy(&record.source, &target, None)?;
+    }
 
     if matches!(
         config.move_delete_dirs,
         crate::types::MoveDeleteDirsMode::Always | crate::types::MoveDeleteDirsMode::Auto
     ) {
-        if let Some(parent) = record.source.parent() {
-            let _ = fs::remove_dir(parent);
-        }
+        remove_empty_source_dirs(record);
     }
 
     Ok(target)
 }
 
 pub fn link_record(record: &FileRecord, config: &Config) -> anyhow::Result<PathBuf> {
+    if is_copyable_member(record) {
+        return copy_record(record, config, None);
+    }
     let target = resolve_output_path(record, config);
     ensure_parent(&target)?;
 
@@ -1403,11 +2680,7 @@ pub fn link_record(record: &FileRecord, config: &Config) -> anyhow::Result<PathB
             {
                 use std::os::unix::fs::symlink;
                 let src = if config.symlink_relative {
-                    pathdiff::diff_paths(
-                        &record.source,
-                        target.parent().unwrap_or_else(|| std::path::Path::new(".")),
-                    )
-                    .unwrap_or(record.source.clone())
+                    relative_link_source(&record.source, &target)
                 } else {
                     record.source.clone()
                 };
@@ -1438,6 +2711,32 @@ pub fn extract_record(record: &FileRecord, config: &Config) -> anyhow::Result<Ve
     Ok(vec![copy_record(record, config, None)?])
 }
 
+/// `--extract-include` / `--extract-exclude` applied to an archive member's path.
+fn extract_member_filter(config: &Config) -> anyhow::Result<impl Fn(&Path) -> bool + use<>> {
+    let compile = |pattern: &Option<String>| -> anyhow::Result<Option<GlobMatcher>> {
+        pattern
+            .as_deref()
+            .map(|pattern| Ok(Glob::new(pattern)?.compile_matcher()))
+            .transpose()
+    };
+    let include = compile(&config.extract_include)?;
+    let exclude = compile(&config.extract_exclude)?;
+    Ok(move |member: &Path| {
+        include.as_ref().is_none_or(|glob| glob.is_match(member))
+            && !exclude.as_ref().is_some_and(|glob| glob.is_match(member))
+    })
+}
+
+/// `--zip-exclude` applied to a file's archive-relative path; true when it should be zipped.
+fn zip_member_filter(config: &Config) -> anyhow::Result<impl Fn(&FileRecord) -> bool + use<>> {
+    let exclude = build_globset(&config.zip_exclude)?;
+    Ok(move |record: &FileRecord| {
+        !exclude
+            .as_ref()
+            .is_some_and(|set| set.is_match(&record.relative))
+    })
+}
+
 fn try_extract_zip(
     record: &FileRecord,
     config: &Config,
@@ -1460,8 +2759,8 @@ fn try_extract_zip(
         Ok(archive) => archive,
         Err(err) => {
             if config.verbose > 0 {
-                eprintln!(
-                    "warning: {:?} has .zip extension but is not a zip archive: {}",
+                vwarn!(
+                    "{:?} has .zip extension but is not a zip archive: {}",
                     record.source, err
                 );
             }
@@ -1476,10 +2775,11 @@ fn try_extract_zip(
     } else {
         None
     };
+    let selected = extract_member_filter(config)?;
     let mut buf = vec![0u8; 1 << 20];
     for i in 0..archive.len() {
         let mut file = archive.by_index(i)?;
-        if file.is_dir() {
+        if file.is_dir() || !selected(Path::new(file.name())) {
             continue;
         }
 
@@ -1499,29 +2799,556 @@ fn try_extract_zip(
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
-        populate_locale_tokens(&mut entry_record);
+        populate_locale_tokens(&mut entry_record, config.region_map.as_ref());
 
         let out_path = resolve_output_path_with_dats(&entry_record, config, dats);
         ensure_parent(&out_path)?;
 
-        let mut output = fs::File::create(&out_path)?;
-        loop {
-            let read = file.read(&mut buf)?;
-            if read == 0 {
+        write_atomically(&out_path, |tmp| {
+            let mut output = fs::File::create(tmp)?;
+            loop {
+                let read = file.read(&mut buf)?;
+                if read == 0 {
+                    break;
+                }
+                output.write_all(&buf[..read])?;
+                aggregate = aggregate.saturating_add(read as u64);
+                if let Some(handle) = progress {
+                    handle.report_bytes(aggregate, total_hint);
+                }
+            }
+            output.flush()?;
+            Ok(())
+        })?;
+        written.push(out_path);
+    }
+
+    Ok(Some(written))
+}
+
+/// Files named by the `FILE` lines of a cue sheet, resolved next to the cue.
+fn cue_referenced_files(cue: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let text = fs::read_to_string(cue).with_context(|| format!("reading cue sheet {:?}", cue))?;
+    let dir = cue.parent().unwrap_or_else(|| Path::new(""));
+    let mut files = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        let Some(rest) = line
+            .get(..5)
+            .filter(|keyword| keyword.eq_ignore_ascii_case("FILE "))
+            .map(|_| line[5..].trim_start())
+        else {
+            continue;
+        };
+        let name = match rest.strip_prefix('"') {
+            Some(quoted) => quoted.split('"').next().unwrap_or(""),
+            None => rest
+                .rsplit_once(char::is_whitespace)
+                .map_or(rest, |(n, _)| n),
+        };
+        if !name.is_empty() {
+            files.push(dir.join(name));
+        }
+    }
+    Ok(files)
+}
+
+/// The cue and track files to delete once `cue` is converted: those this run wrote
+/// (`written`, canonical) under the canonical output `root`. Anything else a `FILE` line
+/// names is kept with a warning.
+fn converted_files_to_remove(
+    cue: &Path,
+    tracks: &[PathBuf],
+    root: Option<&Path>,
+    written: &HashSet<PathBuf>,
+) -> Vec<PathBuf> {
+    let mut remove = Vec::new();
+    for file in tracks
+        .iter()
+        .map(PathBuf::as_path)
+        .chain(std::iter::once(cue))
+    {
+        let Ok(resolved) = file.canonicalize() else {
+            continue;
+        };
+        if root.is_some_and(|root| resolved.starts_with(root)) && written.contains(&resolved) {
+            if resolved.is_file() && !remove.contains(&resolved) {
+                remove.push(resolved);
+            }
+        } else {
+            vwarn!(
+                "keeping {:?} named by {:?}: not a file this run wrote to the output",
+                file, cue
+            );
+        }
+    }
+    remove
+}
+
+/// Convert every `.cue` written to the output into a `.chd` beside it using
+/// `chdman createcd`, then remove the cue and its tracks. Only tracks this run wrote
+/// under the output root are removed; a `FILE` entry pointing anywhere else (an
+/// absolute path, `../`) is left alone with a warning. Returns the CHDs created, or
+/// `None` when `chdman` is not on PATH.
+fn convert_cue_sets_to_chd(
+    action: &Action,
+    records: &[FileRecord],
+    config: &Config,
+    dats: Option<&[crate::dat::DatRom]>,
+    progress: Option<&ProgressReporter>,
+) -> anyhow::Result<Option<Vec<PathBuf>>> {
+    let exe = match which("chdman") {
+        Ok(path) => path,
+        Err(_) => return Ok(None),
+    };
+
+    let targets: Vec<PathBuf> = records
+        .iter()
+        .map(|record| resolve_output_path_with_dats(record, config, dats))
+        .collect();
+    let written: HashSet<PathBuf> = targets
+        .iter()
+        .filter_map(|target| target.canonicalize().ok())
+        .collect();
+    let root = config
+        .output
+        .as_deref()
+        .map(crate::records::output_static_prefix)
+        .and_then(|root| root.canonicalize().ok());
+    let mut cues: Vec<PathBuf> = targets
+        .into_iter()
+        .filter(|target| {
+            target
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("cue"))
+                && target.is_file()
+        })
+        .collect();
+    cues.sort();
+    cues.dedup();
+
+    if let Some(p) = progress {
+        p.begin_action(action, cues.len());
+    }
+    let mut created = Vec::new();
+    for (idx, cue) in cues.iter().enumerate() {
+        let chd = cue.with_extension("chd");
+        if chd.exists() && !config.overwrite {
+            vprintln!(config.verbose, 1, "CHD exists, skipping {:?}", chd);
+        } else {
+            let tracks = cue_referenced_files(cue)?;
+            let status = Command::new(&exe)
+                .arg("createcd")
+                .arg("-i")
+                .arg(cue)
+                .arg("-o")
+                .arg(&chd)
+                .arg("-f")
+                .status()
+                .with_context(|| format!("converting {:?} via {:?}", cue, exe))?;
+            if !status.success() {
+                let _ = fs::remove_file(&chd);
+                anyhow::bail!("chdman failed to convert {:?} (status: {:?})", cue, status);
+            }
+            vprintln!(config.verbose, 1, "CHD {:?} -> {:?}", cue, chd);
+            for file in converted_files_to_remove(cue, &tracks, root.as_deref(), &written) {
+                fs::remove_file(&file).with_context(|| format!("removing converted {:?}", file))?;
+            }
+            created.push(chd);
+        }
+        if let Some(p) = progress {
+            p.advance_action(idx + 1, Some(cue));
+        }
+    }
+    if let Some(p) = progress {
+        p.finish_action(action);
+    }
+    Ok(Some(created))
+}
+
+/// Run the `--exec` command for a file written to `written`, replacing `{path}`, `{crc32}`
+/// and `{game}` in each argument. The program is started directly rather than through a
+/// shell, so file and game names can't inject commands.
+fn run_exec_hook(
+    record: &FileRecord,
+    written: &Path,
+    config: &Config,
+    dats: &[crate::dat::DatRom],
+) -> Option<ExecFailure> {
+    let template = crate::utils::split_command_line(config.exec.as_deref()?).ok()?;
+    let path = written.to_string_lossy();
+    let crc32 = record.checksums.crc32.clone().unwrap_or_default();
+    let game = crate::dat::find_dat_for_record(record, dats)
+        .map(|rom| rom.description.unwrap_or(rom.name))
+        .or_else(|| Some(written.file_stem()?.to_string_lossy().into_owned()))
+        .unwrap_or_default();
+    let values = [
+        ("{path}", path.as_ref()),
+        ("{crc32}", &crc32),
+        ("{game}", &game),
+    ];
+    let command: Vec<String> = template
+        .iter()
+        .map(|arg| substitute_placeholders(arg, &values))
+        .collect();
+    let (program, args) = command.split_first()?;
+    let error = match Command::new(program).args(args).status() {
+        Ok(status) if status.success() => return None,
+        Ok(status) => format!("exited with {status}"),
+        Err(err) => format!("failed to start: {err}"),
+    };
+    Some(ExecFailure {
+        path: written.to_path_buf(),
+        command,
+        error,
+    })
+}
+
+/// Replace each placeholder in one pass, so substituted values are never expanded again.
+fn substitute_placeholders(arg: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(arg.len());
+    let mut rest = arg;
+    'scan: while !rest.is_empty() {
+        for (placeholder, value) in values {
+            if let Some(after) = rest.strip_prefix(placeholder) {
+                out.push_str(value);
+                rest = after;
+                continue 'scan;
+            }
+        }
+        let mut chars = rest.chars();
+        out.extend(chars.next());
+        rest = chars.as_str();
+    }
+    out
+}
+
+/// Apply every `-p` patch to the outputs of the ROM it targets (matched by the CRC32 in
+/// the patch file name), writing the patched copy beside the output under the patch's
+/// name. Outputs that are not loose files, such as zip members, are left unpatched.
+/// Returns the patched files written.
+fn apply_patches_to_outputs(
+    records: &[FileRecord],
+    config: &Config,
+    dats: &[crate::dat::DatRom],
+    progress: Option<&ProgressReporter>,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let patches = crate::patch::load_patches(config)?;
+    let mut written = Vec::new();
+    for patch in &patches {
+        let Some(crc) = crate::patch::patch_target_crc32(patch) else {
+            vwarn!(
+                "skipping patch {:?}: its file name names no CRC32 to apply it to",
+                patch.path
+            );
+            continue;
+        };
+        let targets: Vec<PathBuf> = records
+            .iter()
+            .filter(|record| {
+                record
+                    .checksums
+                    .crc32
+                    .as_deref()
+                    .is_some_and(|value| value.eq_ignore_ascii_case(&crc))
+            })
+            .map(|record| resolve_output_path_with_dats(record, config, Some(dats)))
+            .filter(|target| target.is_file())
+            .collect();
+        for target in targets {
+            let source = fs::read(&target).with_context(|| format!("reading {:?}", target))?;
+            let Some(patched) = crate::patch_apply::apply_patch_to_bytes_with_progress(
+                &patch.path,
+                &source,
+                progress,
+            )
+            .with_context(|| format!("applying patch {:?}", patch.path))?
+            else {
+                vwarn!(
+                    "skipping patch {:?}: {} patches are not supported yet",
+                    patch.path, patch.ext
+                );
                 break;
+            };
+            let dest = target.with_file_name(crate::patch::patched_file_name(patch, &target));
+            if dest.exists() && !config.overwrite {
+                vprintln!(
+                    config.verbose,
+                    1,
+                    "Patched file exists, skipping {:?}",
+                    dest
+                );
+                continue;
             }
-            output.write_all(&buf[..read])?;
-            aggregate = aggregate.saturating_add(read as u64);
-            if let Some(handle) = progress {
-                handle.report_bytes(aggregate, total_hint);
+            write_atomically(&dest, |tmp| Ok(fs::write(tmp, &patched)?))?;
+            vprintln!(
+                config.verbose,
+                1,
+                "PATCH {:?} + {:?} -> {:?}",
+                target,
+                patch.path,
+                dest
+            );
+            written.push(dest);
+        }
+    }
+    if let Some(p) = progress {
+        p.finish_background_task(BackgroundTask::Patch);
+    }
+    Ok(written)
+}
+
+/// Note appended to the copy/move outcome describing the `-p` patch pass.
+fn patch_note(
+    records: &[FileRecord],
+    config: &Config,
+    dats: &[crate::dat::DatRom],
+    progress: Option<&ProgressReporter>,
+) -> anyhow::Result<String> {
+    let written = apply_patches_to_outputs(records, config, dats, progress)?;
+    Ok(format!("; wrote {} patched file(s)", written.len()))
+}
+
+/// Note appended to the copy/move outcome describing the `--to-chd` pass.
+fn chd_conversion_note(
+    action: &Action,
+    records: &[FileRecord],
+    config: &Config,
+    dats: &[crate::dat::DatRom],
+    progress: Option<&ProgressReporter>,
+) -> anyhow::Result<String> {
+    match convert_cue_sets_to_chd(action, records, config, Some(dats), progress)? {
+        Some(created) => Ok(format!("; converted {} cue set(s) to CHD", created.len())),
+        None => {
+            let message = "--to-chd skipped: chdman was not found on PATH".to_string();
+            match progress {
+                Some(p) => p.log_summary(message.clone()),
+                None => vwarn!("{message}"),
             }
+            Ok(format!("; {message}"))
         }
-        output.flush()?;
-        written.push(out_path);
     }
+}
 
-    Ok(Some(written))
+/// The DAT sets (games listing several ROMs) found only in part, as `(set, missing ROMs)`,
+/// for `--allow-incomplete-sets`. Sets none of whose ROMs matched are left out.
+fn incomplete_sets(
+    records: &[FileRecord],
+    dats: &[crate::dat::DatRom],
+    matched: &[crate::dat::DatRom],
+    config: &Config,
+) -> Vec<(String, Vec<String>)> {
+    let matched: HashSet<&str> = matched.iter().map(|rom| rom.name.as_str()).collect();
+    let mut sets = dat_sets(dats);
+    sets.retain(|_, parts| parts.iter().any(|part| matched.contains(part.as_str())));
+    let mut incomplete: Vec<(String, Vec<String>)> =
+        build_write_candidates(&sets, &dat_rom_tuples(dats), records, config)
+            .into_iter()
+            .filter(|candidate| !candidate.missing.is_empty())
+            .map(|candidate| (candidate.name, candidate.missing))
+            .collect();
+    incomplete.sort();
+    incomplete
+}
+
+/// A multi-disc game `--merge-discs` writes into one folder named after the game.
+struct DiscSet {
+    title: String,
+    /// Members after renaming, ordered by disc number.
+    members: Vec<(u32, FileRecord)>,
+}
+
+/// Merge the multi-disc games among the multi-file sets [`build_write_candidates`] finds
+/// (see [`group_sets`]): sets whose parts span two or more disc numbers. The records
+/// chosen for the discs get an output name of `<title>/<title> (Disc N)<rest>.<ext>`. With
+/// DATs the games are the DAT games, and other records the DAT matched to the same disc
+/// join it; without DATs each file is a game named after it.
+fn merge_disc_sets(
+    records: &mut [FileRecord],
+    dats: &[crate::dat::DatRom],
+    dat_index: &DatIndex,
+    config: &Config,
+) -> Vec<DiscSet> {
+    // The DAT ROM name each record matched; without DATs every record stands for itself.
+    let parts: Vec<Option<String>> = records
+        .iter()
+        .map(|record| {
+            if dats.is_empty() {
+                Some(disc_part_name(record))
+            } else {
+                find_dat_for_record_with_index(record, dats, dat_index).map(|rom| rom.name)
+            }
+        })
+        .collect();
+    let (entries, tuples): (Vec<(String, String)>, Vec<DatRomTuple>) = if dats.is_empty() {
+        records
+            .iter()
+            .zip(&parts)
+            .filter_map(|(record, part)| {
+                let part = part.clone()?;
+                let game = Path::new(&part).file_stem()?.to_str()?.to_string();
+                let checksums = &record.checksums;
+                let tuple = (
+                    part.clone(),
+                    checksums.crc32.clone(),
+                    checksums.md5.clone(),
+                    checksums.sha1.clone(),
+                    Some(record.size),
+                );
+                Some(((game, part), tuple))
+            })
+            .unzip()
+    } else {
+        (
+            dats.iter()
+                .map(|rom| (dat_game(rom), rom.name.clone()))
+                .collect(),
+            dat_rom_tuples(dats),
+        )
+    };
+    let disc_of: HashMap<&str, u32> = entries
+        .iter()
+        .filter_map(|(game, part)| Some((part.as_str(), split_disc_tag(game)?.1)))
+        .collect();
+    let found: HashSet<&str> = parts.iter().flatten().map(String::as_str).collect();
+    let mut sets = group_sets(entries.iter().cloned());
+    sets.retain(|_, names| {
+        let numbers: HashSet<u32> = names
+            .iter()
+            .filter_map(|name| disc_of.get(name.as_str()).copied())
+            .collect();
+        numbers.len() >= 2 && names.iter().any(|name| found.contains(name.as_str()))
+    });
+    if sets.is_empty() {
+        return Vec::new();
+    }
+
+    let mut merged = Vec::new();
+    for candidate in build_write_candidates(&sets, &tuples, records, config) {
+        let mut discs: Vec<(u32, String, String)> = Vec::new();
+        for (part, chosen) in &candidate.files_map {
+            let Some(&number) = disc_of.get(part.as_str()) else {
+                continue;
+            };
+            let Some(renamed) = disc_file_name(part) else {
+                continue;
+            };
+            // A title match alone does not make a record one of the discs.
+            let matched = records.iter().zip(&parts).any(|(record, name)| {
+                record.identity() == chosen.identity() && name.as_ref() == Some(part)
+            });
+            if !matched {
+                continue;
+            }
+            discs.push((number, renamed, part.clone()));
+        }
+        let numbers: HashSet<u32> = discs.iter().map(|(number, _, _)| *number).collect();
+        if numbers.len() < 2 {
+            continue;
+        }
+        discs.sort();
+        let title = candidate.name;
+        let mut members = Vec::new();
+        for (number, renamed, part) in discs {
+            for idx in 0..records.len() {
+                if parts[idx].as_ref() == Some(&part) {
+                    records[idx].output_name = Some(format!("{title}/{renamed}"));
+                    members.push((number, records[idx].clone()));
+                }
+            }
+        }
+        merged.push(DiscSet { title, members });
+    }
+    merged.sort_by(|a, b| a.title.cmp(&b.title));
+    merged
+}
+
+/// The name a record goes by as a disc when no DAT names it.
+fn disc_part_name(record: &FileRecord) -> String {
+    match &record.output_name {
+        Some(name) => name.clone(),
+        None => record.relative.to_string_lossy().to_string(),
+    }
+}
+
+/// The file name of a merged disc: files of a disc (a cue and its tracks) keep their names
+/// apart from a uniform `(Disc N)` tag.
+fn disc_file_name(part: &str) -> Option<String> {
+    let name = Path::new(part);
+    let file_name = name.file_name()?.to_str()?;
+    let renamed = match name
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .and_then(split_disc_tag)
+    {
+        Some((file_title, file_number, rest)) => match name.extension().and_then(|e| e.to_str()) {
+            Some(ext) => format!("{file_title} (Disc {file_number}){rest}.{ext}"),
+            None => format!("{file_title} (Disc {file_number}){rest}"),
+        },
+        None => file_name.to_string(),
+    };
+    Some(renamed)
+}
+
+/// Where [`write_disc_playlists`] puts the `.m3u` of `set`: beside its discs.
+fn disc_playlist_path(
+    set: &DiscSet,
+    config: &Config,
+    dats: &[crate::dat::DatRom],
+) -> Option<PathBuf> {
+    let (_, first) = set.members.first()?;
+    let target = resolve_output_path_with_dats(first, config, Some(dats));
+    Some(target.parent()?.join(format!("{}.m3u", set.title)))
+}
+
+/// Write `<title>.m3u` into the folder of every merged disc set, listing one file per
+/// disc (a cue/gdi sheet when the disc has one). Returns the note for the action outcome.
+fn write_disc_playlists(
+    sets: &[DiscSet],
+    config: &Config,
+    dats: &[crate::dat::DatRom],
+) -> anyhow::Result<String> {
+    let mut written = 0;
+    for set in sets {
+        let mut any_written = false;
+        let mut entries: Vec<(u32, String)> = Vec::new();
+        for (number, record) in &set.members {
+            let target = resolve_output_path_with_dats(record, config, Some(dats));
+            if fs::symlink_metadata(&target).is_err() {
+                continue;
+            }
+            let Some(name) = target.file_name().map(|n| n.to_string_lossy().to_string()) else {
+                continue;
+            };
+            any_written = true;
+            let is_sheet = Path::new(&name)
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case("cue") || e.eq_ignore_ascii_case("gdi"));
+            match entries.iter_mut().find(|(n, _)| n == number) {
+                Some(entry) if is_sheet => entry.1 = name,
+                Some(_) => {}
+                None => entries.push((*number, name)),
+            }
+        }
+        let Some(playlist) = disc_playlist_path(set, config, dats).filter(|_| any_written) else {
+            continue;
+        };
+        let contents: String = entries
+            .iter()
+            .map(|(_, name)| format!("{name}\n"))
+            .collect();
+        write_atomically(&playlist, |tmp| Ok(fs::write(tmp, &contents)?))?;
+        written += 1;
+    }
+    Ok(format!("; wrote {written} multi-disc playlist(s)"))
 }
 
 fn try_extract_with_7z(
@@ -1538,8 +3365,8 @@ fn try_extract_with_7z(
         Ok(path) => path,
         Err(_) => {
             if config.verbose > 0 {
-                eprintln!(
-                    "warning: {:?} appears to be an archive but 7z is not available on PATH",
+                vwarn!(
+                    "{:?} appears to be an archive but 7z is not available on PATH",
                     record.source
                 );
             }
@@ -1558,8 +3385,8 @@ fn try_extract_with_7z(
 
     if !status.success() {
         if config.verbose > 0 {
-            eprintln!(
-                "warning: failed to extract {:?} via {:?} (status: {:?})",
+            vwarn!(
+                "failed to extract {:?} via {:?} (status: {:?})",
                 record.source, exe, status
             );
         }
@@ -1573,6 +3400,7 @@ fn try_extract_with_7z(
     } else {
         None
     };
+    let selected = extract_member_filter(config)?;
     let mut buf = vec![0u8; 1 << 20];
     for entry in WalkDir::new(tmp.path())
         .into_iter()
@@ -1584,6 +3412,9 @@ fn try_extract_with_7z(
             .strip_prefix(tmp.path())
             .unwrap_or_else(|_| entry.path())
             .to_path_buf();
+        if !selected(&rel) {
+            continue;
+        }
         let metadata = entry.metadata()?;
 
         let mut entry_record = FileRecord {
@@ -1602,68 +3433,61 @@ fn try_extract_with_7z(
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
-        populate_locale_tokens(&mut entry_record);
+        populate_locale_tokens(&mut entry_record, config.region_map.as_ref());
 
         let out_path = resolve_output_path_with_dats(&entry_record, config, dats);
         ensure_parent(&out_path)?;
         let mut reader = fs::File::open(entry.path())?;
-        let mut writer = fs::File::create(&out_path)?;
-        loop {
-            let read = reader.read(&mut buf)?;
-            if read == 0 {
-                break;
-            }
-            writer.write_all(&buf[..read])?;
-            aggregate = aggregate.saturating_add(read as u64);
-            if let Some(handle) = progress {
-                handle.report_bytes(aggregate, total_hint);
+        write_atomically(&out_path, |tmp| {
+            let mut writer = fs::File::create(tmp)?;
+            loop {
+                let read = reader.read(&mut buf)?;
+                if read == 0 {
+                    break;
+                }
+                writer.write_all(&buf[..read])?;
+                aggregate = aggregate.saturating_add(read as u64);
+                if let Some(handle) = progress {
+                    handle.report_bytes(aggregate, total_hint);
+                }
             }
-        }
-        writer.flush()?;
+            writer.flush()?;
+            Ok(())
+        })?;
         written.push(out_path);
     }
 
     Ok(Some(written))
 }
 
-fn looks_like_external_archive(path: &Path) -> bool {
-    let name = path
-        .file_name()
-        .and_then(|s| s.to_str())
-        .map(|s| s.to_ascii_lowercase())
-        .unwrap_or_default();
-
-    const SUFFIXES: &[&str] = &[
-        ".7z",
-        ".rar",
-        ".tar",
-        ".tar.gz",
-        ".tgz",
-        ".tar.bz2",
-        ".tbz",
-        ".tbz2",
-        ".tar.xz",
-        ".txz",
-        ".tar.zst",
-        ".tzst",
-        ".tar.lz",
-        ".tar.lzma",
-        ".tlz",
-    ];
-
-    SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
-}
-
 pub fn zip_record(
     record: &FileRecord,
     config: &Config,
     dats: Option<&[crate::dat::DatRom]>,
     progress: Option<&ActionProgressHandle>,
 ) -> anyhow::Result<PathBuf> {
+    zip_record_hashed(record, config, dats, progress).map(|(target, _)| target)
+}
+
+/// [`zip_record`], also returning the SHA256 of the zipped ROM when the run computes SHA256
+/// (e.g. `--input-checksum-max sha256`). It is hashed while the entry is written; a scanned
+/// SHA256 that disagrees fails the write before the archive replaces the target.
+fn zip_record_hashed(
+    record: &FileRecord,
+    config: &Config,
+    dats: Option<&[crate::dat::DatRom]>,
+    progress: Option<&ActionProgressHandle>,
+) -> anyhow::Result<(PathBuf, Option<String>)> {
     let target = resolve_output_path_with_dats(record, config, dats).with_extension("zip");
     ensure_parent(&target)?;
-
+    let stream_sha256 = crate::checksum::checksum_targets(config).contains(&Checksum::Sha256);
     // choose implementation based on format
     if matches!(
         config.zip_format,
@@ -1679,46 +3503,104 @@ pub fn zip_record(
             .and_then(|n| n.to_str())
             .unwrap_or("rom.bin");
         let srcs: Vec<(&Path, &str)> = vec![(record.source.as_path(), filename_in_zip)];
-        crate::torrentzip_zip64::write_torrentzip_zip64(
-            &srcs,
-            &target,
-            config.zip_format.clone(),
-            progress,
-        )?;
-        Ok(target)
+        let sha256 = write_atomically(&target, |tmp| {
+            let sha256 = if stream_sha256 {
+                let digest = crate::torrentzip_zip64::write_torrentzip_zip64_with_sha256(
+                    &srcs,
+                    tmp,
+                    config.zip_format.clone(),
+                    progress,
+                )?;
+                verify_streamed_sha256(record, &digest)?;
+                Some(digest)
+            } else {
+                crate::torrentzip_zip64::write_torrentzip_zip64(
+                    &srcs,
+                    tmp,
+                    config.zip_format.clone(),
+                    progress,
+                )?;
+                None
+            };
+            apply_zip_comment(tmp, config)?;
+            Ok(sha256)
+        })?;
+        Ok((target, sha256))
     } else {
         // fallback: simple zip using zip crate
-        let mut file = fs::File::create(&target)?;
-        let mut zip = zip::ZipWriter::new(&mut file);
-        let options: FileOptions<'_, zip::write::ExtendedFileOptions> =
-            FileOptions::default().compression_method(zip::CompressionMethod::Stored);
-
-        let mut input = fs::File::open(&record.source)?;
-        zip.start_file(
-            record
-                .relative
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("rom.bin"),
-            options,
-        )?;
-        let total = fs::metadata(&record.source).map(|m| m.len()).unwrap_or(record.size);
-        let mut buf = vec![0u8; 1 << 20];
-        let mut written = 0u64;
-        loop {
-            let n = input.read(&mut buf)?;
-            if n == 0 {
-                break;
-            }
-            zip.write_all(&buf[..n])?;
-            written = written.saturating_add(n as u64);
-            if let Some(handle) = progress {
-                handle.report_bytes(written, Some(total));
+        let sha256 = write_atomically(&target, |tmp| {
+            let mut file = fs::File::create(tmp)?;
+            let mut zip = zip::ZipWriter::new(&mut file);
+            let options: FileOptions<'_, zip::write::ExtendedFileOptions> =
+                FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+
+            let mut input = fs::File::open(&record.source)?;
+            zip.start_file(
+                record
+                    .relative
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("rom.bin"),
+                options,
+            )?;
+            let total = fs::metadata(&record.source)
+                .map(|m| m.len())
+                .unwrap_or(record.size);
+            let mut buf = vec![0u8; 1 << 20];
+            let mut written = 0u64;
+            let mut hasher = stream_sha256.then(Sha256::new);
+            loop {
+                let n = input.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                zip.write_all(&buf[..n])?;
+                if let Some(hasher) = hasher.as_mut() {
+                    hasher.update(&buf[..n]);
+                }
+                written = written.saturating_add(n as u64);
+                if let Some(handle) = progress {
+                    handle.report_bytes(written, Some(total));
+                }
             }
-        }
-        zip.finish()?;
+            zip.finish()?;
+            let sha256 = hasher.map(|h| hex::encode(h.finalize()));
+            if let Some(digest) = &sha256 {
+                verify_streamed_sha256(record, digest)?;
+            }
+            apply_zip_comment(tmp, config)?;
+            Ok(sha256)
+        })?;
+
+        Ok((target, sha256))
+    }
+}
+
+/// Compare the SHA256 hashed while zipping with the one the scan recorded, if any. A scan
+/// that left out a detected header hashed fewer bytes than the zip holds, so there is
+/// nothing to compare.
+fn verify_streamed_sha256(record: &FileRecord, digest: &str) -> anyhow::Result<()> {
+    if record
+        .scan_info
+        .as_ref()
+        .is_some_and(|info| info.header_size.is_some())
+    {
+        return Ok(());
+    }
+    match record.checksums.sha256.as_deref() {
+        Some(scanned) if !scanned.eq_ignore_ascii_case(digest) => Err(anyhow!(
+            "zip verification failed for {}: wrote SHA256 {digest}, scanned {scanned}",
+            record.source.display()
+        )),
+        _ => Ok(()),
+    }
+}
 
-        Ok(target)
+/// Stamp `--zip-comment` onto a freshly written archive.
+fn apply_zip_comment(path: &Path, config: &Config) -> anyhow::Result<()> {
+    match config.zip_comment.as_deref() {
+        Some(comment) => crate::torrentzip::set_zip_comment(path, comment),
+        None => Ok(()),
     }
 }
 
@@ -1741,7 +3623,15 @@ pub fn zip_records(records: &[FileRecord], config: &Config) -> anyhow::Result<Pa
         })
         .collect();
 
-    crate::torrentzip_zip64::write_torrentzip_zip64(&srcs, &target, config.zip_format.clone(), None)?;
+    write_atomically(&target, |tmp| {
+        crate::torrentzip_zip64::write_torrentzip_zip64(
+            &srcs,
+            tmp,
+            config.zip_format.clone(),
+            None,
+        )?;
+        apply_zip_comment(tmp, config)
+    })?;
     Ok(target)
 }
 
@@ -1753,15 +3643,68 @@ pub fn playlist(records: &[FileRecord], config: &Config) -> anyhow::Result<PathB
     target.push("playlist.m3u");
     ensure_parent(&target)?;
 
-    let mut file = fs::File::create(&target)?;
-    for record in records {
-        writeln!(file, "{}", record.relative.to_string_lossy())?;
-    }
+    write_atomically(&target, |tmp| {
+        let mut file = fs::File::create(tmp)?;
+        for record in records {
+            writeln!(file, "{}", record.relative.to_string_lossy())?;
+        }
+        Ok(())
+    })?;
 
     Ok(target)
 }
 
-pub fn write_report(records: &[FileRecord], config: &Config) -> anyhow::Result<PathBuf> {
+/// Compute the full checksum set used by the report action. With `--skip-existing-checksum`
+/// the cache is consulted first (keyed by path, size and mtime) and fresh results are stored
+/// back. Returns the checksums and whether the file actually had to be hashed.
+fn report_checksums(
+    path: &Path,
+    config: &Config,
+    cache: Option<&cache::Cache>,
+) -> anyhow::Result<(ChecksumSet, bool)> {
+    use crate::checksum::compute_all_checksums;
+
+    let stamp = if config.skip_existing_checksum {
+        cache.and_then(|c| {
+            let metadata = fs::metadata(path).ok()?;
+            let mtime = metadata
+                .modified()
+                .ok()?
+                .duration_since(std::time::UNIX_EPOCH)
+                .ok()?
+                .as_nanos() as i64;
+            Some((c, metadata.len(), mtime))
+        })
+    } else {
+        None
+    };
+
+    let cached = stamp.and_then(|(c, size, mtime)| {
+        c.get_checksums_by_path(path, size, mtime).ok().flatten()
+    });
+    if let Some(cached) = cached {
+        vprintln!(
+            config.verbose,
+            2,
+            "CACHE-HIT report checksums: {}",
+            path.to_string_lossy()
+        );
+        return Ok((cached, false));
+    }
+
+    let computed = compute_all_checksums(path)?;
+    if let Some((c, size, mtime)) = stamp {
+        let _ = c.set_checksums_by_path(path, size, mtime, &computed);
+    }
+    Ok((computed, true))
+}
+
+pub fn write_report(
+    records: &[FileRecord],
+    config: &Config,
+    cache: Option<&cache::Cache>,
+    dats: &[crate::dat::DatRom],
+) -> anyhow::Result<PathBuf> {
     let mut target = config
         .output
         .clone()
@@ -1770,7 +3713,6 @@ pub fn write_report(records: &[FileRecord], config: &Config) -> anyhow::Result<P
     ensure_parent(&target)?;
 
     // Ensure checksums present: compute md5 and crc32 (and sha256) for each record if missing
-    use crate::checksum::compute_all_checksums;
     use crate::dat::{OnlineMatch, query_hasheous, query_igdb};
 
     let mut enriched = Vec::new();
@@ -1798,16 +3740,26 @@ pub fn write_report(records: &[FileRecord], config: &Config) -> anyhow::Result<P
     for (idx, rec) in records.iter().enumerate() {
         let mut rec = rec.clone();
         let mut attempted_slug_lookups: HashSet<String> = HashSet::new();
-        let all = compute_all_checksums(&rec.source).ok();
-        if let Some(a) = all {
-            if rec.checksums.crc32.is_none() {
-                rec.checksums.crc32 = a.crc32.clone();
-            }
-            if rec.checksums.md5.is_none() {
-                rec.checksums.md5 = a.md5.clone();
-            }
-            if rec.checksums.sha256.is_none() {
-                rec.checksums.sha256 = a.sha256.clone();
+        // Hashing `source` would describe the whole archive, not the member.
+        let missing = rec.checksums.crc32.is_none()
+            || rec.checksums.md5.is_none()
+            || rec.checksums.sha256.is_none();
+        let all = (missing && rec.source.ends_with(&rec.relative))
+            .then(|| report_checksums(&rec.source, config, cache).ok())
+            .flatten();
+        // Only rows that took a value from `report_checksums` say where it came from.
+        let mut checksum_source = None;
+        if let Some((a, hashed)) = all {
+            let origin = if hashed { "computed" } else { "cache" };
+            for (slot, value) in [
+                (&mut rec.checksums.crc32, a.crc32),
+                (&mut rec.checksums.md5, a.md5),
+                (&mut rec.checksums.sha256, a.sha256),
+            ] {
+                if slot.is_none() && value.is_some() {
+                    *slot = value;
+                    checksum_source = Some(origin);
+                }
             }
         }
 
@@ -2084,8 +4036,14 @@ pub fn write_report(records: &[FileRecord], config: &Config) -> anyhow::Result<P
         }
 
         if hasheous_res.is_some() || igdb_res.is_some() {
+            // Loose files keep their input-relative name; members name their archive too.
+            let name = if rec.source.ends_with(&rec.relative) {
+                rec.relative.to_string_lossy().to_string()
+            } else {
+                display_path(&rec)
+            };
             online_matches.push(OnlineMatch {
-                name: rec.relative.to_string_lossy().to_string(),
+                name,
                 source_dat: None,
                 source_path: Some(rec.source.clone()),
                 hasheous: hasheous_res,
@@ -2093,11 +4051,35 @@ pub fn write_report(records: &[FileRecord], config: &Config) -> anyhow::Result<P
             });
         }
 
-        enriched.push(rec);
+        enriched.push((rec, checksum_source));
     }
 
-    // write report and online_matches
-    let json = serde_json::to_string_pretty(&enriched)?;
+    // write report and online_matches; `path` keeps archive members distinct from loose files,
+    // `checksum_source` tells whether the file was rehashed or served from the cache, and
+    // `serial`/`version`/`region` come from the DAT game the file matched (the region falls
+    // back to the one in its file name)
+    let dat_index = DatIndex::from_dats(dats);
+    let mut rows = Vec::with_capacity(enriched.len());
+    for (rec, checksum_source) in &enriched {
+        let mut row = serde_json::to_value(rec)?;
+        row["path"] = serde_json::Value::String(display_path(rec));
+        if let Some(checksum_source) = checksum_source {
+            row["checksum_source"] = serde_json::Value::String(checksum_source.to_string());
+        }
+        let dat = find_dat_for_record_with_index(rec, dats, &dat_index);
+        let (serial, version, region) = match dat {
+            Some(dat) => (dat.serial, dat.version, dat.region),
+            None => (None, None, None),
+        };
+        let region = region.or_else(|| rec.derived_region.clone());
+        for (key, value) in [("serial", serial), ("version", version), ("region", region)] {
+            if let Some(value) = value {
+                row[key] = serde_json::Value::String(value);
+            }
+        }
+        rows.push(row);
+    }
+    let json = serde_json::to_string_pretty(&rows)?;
     fs::write(&target, json)?;
 
     // Transform online_matches into a compact mapping of filename -> extracted metadata IDs
@@ -2168,9 +4150,38 @@ pub fn write_report(records: &[FileRecord], config: &Config) -> anyhow::Result<P
     let om_json = serde_json::to_string_pretty(&compact)?;
     fs::write(&om_target, om_json)?;
 
+    if config.report_duplicates {
+        let dup_json = serde_json::to_string_pretty(&duplicate_groups(records))?;
+        fs::write(base_out.join("duplicates.json"), dup_json)?;
+    }
+
     Ok(target)
 }
 
+/// Group records by their strongest checksum, keeping only groups with more than one copy.
+/// Groups are ordered by checksum and files by path so the report is stable between runs.
+pub fn duplicate_groups(records: &[FileRecord]) -> Vec<DuplicateGroup> {
+    let mut groups: std::collections::BTreeMap<String, Vec<DuplicateFile>> =
+        std::collections::BTreeMap::new();
+    for record in records {
+        if let Some(key) = best_checksum_key(&record.checksums) {
+            groups.entry(key).or_default().push(DuplicateFile {
+                source: record.source.clone(),
+                relative: record.relative.clone(),
+                size: record.size,
+            });
+        }
+    }
+    groups
+        .into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .map(|(checksum, mut files)| {
+            files.sort_by(|a, b| (&a.source, &a.relative).cmp(&(&b.source, &b.relative)));
+            DuplicateGroup { checksum, files }
+        })
+        .collect()
+}
+
 pub fn write_dir2dat(records: &[FileRecord], config: &Config) -> anyhow::Result<PathBuf> {
     let mut target = config
         .output
@@ -2193,7 +4204,10 @@ pub fn write_fixdat(
         .output
         .clone()
         .unwrap_or_else(|| PathBuf::from("output"));
-    target.push("fixdat.json");
+    target.push(match config.fixdat_format {
+        Some(_) => "fixdat.dat",
+        None => "fixdat.json",
+    });
     ensure_parent(&target)?;
 
     let mut missing = Vec::new();
@@ -2203,23 +4217,702 @@ pub fn write_fixdat(
         }
     }
 
-    let json = serde_json::to_string_pretty(&missing)?;
-    fs::write(&target, json)?;
+    let contents = match config.fixdat_format {
+        None => serde_json::to_string_pretty(&missing)?,
+        Some(FixdatFormat::Logiqx) => fixdat_logiqx(&fixdat_games(&missing)),
+        Some(FixdatFormat::Clrmamepro) => fixdat_clrmamepro(&fixdat_games(&missing)),
+    };
+    fs::write(&target, contents)?;
     Ok(target)
 }
 
+/// A fixdat game: its name and the missing ROM records it lists.
+type FixdatGame<'a> = (String, Vec<&'a FileRecord>);
+
+/// Group missing records into fixdat games keyed by file stem, sorted for stable output.
+fn fixdat_games<'a>(missing: &[&'a FileRecord]) -> Vec<FixdatGame<'a>> {
+    let mut games: std::collections::BTreeMap<String, Vec<&FileRecord>> =
+        std::collections::BTreeMap::new();
+    for record in missing {
+        let name = record
+            .relative
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| record.relative.to_string_lossy().to_string());
+        games.entry(name).or_default().push(record);
+    }
+    games.into_iter().collect()
+}
+
+fn fixdat_rom_name(record: &FileRecord) -> String {
+    record
+        .relative
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| record.relative.to_string_lossy().to_string())
+}
+
+fn fixdat_logiqx(games: &[FixdatGame]) -> String {
+    use quick_xml::escape::escape;
+
+    let mut out = String::from("<?xml version=\"1.0\"?>\n");
+    out.push_str("<!DOCTYPE datafile PUBLIC \"-//Logiqx//DTD ROM Management Datafile//EN\" \"http://www.logiqx.com/Dats/datafile.dtd\">\n");
+    out.push_str("<datafile>\n\t<header>\n\t\t<name>fixdat</name>\n\t\t<description>igir fixdat</description>\n\t</header>\n");
+    for (name, roms) in games {
+        let name = escape(name.as_str());
+        out.push_str(&format!(
+            "\t<game name=\"{name}\">\n\t\t<description>{name}</description>\n"
+        ));
+        for rom in roms {
+            out.push_str(&format!(
+                "\t\t<rom name=\"{}\" size=\"{}\"",
+                escape(fixdat_rom_name(rom).as_str()),
+                rom.size
+            ));
+            for (key, value) in fixdat_checksum_fields(&rom.checksums) {
+                out.push_str(&format!(" {key}=\"{value}\""));
+            }
+            out.push_str("/>\n");
+        }
+        out.push_str("\t</game>\n");
+    }
+    out.push_str("</datafile>\n");
+    out
+}
+
+fn fixdat_clrmamepro(games: &[FixdatGame]) -> String {
+    let quote = |s: &str| s.replace('"', "'");
+
+    let mut out =
+        String::from("clrmamepro (\n\tname \"fixdat\"\n\tdescription \"igir fixdat\"\n)\n");
+    for (name, roms) in games {
+        let name = quote(name);
+        out.push_str(&format!(
+            "\ngame (\n\tname \"{name}\"\n\tdescription \"{name}\"\n"
+        ));
+        for rom in roms {
+            out.push_str(&format!(
+                "\trom ( name \"{}\" size {}",
+                quote(&fixdat_rom_name(rom)),
+                rom.size
+            ));
+            for (key, value) in fixdat_checksum_fields(&rom.checksums) {
+                out.push_str(&format!(" {key} {value}"));
+            }
+            out.push_str(" )\n");
+        }
+        out.push_str(")\n");
+    }
+    out
+}
+
+fn fixdat_checksum_fields(checksums: &ChecksumSet) -> Vec<(&'static str, &str)> {
+    [
+        ("crc", checksums.crc32.as_deref()),
+        ("md5", checksums.md5.as_deref()),
+        ("sha1", checksums.sha1.as_deref()),
+        ("sha256", checksums.sha256.as_deref()),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| value.map(|v| (key, v)))
+    .collect()
+}
+
+/// Path identifying a record in reports: the file itself, or `archive#member` for a
+/// member read from inside an archive.
+fn display_path(record: &FileRecord) -> String {
+    if record.source.ends_with(&record.relative) {
+        record.source.display().to_string()
+    } else {
+        format!("{}#{}", record.source.display(), record.relative.display())
+    }
+}
+
+/// Build the `verify` report: DAT ROM names found and missing, and inputs that match no
+/// DAT entry (archive members shown as `archive#member`). An input named like a missing
+/// ROM is `bad` (a corrupt copy), or `suspect` when its size is a power-of-two multiple or
+/// fraction of that ROM's; inputs no DAT entry names are `unknown`. Lists are sorted.
+fn verify_collection<'a>(
+    records: impl Iterator<Item = &'a FileRecord>,
+    dat_roms: &[crate::dat::DatRom],
+    dat_index: &DatIndex,
+    matched: &[crate::dat::DatRom],
+    unmatched: &[crate::dat::DatRom],
+    options: &MatchOptions,
+) -> VerifyReport {
+    let fold = |name: &str| {
+        if options.ignore_case {
+            name.to_lowercase()
+        } else {
+            name.to_string()
+        }
+    };
+    let missing: HashMap<String, &crate::dat::DatRom> =
+        unmatched.iter().map(|dat| (fold(&dat.name), dat)).collect();
+    let mut report = VerifyReport {
+        have: matched.iter().map(|dat| dat.name.clone()).collect(),
+        miss: unmatched.iter().map(|dat| dat.name.clone()).collect(),
+        ..VerifyReport::default()
+    };
+    for record in records
+        .filter(|record| find_dat_for_record_with_index(record, dat_roms, dat_index).is_none())
+    {
+        let named = record
+            .relative
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| missing.get(&fold(name)));
+        let Some(dat) = named else {
+            report.unknown.push(display_path(record));
+            continue;
+        };
+        let anomaly = dat
+            .variants()
+            .iter()
+            .filter_map(|variant| variant.size)
+            .find_map(|dat_size| crate::candidates::size_anomaly(record.size, dat_size));
+        match anomaly {
+            Some(anomaly) => report.suspect.push(format!(
+                "{}: {} of {}",
+                display_path(record),
+                anomaly.label(),
+                dat.name
+            )),
+            None => report.bad.push(display_path(record)),
+        }
+    }
+    report.have.sort();
+    report.miss.sort();
+    report.bad.sort();
+    report.suspect.sort();
+    report.unknown.sort();
+    report
+}
+
+/// Indices of records whose planned output paths are equal when compared
+/// case-insensitively, one group per shared path (in record order). Paths that differ
+/// only in case land on the same file on macOS and Windows.
+pub fn output_collisions(
+    records: &[FileRecord],
+    config: &Config,
+    dats: Option<&[crate::dat::DatRom]>,
+) -> Vec<Vec<usize>> {
+    let mut by_target: std::collections::BTreeMap<String, Vec<usize>> =
+        std::collections::BTreeMap::new();
+    for (idx, record) in records.iter().enumerate() {
+        let target = resolve_output_path_with_dats(record, config, dats);
+        by_target
+            .entry(target.to_string_lossy().to_lowercase())
+            .or_default()
+            .push(idx);
+    }
+    by_target
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect()
+}
+
+/// Order one output collision group so the record that keeps the path comes first: in
+/// record order, or by `--priority-input` / `--tiebreak-mtime`.
+fn order_collision_group(group: &mut [usize], records: &[FileRecord], config: &Config) {
+    match config.tiebreak_mtime {
+        MtimeTiebreak::Path => {}
+        MtimeTiebreak::Newest => {
+            group.sort_by(|&a, &b| records[b].modified.cmp(&records[a].modified))
+        }
+        MtimeTiebreak::Oldest => group.sort_by_key(|&idx| {
+            let modified = records[idx].modified;
+            (modified.is_none(), modified)
+        }),
+    }
+    // Stable, so `--tiebreak-mtime` still orders files from the same input.
+    if config.priority_input {
+        group.sort_by_key(|&idx| input_precedence(&records[idx]));
+    }
+}
+
+/// Apply `--on-collision` to the records of every output collision group; the first
+/// record of a group (see [`order_collision_group`]) always keeps its path. Identical
+/// copies (same size and checksum) of a record already bound for the path are dropped
+/// first, since writing them again would only duplicate the file.
+fn resolve_output_collisions(
+    mut records: Vec<FileRecord>,
+    config: &Config,
+    dats: &[crate::dat::DatRom],
+    progress: Option<&ProgressReporter>,
+) -> anyhow::Result<Vec<FileRecord>> {
+    let mut identical: HashSet<usize> = HashSet::new();
+    for mut group in output_collisions(&records, config, Some(dats)) {
+        order_collision_group(&mut group, &records, config);
+        let mut contents = HashSet::new();
+        for idx in group {
+            if let Some(key) = best_checksum_key(&records[idx].checksums)
+                && !contents.insert((records[idx].size, key))
+            {
+                identical.insert(idx);
+            }
+        }
+    }
+    if !identical.is_empty() {
+        let message = format!(
+            "skipped {} identical input(s) sharing an output path",
+            identical.len()
+        );
+        match progress {
+            Some(p) => p.log_summary(message),
+            None => vwarn!("{message}"),
+        }
+        records = records
+            .into_iter()
+            .enumerate()
+            .filter(|(idx, _)| !identical.contains(idx))
+            .map(|(_, record)| record)
+            .collect();
+    }
+
+    let mut groups = output_collisions(&records, config, Some(dats));
+    for group in groups.iter_mut() {
+        order_collision_group(group, &records, config);
+    }
+    let Some(first) = groups.first() else {
+        return Ok(records);
+    };
+    let later = groups.iter().map(|group| group.len() - 1).sum::<usize>();
+    let message = match config.on_collision {
+        CollisionPolicy::Error => {
+            let sources: Vec<String> = first
+                .iter()
+                .map(|&idx| format!("{:?}", records[idx].source))
+                .collect();
+            anyhow::bail!(
+                "{} output path collision(s) (case-insensitive), e.g. {}; use --on-collision skip or rename",
+                groups.len(),
+                sources.join(", ")
+            );
+        }
+        CollisionPolicy::Skip => {
+            let dropped: HashSet<usize> = groups
+                .iter()
+                .flat_map(|group| group[1..].iter().copied())
+                .collect();
+            let mut lines = vec![format!(
+                "skipped {later} input(s) whose output path collides with another:"
+            )];
+            for group in &groups {
+                let kept = &records[group[0]].source;
+                for &idx in &group[1..] {
+                    lines.push(format!("  {:?} (kept {:?})", records[idx].source, kept));
+                }
+            }
+            records = records
+                .into_iter()
+                .enumerate()
+                .filter(|(idx, _)| !dropped.contains(idx))
+                .map(|(_, record)| record)
+                .collect();
+            lines.join("\n")
+        }
+        CollisionPolicy::Rename => {
+            let mut taken: HashSet<String> = records
+                .iter()
+                .map(|record| {
+                    resolve_output_path_with_dats(record, config, Some(dats))
+                        .to_string_lossy()
+                        .to_lowercase()
+                })
+                .collect();
+            for group in &groups {
+                for &idx in &group[1..] {
+                    let target = resolve_output_path_with_dats(&records[idx], config, Some(dats));
+                    let stem = target
+                        .file_stem()
+                        .map(|s| s.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let extension = target.extension().map(|e| e.to_string_lossy().to_string());
+                    let mut counter = 1;
+                    let unique = loop {
+                        let candidate = match &extension {
+                            Some(ext) => format!("{stem} ({counter}).{ext}"),
+                            None => format!("{stem} ({counter})"),
+                        };
+                        if taken.insert(
+                            target
+                                .with_file_name(&candidate)
+                                .to_string_lossy()
+                                .to_lowercase(),
+                        ) {
+                            break candidate;
+                        }
+                        counter += 1;
+                    };
+                    records[idx].output_name = Some(unique);
+                }
+            }
+            format!("renamed {later} input(s) whose output path collides with another")
+        }
+    };
+    match progress {
+        Some(p) => p.log_summary(message),
+        None => vwarn!("{message}"),
+    }
+    Ok(records)
+}
+
+/// Split `records` into those sharing a source with at least one DAT match and
+/// the remaining unmatched inputs (one record per source file).
+fn partition_unmatched_inputs(
+    records: Vec<FileRecord>,
+    dat_roms: &[crate::dat::DatRom],
+    dat_index: &DatIndex,
+) -> (Vec<FileRecord>, Vec<FileRecord>) {
+    let matched_sources: HashSet<PathBuf> = records
+        .iter()
+        .filter(|record| find_dat_for_record_with_index(record, dat_roms, dat_index).is_some())
+        .map(|record| record.source.clone())
+        .collect();
+    let (matched, unmatched): (Vec<_>, Vec<_>) = records
+        .into_iter()
+        .partition(|record| matched_sources.contains(&record.source));
+    let mut seen = HashSet::new();
+    let unmatched = unmatched
+        .into_iter()
+        .filter(|record| seen.insert(record.source.clone()))
+        .collect();
+    (matched, unmatched)
+}
+
+/// Move `record`'s source file into `quarantine`, keeping its path relative to
+/// the input root it was scanned from. Returns the target and whether the file was
+/// moved: an existing file at the target is left alone and the source stays in place.
+pub fn quarantine_record(
+    record: &FileRecord,
+    quarantine: &Path,
+) -> anyhow::Result<(PathBuf, bool)> {
+    let relative = record
+        .input_root
+        .as_deref()
+        .and_then(|root| record.source.strip_prefix(root).ok())
+        .map(Path::to_path_buf)
+        .or_else(|| record.source.file_name().map(PathBuf::from))
+        .unwrap_or_else(|| record.relative.clone());
+    let target = quarantine.join(relative);
+    ensure_parent(&target)?;
+    if target.exists() {
+        return Ok((target, false));
+    }
+    fs::rename(&record.source, &target)
+        .or_else(|_| {
+            fs::copy(&record.source, &target)?;
+            fs::remove_file(&record.source)
+        })
+        .with_context(|| format!("quarantining {:?} to {:?}", record.source, target))?;
+    Ok((target, true))
+}
+
+/// Whether `source` lives on the same volume as `output` (or its nearest
+/// existing ancestor), so a move is a rename that needs no extra space.
+#[cfg(unix)]
+fn same_volume(source: &Path, output: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    let Some(existing) = output.ancestors().find(|p| p.exists()) else {
+        return false;
+    };
+    match (fs::metadata(source), fs::metadata(existing)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_volume(_source: &Path, _output: &Path) -> bool {
+    false
+}
+
+/// Bytes the write actions are expected to add to the output volume, counting each
+/// output path once and leaving out outputs that already exist and won't be overwritten.
+/// Links and same-volume moves take (almost) no space.
+fn planned_write_bytes(
+    records: &[FileRecord],
+    config: &Config,
+    dats: &[crate::dat::DatRom],
+) -> u64 {
+    let mut targets = HashSet::new();
+    let writes: Vec<&FileRecord> = records
+        .iter()
+        .filter(|record| {
+            let target = resolve_output_path_with_dats(record, config, Some(dats));
+            (config.overwrite || config.overwrite_invalid || !target.exists())
+                && targets.insert(target)
+        })
+        .collect();
+    let total_size = |records: &mut dyn Iterator<Item = &&FileRecord>| {
+        records
+            .map(|record| record.size)
+            .fold(0u64, u64::saturating_add)
+    };
+    config
+        .commands
+        .iter()
+        .map(|action| match (action, config.output.as_deref()) {
+            (Action::Copy | Action::Extract | Action::Zip, _) => total_size(&mut writes.iter()),
+            (Action::Move, Some(output)) => total_size(
+                &mut writes
+                    .iter()
+                    .filter(|record| !same_volume(&record.source, output)),
+            ),
+            _ => 0,
+        })
+        .fold(0u64, u64::saturating_add)
+}
+
+/// Fail up front when `--output` names an existing non-directory (trailing slash or
+/// not), and create the directory when a write action will fill it. A templated output
+/// only has its part before the first `{token}` checked and created.
+fn preflight_output_dir(config: &Config) -> anyhow::Result<()> {
+    let Some(output) = config.output.as_deref() else {
+        return Ok(());
+    };
+    // Collecting components also drops the trailing separator, so a file named `out/`
+    // reports as a file.
+    let output = crate::records::output_static_prefix(output);
+    if output.as_os_str().is_empty() {
+        return Ok(());
+    }
+    if output.exists() && !output.is_dir() {
+        anyhow::bail!(
+            "--output {} exists and is not a directory",
+            output.display()
+        );
+    }
+    let writes = config.commands.iter().any(|action| {
+        matches!(
+            action,
+            Action::Copy | Action::Move | Action::Link | Action::Extract | Action::Zip
+        )
+    });
+    if writes && !config.plan_diff {
+        fs::create_dir_all(&output)
+            .with_context(|| format!("failed to create --output {}", output.display()))?;
+    }
+    Ok(())
+}
+
+/// Fail before any write when the planned output exceeds the free space on the
+/// output volume. An unknown `available` value skips the check.
+fn preflight_output_space(
+    records: &[FileRecord],
+    config: &Config,
+    dats: &[crate::dat::DatRom],
+    available: Option<u64>,
+) -> anyhow::Result<()> {
+    let Some(available) = available else {
+        return Ok(());
+    };
+    let required = planned_write_bytes(records, config, dats);
+    if required > available {
+        anyhow::bail!(
+            "output volume has {available} bytes free but the planned writes need {required} bytes (use --no-space-check to skip this check)"
+        );
+    }
+    Ok(())
+}
+
+/// Fail before any write when an output path, made absolute, is longer than `limit`
+/// characters (Windows' `MAX_PATH`), naming the first offender instead of letting the write
+/// fail cryptically.
+fn preflight_output_path_lengths(
+    records: &[FileRecord],
+    config: &Config,
+    dats: &[crate::dat::DatRom],
+    limit: usize,
+) -> anyhow::Result<()> {
+    let overlong: Vec<PathBuf> = records
+        .iter()
+        .map(|record| resolve_output_path_with_dats(record, config, Some(dats)))
+        .map(|target| std::path::absolute(&target).unwrap_or(target))
+        .filter(|target| target.to_string_lossy().chars().count() > limit)
+        .collect();
+    if let Some(first) = overlong.first() {
+        anyhow::bail!(
+            "{} output path(s) exceed {limit} characters, e.g. {} ({} characters); use --shorten-paths to truncate output directories",
+            overlong.len(),
+            first.display(),
+            first.to_string_lossy().chars().count()
+        );
+    }
+    Ok(())
+}
+
+/// How a planned write relates to what is already at its destination (`--plan-diff`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WriteDiff {
+    New,
+    Identical,
+    Overwrite,
+}
+
+impl WriteDiff {
+    fn label(self) -> &'static str {
+        match self {
+            WriteDiff::New => "new",
+            WriteDiff::Identical => "identical",
+            WriteDiff::Overwrite => "overwrite",
+        }
+    }
+}
+
+/// Classify a write of `record` to `target`. An existing destination is `identical` only
+/// when its size and one checksum both match: the strongest the record carries that its
+/// DAT entry `dat` lists too (any the record carries without a DAT). Only that checksum
+/// is hashed from the destination.
+fn write_diff(record: &FileRecord, target: &Path, dat: Option<&crate::dat::DatRom>) -> WriteDiff {
+    let Ok(metadata) = fs::metadata(target) else {
+        return WriteDiff::New;
+    };
+    if metadata.len() != record.size {
+        return WriteDiff::Overwrite;
+    }
+    let value = |set: &ChecksumSet, kind: Checksum| match kind {
+        Checksum::Sha256 => set.sha256.clone(),
+        Checksum::Sha1 => set.sha1.clone(),
+        Checksum::Md5 => set.md5.clone(),
+        Checksum::Crc32 => set.crc32.clone(),
+    };
+    let dat_lists = |kind: Checksum| {
+        dat.is_none_or(|dat| match kind {
+            Checksum::Sha256 => dat.sha256.is_some(),
+            Checksum::Sha1 => dat.sha1.is_some(),
+            Checksum::Md5 => dat.md5.is_some(),
+            Checksum::Crc32 => dat.crc32.is_some(),
+        })
+    };
+    let Some(kind) = [
+        Checksum::Sha256,
+        Checksum::Sha1,
+        Checksum::Md5,
+        Checksum::Crc32,
+    ]
+    .into_iter()
+    .find(|&kind| value(&record.checksums, kind).is_some() && dat_lists(kind)) else {
+        return WriteDiff::Overwrite;
+    };
+    let Ok(existing) = crate::checksum::compute_checksum_kinds(target, &[kind]) else {
+        return WriteDiff::Overwrite;
+    };
+    match value(&record.checksums, kind).zip(value(&existing, kind)) {
+        Some((want, have)) if want.eq_ignore_ascii_case(&have) => WriteDiff::Identical,
+        _ => WriteDiff::Overwrite,
+    }
+}
+
+/// Actions that write, move or delete files, every one of which `--plan-diff` skips. The
+/// `--to-chd` and `--exec` passes run inside copy/move/link and are skipped with them.
+fn action_changes_files(action: &Action) -> bool {
+    matches!(
+        action,
+        Action::Copy
+            | Action::Move
+            | Action::Link
+            | Action::Rename
+            | Action::Extract
+            | Action::Zip
+            | Action::Clean
+            | Action::CleanEmptyDirs
+    )
+}
+
+/// Print the `--plan-diff` classification of every planned write and summarize the counts.
+/// `targets[i]` is the final output path of `records[i]`, as the write actions resolve it.
+fn print_plan_diff(
+    records: &[FileRecord],
+    targets: &[PathBuf],
+    dats: &[crate::dat::DatRom],
+    dat_index: &DatIndex,
+) -> String {
+    let mut counts = [0usize; 3];
+    for (record, target) in records.iter().zip(targets) {
+        let dat = find_dat_for_record_with_index(record, dats, dat_index);
+        let diff = write_diff(record, target, dat.as_ref());
+        counts[diff as usize] += 1;
+        eprintln!("{:<9} {}", diff.label(), target.display());
+    }
+    format!(
+        "Plan diff: {} new, {} identical, {} overwrite (nothing written)",
+        counts[0], counts[1], counts[2]
+    )
+}
+
+/// Remove every empty directory below each `--input` directory, deepest first, so trees
+/// emptied by a move disappear entirely; the input roots themselves are kept. With
+/// `--clean-dry-run` nothing is removed. Returns the (would-be) removed directories, sorted.
+pub fn remove_empty_input_dirs(config: &Config) -> anyhow::Result<Vec<PathBuf>> {
+    let mut removed: HashSet<PathBuf> = HashSet::new();
+    for input in config.input.iter().filter(|input| input.is_dir()) {
+        for entry in WalkDir::new(input)
+            .min_depth(1)
+            .contents_first(true)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|e| e.file_type().is_dir())
+        {
+            let dir = entry.into_path();
+            // Children are visited first, so a directory holding only removed
+            // directories counts as empty (this is what makes dry runs accurate).
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            let empty = entries
+                .filter_map(Result::ok)
+                .all(|child| removed.contains(&child.path()));
+            if !empty {
+                continue;
+            }
+            if !config.clean_dry_run {
+                fs::remove_dir(&dir).with_context(|| format!("removing empty dir {dir:?}"))?;
+            }
+            removed.insert(dir);
+        }
+    }
+    let mut removed: Vec<PathBuf> = removed.into_iter().collect();
+    removed.sort();
+    Ok(removed)
+}
+
 pub fn clean_output(
     records: &[FileRecord],
     config: &Config,
     dats: Option<&[crate::dat::DatRom]>,
+) -> anyhow::Result<Vec<PathBuf>> {
+    clean_output_keeping(records, config, dats, &[])
+}
+
+/// [`clean_output`] that also keeps `extra`, files written for the run beyond the records
+/// themselves (such as `--merge-discs` playlists).
+fn clean_output_keeping(
+    records: &[FileRecord],
+    config: &Config,
+    dats: Option<&[crate::dat::DatRom]>,
+    extra: &[PathBuf],
 ) -> anyhow::Result<Vec<PathBuf>> {
     let mut cleaned = Vec::new();
-    let mut expected = HashMap::new();
+    let mut expected: HashMap<PathBuf, ()> = extra.iter().map(|path| (path.clone(), ())).collect();
     for record in records {
-        expected.insert(resolve_output_path_with_dats(record, config, dats), ());
+        let target = resolve_output_path_with_dats(record, config, dats);
+        // --to-chd replaces each written cue set with a `.chd` beside the cue
+        if config.to_chd
+            && target
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("cue"))
+        {
+            expected.insert(target.with_extension("chd"), ());
+        }
+        expected.insert(target, ());
     }
 
     let exclude = build_globset(&config.clean_exclude)?;
+    // Each run backs up into its own dated folder, created on the first removal
+    let mut backup_run_dir: Option<PathBuf> = None;
     if let Some(output) = &config.output {
         for entry in WalkDir::new(output)
             .into_iter()
@@ -2244,7 +4937,14 @@ pub fn clean_output(
             }
 
             if let Some(backup) = &config.clean_backup {
-                let target = backup.join(path.file_name().unwrap_or_default());
+                if backup_run_dir.is_none() {
+                    backup_run_dir = Some(create_clean_backup_run_dir(backup)?);
+                }
+                let run_dir = backup_run_dir
+                    .as_ref()
+                    .expect("backup run dir just created");
+                let relative = path.strip_prefix(output).unwrap_or(&path);
+                let target = run_dir.join(relative);
                 ensure_parent(&target)?;
                 fs::rename(&path, &target).or_else(|_| {
                     fs::copy(&path, &target)?;
@@ -2258,9 +4958,70 @@ pub fn clean_output(
         }
     }
 
+    if let (Some(backup), Some(keep)) = (&config.clean_backup, config.clean_backup_keep)
+        && !config.clean_dry_run
+    {
+        prune_clean_backups(backup, keep)?;
+    }
+
+    // WalkDir yields filesystem order, which differs across platforms.
+    cleaned.sort();
     Ok(cleaned)
 }
 
+const CLEAN_BACKUP_RUN_FORMAT: &str = "%Y-%m-%dT%H-%M-%S";
+
+/// Create a new `<backup>/<timestamp>` folder for this clean run. Runs started within the
+/// same second get a `-N` suffix so earlier backups are never reused.
+fn create_clean_backup_run_dir(backup: &Path) -> anyhow::Result<PathBuf> {
+    let stamp = chrono::Local::now()
+        .format(CLEAN_BACKUP_RUN_FORMAT)
+        .to_string();
+    let mut dir = backup.join(&stamp);
+    let mut suffix = 1;
+    while dir.exists() {
+        dir = backup.join(format!("{stamp}-{suffix}"));
+        suffix += 1;
+    }
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("creating clean backup folder {}", dir.display()))?;
+    Ok(dir)
+}
+
+/// Sort key for a dated clean backup folder name, or `None` for unrelated folders.
+fn clean_backup_run_key(name: &str) -> Option<(chrono::NaiveDateTime, u32)> {
+    let (stamp, rest) = name.split_at_checked(19)?;
+    let time = chrono::NaiveDateTime::parse_from_str(stamp, CLEAN_BACKUP_RUN_FORMAT).ok()?;
+    let suffix = match rest.strip_prefix('-') {
+        Some(n) => n.parse().ok()?,
+        None if rest.is_empty() => 0,
+        None => return None,
+    };
+    Some((time, suffix))
+}
+
+/// Delete the oldest dated run folders under `backup` so at most `keep` remain.
+fn prune_clean_backups(backup: &Path, keep: usize) -> anyhow::Result<()> {
+    if !backup.is_dir() {
+        return Ok(());
+    }
+    let mut runs: Vec<_> = fs::read_dir(backup)?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|entry| {
+            clean_backup_run_key(&entry.file_name().to_string_lossy())
+                .map(|key| (key, entry.path()))
+        })
+        .collect();
+    runs.sort_by_key(|(key, _)| *key);
+    let excess = runs.len().saturating_sub(keep);
+    for (_, dir) in runs.into_iter().take(excess) {
+        fs::remove_dir_all(&dir)
+            .with_context(|| format!("pruning clean backup folder {}", dir.display()))?;
+    }
+    Ok(())
+}
+
 fn run_action_with_progress<F>(
     action: &Action,
     records: &[FileRecord],
@@ -2372,22 +5133,41 @@ where
 pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
     let progress = ProgressReporter::maybe_new(config);
     let run_start = Instant::now();
-    let mut scan_config = config.clone();
-    if scan_config.input_checksum_max.is_none()
-        && scan_config.input_checksum_min.rank() < Checksum::Sha1.rank()
-    {
-        scan_config.input_checksum_max = Some(Checksum::Sha1);
-    }
     let mut diag_timings: Vec<(String, Duration)> = Vec::new();
+    preflight_output_dir(config)?;
+    // DATs load before scanning so `--input-checksum-auto` can skip hashes they never list.
+    let dat_roms = with_diag_timing(
+        "load_dats",
+        progress.as_ref(),
+        config.diag,
+        config.profile,
+        &mut diag_timings,
+        || load_dat_roms(config, progress.as_ref()),
+    )?;
+    log_diag_step(
+        progress.as_ref(),
+        config.diag,
+        format!("step=load_dats dats={}", dat_roms.len(),),
+    );
+    let scan_config = effective_scan_config(&dat_roms, config);
+    check_dat_checksum_coverage(&dat_roms, &scan_config)?;
     let collection = with_diag_timing(
         "collect_inputs",
         progress.as_ref(),
         config.diag,
+        config.profile,
         &mut diag_timings,
         || collect_files(&scan_config, progress.as_ref()),
     )?;
     let mut records = collection.records;
-    let skipped = collection.skipped;
+    let mut skipped = collection.skipped;
+    if !dat_roms.is_empty() {
+        records = crate::candidate_archive_hasher::drop_archive_level_records(
+            records,
+            &dat_rom_tuples(&dat_roms),
+            config,
+        );
+    }
     log_diag_step(
         progress.as_ref(),
         config.diag,
@@ -2400,22 +5180,11 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
     if let Some(p) = progress.as_ref() {
         p.hint_background_task_total(BackgroundTask::Cache, Some(records.len()));
     }
-    let dat_roms = with_diag_timing(
-        "load_dats",
-        progress.as_ref(),
-        config.diag,
-        &mut diag_timings,
-        || load_dat_roms(config, progress.as_ref()),
-    )?;
-    log_diag_step(
-        progress.as_ref(),
-        config.diag,
-        format!("step=load_dats dats={}", dat_roms.len(),),
-    );
     let dat_index = with_diag_timing(
         "index_dats",
         progress.as_ref(),
         config.diag,
+        config.profile,
         &mut diag_timings,
         || Ok(DatIndex::from_dats(&dat_roms)),
     )?;
@@ -2428,6 +5197,7 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
         "partition_matches",
         progress.as_ref(),
         config.diag,
+        config.profile,
         &mut diag_timings,
         || Ok(partition_dat_matches(&records, &dat_roms)),
     )?;
@@ -2440,6 +5210,39 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
             unmatched_dat_entries.len()
         ),
     );
+    // With overlapping DATs, narrow the entries used for writing so each file
+    // resolves to its single best DAT match; the match summary above still
+    // reflects every loaded DAT.
+    let (dat_roms, dat_index) = if config.dat_best_match {
+        let best = with_diag_timing(
+            "dat_best_match",
+            progress.as_ref(),
+            config.diag,
+            config.profile,
+            &mut diag_timings,
+            || {
+                let priorities = config
+                    .dat_priority
+                    .iter()
+                    .map(|rule| parse_dat_priority(rule))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                Ok(select_best_dat_matches(&records, &dat_roms, &priorities))
+            },
+        )?;
+        log_diag_step(
+            progress.as_ref(),
+            config.diag,
+            format!(
+                "step=dat_best_match kept={} dropped={}",
+                best.len(),
+                dat_roms.len() - best.len()
+            ),
+        );
+        let index = DatIndex::from_dats(&best);
+        (best, index)
+    } else {
+        (dat_roms, dat_index)
+    };
     let matched = matched_dat_entries.len();
     let mut steps = Vec::new();
     let mut action_durations: Vec<Duration> = Vec::new();
@@ -2475,7 +5278,7 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
             Ok(c) => Some(c),
             Err(e) => {
                 if config.verbose > 0 {
-                    eprintln!("warning: unable to open cache DB: {}", e);
+                    vwarn!("unable to open cache DB: {}", e);
                 }
                 None
             }
@@ -3374,6 +6177,7 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
         enrich_elapsed,
         progress.as_ref(),
         config.diag,
+        config.profile,
         &mut diag_timings,
     );
 
@@ -3382,6 +6186,7 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
         "records_without_dat_match",
         progress.as_ref(),
         config.diag,
+        config.profile,
         &mut diag_timings,
         || {
             Ok(records_without_dat_match_with_index(
@@ -3409,6 +6214,7 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
         "online_lookup",
         progress.as_ref(),
         config.diag,
+        config.profile,
         &mut diag_timings,
         || online_lookup(&unmatched_records, online_lookup_config),
     )?;
@@ -3417,6 +6223,74 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
         config.diag,
         format!("step=online_lookup matches={}", online_matches.len(),),
     );
+    // Genre/platform filters need the enriched metadata, so they run only now.
+    let records = filter_by_metadata(records, config, &mut skipped);
+    // With --quarantine, inputs no DAT entry matched are kept out of the write
+    // actions and moved aside once they finish.
+    let (records, unmatched_inputs) = match config.quarantine {
+        Some(_) if !dat_roms.is_empty() => {
+            partition_unmatched_inputs(records, &dat_roms, &dat_index)
+        }
+        _ => (records, Vec::new()),
+    };
+    let writes_output = config
+        .commands
+        .iter()
+        .any(|action| matches!(action, Action::Copy | Action::Move | Action::Link));
+    let mut records = records;
+    crate::records::assign_output_names(&mut records, config);
+    let disc_sets = if config.merge_discs && writes_output {
+        merge_disc_sets(&mut records, &dat_roms, &dat_index, config)
+    } else {
+        Vec::new()
+    };
+    let mut records = if writes_output {
+        resolve_output_collisions(records, config, &dat_roms, progress.as_ref())?
+    } else {
+        records
+    };
+    let incomplete = if config.allow_incomplete_sets && !dat_roms.is_empty() {
+        incomplete_sets(&records, &dat_roms, &matched_dat_entries, config)
+    } else {
+        Vec::new()
+    };
+    let mut corrupt_archives: Vec<PathBuf> = Vec::new();
+    // `--plan-diff` writes nothing, so it needs no space.
+    if !config.no_space_check
+        && !config.plan_diff
+        && let Some(output) = config.output.as_deref()
+    {
+        preflight_output_space(&records, config, &dat_roms, available_space(output))?;
+    }
+    if cfg!(windows) && writes_output && !config.shorten_paths {
+        preflight_output_path_lengths(&records, config, &dat_roms, MAX_OUTPUT_PATH)?;
+    }
+    let plan_diff_note = (config.plan_diff && writes_output).then(|| {
+        // Records already carry their merged-disc, flat and collision-renamed names here,
+        // so these are the paths copy/move/link write.
+        let targets: Vec<PathBuf> = records
+            .iter()
+            .map(|record| resolve_output_path_with_dats(record, config, Some(&dat_roms)))
+            .collect();
+        print_plan_diff(&records, &targets, &dat_roms, &dat_index)
+    });
+    let exec_failures: std::sync::Mutex<Vec<ExecFailure>> = Default::default();
+    // Records a writing command produced output for, for the romvault "Fixed" list.
+    let written_records: std::sync::Mutex<Vec<FileRecord>> = Default::default();
+    let note_written = |record: &FileRecord| {
+        written_records
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(record.clone());
+    };
+    let run_exec = |record: &FileRecord, written: &Path| {
+        if let Some(failure) = run_exec_hook(record, written, config, &dat_roms) {
+            exec_failures
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(failure);
+        }
+    };
     for action in &config.commands {
         log_diag_step(
             progress.as_ref(),
@@ -3427,6 +6301,18 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                 records.len(),
             ),
         );
+        if config.plan_diff && action_changes_files(action) {
+            let note = match (&plan_diff_note, action) {
+                (Some(note), Action::Copy | Action::Move | Action::Link) => note.clone(),
+                _ => "Skipped by --plan-diff (nothing written)".to_string(),
+            };
+            steps.push(ActionOutcome {
+                action: action.clone(),
+                status: "planned".to_string(),
+                note,
+            });
+            continue;
+        }
         match action {
             Action::Copy => {
                 let duration =
@@ -3437,13 +6323,38 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                             .unwrap_or_else(|| "heuristic".to_string());
                         let _target =
                             resolve_output_path_with_dats(record, config, Some(&dat_roms));
-                        copy_record_with_dats(record, config, Some(&dat_roms), handle.as_ref())?;
+                        let (target, written) = copy_record_with_dats(
+                            record,
+                            config,
+                            Some(&dat_roms),
+                            handle.as_ref(),
+                        )?;
+                        if written {
+                            note_written(record);
+                            run_exec(record, &target);
+                        }
                         Ok(())
                     })?;
+                let mut note = "Copied input files to output".to_string();
+                if !disc_sets.is_empty() {
+                    note.push_str(&write_disc_playlists(&disc_sets, config, &dat_roms)?);
+                }
+                if !config.patch.is_empty() {
+                    note.push_str(&patch_note(&records, config, &dat_roms, progress.as_ref())?);
+                }
+                if config.to_chd {
+                    note.push_str(&chd_conversion_note(
+                        action,
+                        &records,
+                        config,
+                        &dat_roms,
+                        progress.as_ref(),
+                    )?);
+                }
                 steps.push(ActionOutcome {
                     action: action.clone(),
                     status: "ok".to_string(),
-                    note: "Copied input files to output".to_string(),
+                    note,
                 });
                 action_durations.push(duration);
                 record_diag_duration(
@@ -3451,6 +6362,7 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                     duration,
                     progress.as_ref(),
                     config.diag,
+                    config.profile,
                     &mut diag_timings,
                 );
             }
@@ -3463,13 +6375,38 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                             .unwrap_or_else(|| "heuristic".to_string());
                         let _target =
                             resolve_output_path_with_dats(record, config, Some(&dat_roms));
-                        move_record_with_dats(record, config, Some(&dat_roms), handle.as_ref())?;
+                        let (target, written) = move_record_with_dats(
+                            record,
+                            config,
+                            Some(&dat_roms),
+                            handle.as_ref(),
+                        )?;
+                        if written {
+                            note_written(record);
+                            run_exec(record, &target);
+                        }
                         Ok(())
                     })?;
+                let mut note = "Moved input files to output".to_string();
+                if !disc_sets.is_empty() {
+                    note.push_str(&write_disc_playlists(&disc_sets, config, &dat_roms)?);
+                }
+                if !config.patch.is_empty() {
+                    note.push_str(&patch_note(&records, config, &dat_roms, progress.as_ref())?);
+                }
+                if config.to_chd {
+                    note.push_str(&chd_conversion_note(
+                        action,
+                        &records,
+                        config,
+                        &dat_roms,
+                        progress.as_ref(),
+                    )?);
+                }
                 steps.push(ActionOutcome {
                     action: action.clone(),
                     status: "ok".to_string(),
-                    note: "Moved input files to output".to_string(),
+                    note,
                 });
                 action_durations.push(duration);
                 record_diag_duration(
@@ -3477,6 +6414,43 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                     duration,
                     progress.as_ref(),
                     config.diag,
+                    config.profile,
+                    &mut diag_timings,
+                );
+            }
+            Action::Rename => {
+                let duration = run_action_with_progress(
+                    action,
+                    &records,
+                    progress.as_ref(),
+                    |record, _, _| {
+                        if let Some(dat) =
+                            find_dat_for_record_with_index(record, &dat_roms, &dat_index)
+                            && let Some(target) = rename_record_to_dat(record, &dat)?
+                        {
+                            vprintln!(
+                                config.verbose,
+                                2,
+                                "RENAME {:?} -> {:?}",
+                                record.source,
+                                target
+                            );
+                        }
+                        Ok(())
+                    },
+                )?;
+                steps.push(ActionOutcome {
+                    action: action.clone(),
+                    status: "ok".to_string(),
+                    note: "Renamed input files to their DAT names in place".to_string(),
+                });
+                action_durations.push(duration);
+                record_diag_duration(
+                    "action_rename",
+                    duration,
+                    progress.as_ref(),
+                    config.diag,
+                    config.profile,
                     &mut diag_timings,
                 );
             }
@@ -3489,13 +6463,26 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                             .unwrap_or_else(|| "heuristic".to_string());
                         let _target =
                             resolve_output_path_with_dats(record, config, Some(&dat_roms));
-                        link_record_with_dats(record, config, Some(&dat_roms), handle.as_ref())?;
+                        let (target, written) = link_record_with_dats(
+                            record,
+                            config,
+                            Some(&dat_roms),
+                            handle.as_ref(),
+                        )?;
+                        if written {
+                            note_written(record);
+                            run_exec(record, &target);
+                        }
                         Ok(())
                     })?;
+                let mut note = format!("Linked files using {:?}", config.link_mode);
+                if !disc_sets.is_empty() {
+                    note.push_str(&write_disc_playlists(&disc_sets, config, &dat_roms)?);
+                }
                 steps.push(ActionOutcome {
                     action: action.clone(),
                     status: "ok".to_string(),
-                    note: format!("Linked files using {:?}", config.link_mode),
+                    note,
                 });
                 action_durations.push(duration);
                 record_diag_duration(
@@ -3503,6 +6490,7 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                     duration,
                     progress.as_ref(),
                     config.diag,
+                    config.profile,
                     &mut diag_timings,
                 );
             }
@@ -3524,7 +6512,18 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                             .unwrap_or_else(|| "heuristic".to_string());
                         let _target =
                             resolve_output_path_with_dats(record, config, Some(&dat_roms));
-                        extract_record_with_dats(record, config, Some(&dat_roms), handle.as_ref())?;
+                        let written = extract_record_with_dats(
+                            record,
+                            config,
+                            Some(&dat_roms),
+                            handle.as_ref(),
+                        )?;
+                        if !written.is_empty() {
+                            note_written(record);
+                        }
+                        for path in &written {
+                            run_exec(record, path);
+                        }
                         Ok(())
                     },
                 )?;
@@ -3547,13 +6546,24 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                     duration,
                     progress.as_ref(),
                     config.diag,
+                    config.profile,
                     &mut diag_timings,
                 );
             }
             Action::Zip => {
                 let mut zip_targets: Vec<FileRecord> = Vec::new();
                 let mut skipped: Vec<&FileRecord> = Vec::new();
+                let keep_member = zip_member_filter(config)?;
                 for record in records.iter() {
+                    if !keep_member(record) {
+                        vprintln!(
+                            config.verbose,
+                            2,
+                            "Skipping zip for {} (--zip-exclude)",
+                            record.relative.to_string_lossy()
+                        );
+                        continue;
+                    }
                     if record_should_zip(record, Some(&dat_roms)) {
                         zip_targets.push(record.clone());
                     } else {
@@ -3587,6 +6597,11 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                     continue;
                 }
 
+                // SHA256s hashed while zipping, keyed by record identity, for the report.
+                let streamed: std::sync::Mutex<HashMap<String, String>> = Default::default();
+                // Archives created, each with the first record zipped into it, for --exec.
+                let created_archives: std::sync::Mutex<Vec<(PathBuf, FileRecord)>> =
+                    Default::default();
                 let duration = run_action_with_progress(
                     action,
                     &zip_targets,
@@ -3599,7 +6614,15 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                         let _target =
                             resolve_output_path_with_dats(record, config, Some(&dat_roms))
                                 .with_extension("zip");
-                        let created = zip_record(record, config, Some(&dat_roms), handle.as_ref())?;
+                        let (created, sha256) =
+                            zip_record_hashed(record, config, Some(&dat_roms), handle.as_ref())?;
+                        note_written(record);
+                        if let Some(sha256) = sha256 {
+                            streamed
+                                .lock()
+                                .unwrap_or_else(|e| e.into_inner())
+                                .insert(record.identity(), sha256);
+                        }
                         let unzipped =
                             resolve_output_path_with_dats(record, config, Some(&dat_roms));
                         if unzipped.exists() {
@@ -3619,10 +6642,22 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                                 ),
                             }
                         }
-                        let _ = created;
+                        if config.exec.is_some() {
+                            let mut archives =
+                                created_archives.lock().unwrap_or_else(|e| e.into_inner());
+                            if !archives.iter().any(|(path, _)| *path == created) {
+                                archives.push((created, record.clone()));
+                            }
+                        }
                         Ok(())
                     },
                 )?;
+                for (archive, record) in created_archives
+                    .into_inner()
+                    .unwrap_or_else(|e| e.into_inner())
+                {
+                    run_exec(&record, &archive);
+                }
                 let zipped_count = zip_targets.len();
                 steps.push(ActionOutcome {
                     action: action.clone(),
@@ -3642,12 +6677,19 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                         }
                     ),
                 });
+                let streamed = streamed.into_inner().unwrap_or_else(|e| e.into_inner());
+                for record in records.iter_mut() {
+                    if record.checksums.sha256.is_none() {
+                        record.checksums.sha256 = streamed.get(&record.identity()).cloned();
+                    }
+                }
                 action_durations.push(duration);
                 record_diag_duration(
                     "action_zip",
                     duration,
                     progress.as_ref(),
                     config.diag,
+                    config.profile,
                     &mut diag_timings,
                 );
             }
@@ -3666,12 +6708,13 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                     elapsed,
                     progress.as_ref(),
                     config.diag,
+                    config.profile,
                     &mut diag_timings,
                 );
             }
             Action::Report => {
                 let start = Instant::now();
-                let _ = write_report(&records, config)?;
+                let _ = write_report(&records, config, cache.as_ref(), &dat_roms)?;
                 steps.push(ActionOutcome {
                     action: action.clone(),
                     status: "ok".to_string(),
@@ -3684,6 +6727,7 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                     elapsed,
                     progress.as_ref(),
                     config.diag,
+                    config.profile,
                     &mut diag_timings,
                 );
             }
@@ -3702,6 +6746,7 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                     elapsed,
                     progress.as_ref(),
                     config.diag,
+                    config.profile,
                     &mut diag_timings,
                 );
             }
@@ -3711,7 +6756,11 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                 steps.push(ActionOutcome {
                     action: action.clone(),
                     status: "ok".to_string(),
-                    note: "Generated fixdat JSON".to_string(),
+                    note: match config.fixdat_format {
+                        Some(FixdatFormat::Logiqx) => "Generated Logiqx fixdat".to_string(),
+                        Some(FixdatFormat::Clrmamepro) => "Generated ClrMamePro fixdat".to_string(),
+                        None => "Generated fixdat JSON".to_string(),
+                    },
                 });
                 let elapsed = start.elapsed();
                 action_durations.push(elapsed);
@@ -3720,12 +6769,17 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                     elapsed,
                     progress.as_ref(),
                     config.diag,
+                    config.profile,
                     &mut diag_timings,
                 );
             }
             Action::Clean => {
                 let start = Instant::now();
-                let cleaned = clean_output(&records, config, Some(&dat_roms))?;
+                let playlists: Vec<PathBuf> = disc_sets
+                    .iter()
+                    .filter_map(|set| disc_playlist_path(set, config, &dat_roms))
+                    .collect();
+                let cleaned = clean_output_keeping(&records, config, Some(&dat_roms), &playlists)?;
                 steps.push(ActionOutcome {
                     action: action.clone(),
                     status: "ok".to_string(),
@@ -3738,6 +6792,118 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                     elapsed,
                     progress.as_ref(),
                     config.diag,
+                    config.profile,
+                    &mut diag_timings,
+                );
+            }
+            Action::Verify => {
+                let start = Instant::now();
+                let report = verify_collection(
+                    records.iter().chain(unmatched_inputs.iter()),
+                    &dat_roms,
+                    &dat_index,
+                    &matched_dat_entries,
+                    &unmatched_dat_entries,
+                    &MatchOptions::from_config(config),
+                );
+                let summary = report.summary_line();
+                eprintln!("{summary}");
+                let note = match config.output.as_deref() {
+                    Some(output) => {
+                        let target = output.join("verify.json");
+                        ensure_parent(&target)?;
+                        fs::write(&target, serde_json::to_string_pretty(&report)?)?;
+                        format!("{summary}; wrote {}", target.display())
+                    }
+                    None => summary,
+                };
+                steps.push(ActionOutcome {
+                    action: action.clone(),
+                    status: if report.miss.is_empty()
+                        && report.bad.is_empty()
+                        && report.suspect.is_empty()
+                    {
+                        "ok"
+                    } else {
+                        "incomplete"
+                    }
+                    .to_string(),
+                    note,
+                });
+                let elapsed = start.elapsed();
+                action_durations.push(elapsed);
+                record_diag_duration(
+                    "action_verify",
+                    elapsed,
+                    progress.as_ref(),
+                    config.diag,
+                    config.profile,
+                    &mut diag_timings,
+                );
+            }
+            Action::CleanEmptyDirs => {
+                let start = Instant::now();
+                let removed = remove_empty_input_dirs(config)?;
+                steps.push(ActionOutcome {
+                    action: action.clone(),
+                    status: "ok".to_string(),
+                    note: format!(
+                        "{} {} empty input directories",
+                        if config.clean_dry_run {
+                            "Would remove"
+                        } else {
+                            "Removed"
+                        },
+                        removed.len()
+                    ),
+                });
+                let elapsed = start.elapsed();
+                action_durations.push(elapsed);
+                record_diag_duration(
+                    "action_clean_empty_dirs",
+                    elapsed,
+                    progress.as_ref(),
+                    config.diag,
+                    config.profile,
+                    &mut diag_timings,
+                );
+            }
+            Action::VerifyZip => {
+                let start = Instant::now();
+                let (checked, corrupt) = verify_archives(&records);
+                for (path, problem) in &corrupt {
+                    vwarn!("corrupt archive {}: {}", path.display(), problem);
+                }
+                corrupt_archives.extend(corrupt.iter().map(|(path, _)| path.clone()));
+                steps.push(ActionOutcome {
+                    action: action.clone(),
+                    status: if corrupt.is_empty() { "ok" } else { "failed" }.to_string(),
+                    note: format!(
+                        "Verified {} archive(s); {} corrupt{}",
+                        checked,
+                        corrupt.len(),
+                        if corrupt.is_empty() {
+                            String::new()
+                        } else {
+                            format!(
+                                ": {}",
+                                corrupt
+                                    .iter()
+                                    .map(|(path, _)| path.display().to_string())
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            )
+                        }
+                    ),
+                });
+                let elapsed = start.elapsed();
+                action_durations.push(elapsed);
+                record_diag_duration(
+                    "action_verify_zip",
+                    elapsed,
+                    progress.as_ref(),
+                    config.diag,
+                    config.profile,
                     &mut diag_timings,
                 );
             }
@@ -3748,6 +6914,29 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                     status: "ok".to_string(),
                     note: "Validated configuration only".to_string(),
                 });
+                for miss in near_misses(
+                    &unmatched_dat_entries,
+                    &records,
+                    &MatchOptions::from_config(config),
+                    config.min_score.unwrap_or(0.0),
+                ) {
+                    steps.push(ActionOutcome {
+                        action: action.clone(),
+                        status: if miss.size_anomaly.is_some() {
+                            "size-suspect"
+                        } else {
+                            "near-miss"
+                        }
+                        .to_string(),
+                        note: format!(
+                            "{}: closest input {} scored {:.1} ({})",
+                            miss.name,
+                            miss.relative.display(),
+                            miss.score,
+                            miss.reasons.join(", ")
+                        ),
+                    });
+                }
                 let elapsed = start.elapsed();
                 action_durations.push(elapsed);
                 record_diag_duration(
@@ -3755,6 +6944,7 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                     elapsed,
                     progress.as_ref(),
                     config.diag,
+                    config.profile,
                     &mut diag_timings,
                 );
             }
@@ -3766,6 +6956,40 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
         );
     }
 
+    if let Some(quarantine) = config.quarantine.as_deref()
+        && !config.plan_diff
+    {
+        let corrupt_records = records
+            .iter()
+            .filter(|record| corrupt_archives.contains(&record.source));
+        for (record, detail) in unmatched_inputs
+            .iter()
+            .map(|record| (record, "no DAT entry matched".to_string()))
+            .chain(
+                corrupt_records.map(|record| (record, "archive failed verification".to_string())),
+            )
+        {
+            if !record.source.exists() {
+                // Another entry of the same archive already moved it.
+                continue;
+            }
+            let (target, moved) = quarantine_record(record, quarantine)?;
+            skipped.push(if moved {
+                SkippedFile {
+                    path: record.source.clone(),
+                    reason: SkipReason::Quarantined,
+                    detail: Some(format!("{detail}; moved to {}", target.display())),
+                }
+            } else {
+                SkippedFile {
+                    path: record.source.clone(),
+                    reason: SkipReason::QuarantineConflict,
+                    detail: Some(format!("{detail}; {} already exists", target.display())),
+                }
+            });
+        }
+    }
+
     if !dat_roms.is_empty() {
         steps.push(ActionOutcome {
             action: Action::Fixdat,
@@ -3781,6 +7005,13 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
                 }
             ),
         });
+        for (set, missing) in &incomplete {
+            steps.push(ActionOutcome {
+                action: Action::Fixdat,
+                status: "incomplete".to_string(),
+                note: format!("{set}: missing {}", missing.join(", ")),
+            });
+        }
     }
 
     if let Err(err) =
@@ -3807,18 +7038,38 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
         unmatched_dat_entries.len(),
     );
     let total_duration = run_start.elapsed();
+    let written_records = written_records
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner());
+    let (fixed_dat_entries, _) = partition_dat_matches(&written_records, &matched_dat_entries);
     emit_summary(
         &summary,
         &skipped,
         &steps,
         config,
         &matched_dat_entries,
+        &unmatched_dat_entries,
+        &fixed_dat_entries,
         &action_durations,
         total_duration,
     );
 
-    if config.diag && !diag_timings.is_empty() {
-        eprintln!("\nDiag timings:");
+    let exec_failures = exec_failures
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner());
+    for failure in &exec_failures {
+        vwarn!("--exec {} for {}", failure.error, failure.path.display());
+    }
+
+    if (config.diag || config.profile) && !diag_timings.is_empty() {
+        eprintln!(
+            "\n{}:",
+            if config.profile {
+                "Profile"
+            } else {
+                "Diag timings"
+            }
+        );
         for (phase, duration) in &diag_timings {
             eprintln!(
                 "  - {:<24} {} ({:.2} ms)",
@@ -3841,6 +7092,7 @@ pub fn perform_actions(config: &Config) -> anyhow::Result<ExecutionPlan> {
         },
         online_matches,
         skipped,
+        exec_failures,
         summary,
     })
 }
@@ -3863,11 +7115,13 @@ fn build_run_summary(
         .collect();
     breakdown.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.reason.cmp(&b.reason)));
 
-    let files_copied = if steps.iter().any(|s| matches!(s.action, Action::Copy)) {
-        Some(processed)
-    } else {
-        None
-    };
+    // a --plan-diff dry run plans its copies without writing any
+    let files_copied =
+        if !config.plan_diff && steps.iter().any(|s| matches!(s.action, Action::Copy)) {
+            Some(processed)
+        } else {
+            None
+        };
 
     RunSummary {
         total_inputs: config.input.len(),
@@ -4041,15 +7295,29 @@ fn format_duration(duration: Duration) -> String {
     format!("{:.2}s", duration.as_secs_f64())
 }
 
+/// Print the end-of-run summary to stderr in the `--log-style` layout; `-q` hides it.
+#[allow(clippy::too_many_arguments)]
 fn emit_summary(
     summary: &RunSummary,
     skipped: &[SkippedFile],
     steps: &[ActionOutcome],
     config: &Config,
     matched_dat: &[crate::dat::DatRom],
+    unmatched_dat: &[crate::dat::DatRom],
+    fixed_dat: &[crate::dat::DatRom],
     action_durations: &[Duration],
     total_duration: Duration,
 ) {
+    if config.quiet > 0 {
+        return;
+    }
+    if config.log_style == LogStyle::Romvault {
+        eprint!(
+            "\n{}",
+            romvault_report(matched_dat, unmatched_dat, fixed_dat)
+        );
+        return;
+    }
     eprintln!("\n=== IGIR Summary ===");
 
     if summary.input_roots.is_empty() {
@@ -4184,3 +7452,54 @@ fn emit_summary(
         }
     }
 }
+
+/// `--log-style romvault`: the matched/missing results laid out like a RomVault report.
+/// DATs are complete when every ROM is present, partial when some are, empty when none;
+/// `fixed` lists the matched ROMs a writing command produced output for.
+fn romvault_report(
+    matched: &[crate::dat::DatRom],
+    unmatched: &[crate::dat::DatRom],
+    fixed: &[crate::dat::DatRom],
+) -> String {
+    let mut dats: std::collections::BTreeMap<String, (usize, usize)> =
+        std::collections::BTreeMap::new();
+    for rom in matched {
+        dats.entry(rom.source_dat_name()).or_default().0 += 1;
+    }
+    for rom in unmatched {
+        dats.entry(rom.source_dat_name()).or_default().1 += 1;
+    }
+
+    let mut out = String::from("RomVault Report\n===============\n");
+    let section = |have: usize, miss: usize| match (have, miss) {
+        (0, _) => 2,
+        (_, 0) => 0,
+        _ => 1,
+    };
+    for (idx, title) in ["Complete DATs", "Partial DATs", "Empty DATs"]
+        .into_iter()
+        .enumerate()
+    {
+        let rows: Vec<_> = dats
+            .iter()
+            .filter(|(_, (have, miss))| section(*have, *miss) == idx)
+            .collect();
+        out.push_str(&format!("\n{title} ({})\n", rows.len()));
+        for (name, (have, miss)) in rows {
+            out.push_str(&format!("  {name}  {have}/{}\n", have + miss));
+        }
+    }
+    for (title, roms) in [("Missing ROMs", unmatched), ("Fixed ROMs", fixed)] {
+        out.push_str(&format!("\n{title} ({})\n", roms.len()));
+        for rom in roms {
+            out.push_str(&format!("  {}\\{}\n", rom.source_dat_name(), rom.name));
+        }
+    }
+    out.push_str(&format!(
+        "\nHave: {}  Missing: {}  Fixed: {}\n",
+        matched.len(),
+        unmatched.len(),
+        fixed.len()
+    ));
+    out
+}
@@ -1,18 +1,17 @@
 use anyhow::Context;
 use std::collections::HashSet;
 use std::fs::File;
-use std::path::Path;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::sync::mpsc::Sender;
-
-#[cfg(test)]
-use std::sync::mpsc;
+use std::sync::mpsc::{self, Sender};
 use zip::read::ZipArchive;
 
-use crate::checksum::compute_checksums_stream;
+use crate::checksum::{checksum_targets, compute_checksums_from_bytes, compute_checksums_stream};
 use crate::config::Config;
 use crate::progress::ProgressEvent;
-use crate::types::FileRecord;
+use crate::roms::nds;
+use crate::types::{Checksum, ChecksumSet, FileRecord};
 
 /// Scan a local zip archive and return in-archive FileRecords (checksums computed from extracted bytes)
 pub fn scan_zip_entries(
@@ -22,37 +21,708 @@ pub fn scan_zip_entries(
 ) -> anyhow::Result<Vec<FileRecord>> {
     let f = File::open(path).with_context(|| format!("opening archive: {:?}", path))?;
     let mut zip = ZipArchive::new(f)?;
-    let mut out = Vec::new();
 
+    let hash_threads = config.hash_threads.unwrap_or_else(num_cpus::get).max(1);
+    let file_entries = zip.file_names().filter(|n| !n.ends_with('/')).count();
+    let has_nested = zip.file_names().any(is_zip_name);
+    let mut hashed = if quick_crc_only(config) {
+        let mut hashed = Vec::new();
+        for i in 0..zip.len() {
+            let entry = zip.by_index_raw(i)?;
+            if entry.is_file() {
+                let name = entry.name().to_string();
+                send_zip_entry_progress(progress.as_ref(), path, &name, entry.size());
+                hashed.push((i, name, entry.size(), central_directory_checksums(&entry)));
+            }
+        }
+        hashed
+    } else if hash_threads > 1 && file_entries > 1 {
+        hash_zip_entries_parallel(zip, config, hash_threads, progress.as_ref(), path)?
+    } else {
+        let mut hashed = Vec::new();
+        for i in 0..zip.len() {
+            let mut entry = zip.by_index(i)?;
+            if entry.is_file() {
+                let name = entry.name().to_string();
+                let mut guarded = DecompressionGuard::for_entry(&mut entry, config);
+                let result = compute_checksums_stream(&mut guarded, config);
+                if let Some(reason) = guarded.tripped {
+                    warn_skipped_entry(path, &name, &reason);
+                    continue;
+                }
+                let (checksums, size) = result?;
+                send_zip_entry_progress(progress.as_ref(), path, &name, size);
+                hashed.push((i, name, size, checksums));
+            }
+        }
+        hashed
+    };
+
+    if config.trim_scan_archives {
+        let mut zip = ZipArchive::new(File::open(path)?)?;
+        for (i, name, size, checksums) in hashed.iter_mut() {
+            if !has_nds_extension(name) {
+                continue;
+            }
+            let mut entry = zip.by_index(*i)?;
+            let mut guarded = DecompressionGuard::for_entry(&mut entry, config);
+            if let Some(untrimmed) = untrimmed_checksums(&mut guarded, *size, config)? {
+                (*checksums, *size) = untrimmed;
+            }
+        }
+    }
+
+    if has_nested {
+        let mut zip = ZipArchive::new(File::open(path)?)?;
+        let mut nested = Vec::new();
+        scan_nested_zips(&mut zip, path, "", config, 1, &mut nested)?;
+        let offset = hashed.len();
+        hashed.extend(
+            nested
+                .into_iter()
+                .enumerate()
+                .map(|(i, (name, size, checksums))| (offset + i, name, size, checksums)),
+        );
+    }
+
+    Ok(hashed
+        .into_iter()
+        .map(|(_, name, size, checksums)| FileRecord {
+            source: path.to_path_buf(),
+            relative: Path::new(&name).to_path_buf(),
+            size,
+            checksums,
+            letter_dir: None,
+            derived_platform: None,
+            derived_genres: Vec::new(),
+            derived_region: None,
+            derived_languages: Vec::new(),
+            scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
+        })
+        .collect())
+}
+
+/// Hash a single member of a zip (`-i archive.zip#member`) without touching the others.
+pub fn scan_zip_member(path: &Path, member: &str, config: &Config) -> anyhow::Result<FileRecord> {
+    let f = File::open(path).with_context(|| format!("opening archive: {:?}", path))?;
+    let mut zip =
+        ZipArchive::new(f).with_context(|| format!("reading {path:?} as a zip archive"))?;
+    let mut entry = zip
+        .by_name(member)
+        .map_err(|_| anyhow::anyhow!("archive member {member:?} not found in {path:?}"))?;
+    if !entry.is_file() {
+        anyhow::bail!("archive member {member:?} in {path:?} is not a file");
+    }
+    let mut guarded = DecompressionGuard::for_entry(&mut entry, config);
+    let result = compute_checksums_stream(&mut guarded, config);
+    if let Some(reason) = guarded.tripped {
+        anyhow::bail!("archive member {member:?} in {path:?} skipped: {reason}");
+    }
+    let (checksums, size) = result?;
+    Ok(FileRecord {
+        source: path.to_path_buf(),
+        relative: Path::new(member).to_path_buf(),
+        size,
+        checksums,
+        letter_dir: None,
+        derived_platform: None,
+        derived_genres: Vec::new(),
+        derived_region: None,
+        derived_languages: Vec::new(),
+        scan_info: None,
+        output_name: None,
+        set_name: None,
+        input_root: None,
+        modified: None,
+        input_index: None,
+        fixed_extension: None,
+    })
+}
+
+fn has_nds_extension(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("nds"))
+}
+
+/// With `--trim-scan-archives`, hash a trimmed NDS member as its full-size
+/// `0xFF`-padded dump so it matches untrimmed DAT entries. `reader` must start at
+/// the beginning of the `size`-byte member; returns `None` for untrimmed or
+/// non-NDS data.
+fn untrimmed_checksums<R: Read>(
+    reader: &mut R,
+    size: u64,
+    config: &Config,
+) -> anyhow::Result<Option<(ChecksumSet, u64)>> {
+    let mut header = Vec::with_capacity(nds::HEADER_LEN);
+    reader
+        .by_ref()
+        .take(nds::HEADER_LEN as u64)
+        .read_to_end(&mut header)?;
+    let Some(untrimmed) = nds::untrimmed_size(&header, size) else {
+        return Ok(None);
+    };
+    let padded = nds::padded_reader(&header, reader, size, untrimmed);
+    Ok(Some(compute_checksums_stream(padded, config)?))
+}
+
+/// Quick mode can trust the CRC32 stored in archive headers when nothing stronger is needed.
+/// `config` must be the one inputs are scanned with (see
+/// [`crate::dat::effective_scan_config`]), which hashes SHA1 unless told otherwise.
+pub(crate) fn quick_crc_only(config: &Config) -> bool {
+    config.input_checksum_quick && checksum_targets(config) == [Checksum::Crc32]
+}
+
+fn is_zip_name(name: &str) -> bool {
+    name.to_ascii_lowercase().ends_with(".zip")
+}
+
+/// Checksums for a zip entry taken from its central directory record, without decompressing.
+fn central_directory_checksums(entry: &zip::read::ZipFile) -> ChecksumSet {
+    ChecksumSet {
+        crc32: Some(format!("{:08x}", entry.crc32())),
+        md5: None,
+        sha1: None,
+        sha256: None,
+    }
+}
+
+/// How many levels of zips inside zips are opened.
+const MAX_NESTED_ZIP_DEPTH: usize = 4;
+
+/// Nested zips are opened in memory, so larger ones are only recorded as a whole.
+const NESTED_ZIP_MAX_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Open every `.zip` entry of `zip` in memory and record its members as
+/// `<entry>/<member>`, recursing up to `MAX_NESTED_ZIP_DEPTH`. Quick mode applies at every
+/// level, so inner members use their central-directory CRCs.
+fn scan_nested_zips<R: std::io::Read + std::io::Seek>(
+    zip: &mut ZipArchive<R>,
+    path: &Path,
+    prefix: &str,
+    config: &Config,
+    depth: usize,
+    out: &mut Vec<(String, u64, ChecksumSet)>,
+) -> anyhow::Result<()> {
+    use std::io::Read;
+
+    if depth > MAX_NESTED_ZIP_DEPTH {
+        return Ok(());
+    }
+    let quick = quick_crc_only(config);
     for i in 0..zip.len() {
         let mut entry = zip.by_index(i)?;
-        if entry.is_file() {
-            let name = entry.name().to_string();
-            let (checksums, size) = compute_checksums_stream(&mut entry, config)?;
-            let rec = FileRecord {
-                source: path.to_path_buf(),
-                relative: Path::new(&name).to_path_buf(),
-                size,
-                checksums,
-                letter_dir: None,
-                derived_platform: None,
-                derived_genres: Vec::new(),
-                derived_region: None,
-                derived_languages: Vec::new(),
-                scan_info: None,
-            };
-            out.push(rec);
+        if !entry.is_file() || !is_zip_name(entry.name()) {
+            continue;
+        }
+        let outer_name = format!("{prefix}{}", entry.name());
+        if entry.size() > NESTED_ZIP_MAX_BYTES {
+            warn_skipped_entry(path, &outer_name, "too large to open as a nested zip");
+            continue;
+        }
+        let mut bytes = Vec::new();
+        let mut guarded = DecompressionGuard::for_entry(&mut entry, config);
+        let result = guarded.read_to_end(&mut bytes);
+        if let Some(reason) = guarded.tripped {
+            warn_skipped_entry(path, &outer_name, &reason);
+            continue;
+        }
+        result?;
+        drop(entry);
 
-            if let Some(tx) = progress.as_ref() {
-                let hint = path.join(Path::new(&name));
-                let _ = tx.send(ProgressEvent::hashing(hint, size, Some(size)));
+        let Ok(mut inner) = ZipArchive::new(std::io::Cursor::new(bytes)) else {
+            // not actually a zip; the entry itself was already recorded
+            continue;
+        };
+        for j in 0..inner.len() {
+            let name;
+            let size;
+            let checksums;
+            if quick {
+                let member = inner.by_index_raw(j)?;
+                if !member.is_file() {
+                    continue;
+                }
+                name = member.name().to_string();
+                size = member.size();
+                checksums = central_directory_checksums(&member);
+            } else {
+                let mut member = inner.by_index(j)?;
+                if !member.is_file() {
+                    continue;
+                }
+                name = member.name().to_string();
+                let mut guarded = DecompressionGuard::for_entry(&mut member, config);
+                let result = compute_checksums_stream(&mut guarded, config);
+                if let Some(reason) = guarded.tripped {
+                    warn_skipped_entry(path, &format!("{outer_name}/{name}"), &reason);
+                    continue;
+                }
+                match result {
+                    Ok(computed) => (checksums, size) = computed,
+                    Err(e) => {
+                        warn_skipped_entry(path, &format!("{outer_name}/{name}"), &e.to_string());
+                        continue;
+                    }
+                }
+            }
+            out.push((format!("{outer_name}/{name}"), size, checksums));
+        }
+        scan_nested_zips(
+            &mut inner,
+            path,
+            &format!("{outer_name}/"),
+            config,
+            depth + 1,
+            out,
+        )?;
+    }
+    Ok(())
+}
+
+/// Entries larger than this are hashed while streaming instead of being buffered for the
+/// hashing pool, so a zip full of disc images can't exhaust memory.
+const PARALLEL_ZIP_ENTRY_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+type HashedZipEntry = (usize, String, u64, ChecksumSet);
+
+/// Decompress entries on a producer thread and hash them on a pool of `threads` workers.
+/// The channel holds at most `threads` buffered entries; results are returned in archive order.
+fn hash_zip_entries_parallel(
+    mut zip: ZipArchive<File>,
+    config: &Config,
+    threads: usize,
+    progress: Option<&Sender<ProgressEvent>>,
+    path: &Path,
+) -> anyhow::Result<Vec<HashedZipEntry>> {
+    use rayon::prelude::*;
+    use std::io::Read;
+
+    // Members are hashed on the pool we are already running on (the global pool, or the
+    // `--archive-threads` pool) rather than a pool built per archive.
+    let (tx, rx) = mpsc::sync_channel::<(usize, String, Vec<u8>)>(threads);
+
+    let (streamed, buffered) = std::thread::scope(|scope| {
+        let producer = scope.spawn(move || -> anyhow::Result<Vec<HashedZipEntry>> {
+            let mut streamed = Vec::new();
+            for i in 0..zip.len() {
+                let mut entry = zip.by_index(i)?;
+                if !entry.is_file() {
+                    continue;
+                }
+                let name = entry.name().to_string();
+                let declared = entry.size();
+                let mut guarded = DecompressionGuard::for_entry(&mut entry, config);
+                if declared > PARALLEL_ZIP_ENTRY_MAX_BYTES {
+                    let result = compute_checksums_stream(&mut guarded, config);
+                    if let Some(reason) = guarded.tripped {
+                        warn_skipped_entry(path, &name, &reason);
+                        continue;
+                    }
+                    let (checksums, size) = result?;
+                    send_zip_entry_progress(progress, path, &name, size);
+                    streamed.push((i, name, size, checksums));
+                    continue;
+                }
+                let mut buf = Vec::with_capacity(declared as usize);
+                let result = guarded.read_to_end(&mut buf);
+                if let Some(reason) = guarded.tripped {
+                    warn_skipped_entry(path, &name, &reason);
+                    continue;
+                }
+                result?;
+                if tx.send((i, name, buf)).is_err() {
+                    // the hashing side failed and hung up; its error is reported instead
+                    break;
+                }
+            }
+            Ok(streamed)
+        });
+
+        let buffered: anyhow::Result<Vec<HashedZipEntry>> = rx
+            .into_iter()
+            .par_bridge()
+            .map(|(i, name, buf)| {
+                let checksums = compute_checksums_from_bytes(&buf, config)?;
+                let size = buf.len() as u64;
+                send_zip_entry_progress(progress, path, &name, size);
+                Ok((i, name, size, checksums))
+            })
+            .collect();
+
+        let streamed = producer
+            .join()
+            .map_err(|_| anyhow::anyhow!("zip decompression thread panicked"))?;
+        Ok::<_, anyhow::Error>((streamed, buffered))
+    })?;
+
+    let mut hashed = buffered?;
+    hashed.extend(streamed?);
+    hashed.sort_by_key(|(i, ..)| *i);
+    Ok(hashed)
+}
+
+/// Expansion ratio above which an entry is treated as a zip bomb. Deflate tops out around
+/// 1032:1, so only crafted (or zstd-packed) data gets here.
+const MAX_EXPANSION_RATIO: u64 = 1100;
+
+/// Ratios are only judged once an entry has produced this much, so tiny files never trip it.
+const EXPANSION_CHECK_MIN_BYTES: u64 = 1024 * 1024;
+
+/// Counts the bytes actually decompressed from an archive entry (declared sizes can lie) and
+/// fails the read once `--max-decompressed-size` or the expansion ratio limit is exceeded.
+/// `tripped` records why, so callers can skip just that entry.
+struct DecompressionGuard<R> {
+    inner: R,
+    limit: Option<u64>,
+    compressed: u64,
+    produced: u64,
+    tripped: Option<String>,
+}
+
+impl<'a, 'b> DecompressionGuard<&'a mut zip::read::ZipFile<'b>> {
+    fn for_entry(entry: &'a mut zip::read::ZipFile<'b>, config: &Config) -> Self {
+        let limit = config.max_decompressed_size;
+        let tripped = limit.filter(|max| entry.size() > *max).map(|max| {
+            format!(
+                "declared size {} exceeds --max-decompressed-size {max}",
+                entry.size()
+            )
+        });
+        DecompressionGuard {
+            compressed: entry.compressed_size(),
+            inner: entry,
+            limit,
+            produced: 0,
+            tripped,
+        }
+    }
+}
+
+impl<R: std::io::Read> std::io::Read for DecompressionGuard<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if let Some(reason) = &self.tripped {
+            return Err(std::io::Error::other(reason.clone()));
+        }
+        let n = self.inner.read(buf)?;
+        self.produced = self.produced.saturating_add(n as u64);
+        if let Some(max) = self.limit {
+            if self.produced > max {
+                self.tripped = Some(format!(
+                    "decompressed size exceeds --max-decompressed-size {max}"
+                ));
+            } else if self.produced > EXPANSION_CHECK_MIN_BYTES
+                && self.produced / self.compressed.max(1) > MAX_EXPANSION_RATIO
+            {
+                self.tripped = Some(format!(
+                    "expansion ratio exceeds {MAX_EXPANSION_RATIO}:1 ({} compressed bytes)",
+                    self.compressed
+                ));
             }
         }
+        match &self.tripped {
+            Some(reason) => Err(std::io::Error::other(reason.clone())),
+            None => Ok(n),
+        }
+    }
+}
+
+fn warn_skipped_entry(path: &Path, name: &str, reason: &str) {
+    vwarn!(
+        "skipping {} in {}: {}",
+        name,
+        path.to_string_lossy(),
+        reason
+    );
+}
+
+fn send_zip_entry_progress(
+    progress: Option<&Sender<ProgressEvent>>,
+    path: &Path,
+    name: &str,
+    size: u64,
+) {
+    if let Some(tx) = progress {
+        let hint = path.join(Path::new(name));
+        let _ = tx.send(ProgressEvent::hashing(hint, size, Some(size)));
+    }
+}
+
+/// Whether the scanner lists the members of `path`: zip, rar and (optionally gzipped) tar.
+pub fn scans_archive_members(path: &Path) -> bool {
+    let name = lowercase_file_name(path);
+    name.ends_with(".zip") || name.ends_with(".rar") || is_tar_name(&name)
+}
+
+/// Scan the members of an archive accepted by [`scans_archive_members`] with the reader its
+/// name calls for.
+pub fn scan_archive_entries(
+    path: &Path,
+    config: &Config,
+    progress: Option<Sender<ProgressEvent>>,
+) -> anyhow::Result<Vec<FileRecord>> {
+    scan_archive_entries_as(path, path, config, progress)
+}
+
+/// [`scan_archive_entries`] for an archive whose format is named by `kind` rather than by
+/// its own path, e.g. a temporary file holding an archive member.
+pub fn scan_archive_entries_as(
+    path: &Path,
+    kind: &Path,
+    config: &Config,
+    progress: Option<Sender<ProgressEvent>>,
+) -> anyhow::Result<Vec<FileRecord>> {
+    let name = lowercase_file_name(kind);
+    if name.ends_with(".rar") {
+        scan_rar_entries(path, config, progress)
+    } else if is_tar_name(&name) {
+        scan_tar_entries(path, config, progress)
+    } else {
+        scan_zip_entries(path, config, progress)
+    }
+}
+
+fn lowercase_file_name(path: &Path) -> String {
+    path.file_name()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+fn is_tar_name(name: &str) -> bool {
+    name.ends_with(".tar") || is_gzip_tar_name(name)
+}
+
+fn is_gzip_tar_name(name: &str) -> bool {
+    name.ends_with(".tar.gz") || name.ends_with(".tgz")
+}
+
+/// Open a `.tar`, `.tar.gz` or `.tgz` archive for a single streaming pass over its entries.
+fn open_tar(path: &Path) -> anyhow::Result<tar::Archive<Box<dyn Read>>> {
+    let file = std::io::BufReader::new(
+        File::open(path).with_context(|| format!("opening archive: {:?}", path))?,
+    );
+    let reader: Box<dyn Read> = if is_gzip_tar_name(&lowercase_file_name(path)) {
+        Box::new(flate2::read::GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+/// In-archive path of a tar entry, keeping only normal components so `./` prefixes and
+/// `..` never leave the archive. `None` for anything but a regular file.
+fn tar_member_path<R: Read>(entry: &tar::Entry<R>) -> anyhow::Result<Option<PathBuf>> {
+    if !matches!(
+        entry.header().entry_type(),
+        tar::EntryType::Regular | tar::EntryType::Continuous
+    ) {
+        return Ok(None);
+    }
+    let name = entry.path()?;
+    // pre-POSIX tars mark directories with a trailing slash only
+    if name.to_string_lossy().ends_with('/') {
+        return Ok(None);
+    }
+    let relative: PathBuf = name
+        .components()
+        .filter(|c| matches!(c, std::path::Component::Normal(_)))
+        .collect();
+    Ok((!relative.as_os_str().is_empty()).then_some(relative))
+}
+
+/// Hash every regular file of a `.tar`, `.tar.gz` or `.tgz` archive in one streaming pass.
+/// Directories, links and other special entries are skipped.
+pub fn scan_tar_entries(
+    path: &Path,
+    config: &Config,
+    progress: Option<Sender<ProgressEvent>>,
+) -> anyhow::Result<Vec<FileRecord>> {
+    let mut archive = open_tar(path)?;
+    let mut out = Vec::new();
+    for entry in archive
+        .entries()
+        .with_context(|| format!("reading tar archive: {:?}", path))?
+    {
+        let entry = entry.with_context(|| format!("reading tar archive: {:?}", path))?;
+        let Some(relative) = tar_member_path(&entry)? else {
+            continue;
+        };
+        let name = relative.to_string_lossy().into_owned();
+        let size = entry.size();
+        if let Some(max) = config.max_decompressed_size.filter(|max| size > *max) {
+            warn_skipped_entry(
+                path,
+                &name,
+                &format!("size {size} exceeds --max-decompressed-size {max}"),
+            );
+            continue;
+        }
+        let (checksums, size) = compute_checksums_stream(entry, config)?;
+        send_zip_entry_progress(progress.as_ref(), path, &name, size);
+        out.push(FileRecord {
+            source: path.to_path_buf(),
+            relative,
+            size,
+            checksums,
+            letter_dir: None,
+            derived_platform: None,
+            derived_genres: Vec::new(),
+            derived_region: None,
+            derived_languages: Vec::new(),
+            scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
+        });
     }
 
     Ok(out)
 }
 
+/// Whether [`copy_archive_member`] can read a single member out of `archive`.
+pub fn copies_archive_members(archive: &Path) -> bool {
+    let name = lowercase_file_name(archive);
+    is_zip_name(&name) || is_tar_name(&name) || name.ends_with(".rar")
+}
+
+/// Stream the member `member` of `archive` into `out`, returning the bytes written.
+pub fn copy_archive_member(
+    archive: &Path,
+    member: &Path,
+    out: &mut dyn std::io::Write,
+) -> anyhow::Result<u64> {
+    let name = lowercase_file_name(archive);
+    if is_tar_name(&name) {
+        let mut tar = open_tar(archive)?;
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            if tar_member_path(&entry)?.as_deref() == Some(member) {
+                return Ok(std::io::copy(&mut entry, out)?);
+            }
+        }
+        anyhow::bail!("{:?} has no member {:?}", archive, member);
+    }
+    if name.ends_with(".rar") {
+        return copy_rar_member(archive, member, out);
+    }
+    if is_zip_name(&name) {
+        let zip = ZipArchive::new(
+            File::open(archive).with_context(|| format!("opening archive: {:?}", archive))?,
+        )?;
+        let member_name = member.to_string_lossy().replace('\\', "/");
+        return copy_zip_member(zip, &member_name, out)
+            .with_context(|| format!("reading {:?} from {:?}", member, archive));
+    }
+    anyhow::bail!("cannot read single members out of {:?}", archive)
+}
+
+/// Whether `path` names an archive igir can read: a zip, or anything [`looks_like_external_archive`].
+pub fn is_archive_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+        || looks_like_external_archive(path)
+}
+
+/// Whether `path` has the suffix of an archive format read through the external `7z` tool.
+pub fn looks_like_external_archive(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    const SUFFIXES: &[&str] = &[
+        ".7z",
+        ".rar",
+        ".tar",
+        ".tar.gz",
+        ".tgz",
+        ".tar.bz2",
+        ".tbz",
+        ".tbz2",
+        ".tar.xz",
+        ".txz",
+        ".tar.zst",
+        ".tzst",
+        ".tar.lz",
+        ".tar.lzma",
+        ".tlz",
+    ];
+
+    SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+/// Read every entry of a zip or 7z archive and compare it with the CRC stored in the archive,
+/// without writing anything to disk. Returns a description of the first problem found, or
+/// `None` when the archive is intact.
+pub fn verify_archive(path: &Path) -> anyhow::Result<Option<String>> {
+    let is_7z = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("7z"));
+    if is_7z {
+        return verify_7z(path);
+    }
+
+    let f = File::open(path).with_context(|| format!("opening archive: {:?}", path))?;
+    let mut zip = match ZipArchive::new(f) {
+        Ok(zip) => zip,
+        Err(e) => return Ok(Some(format!("unreadable zip: {e}"))),
+    };
+    for i in 0..zip.len() {
+        let mut entry = match zip.by_index(i) {
+            Ok(entry) => entry,
+            Err(e) => return Ok(Some(format!("entry #{i}: {e}"))),
+        };
+        if !entry.is_file() {
+            continue;
+        }
+        // the zip reader checks the stored CRC once the entry has been read to the end
+        if let Err(e) = std::io::copy(&mut entry, &mut std::io::sink()) {
+            return Ok(Some(format!("{}: {e}", entry.name())));
+        }
+    }
+    Ok(None)
+}
+
+fn verify_7z(path: &Path) -> anyhow::Result<Option<String>> {
+    let exe = which::which("7z")
+        .or_else(|_| which::which("7za"))
+        .map_err(|_| anyhow::anyhow!("7z not found; cannot verify {:?}", path))?;
+    let output = Command::new(&exe)
+        .arg("t")
+        .arg(path.as_os_str())
+        .output()
+        .with_context(|| format!("running 7z to test archive: {:?}", path))?;
+    if output.status.success() {
+        return Ok(None);
+    }
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let detail = text
+        .lines()
+        .map(str::trim)
+        .rfind(|l| l.contains("ERROR") || l.contains("CRC Failed") || l.contains("Data Error"))
+        .unwrap_or("7z reported errors")
+        .to_string();
+    Ok(Some(detail))
+}
+
 /// Try to list entries from a 7z archive and extract a specific entry to bytes using the system 7z binary.
 /// This is a pragmatic approach when no native crate is available.
 pub fn scan_7z_entries(
@@ -65,6 +735,14 @@ pub fn scan_7z_entries(
         Ok(path) => path,
         Err(_) => return Ok(Vec::new()),
     };
+
+    // quick mode: trust the CRC32 values stored in the archive headers when nothing stronger is needed
+    if quick_crc_only(config)
+        && let Some(records) = quick_7z_records(&exe, path, progress.as_ref())?
+    {
+        return Ok(records);
+    }
+
     // list entries
     let output = Command::new(&exe)
         .arg("l")
@@ -139,6 +817,104 @@ pub fn scan_7z_entries(
     extract_7z_to_temp_and_scan(&exe, path, Some(&names), config, progress)
 }
 
+/// Build records from the header CRC32 values printed by `7z l -slt`, without extracting anything.
+/// Returns `None` when the listing fails or any file entry lacks a CRC so callers can fall back to extraction.
+fn quick_7z_records(
+    exe: &std::path::PathBuf,
+    path: &Path,
+    progress: Option<&Sender<ProgressEvent>>,
+) -> anyhow::Result<Option<Vec<FileRecord>>> {
+    let output = Command::new(exe)
+        .arg("l")
+        .arg("-slt")
+        .arg(path.as_os_str())
+        .output()
+        .with_context(|| format!("running 7z to list archive: {:?}", path))?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let Some(entries) = parse_7z_slt_entries(&text) else {
+        return Ok(None);
+    };
+
+    let mut out = Vec::new();
+    for (name, size, crc32) in entries {
+        if let Some(tx) = progress {
+            let _ = tx.send(ProgressEvent::hashing(path.join(&name), size, Some(size)));
+        }
+        out.push(FileRecord {
+            source: path.to_path_buf(),
+            relative: Path::new(&name).to_path_buf(),
+            size,
+            checksums: ChecksumSet {
+                crc32: Some(crc32),
+                md5: None,
+                sha1: None,
+                sha256: None,
+            },
+            letter_dir: None,
+            derived_platform: None,
+            derived_genres: Vec::new(),
+            derived_region: None,
+            derived_languages: Vec::new(),
+            scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
+        });
+    }
+
+    Ok(Some(out))
+}
+
+/// Parse the technical (`-slt`) listing into `(path, size, crc32)` tuples for every file entry.
+fn parse_7z_slt_entries(text: &str) -> Option<Vec<(String, u64, String)>> {
+    // the archive's own properties precede the first dashed separator; entries follow it
+    let (_, body) = text.split_once("\n----------")?;
+
+    let mut entries = Vec::new();
+    for block in body.split("\n\n") {
+        let mut name = None;
+        let mut size = 0u64;
+        let mut crc = None;
+        let mut is_dir = false;
+
+        for line in block.lines() {
+            let Some((key, value)) = line.split_once(" = ") else {
+                continue;
+            };
+            match key.trim() {
+                "Path" => name = Some(value.trim().to_string()),
+                "Size" => size = value.trim().parse().unwrap_or(0),
+                "CRC" if !value.trim().is_empty() => crc = Some(value.trim().to_lowercase()),
+                "Folder" => is_dir |= value.trim() == "+",
+                "Attributes" => is_dir |= value.trim().starts_with('D'),
+                _ => {}
+            }
+        }
+
+        let Some(name) = name else {
+            continue;
+        };
+        if is_dir {
+            continue;
+        }
+        entries.push((name, size, crc?));
+    }
+
+    if entries.is_empty() {
+        None
+    } else {
+        Some(entries)
+    }
+}
+
 fn extract_7z_to_temp_and_scan(
     exe: &std::path::PathBuf,
     path: &Path,
@@ -172,6 +948,111 @@ fn extract_7z_to_temp_and_scan(
         return Ok(Vec::new());
     }
 
+    scan_extracted_dir(path, tmp_path, config, progress)
+}
+
+/// List and hash the entries of a RAR archive. The `unrar` binary is preferred; `7z`/`7za`
+/// read RAR as well when built with RAR support. Returns no records when neither is installed.
+pub fn scan_rar_entries(
+    path: &Path,
+    config: &Config,
+    progress: Option<Sender<ProgressEvent>>,
+) -> anyhow::Result<Vec<FileRecord>> {
+    let exe = match which::which("unrar") {
+        Ok(path) => path,
+        Err(_) => return scan_7z_entries(path, config, progress),
+    };
+
+    let tmp = tempfile::tempdir()?;
+    // x -y -idq <archive> <dir>/ keeps the in-archive folders and never prompts
+    let status = Command::new(&exe)
+        .arg("x")
+        .arg("-y")
+        .arg("-idq")
+        .arg(path.as_os_str())
+        .arg(format!(
+            "{}{}",
+            tmp.path().display(),
+            std::path::MAIN_SEPARATOR
+        ))
+        .status()
+        .with_context(|| format!("extracting rar archive to tempdir: {:?}", path))?;
+
+    if !status.success() {
+        return Ok(Vec::new());
+    }
+
+    scan_extracted_dir(path, tmp.path(), config, progress)
+}
+
+/// Stream the entry `name` of `zip` to `out`. Names reaching into a nested zip
+/// (`inner.zip/rom.bin`, as [`scan_zip_entries`] reports them) are followed into it.
+fn copy_zip_member<R: Read + std::io::Seek>(
+    mut zip: ZipArchive<R>,
+    name: &str,
+    out: &mut dyn std::io::Write,
+) -> anyhow::Result<u64> {
+    if zip.index_for_name(name).is_some() {
+        return Ok(std::io::copy(&mut zip.by_name(name)?, out)?);
+    }
+    let nested = name
+        .match_indices('/')
+        .map(|(idx, _)| (&name[..idx], &name[idx + 1..]))
+        .find(|(inner, _)| is_zip_name(inner) && zip.index_for_name(inner).is_some());
+    let Some((inner, rest)) = nested else {
+        anyhow::bail!("no member {name:?}");
+    };
+    let mut bytes = Vec::new();
+    zip.by_name(inner)?.read_to_end(&mut bytes)?;
+    copy_zip_member(ZipArchive::new(std::io::Cursor::new(bytes))?, rest, out)
+}
+
+/// Stream one RAR member to `out` with `unrar p`, falling back to `7z e -so` like
+/// [`scan_rar_entries`] does.
+fn copy_rar_member(
+    archive: &Path,
+    member: &Path,
+    out: &mut dyn std::io::Write,
+) -> anyhow::Result<u64> {
+    let mut command = if let Ok(unrar) = which::which("unrar") {
+        let mut command = Command::new(unrar);
+        command.arg("p").arg("-inul");
+        command
+    } else {
+        let exe = which::which("7z")
+            .or_else(|_| which::which("7za"))
+            .with_context(|| format!("neither unrar nor 7z is available to read {:?}", archive))?;
+        let mut command = Command::new(exe);
+        command.arg("e").arg("-so").arg("-y");
+        command
+    };
+    let mut child = command
+        .arg(archive.as_os_str())
+        .arg(member.as_os_str())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .with_context(|| format!("reading {:?} from {:?}", member, archive))?;
+    let copied = child
+        .stdout
+        .take()
+        .map(|mut stdout| std::io::copy(&mut stdout, out))
+        .transpose()?
+        .unwrap_or(0);
+    let status = child.wait()?;
+    if !status.success() {
+        anyhow::bail!("reading {:?} from {:?} failed: {}", member, archive, status);
+    }
+    Ok(copied)
+}
+
+/// Hash every file extracted from `path` into `tmp_path`, keeping in-archive relative paths.
+fn scan_extracted_dir(
+    path: &Path,
+    tmp_path: &Path,
+    config: &Config,
+    progress: Option<Sender<ProgressEvent>>,
+) -> anyhow::Result<Vec<FileRecord>> {
     let mut out = Vec::new();
     for entry in walkdir::WalkDir::new(tmp_path)
         .into_iter()
@@ -182,7 +1063,13 @@ fn extract_7z_to_temp_and_scan(
         if let Ok(mut file) = File::open(&p) {
             let rel = p.strip_prefix(tmp_path).unwrap_or(&p).to_path_buf();
             let rel_hint = rel.clone();
-            let (checksums, size) = compute_checksums_stream(&mut file, config)?;
+            let (mut checksums, mut size) = compute_checksums_stream(&mut file, config)?;
+            if config.trim_scan_archives && has_nds_extension(&rel.to_string_lossy()) {
+                let mut file = File::open(&p)?;
+                if let Some(untrimmed) = untrimmed_checksums(&mut file, size, config)? {
+                    (checksums, size) = untrimmed;
+                }
+            }
             out.push(FileRecord {
                 source: path.to_path_buf(),
                 relative: rel,
@@ -194,6 +1081,12 @@ fn extract_7z_to_temp_and_scan(
                 derived_region: None,
                 derived_languages: Vec::new(),
                 scan_info: None,
+                output_name: None,
+                set_name: None,
+                input_root: None,
+                modified: None,
+                input_index: None,
+                fixed_extension: None,
             });
 
             if let Some(tx) = progress.as_ref() {
@@ -232,19 +1125,28 @@ mod tests {
             input: Vec::new(),
             input_exclude: Vec::new(),
             input_checksum_quick: false,
+            input_checksum_auto: false,
+            scan_files_only: false,
+            scan_archives_only: false,
             input_checksum_min: crate::types::Checksum::Crc32,
             input_checksum_max: Some(crate::types::Checksum::Sha256),
             input_checksum_archives: crate::types::ArchiveChecksumMode::Auto,
             dat: Vec::new(),
             dat_exclude: Vec::new(),
+            dat_url: vec![],
             dat_name_regex: None,
             dat_name_regex_exclude: None,
+            game_name_regex: None,
+            game_name_regex_exclude: None,
+            system: None,
+            dat_name_rewrite: None,
             dat_description_regex: None,
             dat_description_regex_exclude: None,
             dat_combine: false,
             dat_ignore_parent_clone: false,
             list_unmatched_dats: false,
             print_plan: true,
+            summary_only: false,
             enable_hasheous: false,
             igdb_client_id: None,
             igdb_client_secret: None,
@@ -255,24 +1157,37 @@ mod tests {
             patch_exclude: Vec::new(),
             output: None,
             dir_mirror: false,
+            dir_mirror_root: false,
             dir_dat_mirror: false,
             dir_dat_name: false,
             dir_dat_description: false,
             dir_letter: false,
+            dir_region: false,
             dir_letter_count: None,
             dir_letter_limit: None,
             dir_letter_group: false,
+            dir_letter_ignore_articles: false,
             dir_game_subdir: crate::types::DirGameSubdirMode::Never,
             fix_extension: crate::types::FixExtensionMode::Never,
+            normalize_extension_case: false,
+            shorten_paths: false,
             overwrite: false,
             overwrite_invalid: false,
+            on_collision: crate::types::CollisionPolicy::Skip,
+            quarantine: None,
+            no_space_check: false,
             move_delete_dirs: crate::types::MoveDeleteDirsMode::Never,
+            extract_include: None,
+            extract_exclude: None,
             clean_exclude: Vec::new(),
             clean_backup: None,
+            clean_backup_keep: None,
             clean_dry_run: false,
             zip_format: crate::types::ZipFormat::Torrentzip,
-            zip_exclude: None,
+            zip_exclude: vec![],
             zip_dat_name: false,
+            zip_comment: None,
+            to_chd: false,
             link_mode: crate::types::LinkMode::Hardlink,
             symlink_relative: false,
             header: None,
@@ -287,8 +1202,12 @@ mod tests {
             filter_regex: None,
             filter_regex_exclude: None,
             filter_language: None,
+            keep_languages: None,
+            keep_top_n: None,
             filter_region: None,
             filter_category_regex: None,
+            only_genre: None,
+            only_platform: None,
             no_bios: false,
             no_device: false,
             no_unlicensed: false,
@@ -300,13 +1219,43 @@ mod tests {
             no_prototype: false,
             no_program: false,
             verbose: 0,
+            no_color: false,
             quiet: 0,
             diag: false,
+            plan_diff: false,
+            region_map: None,
+            profile: false,
+            log_style: crate::types::LogStyle::Default,
+            exec: None,
             cache_only: false,
             cache_db: None,
+            xattr_cache: false,
             hash_threads: None,
             scan_threads: None,
+            archive_threads: None,
             show_match_reasons: false,
+            min_score: None,
+            tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+            priority_input: false,
+            title_strip_regex: vec![],
+            ignore_case: true,
+            output_structure: None,
+            threads: None,
+            strict: false,
+            allow_no_matches: false,
+            dat_best_match: false,
+            dat_priority: Vec::new(),
+            dat_diff: None,
+            fixdat_format: None,
+            hash_algorithms: None,
+            hash_checkpoint_mib: None,
+            expect_crc32: None,
+            expect_md5: None,
+            expect_sha1: None,
+            expect_sha256: None,
+            max_decompressed_size: None,
+            skip_existing_checksum: false,
+            report_duplicates: false,
             online_timeout_secs: Some(5),
             online_max_retries: Some(3),
             online_throttle_ms: None,
@@ -330,6 +1279,256 @@ mod tests {
             .unwrap_or("");
         assert_eq!(filename, "a.txt");
     }
+
+    #[test]
+    fn trim_scan_archives_matches_trimmed_nds_members_to_full_dumps() {
+        let mut trimmed = vec![0x11u8; 4096];
+        trimmed[0x14] = 0; // 128 KiB cartridge
+        trimmed[0x15C..0x15E].copy_from_slice(&[0x56, 0xCF]);
+        let mut full = trimmed.clone();
+        full.resize(128 * 1024, 0xFF);
+
+        let f = NamedTempFile::new().unwrap();
+        {
+            let mut zipw = zip::ZipWriter::new(f.reopen().unwrap());
+            zipw.start_file::<_, ()>("Game (Trimmed).nds", FileOptions::default())
+                .unwrap();
+            zipw.write_all(&trimmed).unwrap();
+            zipw.finish().unwrap();
+        }
+
+        let mut cfg = crate::config::Config::default();
+        let full_checksums = compute_checksums_from_bytes(&full, &cfg).unwrap();
+        let dat = crate::dat::DatRom {
+            name: "Game.nds".to_string(),
+            description: Some("Game".to_string()),
+            source_dat: std::path::PathBuf::from("nds.dat"),
+            size: Some(full.len() as u64),
+            crc32: full_checksums.crc32.clone(),
+            md5: None,
+            sha1: None,
+            sha256: None,
+            match_reasons: None,
+            serial: None,
+            version: None,
+            region: None,
+            alternates: Vec::new(),
+            dat_name: None,
+            dat_description: None,
+            set_info: None,
+        };
+
+        let records = scan_zip_entries(f.path(), &cfg, None).unwrap();
+        assert_eq!(records[0].size, trimmed.len() as u64);
+        assert!(crate::dat::find_dat_for_record(&records[0], std::slice::from_ref(&dat)).is_none());
+
+        cfg.trim_scan_archives = true;
+        let records = scan_zip_entries(f.path(), &cfg, None).unwrap();
+        assert_eq!(records[0].size, full.len() as u64);
+        assert_eq!(records[0].checksums.crc32, full_checksums.crc32);
+        assert!(crate::dat::find_dat_for_record(&records[0], std::slice::from_ref(&dat)).is_some());
+    }
+
+    #[test]
+    fn max_decompressed_size_skips_oversized_entries() {
+        let f = NamedTempFile::new().unwrap();
+        {
+            let mut zipw = zip::ZipWriter::new(f.reopen().unwrap());
+            zipw.start_file::<_, ()>("small.txt", FileOptions::default())
+                .unwrap();
+            zipw.write_all(b"tiny").unwrap();
+            zipw.start_file::<_, ()>("bomb.bin", FileOptions::default())
+                .unwrap();
+            zipw.write_all(&vec![0u8; 1024 * 1024]).unwrap();
+            let zstd =
+                FileOptions::<()>::default().compression_method(zip::CompressionMethod::Zstd);
+            zipw.start_file("dense.bin", zstd).unwrap();
+            zipw.write_all(&vec![0u8; 8 * 1024 * 1024]).unwrap();
+            zipw.finish().unwrap();
+        }
+
+        for threads in [1, 4] {
+            let mut cfg = crate::config::Config {
+                hash_threads: Some(threads),
+                ..Default::default()
+            };
+            let names = |cfg: &crate::config::Config| -> Vec<String> {
+                scan_zip_entries(f.path(), cfg, None)
+                    .unwrap()
+                    .into_iter()
+                    .map(|r| r.relative.to_string_lossy().to_string())
+                    .collect()
+            };
+            assert_eq!(names(&cfg), vec!["small.txt", "bomb.bin", "dense.bin"]);
+
+            // the size limit trips on bomb.bin; dense.bin is under it but expands far too much
+            cfg.max_decompressed_size = Some(1000);
+            assert_eq!(names(&cfg), vec!["small.txt"], "threads={threads}");
+            cfg.max_decompressed_size = Some(1 << 30);
+            assert_eq!(
+                names(&cfg),
+                vec!["small.txt", "bomb.bin"],
+                "threads={threads}"
+            );
+        }
+    }
+
+    #[test]
+    fn quick_mode_reads_nested_zip_crcs_from_central_directory() {
+        let mut inner = std::io::Cursor::new(Vec::new());
+        {
+            let mut zipw = zip::ZipWriter::new(&mut inner);
+            let stored =
+                FileOptions::<()>::default().compression_method(zip::CompressionMethod::Stored);
+            zipw.start_file("rom.bin", stored).unwrap();
+            zipw.write_all(b"original data").unwrap();
+            zipw.finish().unwrap();
+        }
+        // damage the stored bytes: only the central directory still knows the real CRC
+        let mut inner = inner.into_inner();
+        let pos = inner
+            .windows(b"original data".len())
+            .position(|w| w == b"original data")
+            .unwrap();
+        inner[pos] = b'O';
+
+        let f = NamedTempFile::new().unwrap();
+        {
+            let mut zipw = zip::ZipWriter::new(f.reopen().unwrap());
+            zipw.start_file::<_, ()>("inner.zip", FileOptions::default())
+                .unwrap();
+            zipw.write_all(&inner).unwrap();
+            zipw.finish().unwrap();
+        }
+
+        let mut cfg = crate::config::Config {
+            input_checksum_quick: true,
+            input_checksum_auto: false,
+            scan_files_only: false,
+            scan_archives_only: false,
+            input_checksum_min: crate::types::Checksum::Crc32,
+            input_checksum_max: None,
+            ..Default::default()
+        };
+        let recs = scan_zip_entries(f.path(), &cfg, None).unwrap();
+        let nested = recs
+            .iter()
+            .find(|r| r.relative == Path::new("inner.zip/rom.bin"))
+            .expect("nested member recorded");
+        let expected = format!("{:08x}", crc32fast::hash(b"original data"));
+        assert_eq!(nested.checksums.crc32.as_deref(), Some(expected.as_str()));
+        assert_eq!(nested.size, 13);
+        assert!(recs.iter().any(|r| r.relative == Path::new("inner.zip")));
+
+        // decompressing the damaged member fails its CRC check, so it is skipped
+        cfg.input_checksum_quick = false;
+        let recs = scan_zip_entries(f.path(), &cfg, None).unwrap();
+        assert!(
+            recs.iter()
+                .all(|r| r.relative != Path::new("inner.zip/rom.bin"))
+        );
+    }
+
+    #[test]
+    fn verify_archive_reports_crc_mismatch() {
+        let f = NamedTempFile::new().unwrap();
+        {
+            let mut zipw = zip::ZipWriter::new(f.reopen().unwrap());
+            let stored =
+                FileOptions::<()>::default().compression_method(zip::CompressionMethod::Stored);
+            zipw.start_file("good.bin", stored).unwrap();
+            zipw.write_all(b"intact payload").unwrap();
+            zipw.start_file("bad.bin", stored).unwrap();
+            zipw.write_all(b"original bytes").unwrap();
+            zipw.finish().unwrap();
+        }
+        assert_eq!(verify_archive(f.path()).unwrap(), None);
+
+        // flip the stored data of bad.bin so it no longer matches its recorded CRC
+        let mut bytes = std::fs::read(f.path()).unwrap();
+        let pos = bytes
+            .windows(b"original bytes".len())
+            .position(|w| w == b"original bytes")
+            .unwrap();
+        bytes[pos] = b'O';
+        std::fs::write(f.path(), &bytes).unwrap();
+
+        let problem = verify_archive(f.path())
+            .unwrap()
+            .expect("corruption detected");
+        assert!(problem.contains("bad.bin"), "{problem}");
+    }
+
+    #[test]
+    fn scan_zip_entries_hashes_in_order_for_any_thread_count() {
+        let f = NamedTempFile::new().unwrap();
+        let payloads: Vec<(String, Vec<u8>)> = (0..12)
+            .map(|i| (format!("dir/rom{i:02}.bin"), vec![i as u8; 1000 + i * 997]))
+            .collect();
+        {
+            let mut zipw = zip::ZipWriter::new(f.reopen().unwrap());
+            zipw.add_directory::<_, ()>("dir/", FileOptions::default())
+                .unwrap();
+            for (name, data) in &payloads {
+                zipw.start_file::<_, ()>(name.as_str(), FileOptions::default())
+                    .unwrap();
+                zipw.write_all(data).unwrap();
+            }
+            zipw.finish().unwrap();
+        }
+
+        for threads in [1, 2, 8] {
+            let cfg = crate::config::Config {
+                input_checksum_min: crate::types::Checksum::Crc32,
+                input_checksum_max: Some(crate::types::Checksum::Sha1),
+                hash_threads: Some(threads),
+                ..Default::default()
+            };
+            let (tx, rx) = mpsc::channel();
+            let recs = scan_zip_entries(f.path(), &cfg, Some(tx)).unwrap();
+            assert_eq!(recs.len(), payloads.len(), "threads={threads}");
+            assert_eq!(rx.into_iter().count(), payloads.len());
+            for (rec, (name, data)) in recs.iter().zip(&payloads) {
+                let expected = crate::checksum::compute_checksums_from_bytes(data, &cfg).unwrap();
+                assert_eq!(rec.relative.to_string_lossy(), name.as_str());
+                assert_eq!(rec.size, data.len() as u64);
+                assert_eq!(rec.checksums.crc32, expected.crc32, "threads={threads}");
+                assert_eq!(rec.checksums.sha1, expected.sha1, "threads={threads}");
+            }
+        }
+    }
+
+    #[test]
+    fn scan_zip_entries_computes_exactly_a_non_contiguous_checksum_set() {
+        let f = NamedTempFile::new().unwrap();
+        {
+            let mut zipw = zip::ZipWriter::new(f.reopen().unwrap());
+            zipw.start_file::<_, ()>("a.bin", FileOptions::default())
+                .unwrap();
+            zipw.write_all(b"gappy").unwrap();
+            zipw.finish().unwrap();
+        }
+
+        let cfg = crate::config::Config {
+            hash_algorithms: Some(vec![Checksum::Crc32, Checksum::Sha256]),
+            hash_checkpoint_mib: None,
+            expect_crc32: None,
+            expect_md5: None,
+            expect_sha1: None,
+            expect_sha256: None,
+            ..Default::default()
+        };
+        let recs = scan_zip_entries(f.path(), &cfg, None).unwrap();
+        assert_eq!(recs.len(), 1);
+        let checksums = &recs[0].checksums;
+        assert_eq!(
+            checksums.crc32.as_deref(),
+            Some(format!("{:08x}", crc32fast::hash(b"gappy")).as_str())
+        );
+        assert!(checksums.md5.is_none());
+        assert!(checksums.sha1.is_none());
+        assert!(checksums.sha256.is_some());
+    }
 }
 
 #[test]
@@ -372,19 +1571,28 @@ fn scan_7z_entries_if_available() {
         input: Vec::new(),
         input_exclude: Vec::new(),
         input_checksum_quick: false,
+        input_checksum_auto: false,
+        scan_files_only: false,
+        scan_archives_only: false,
         input_checksum_min: crate::types::Checksum::Crc32,
         input_checksum_max: Some(crate::types::Checksum::Sha256),
         input_checksum_archives: crate::types::ArchiveChecksumMode::Auto,
         dat: Vec::new(),
         dat_exclude: Vec::new(),
+        dat_url: vec![],
         dat_name_regex: None,
         dat_name_regex_exclude: None,
+        game_name_regex: None,
+        game_name_regex_exclude: None,
+        system: None,
+        dat_name_rewrite: None,
         dat_description_regex: None,
         dat_description_regex_exclude: None,
         dat_combine: false,
         dat_ignore_parent_clone: false,
         list_unmatched_dats: false,
         print_plan: true,
+        summary_only: false,
         enable_hasheous: false,
         igdb_client_id: None,
         igdb_client_secret: None,
@@ -395,24 +1603,37 @@ fn scan_7z_entries_if_available() {
         patch_exclude: Vec::new(),
         output: None,
         dir_mirror: false,
+        dir_mirror_root: false,
         dir_dat_mirror: false,
         dir_dat_name: false,
         dir_dat_description: false,
         dir_letter: false,
+        dir_region: false,
         dir_letter_count: None,
         dir_letter_limit: None,
         dir_letter_group: false,
+        dir_letter_ignore_articles: false,
         dir_game_subdir: crate::types::DirGameSubdirMode::Never,
         fix_extension: crate::types::FixExtensionMode::Never,
+        normalize_extension_case: false,
+        shorten_paths: false,
         overwrite: false,
         overwrite_invalid: false,
+        on_collision: crate::types::CollisionPolicy::Skip,
+        quarantine: None,
+        no_space_check: false,
         move_delete_dirs: crate::types::MoveDeleteDirsMode::Never,
+        extract_include: None,
+        extract_exclude: None,
         clean_exclude: Vec::new(),
         clean_backup: None,
+        clean_backup_keep: None,
         clean_dry_run: false,
         zip_format: crate::types::ZipFormat::Torrentzip,
-        zip_exclude: None,
+        zip_exclude: vec![],
         zip_dat_name: false,
+        zip_comment: None,
+        to_chd: false,
         link_mode: crate::types::LinkMode::Hardlink,
         symlink_relative: false,
         header: None,
@@ -427,8 +1648,12 @@ fn scan_7z_entries_if_available() {
         filter_regex: None,
         filter_regex_exclude: None,
         filter_language: None,
+        keep_languages: None,
+        keep_top_n: None,
         filter_region: None,
         filter_category_regex: None,
+        only_genre: None,
+        only_platform: None,
         no_bios: false,
         no_device: false,
         no_unlicensed: false,
@@ -440,13 +1665,43 @@ fn scan_7z_entries_if_available() {
         no_prototype: false,
         no_program: false,
         verbose: 0,
+        no_color: false,
         quiet: 0,
         diag: false,
+        plan_diff: false,
+        region_map: None,
+        profile: false,
+        log_style: crate::types::LogStyle::Default,
+        exec: None,
         cache_only: false,
         cache_db: None,
+        xattr_cache: false,
         hash_threads: None,
         scan_threads: None,
+        archive_threads: None,
         show_match_reasons: false,
+        min_score: None,
+        tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+        priority_input: false,
+        title_strip_regex: vec![],
+        ignore_case: true,
+        output_structure: None,
+        threads: None,
+        strict: false,
+        allow_no_matches: false,
+        dat_best_match: false,
+        dat_priority: Vec::new(),
+        dat_diff: None,
+        fixdat_format: None,
+        hash_algorithms: None,
+        hash_checkpoint_mib: None,
+        expect_crc32: None,
+        expect_md5: None,
+        expect_sha1: None,
+        expect_sha256: None,
+        max_decompressed_size: None,
+        skip_existing_checksum: false,
+        report_duplicates: false,
         online_timeout_secs: Some(5),
         online_max_retries: Some(3),
         online_throttle_ms: None,
@@ -503,19 +1758,28 @@ fn scan_7z_nested_dirs_if_available() {
         input: Vec::new(),
         input_exclude: Vec::new(),
         input_checksum_quick: false,
+        input_checksum_auto: false,
+        scan_files_only: false,
+        scan_archives_only: false,
         input_checksum_min: crate::types::Checksum::Crc32,
         input_checksum_max: Some(crate::types::Checksum::Sha256),
         input_checksum_archives: crate::types::ArchiveChecksumMode::Auto,
         dat: Vec::new(),
         dat_exclude: Vec::new(),
+        dat_url: vec![],
         dat_name_regex: None,
         dat_name_regex_exclude: None,
+        game_name_regex: None,
+        game_name_regex_exclude: None,
+        system: None,
+        dat_name_rewrite: None,
         dat_description_regex: None,
         dat_description_regex_exclude: None,
         dat_combine: false,
         dat_ignore_parent_clone: false,
         list_unmatched_dats: false,
         print_plan: true,
+        summary_only: false,
         enable_hasheous: false,
         igdb_client_id: None,
         igdb_client_secret: None,
@@ -526,24 +1790,37 @@ fn scan_7z_nested_dirs_if_available() {
         patch_exclude: Vec::new(),
         output: None,
         dir_mirror: false,
+        dir_mirror_root: false,
         dir_dat_mirror: false,
         dir_dat_name: false,
         dir_dat_description: false,
         dir_letter: false,
+        dir_region: false,
         dir_letter_count: None,
         dir_letter_limit: None,
         dir_letter_group: false,
+        dir_letter_ignore_articles: false,
         dir_game_subdir: crate::types::DirGameSubdirMode::Never,
         fix_extension: crate::types::FixExtensionMode::Never,
+        normalize_extension_case: false,
+        shorten_paths: false,
         overwrite: false,
         overwrite_invalid: false,
+        on_collision: crate::types::CollisionPolicy::Skip,
+        quarantine: None,
+        no_space_check: false,
         move_delete_dirs: crate::types::MoveDeleteDirsMode::Never,
+        extract_include: None,
+        extract_exclude: None,
         clean_exclude: Vec::new(),
         clean_backup: None,
+        clean_backup_keep: None,
         clean_dry_run: false,
         zip_format: crate::types::ZipFormat::Torrentzip,
-        zip_exclude: None,
+        zip_exclude: vec![],
         zip_dat_name: false,
+        zip_comment: None,
+        to_chd: false,
         link_mode: crate::types::LinkMode::Hardlink,
         symlink_relative: false,
         header: None,
@@ -558,8 +1835,12 @@ fn scan_7z_nested_dirs_if_available() {
         filter_regex: None,
         filter_regex_exclude: None,
         filter_language: None,
+        keep_languages: None,
+        keep_top_n: None,
         filter_region: None,
         filter_category_regex: None,
+        only_genre: None,
+        only_platform: None,
         no_bios: false,
         no_device: false,
         no_unlicensed: false,
@@ -571,13 +1852,43 @@ fn scan_7z_nested_dirs_if_available() {
         no_prototype: false,
         no_program: false,
         verbose: 0,
+        no_color: false,
         quiet: 0,
         diag: false,
+        plan_diff: false,
+        region_map: None,
+        profile: false,
+        log_style: crate::types::LogStyle::Default,
+        exec: None,
         cache_only: false,
         cache_db: None,
+        xattr_cache: false,
         hash_threads: None,
         scan_threads: None,
+        archive_threads: None,
         show_match_reasons: false,
+        min_score: None,
+        tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+        priority_input: false,
+        title_strip_regex: vec![],
+        ignore_case: true,
+        output_structure: None,
+        threads: None,
+        strict: false,
+        allow_no_matches: false,
+        dat_best_match: false,
+        dat_priority: Vec::new(),
+        dat_diff: None,
+        fixdat_format: None,
+        hash_algorithms: None,
+        hash_checkpoint_mib: None,
+        expect_crc32: None,
+        expect_md5: None,
+        expect_sha1: None,
+        expect_sha256: None,
+        max_decompressed_size: None,
+        skip_existing_checksum: false,
+        report_duplicates: false,
         online_timeout_secs: Some(5),
         online_max_retries: Some(3),
         online_throttle_ms: None,
@@ -587,19 +1898,28 @@ fn scan_7z_nested_dirs_if_available() {
         input: Vec::new(),
         input_exclude: Vec::new(),
         input_checksum_quick: false,
+        input_checksum_auto: false,
+        scan_files_only: false,
+        scan_archives_only: false,
         input_checksum_min: crate::types::Checksum::Crc32,
         input_checksum_max: Some(crate::types::Checksum::Sha256),
         input_checksum_archives: crate::types::ArchiveChecksumMode::Auto,
         dat: Vec::new(),
         dat_exclude: Vec::new(),
+        dat_url: vec![],
         dat_name_regex: None,
         dat_name_regex_exclude: None,
+        game_name_regex: None,
+        game_name_regex_exclude: None,
+        system: None,
+        dat_name_rewrite: None,
         dat_description_regex: None,
         dat_description_regex_exclude: None,
         dat_combine: false,
         dat_ignore_parent_clone: false,
         list_unmatched_dats: false,
         print_plan: true,
+        summary_only: false,
         enable_hasheous: false,
         igdb_client_id: None,
         igdb_client_secret: None,
@@ -610,24 +1930,37 @@ fn scan_7z_nested_dirs_if_available() {
         patch_exclude: Vec::new(),
         output: None,
         dir_mirror: false,
+        dir_mirror_root: false,
         dir_dat_mirror: false,
         dir_dat_name: false,
         dir_dat_description: false,
         dir_letter: false,
+        dir_region: false,
         dir_letter_count: None,
         dir_letter_limit: None,
         dir_letter_group: false,
+        dir_letter_ignore_articles: false,
         dir_game_subdir: crate::types::DirGameSubdirMode::Never,
         fix_extension: crate::types::FixExtensionMode::Never,
+        normalize_extension_case: false,
+        shorten_paths: false,
         overwrite: false,
         overwrite_invalid: false,
+        on_collision: crate::types::CollisionPolicy::Skip,
+        quarantine: None,
+        no_space_check: false,
         move_delete_dirs: crate::types::MoveDeleteDirsMode::Never,
+        extract_include: None,
+        extract_exclude: None,
         clean_exclude: Vec::new(),
         clean_backup: None,
+        clean_backup_keep: None,
         clean_dry_run: false,
         zip_format: crate::types::ZipFormat::Torrentzip,
-        zip_exclude: None,
+        zip_exclude: vec![],
         zip_dat_name: false,
+        zip_comment: None,
+        to_chd: false,
         link_mode: crate::types::LinkMode::Hardlink,
         symlink_relative: false,
         header: None,
@@ -642,8 +1975,12 @@ fn scan_7z_nested_dirs_if_available() {
         filter_regex: None,
         filter_regex_exclude: None,
         filter_language: None,
+        keep_languages: None,
+        keep_top_n: None,
         filter_region: None,
         filter_category_regex: None,
+        only_genre: None,
+        only_platform: None,
         no_bios: false,
         no_device: false,
         no_unlicensed: false,
@@ -655,13 +1992,43 @@ fn scan_7z_nested_dirs_if_available() {
         no_prototype: false,
         no_program: false,
         verbose: 0,
+        no_color: false,
         quiet: 0,
         diag: false,
+        plan_diff: false,
+        region_map: None,
+        profile: false,
+        log_style: crate::types::LogStyle::Default,
+        exec: None,
         cache_only: false,
         cache_db: None,
+        xattr_cache: false,
         hash_threads: None,
         scan_threads: None,
+        archive_threads: None,
         show_match_reasons: false,
+        min_score: None,
+        tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+        priority_input: false,
+        title_strip_regex: vec![],
+        ignore_case: true,
+        output_structure: None,
+        threads: None,
+        strict: false,
+        allow_no_matches: false,
+        dat_best_match: false,
+        dat_priority: Vec::new(),
+        dat_diff: None,
+        fixdat_format: None,
+        hash_algorithms: None,
+        hash_checkpoint_mib: None,
+        expect_crc32: None,
+        expect_md5: None,
+        expect_sha1: None,
+        expect_sha256: None,
+        max_decompressed_size: None,
+        skip_existing_checksum: false,
+        report_duplicates: false,
         online_timeout_secs: Some(5),
         online_max_retries: Some(3),
         online_throttle_ms: None,
@@ -715,19 +2082,28 @@ fn scan_7z_large_archive_if_available() {
         input: Vec::new(),
         input_exclude: Vec::new(),
         input_checksum_quick: false,
+        input_checksum_auto: false,
+        scan_files_only: false,
+        scan_archives_only: false,
         input_checksum_min: crate::types::Checksum::Crc32,
         input_checksum_max: Some(crate::types::Checksum::Sha256),
         input_checksum_archives: crate::types::ArchiveChecksumMode::Auto,
         dat: Vec::new(),
         dat_exclude: Vec::new(),
+        dat_url: vec![],
         dat_name_regex: None,
         dat_name_regex_exclude: None,
+        game_name_regex: None,
+        game_name_regex_exclude: None,
+        system: None,
+        dat_name_rewrite: None,
         dat_description_regex: None,
         dat_description_regex_exclude: None,
         dat_combine: false,
         dat_ignore_parent_clone: false,
         list_unmatched_dats: false,
         print_plan: true,
+        summary_only: false,
         enable_hasheous: false,
         igdb_client_id: None,
         igdb_client_secret: None,
@@ -738,24 +2114,37 @@ fn scan_7z_large_archive_if_available() {
         patch_exclude: Vec::new(),
         output: None,
         dir_mirror: false,
+        dir_mirror_root: false,
         dir_dat_mirror: false,
         dir_dat_name: false,
         dir_dat_description: false,
         dir_letter: false,
+        dir_region: false,
         dir_letter_count: None,
         dir_letter_limit: None,
         dir_letter_group: false,
+        dir_letter_ignore_articles: false,
         dir_game_subdir: crate::types::DirGameSubdirMode::Never,
         fix_extension: crate::types::FixExtensionMode::Never,
+        normalize_extension_case: false,
+        shorten_paths: false,
         overwrite: false,
         overwrite_invalid: false,
+        on_collision: crate::types::CollisionPolicy::Skip,
+        quarantine: None,
+        no_space_check: false,
         move_delete_dirs: crate::types::MoveDeleteDirsMode::Never,
+        extract_include: None,
+        extract_exclude: None,
         clean_exclude: Vec::new(),
         clean_backup: None,
+        clean_backup_keep: None,
         clean_dry_run: false,
         zip_format: crate::types::ZipFormat::Torrentzip,
-        zip_exclude: None,
+        zip_exclude: vec![],
         zip_dat_name: false,
+        zip_comment: None,
+        to_chd: false,
         link_mode: crate::types::LinkMode::Hardlink,
         symlink_relative: false,
         header: None,
@@ -770,8 +2159,12 @@ fn scan_7z_large_archive_if_available() {
         filter_regex: None,
         filter_regex_exclude: None,
         filter_language: None,
+        keep_languages: None,
+        keep_top_n: None,
         filter_region: None,
         filter_category_regex: None,
+        only_genre: None,
+        only_platform: None,
         no_bios: false,
         no_device: false,
         no_unlicensed: false,
@@ -783,13 +2176,43 @@ fn scan_7z_large_archive_if_available() {
         no_prototype: false,
         no_program: false,
         verbose: 0,
+        no_color: false,
         quiet: 0,
         diag: false,
+        plan_diff: false,
+        region_map: None,
+        profile: false,
+        log_style: crate::types::LogStyle::Default,
+        exec: None,
         cache_only: false,
         cache_db: None,
+        xattr_cache: false,
         hash_threads: None,
         scan_threads: None,
+        archive_threads: None,
         show_match_reasons: false,
+        min_score: None,
+        tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+        priority_input: false,
+        title_strip_regex: vec![],
+        ignore_case: true,
+        output_structure: None,
+        threads: None,
+        strict: false,
+        allow_no_matches: false,
+        dat_best_match: false,
+        dat_priority: Vec::new(),
+        dat_diff: None,
+        fixdat_format: None,
+        hash_algorithms: None,
+        hash_checkpoint_mib: None,
+        expect_crc32: None,
+        expect_md5: None,
+        expect_sha1: None,
+        expect_sha256: None,
+        max_decompressed_size: None,
+        skip_existing_checksum: false,
+        report_duplicates: false,
         online_timeout_secs: Some(5),
         online_max_retries: Some(3),
         online_throttle_ms: None,
@@ -844,19 +2267,28 @@ fn scan_7z_edge_case_filenames_if_available() {
         input: Vec::new(),
         input_exclude: Vec::new(),
         input_checksum_quick: false,
+        input_checksum_auto: false,
+        scan_files_only: false,
+        scan_archives_only: false,
         input_checksum_min: crate::types::Checksum::Crc32,
         input_checksum_max: Some(crate::types::Checksum::Sha256),
         input_checksum_archives: crate::types::ArchiveChecksumMode::Auto,
         dat: Vec::new(),
         dat_exclude: Vec::new(),
+        dat_url: vec![],
         dat_name_regex: None,
         dat_name_regex_exclude: None,
+        game_name_regex: None,
+        game_name_regex_exclude: None,
+        system: None,
+        dat_name_rewrite: None,
         dat_description_regex: None,
         dat_description_regex_exclude: None,
         dat_combine: false,
         dat_ignore_parent_clone: false,
         list_unmatched_dats: false,
         print_plan: true,
+        summary_only: false,
         enable_hasheous: false,
         igdb_client_id: None,
         igdb_client_secret: None,
@@ -867,24 +2299,37 @@ fn scan_7z_edge_case_filenames_if_available() {
         patch_exclude: Vec::new(),
         output: None,
         dir_mirror: false,
+        dir_mirror_root: false,
         dir_dat_mirror: false,
         dir_dat_name: false,
         dir_dat_description: false,
         dir_letter: false,
+        dir_region: false,
         dir_letter_count: None,
         dir_letter_limit: None,
         dir_letter_group: false,
+        dir_letter_ignore_articles: false,
         dir_game_subdir: crate::types::DirGameSubdirMode::Never,
         fix_extension: crate::types::FixExtensionMode::Never,
+        normalize_extension_case: false,
+        shorten_paths: false,
         overwrite: false,
         overwrite_invalid: false,
+        on_collision: crate::types::CollisionPolicy::Skip,
+        quarantine: None,
+        no_space_check: false,
         move_delete_dirs: crate::types::MoveDeleteDirsMode::Never,
+        extract_include: None,
+        extract_exclude: None,
         clean_exclude: Vec::new(),
         clean_backup: None,
+        clean_backup_keep: None,
         clean_dry_run: false,
         zip_format: crate::types::ZipFormat::Torrentzip,
-        zip_exclude: None,
+        zip_exclude: vec![],
         zip_dat_name: false,
+        zip_comment: None,
+        to_chd: false,
         link_mode: crate::types::LinkMode::Hardlink,
         symlink_relative: false,
         header: None,
@@ -899,8 +2344,12 @@ fn scan_7z_edge_case_filenames_if_available() {
         filter_regex: None,
         filter_regex_exclude: None,
         filter_language: None,
+        keep_languages: None,
+        keep_top_n: None,
         filter_region: None,
         filter_category_regex: None,
+        only_genre: None,
+        only_platform: None,
         no_bios: false,
         no_device: false,
         no_unlicensed: false,
@@ -912,13 +2361,43 @@ fn scan_7z_edge_case_filenames_if_available() {
         no_prototype: false,
         no_program: false,
         verbose: 0,
+        no_color: false,
         quiet: 0,
         diag: false,
+        plan_diff: false,
+        region_map: None,
+        profile: false,
+        log_style: crate::types::LogStyle::Default,
+        exec: None,
         cache_only: false,
         cache_db: None,
+        xattr_cache: false,
         hash_threads: None,
         scan_threads: None,
+        archive_threads: None,
         show_match_reasons: false,
+        min_score: None,
+        tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+        priority_input: false,
+        title_strip_regex: vec![],
+        ignore_case: true,
+        output_structure: None,
+        threads: None,
+        strict: false,
+        allow_no_matches: false,
+        dat_best_match: false,
+        dat_priority: Vec::new(),
+        dat_diff: None,
+        fixdat_format: None,
+        hash_algorithms: None,
+        hash_checkpoint_mib: None,
+        expect_crc32: None,
+        expect_md5: None,
+        expect_sha1: None,
+        expect_sha256: None,
+        max_decompressed_size: None,
+        skip_existing_checksum: false,
+        report_duplicates: false,
         online_timeout_secs: Some(5),
         online_max_retries: Some(3),
         online_throttle_ms: None,
@@ -928,19 +2407,28 @@ fn scan_7z_edge_case_filenames_if_available() {
         input: Vec::new(),
         input_exclude: Vec::new(),
         input_checksum_quick: false,
+        input_checksum_auto: false,
+        scan_files_only: false,
+        scan_archives_only: false,
         input_checksum_min: crate::types::Checksum::Crc32,
         input_checksum_max: Some(crate::types::Checksum::Sha256),
         input_checksum_archives: crate::types::ArchiveChecksumMode::Auto,
         dat: Vec::new(),
         dat_exclude: Vec::new(),
+        dat_url: vec![],
         dat_name_regex: None,
         dat_name_regex_exclude: None,
+        game_name_regex: None,
+        game_name_regex_exclude: None,
+        system: None,
+        dat_name_rewrite: None,
         dat_description_regex: None,
         dat_description_regex_exclude: None,
         dat_combine: false,
         dat_ignore_parent_clone: false,
         list_unmatched_dats: false,
         print_plan: true,
+        summary_only: false,
         enable_hasheous: false,
         igdb_client_id: None,
         igdb_client_secret: None,
@@ -951,24 +2439,37 @@ fn scan_7z_edge_case_filenames_if_available() {
         patch_exclude: Vec::new(),
         output: None,
         dir_mirror: false,
+        dir_mirror_root: false,
         dir_dat_mirror: false,
         dir_dat_name: false,
         dir_dat_description: false,
         dir_letter: false,
+        dir_region: false,
         dir_letter_count: None,
         dir_letter_limit: None,
         dir_letter_group: false,
+        dir_letter_ignore_articles: false,
         dir_game_subdir: crate::types::DirGameSubdirMode::Never,
         fix_extension: crate::types::FixExtensionMode::Never,
+        normalize_extension_case: false,
+        shorten_paths: false,
         overwrite: false,
         overwrite_invalid: false,
+        on_collision: crate::types::CollisionPolicy::Skip,
+        quarantine: None,
+        no_space_check: false,
         move_delete_dirs: crate::types::MoveDeleteDirsMode::Never,
+        extract_include: None,
+        extract_exclude: None,
         clean_exclude: Vec::new(),
         clean_backup: None,
+        clean_backup_keep: None,
         clean_dry_run: false,
         zip_format: crate::types::ZipFormat::Torrentzip,
-        zip_exclude: None,
+        zip_exclude: vec![],
         zip_dat_name: false,
+        zip_comment: None,
+        to_chd: false,
         link_mode: crate::types::LinkMode::Hardlink,
         symlink_relative: false,
         header: None,
@@ -983,8 +2484,12 @@ fn scan_7z_edge_case_filenames_if_available() {
         filter_regex: None,
         filter_regex_exclude: None,
         filter_language: None,
+        keep_languages: None,
+        keep_top_n: None,
         filter_region: None,
         filter_category_regex: None,
+        only_genre: None,
+        only_platform: None,
         no_bios: false,
         no_device: false,
         no_unlicensed: false,
@@ -996,13 +2501,43 @@ fn scan_7z_edge_case_filenames_if_available() {
         no_prototype: false,
         no_program: false,
         verbose: 0,
+        no_color: false,
         quiet: 0,
         diag: false,
+        plan_diff: false,
+        region_map: None,
+        profile: false,
+        log_style: crate::types::LogStyle::Default,
+        exec: None,
         cache_only: false,
         cache_db: None,
+        xattr_cache: false,
         hash_threads: None,
         scan_threads: None,
+        archive_threads: None,
         show_match_reasons: false,
+        min_score: None,
+        tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+        priority_input: false,
+        title_strip_regex: vec![],
+        ignore_case: true,
+        output_structure: None,
+        threads: None,
+        strict: false,
+        allow_no_matches: false,
+        dat_best_match: false,
+        dat_priority: Vec::new(),
+        dat_diff: None,
+        fixdat_format: None,
+        hash_algorithms: None,
+        hash_checkpoint_mib: None,
+        expect_crc32: None,
+        expect_md5: None,
+        expect_sha1: None,
+        expect_sha256: None,
+        max_decompressed_size: None,
+        skip_existing_checksum: false,
+        report_duplicates: false,
         online_timeout_secs: Some(5),
         online_max_retries: Some(3),
         online_throttle_ms: None,
@@ -1017,3 +2552,220 @@ fn scan_7z_edge_case_filenames_if_available() {
     assert!(names.iter().any(|n| n == "unicodé-文件.bin"));
     assert!(names.iter().any(|n| n == "weird_#%&[]{}.txt"));
 }
+
+#[test]
+fn scan_7z_quick_mode_reads_header_crcs_if_available() {
+    let exe = which::which("7z").or_else(|_| which::which("7za"));
+    if exe.is_err() {
+        eprintln!("skipping 7z quick test; 7z not found");
+        return;
+    }
+    let exe = exe.unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let src = dir.path().join("rom.bin");
+    std::fs::write(&src, b"quick crc").unwrap();
+    let out7 = dir.path().join("rom.7z");
+    let status = Command::new(&exe).arg("a").arg(&out7).arg(&src).status();
+    if status.is_err() || !status.unwrap().success() {
+        eprintln!("skipping 7z quick test; failed to create 7z");
+        return;
+    }
+
+    let expected = format!("{:08x}", crc32fast::hash(b"quick crc"));
+
+    // the quick path itself never extracts, so it must already carry the CRC32
+    let quick = quick_7z_records(&exe, &out7, None)
+        .unwrap()
+        .expect("7z -slt listing exposes CRCs");
+    assert_eq!(quick.len(), 1);
+    assert_eq!(quick[0].checksums.crc32.as_deref(), Some(expected.as_str()));
+    assert!(quick[0].checksums.md5.is_none());
+
+    let cfg = crate::config::Config {
+        input_checksum_quick: true,
+        input_checksum_auto: false,
+        scan_files_only: false,
+        scan_archives_only: false,
+        input_checksum_min: crate::types::Checksum::Crc32,
+        input_checksum_max: None,
+        ..Default::default()
+    };
+    let recs = scan_7z_entries(&out7, &cfg, None).unwrap();
+    assert_eq!(recs.len(), 1);
+    assert_eq!(recs[0].relative, std::path::PathBuf::from("rom.bin"));
+    assert_eq!(recs[0].size, 9);
+    assert_eq!(recs[0].checksums.crc32.as_deref(), Some(expected.as_str()));
+}
+
+#[test]
+fn parse_7z_slt_entries_requires_crc_for_every_file() {
+    let listing = "Path = a.7z\nType = 7z\n\n----------\nPath = dir\nFolder = +\nSize = 0\nCRC = \n\nPath = dir/a.bin\nFolder = -\nSize = 4\nCRC = DEADBEEF\n\n";
+    let entries = parse_7z_slt_entries(listing).unwrap();
+    assert_eq!(
+        entries,
+        vec![("dir/a.bin".to_string(), 4, "deadbeef".to_string())]
+    );
+
+    let missing = "Path = a.7z\n\n----------\nPath = b.bin\nFolder = -\nSize = 4\nCRC = \n";
+    assert!(parse_7z_slt_entries(missing).is_none());
+}
+
+#[test]
+fn scan_rar_entries_returns_nothing_when_unreadable_or_without_a_backend() {
+    let dir = tempfile::tempdir().unwrap();
+    let rar = dir.path().join("broken.rar");
+    std::fs::write(&rar, b"not really a rar archive").unwrap();
+
+    let recs = scan_rar_entries(&rar, &Config::default(), None).unwrap();
+    assert!(recs.is_empty());
+}
+
+/// A RAR 4 archive storing `members` uncompressed.
+#[cfg(test)]
+fn stored_rar(members: &[(&str, &[u8])]) -> Vec<u8> {
+    fn block(head_type: u8, flags: u16, fields: &[u8]) -> Vec<u8> {
+        let mut head = vec![head_type];
+        head.extend(flags.to_le_bytes());
+        head.extend(((fields.len() + 7) as u16).to_le_bytes());
+        head.extend_from_slice(fields);
+        let crc = (crc32fast::hash(&head) & 0xffff) as u16;
+        let mut out = crc.to_le_bytes().to_vec();
+        out.extend(head);
+        out
+    }
+
+    let mut rar = b"Rar!\x1a\x07\x00".to_vec();
+    rar.extend(block(0x73, 0, &[0; 6]));
+    for (name, data) in members {
+        let mut fields = Vec::new();
+        fields.extend((data.len() as u32).to_le_bytes()); // packed size
+        fields.extend((data.len() as u32).to_le_bytes()); // unpacked size
+        fields.push(3); // host OS: Unix
+        fields.extend(crc32fast::hash(data).to_le_bytes());
+        fields.extend(0x5a21_0000u32.to_le_bytes()); // DOS time
+        fields.push(20); // version needed to extract
+        fields.push(0x30); // method: store
+        fields.extend((name.len() as u16).to_le_bytes());
+        fields.extend(0o100644u32.to_le_bytes());
+        fields.extend_from_slice(name.as_bytes());
+        // 0x8000: the block is followed by `packed size` bytes of data
+        rar.extend(block(0x74, 0x8000, &fields));
+        rar.extend_from_slice(data);
+    }
+    rar.extend(block(0x7b, 0x4000, &[]));
+    rar
+}
+
+#[test]
+fn scan_rar_entries_if_available() {
+    if which::which("unrar").is_err() && which::which("7z").is_err() && which::which("7za").is_err()
+    {
+        eprintln!("skipping: neither unrar nor 7z on PATH");
+        return;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    let archive = dir.path().join("set.rar");
+    std::fs::write(
+        &archive,
+        stored_rar(&[("game.bin", b"rom data"), ("docs/readme.txt", b"notes")]),
+    )
+    .unwrap();
+
+    let cfg = Config::default();
+    assert!(scans_archive_members(&archive));
+    let mut recs = scan_archive_entries(&archive, &cfg, None).unwrap();
+    recs.sort_by(|a, b| a.relative.cmp(&b.relative));
+    let names: Vec<_> = recs.iter().map(|r| r.relative.clone()).collect();
+    assert_eq!(
+        names,
+        vec![
+            Path::new("docs").join("readme.txt"),
+            PathBuf::from("game.bin")
+        ]
+    );
+    let (expected, _) = compute_checksums_stream(&b"rom data"[..], &cfg).unwrap();
+    assert_eq!(recs[1].checksums.crc32, expected.crc32);
+    assert!(recs.iter().all(|r| r.source == archive));
+
+    let mut member = Vec::new();
+    copy_archive_member(&archive, Path::new("game.bin"), &mut member).unwrap();
+    assert_eq!(member, b"rom data");
+}
+
+#[cfg(test)]
+fn tar_member(tar: &mut tar::Builder<Vec<u8>>, name: &str, data: &[u8], kind: tar::EntryType) {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(kind);
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    if kind == tar::EntryType::Symlink {
+        header.set_link_name("game.bin").unwrap();
+    }
+    tar.append_data(&mut header, name, data).unwrap();
+}
+
+#[test]
+fn scan_tar_entries_hashes_files_and_skips_dirs_and_links() {
+    use std::io::Write;
+
+    let long_name = format!("{}/game (long name).bin", "nested".repeat(20));
+    let mut builder = tar::Builder::new(Vec::new());
+    tar_member(&mut builder, "docs/", b"", tar::EntryType::Directory);
+    tar_member(
+        &mut builder,
+        "./docs/readme.txt",
+        b"notes",
+        tar::EntryType::Regular,
+    );
+    tar_member(&mut builder, "link.bin", b"", tar::EntryType::Symlink);
+    tar_member(
+        &mut builder,
+        &long_name,
+        b"long rom",
+        tar::EntryType::Regular,
+    );
+    tar_member(
+        &mut builder,
+        "game.bin",
+        b"rom data",
+        tar::EntryType::Regular,
+    );
+    let tar = builder.into_inner().unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let plain = dir.path().join("set.tar");
+    std::fs::write(&plain, &tar).unwrap();
+    let gzipped = dir.path().join("set.tgz");
+    let mut encoder = flate2::write::GzEncoder::new(
+        File::create(&gzipped).unwrap(),
+        flate2::Compression::default(),
+    );
+    encoder.write_all(&tar).unwrap();
+    encoder.finish().unwrap();
+
+    let cfg = Config::default();
+    for archive in [&plain, &gzipped] {
+        assert!(scans_archive_members(archive));
+        let recs = scan_archive_entries(archive, &cfg, None).unwrap();
+        let names: Vec<_> = recs.iter().map(|r| r.relative.clone()).collect();
+        assert_eq!(
+            names,
+            vec![
+                Path::new("docs").join("readme.txt"),
+                std::path::PathBuf::from(&long_name),
+                std::path::PathBuf::from("game.bin"),
+            ]
+        );
+        let (expected, _) = compute_checksums_stream(&b"rom data"[..], &cfg).unwrap();
+        assert_eq!(recs[2].checksums.crc32, expected.crc32);
+        assert_eq!(recs[2].size, 8);
+        assert_eq!(recs[1].size, 8);
+
+        assert!(copies_archive_members(archive));
+        let mut member = Vec::new();
+        copy_archive_member(archive, Path::new(&long_name), &mut member).unwrap();
+        assert_eq!(member, b"long rom");
+        assert!(copy_archive_member(archive, Path::new("link.bin"), &mut Vec::new()).is_err());
+    }
+}
@@ -28,6 +28,12 @@ fn main() -> anyhow::Result<()> {
         derived_region: None,
         derived_languages: Vec::new(),
         scan_info: None,
+        output_name: None,
+        set_name: None,
+        input_root: None,
+        modified: None,
+        input_index: None,
+        fixed_extension: None,
     };
 
     let candidates = igir::candidates::generate_candidates(&dats, &[rec_checksum]);
@@ -53,6 +53,26 @@ impl Cache {
                 sha256 TEXT,
                 updated_at INTEGER
             );
+            CREATE TABLE IF NOT EXISTS file_checksums (
+                path TEXT PRIMARY KEY,
+                size INTEGER,
+                mtime INTEGER,
+                crc32 TEXT,
+                md5 TEXT,
+                sha1 TEXT,
+                sha256 TEXT,
+                updated_at INTEGER
+            );
+            CREATE TABLE IF NOT EXISTS hash_checkpoint_states (
+                path TEXT PRIMARY KEY,
+                size INTEGER,
+                mtime INTEGER,
+                start INTEGER,
+                targets TEXT,
+                offset INTEGER,
+                state BLOB,
+                updated_at INTEGER
+            );
             CREATE TABLE IF NOT EXISTS hasheous (
                 key TEXT PRIMARY KEY,
                 source TEXT,
@@ -144,6 +164,98 @@ impl Cache {
         Ok(())
     }
 
+    /// Look up checksums previously computed for `path`. Entries are only returned when the
+    /// recorded size and modification time still match, so edited files are rehashed.
+    pub fn get_checksums_by_path(
+        &self,
+        path: &Path,
+        size: u64,
+        mtime: i64,
+    ) -> anyhow::Result<Option<ChecksumSet>> {
+        let p = path.to_string_lossy();
+        let mut stmt = self.conn.prepare(
+            "SELECT crc32, md5, sha1, sha256 FROM file_checksums WHERE path = ?1 AND size = ?2 AND mtime = ?3",
+        )?;
+        let row = stmt
+            .query_row(params![p.as_ref(), size as i64, mtime], |r| {
+                Ok(ChecksumSet {
+                    crc32: r.get::<_, Option<String>>(0)?,
+                    md5: r.get::<_, Option<String>>(1)?,
+                    sha1: r.get::<_, Option<String>>(2)?,
+                    sha256: r.get::<_, Option<String>>(3)?,
+                })
+            })
+            .optional()?;
+        Ok(row)
+    }
+
+    pub fn set_checksums_by_path(
+        &self,
+        path: &Path,
+        size: u64,
+        mtime: i64,
+        set: &ChecksumSet,
+    ) -> anyhow::Result<()> {
+        let p = path.to_string_lossy();
+        let ts = chrono::Utc::now().timestamp();
+        self.conn.execute(
+            "REPLACE INTO file_checksums (path, size, mtime, crc32, md5, sha1, sha256, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![p.as_ref(), size as i64, mtime, set.crc32.as_deref(), set.md5.as_deref(), set.sha1.as_deref(), set.sha256.as_deref(), ts],
+        )?;
+        Ok(())
+    }
+
+    /// Hasher state saved by `--hash-checkpoint` for `path` hashed from byte `start` with the
+    /// checksums named by `targets`: the offset reached and the serialized state of the
+    /// hashers at that point. Checkpoints recorded for another size, modification time or
+    /// set of checksums are ignored.
+    pub fn get_hash_checkpoint(
+        &self,
+        path: &Path,
+        size: u64,
+        mtime: i64,
+        start: u64,
+        targets: &str,
+    ) -> anyhow::Result<Option<(u64, Vec<u8>)>> {
+        let p = path.to_string_lossy();
+        let row = self
+            .conn
+            .query_row(
+                "SELECT offset, state FROM hash_checkpoint_states WHERE path = ?1 AND size = ?2 AND mtime = ?3 AND start = ?4 AND targets = ?5",
+                params![p.as_ref(), size as i64, mtime, start as i64, targets],
+                |r| Ok((r.get::<_, i64>(0)? as u64, r.get::<_, Vec<u8>>(1)?)),
+            )
+            .optional()?;
+        Ok(row)
+    }
+
+    pub fn set_hash_checkpoint(
+        &self,
+        path: &Path,
+        size: u64,
+        mtime: i64,
+        start: u64,
+        targets: &str,
+        (offset, state): (u64, &[u8]),
+    ) -> anyhow::Result<()> {
+        let p = path.to_string_lossy();
+        let ts = chrono::Utc::now().timestamp();
+        self.conn.execute(
+            "REPLACE INTO hash_checkpoint_states (path, size, mtime, start, targets, offset, state, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![p.as_ref(), size as i64, mtime, start as i64, targets, offset as i64, state, ts],
+        )?;
+        Ok(())
+    }
+
+    pub fn clear_hash_checkpoint(&self, path: &Path) -> anyhow::Result<()> {
+        let p = path.to_string_lossy();
+        self.conn.execute(
+            "DELETE FROM hash_checkpoint_states WHERE path = ?1",
+            params![p.as_ref()],
+        )?;
+        Ok(())
+    }
+
     pub fn get_hasheous_raw_by_key(&self, key: &str) -> anyhow::Result<Option<Value>> {
         let mut stmt = self
             .conn
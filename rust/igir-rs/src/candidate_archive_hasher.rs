@@ -1,4 +1,6 @@
+use crate::candidates::DatRomTuple;
 use crate::config::Config;
+use crate::types::{ArchiveChecksumMode, FileRecord};
 use once_cell::sync::OnceCell;
 use std::collections::HashMap;
 use std::sync::Mutex;
@@ -15,69 +17,116 @@ pub struct InnerEntryChecksum {
 static LAST_ARCHIVE_SCAN: OnceCell<Mutex<HashMap<std::path::PathBuf, Vec<InnerEntryChecksum>>>> =
     OnceCell::new();
 
+/// Decide whether candidate archives should be hashed for this run. `Always`/`Never` are
+/// honored as-is; `Auto` runs a pre-pass and only enables hashing when some DAT entry's
+/// checksum matches an archive file itself rather than one of its members.
+pub fn archive_hashing_enabled(
+    dat_roms: &[DatRomTuple],
+    records: &[FileRecord],
+    config: &Config,
+) -> bool {
+    match config.input_checksum_archives {
+        ArchiveChecksumMode::Never => false,
+        ArchiveChecksumMode::Always => true,
+        ArchiveChecksumMode::Auto => records
+            .iter()
+            .filter(|rec| is_archive_level_record(rec))
+            .any(|rec| {
+                let same = |dat: &Option<String>, ours: &Option<String>| {
+                    matches!((dat, ours), (Some(a), Some(b)) if a.eq_ignore_ascii_case(b))
+                };
+                dat_roms.iter().any(|(_, crc, md5, sha1, _)| {
+                    same(crc, &rec.checksums.crc32)
+                        || same(md5, &rec.checksums.md5)
+                        || same(sha1, &rec.checksums.sha1)
+                })
+            }),
+    }
+}
+
+/// Drop the records of zip/7z files as a whole when [`archive_hashing_enabled`] says no
+/// DAT entry is an archive, so only their members are matched and written. Archives whose
+/// members were not scanned keep their record.
+pub fn drop_archive_level_records(
+    records: Vec<FileRecord>,
+    dat_roms: &[DatRomTuple],
+    config: &Config,
+) -> Vec<FileRecord> {
+    if archive_hashing_enabled(dat_roms, &records, config) {
+        return records;
+    }
+    let scanned: std::collections::HashSet<std::path::PathBuf> = records
+        .iter()
+        .filter(|rec| !rec.source.ends_with(&rec.relative))
+        .map(|rec| rec.source.clone())
+        .collect();
+    records
+        .into_iter()
+        .filter(|rec| !(is_archive_level_record(rec) && scanned.contains(&rec.source)))
+        .collect()
+}
+
+/// A record describing a zip/7z file as a whole (not an entry inside it).
+fn is_archive_level_record(rec: &FileRecord) -> bool {
+    let is_archive = rec
+        .source
+        .extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip") || ext.eq_ignore_ascii_case("7z"));
+    is_archive && rec.source.ends_with(&rec.relative)
+}
+
 /// Scan archives referenced by candidates and populate an in-memory map of
 /// archive_path -> inner-entry checksum list. Returns the incoming candidates
 /// unchanged for now. This function preferentially uses the existing
-/// `archives::scan_zip_entries` / `archives::scan_7z_entries` helpers which may
-/// use native or external tools as available.
+/// `archives::scan_zip_entries` / `scan_7z_entries` / `scan_rar_entries` /
+/// `scan_tar_entries` helpers which may use native or external tools as available.
 pub fn process_archive_hashes(
     candidates: Vec<crate::candidates::Candidate>,
     config: &Config,
 ) -> Vec<crate::candidates::Candidate> {
     let mut map: HashMap<std::path::PathBuf, Vec<InnerEntryChecksum>> = HashMap::new();
 
-    // collect unique archive paths from candidates along with a detected extension
-    let mut archives: std::collections::HashMap<std::path::PathBuf, String> =
+    // collect unique archive paths from candidates along with the path naming their format
+    let mut archives: std::collections::HashMap<std::path::PathBuf, std::path::PathBuf> =
         std::collections::HashMap::new();
+    let is_archive = |path: &std::path::Path| {
+        crate::archives::scans_archive_members(path)
+            || path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("7z"))
+    };
     for cand in candidates.iter() {
         for rec in cand.matches.iter() {
-            // consider either the physical source extension or the in-archive relative
-            // extension (some temp files may not have a .zip suffix but still be zip data)
-            let mut detected: Option<String> = None;
-            if let Some(ext) = rec.relative.extension().and_then(|s| s.to_str()) {
-                let el = ext.to_ascii_lowercase();
-                if el == "zip" || el == "7z" {
-                    detected = Some(el);
-                }
-            }
-            if detected.is_none() {
-                if let Some(ext) = rec.source.extension().and_then(|s| s.to_str()) {
-                    let el = ext.to_ascii_lowercase();
-                    if el == "zip" || el == "7z" {
-                        detected = Some(el);
-                    }
-                }
-            }
-            if let Some(ext) = detected {
-                archives.entry(rec.source.clone()).or_insert(ext);
+            // consider either the in-archive relative name or the physical source (some
+            // temp files may not have a .zip suffix but still be zip data)
+            let detected = [&rec.relative, &rec.source]
+                .into_iter()
+                .find(|path| is_archive(path));
+            if let Some(kind) = detected {
+                archives
+                    .entry(rec.source.clone())
+                    .or_insert_with(|| kind.clone());
             }
         }
     }
 
-    for (a, detected_ext) in archives.into_iter() {
+    for (a, kind) in archives.into_iter() {
         let mut entries: Vec<InnerEntryChecksum> = Vec::new();
-        // prefer scan based on detected extension (from relative or source)
-        if detected_ext == "zip" {
-            if let Ok(recs) = crate::archives::scan_zip_entries(&a, config, None) {
-                for r in recs.into_iter() {
-                    entries.push(InnerEntryChecksum {
-                        entry_path: r.relative.to_string_lossy().to_string(),
-                        crc32: r.checksums.crc32,
-                        md5: r.checksums.md5,
-                        sha1: r.checksums.sha1,
-                    });
-                }
-            }
-        } else if detected_ext == "7z" {
-            if let Ok(recs) = crate::archives::scan_7z_entries(&a, config, None) {
-                for r in recs.into_iter() {
-                    entries.push(InnerEntryChecksum {
-                        entry_path: r.relative.to_string_lossy().to_string(),
-                        crc32: r.checksums.crc32,
-                        md5: r.checksums.md5,
-                        sha1: r.checksums.sha1,
-                    });
-                }
+        // scan with the reader the detected name (from relative or source) calls for
+        let scanned = if crate::archives::scans_archive_members(&kind) {
+            crate::archives::scan_archive_entries_as(&a, &kind, config, None)
+        } else {
+            crate::archives::scan_7z_entries(&a, config, None)
+        };
+        if let Ok(recs) = scanned {
+            for r in recs.into_iter() {
+                entries.push(InnerEntryChecksum {
+                    entry_path: r.relative.to_string_lossy().to_string(),
+                    crc32: r.checksums.crc32,
+                    md5: r.checksums.md5,
+                    sha1: r.checksums.sha1,
+                });
             }
         }
         if !entries.is_empty() {
@@ -108,12 +157,106 @@ pub fn get_last_archive_scan() -> Option<HashMap<std::path::PathBuf, Vec<InnerEn
 mod tests {
     use super::*;
     use crate::candidates::Candidate;
-    use crate::types::{ChecksumSet, FileRecord};
+    use crate::types::ChecksumSet;
     use std::io::Write;
     use std::path::PathBuf;
     use tempfile::NamedTempFile;
     use zip::write::FileOptions;
 
+    fn checksum_record(source: &str, relative: &str, crc: &str) -> FileRecord {
+        FileRecord {
+            source: PathBuf::from(source),
+            relative: PathBuf::from(relative),
+            size: 0,
+            checksums: ChecksumSet {
+                crc32: Some(crc.to_string()),
+                md5: None,
+                sha1: None,
+                sha256: None,
+            },
+            letter_dir: None,
+            derived_platform: None,
+            derived_genres: Vec::new(),
+            derived_region: None,
+            derived_languages: Vec::new(),
+            scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
+        }
+    }
+
+    #[test]
+    fn auto_archive_hashing_follows_dat_checksum_level() {
+        let records = vec![
+            checksum_record("/roms/Game.zip", "Game.zip", "aaaa1111"),
+            checksum_record("/roms/Game.zip", "Game.bin", "bbbb2222"),
+        ];
+        let member_dat = vec![(
+            "Game.bin".to_string(),
+            Some("BBBB2222".to_string()),
+            None,
+            None,
+            None,
+        )];
+        let archive_dat = vec![(
+            "Game.zip".to_string(),
+            Some("AAAA1111".to_string()),
+            None,
+            None,
+            None,
+        )];
+
+        let mut cfg = Config::default();
+        assert_eq!(cfg.input_checksum_archives, ArchiveChecksumMode::Auto);
+        assert!(!archive_hashing_enabled(&member_dat, &records, &cfg));
+        assert!(archive_hashing_enabled(&archive_dat, &records, &cfg));
+
+        cfg.input_checksum_archives = ArchiveChecksumMode::Always;
+        assert!(archive_hashing_enabled(&member_dat, &records, &cfg));
+        cfg.input_checksum_archives = ArchiveChecksumMode::Never;
+        assert!(!archive_hashing_enabled(&archive_dat, &records, &cfg));
+    }
+
+    #[test]
+    fn auto_drops_archive_records_for_a_member_level_dat() {
+        let records = vec![
+            checksum_record("/roms/Game.zip", "Game.zip", "aaaa1111"),
+            checksum_record("/roms/Game.zip", "Game.bin", "bbbb2222"),
+            checksum_record("/roms/Other.zip", "Other.zip", "cccc3333"),
+        ];
+        let member_dat = vec![(
+            "Game.bin".to_string(),
+            Some("BBBB2222".to_string()),
+            None,
+            None,
+            None,
+        )];
+        let archive_dat = vec![(
+            "Game.zip".to_string(),
+            Some("AAAA1111".to_string()),
+            None,
+            None,
+            None,
+        )];
+        let cfg = Config::default();
+
+        let kept = drop_archive_level_records(records.clone(), &member_dat, &cfg);
+        let relatives: Vec<_> = kept.iter().map(|rec| rec.relative.clone()).collect();
+        // Other.zip had no members scanned, so it stays as the only record of its archive.
+        assert_eq!(
+            relatives,
+            vec![PathBuf::from("Game.bin"), PathBuf::from("Other.zip")]
+        );
+        assert_eq!(
+            drop_archive_level_records(records, &archive_dat, &cfg).len(),
+            3
+        );
+    }
+
     #[test]
     fn zip_scan_populates_map() {
         let f = NamedTempFile::new().unwrap();
@@ -141,6 +284,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
         let cand = Candidate {
             name: "a".to_string(),
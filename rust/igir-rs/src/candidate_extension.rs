@@ -1,19 +1,60 @@
 use std::fs::File;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::config::Config;
 use crate::types::FixExtensionMode;
 
+/// Minimum detection confidence required before `Auto` renames a file.
+const AUTO_CONFIDENCE_THRESHOLD: f32 = 0.9;
+
+/// Number of leading bytes inspected when sniffing a file. Large enough to
+/// reach the Game Boy logo at 0x104 and the Mega Drive header at 0x100.
+const SNIFF_BYTES: usize = 1024;
+
+/// Extensions that describe the same content as a detected type, so `Auto`
+/// does not treat e.g. a `.sfc` as contradicting an `smc` detection.
+const EXTENSION_ALIASES: &[(&str, &[&str])] = &[
+    ("smc", &["sfc", "swc", "fig"]),
+    ("bin", &["md", "gen", "smd", "32x", "pco"]),
+    ("gb", &["gbc", "sgb"]),
+    ("z64", &["n64", "v64"]),
+    ("gz", &["tgz"]),
+];
+
 /// Return (extension, confidence) if a signature is recognized.
 fn detect_extension_from_bytes(buf: &[u8]) -> Option<(&'static str, f32)> {
+    // Archive and container magics are exact and take precedence over the
+    // ROM heuristics below.
+    if buf.starts_with(b"PK\x03\x04") || buf.starts_with(b"PK\x05\x06") {
+        return Some(("zip", 0.99));
+    }
+    if buf.starts_with(&[0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C]) {
+        return Some(("7z", 0.99));
+    }
+    if buf.starts_with(b"Rar!\x1A\x07") {
+        return Some(("rar", 0.99));
+    }
+    if buf.starts_with(&[0x1F, 0x8B]) {
+        return Some(("gz", 0.95));
+    }
+    if buf.starts_with(b"MComprHD") {
+        return Some(("chd", 0.99));
+    }
+
     // Check common exact headers first (high confidence)
     if buf.len() >= 4 {
         // NES: "NES\x1A"
         if buf[0..4] == [0x4E, 0x45, 0x53, 0x1A] {
             return Some(("nes", 0.99));
         }
-        // Game Boy ROM header: at 0x104 the Nintendo logo begins; detect by 0x00 at 0x104..0x104+6 heuristic
+        // N64 in big-endian, byte-swapped and little-endian layouts
+        match buf[0..4] {
+            [0x80, 0x37, 0x12, 0x40] => return Some(("z64", 0.99)),
+            [0x37, 0x80, 0x40, 0x12] => return Some(("v64", 0.99)),
+            [0x40, 0x12, 0x37, 0x80] => return Some(("n64", 0.99)),
+            _ => {}
+        }
     }
 
     if buf.len() >= 64 {
@@ -23,6 +64,16 @@ fn detect_extension_from_bytes(buf: &[u8]) -> Option<(&'static str, f32)> {
         }
     }
 
+    // Game Boy: the Nintendo logo starts at 0x104 with CE ED 66 66
+    if buf.len() >= 0x108 && buf[0x104..0x108] == [0xCE, 0xED, 0x66, 0x66] {
+        return Some(("gb", 0.95));
+    }
+
+    // Heuristic patterns: look for 'SEGA' at offset 0x100 for Mega Drive / Genesis
+    if buf.len() >= 0x200 && buf[0x100..0x104] == [0x53, 0x45, 0x47, 0x41] {
+        return Some(("bin", 0.9));
+    }
+
     if buf.len() >= 512 {
         // SMC/SFC heuristic: presence of 0x00 at offset 3 is common in SNES headers
         if buf[3] == 0x00 {
@@ -30,55 +81,80 @@ fn detect_extension_from_bytes(buf: &[u8]) -> Option<(&'static str, f32)> {
         }
     }
 
-    // Heuristic patterns: look for 'SEGA' at offset 0x100 for Mega Drive / Genesis
-    if buf.len() >= 0x200 {
-        if buf[0x100..0x104] == [0x53, 0x45, 0x47, 0x41] {
-            return Some(("bin", 0.9));
+    // Not recognized
+    None
+}
+
+fn extension_matches(detected: &str, current: &str) -> bool {
+    let current = current.to_ascii_lowercase();
+    if current == detected {
+        return true;
+    }
+    EXTENSION_ALIASES
+        .iter()
+        .any(|(ext, aliases)| *ext == detected && aliases.contains(&current.as_str()))
+}
+
+/// Sniff the leading bytes of `source` and return the extension `--fix-extension` may
+/// give it under `mode`, or `None` to leave it alone.
+/// - `Never`: never rename.
+/// - `Always`: use the detected extension whenever a signature is found.
+/// - `Auto`: a confident detection only, applied when it contradicts the extension of
+///   the name the file ends up with (see [`fixes_extension`]).
+pub fn sniffed_extension(source: &Path, mode: &FixExtensionMode) -> Option<&'static str> {
+    if matches!(mode, FixExtensionMode::Never) {
+        return None;
+    }
+    let mut buf = vec![0u8; SNIFF_BYTES];
+    let mut file = File::open(source).ok()?;
+    let mut filled = 0;
+    while filled < buf.len() {
+        match file.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(_) => return None,
         }
     }
+    buf.truncate(filled);
+    let (ext, confidence) = detect_extension_from_bytes(&buf)?;
+    match mode {
+        FixExtensionMode::Always => Some(ext),
+        FixExtensionMode::Auto => (confidence >= AUTO_CONFIDENCE_THRESHOLD).then_some(ext),
+        FixExtensionMode::Never => None,
+    }
+}
 
-    // Not recognized
-    None
+/// Whether `detected`, from [`sniffed_extension`], replaces the extension of `file_name`:
+/// always under `Always`, and under `Auto` only when the two contradict (e.g. a `.bin`
+/// that is actually a zip).
+pub fn fixes_extension(detected: &str, file_name: &Path, mode: &FixExtensionMode) -> bool {
+    match mode {
+        FixExtensionMode::Always => true,
+        FixExtensionMode::Auto => file_name
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_none_or(|current| !extension_matches(detected, current)),
+        FixExtensionMode::Never => false,
+    }
 }
 
 /// Post-process candidates to correct file extensions based on headers.
-/// Behavior modes:
-/// - `Never`: do nothing.
-/// - `Always`: always replace extension when a signature is found.
-/// - `Auto`: replace only when the detection confidence exceeds the heuristic threshold.
+/// Behavior modes follow [`sniffed_extension`].
 pub fn postprocess_candidates(
     mut candidates: Vec<crate::candidates::Candidate>,
     config: &Config,
 ) -> Vec<crate::candidates::Candidate> {
-    match config.fix_extension {
-        FixExtensionMode::Never => return candidates,
-        _ => (),
+    if matches!(config.fix_extension, FixExtensionMode::Never) {
+        return candidates;
     }
 
-    let auto_threshold: f32 = 0.9; // confidence threshold for Auto mode
-
     for cand in candidates.iter_mut() {
         for rec in cand.matches.iter_mut() {
-            let src = rec.source.clone();
-            if let Ok(mut f) = File::open(&src) {
-                let to_read = 1024.min(rec.size as usize).max(16);
-                let mut buf = vec![0u8; to_read];
-                if let Ok(n) = f.read(&mut buf) {
-                    buf.truncate(n);
-                    if let Some((ext, conf)) = detect_extension_from_bytes(&buf) {
-                        let should_apply = match config.fix_extension {
-                            FixExtensionMode::Always => true,
-                            FixExtensionMode::Auto => conf >= auto_threshold,
-                            FixExtensionMode::Never => false,
-                        };
-                        if should_apply {
-                            if let Some(stem) = rec.relative.file_stem().and_then(|s| s.to_str()) {
-                                let new_rel = PathBuf::from(format!("{}.{}", stem, ext));
-                                rec.relative = new_rel;
-                            }
-                        }
-                    }
-                }
+            if let Some(ext) = sniffed_extension(&rec.source, &config.fix_extension)
+                && fixes_extension(ext, &rec.relative, &config.fix_extension)
+                && let Some(stem) = rec.relative.file_stem().and_then(|s| s.to_str())
+            {
+                rec.relative = PathBuf::from(format!("{}.{}", stem, ext));
             }
         }
     }
@@ -116,6 +192,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
         let cand = crate::candidates::Candidate {
             name: "g".to_string(),
@@ -124,6 +206,8 @@ mod tests {
 
         let cfg = Config {
             fix_extension: FixExtensionMode::Always,
+            normalize_extension_case: false,
+            shorten_paths: false,
             ..Config::default()
         };
         let out = postprocess_candidates(vec![cand], &cfg);
@@ -155,6 +239,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
         let cand = crate::candidates::Candidate {
             name: "g".to_string(),
@@ -163,6 +253,8 @@ mod tests {
 
         let cfg_auto = Config {
             fix_extension: FixExtensionMode::Auto,
+            normalize_extension_case: false,
+            shorten_paths: false,
             ..Config::default()
         };
         let out_auto = postprocess_candidates(vec![cand.clone()], &cfg_auto);
@@ -174,6 +266,8 @@ mod tests {
 
         let cfg_always = Config {
             fix_extension: FixExtensionMode::Always,
+            normalize_extension_case: false,
+            shorten_paths: false,
             ..Config::default()
         };
         let out_always = postprocess_candidates(vec![cand], &cfg_always);
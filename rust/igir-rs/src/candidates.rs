@@ -4,9 +4,42 @@ use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 use crate::config::Config;
-use crate::types::FileRecord;
+use crate::types::{FileRecord, MtimeTiebreak};
 use crate::write_candidate::WriteCandidate;
 use rayon::prelude::*;
+use regex::Regex;
+
+/// A DAT ROM as `(name, crc32, md5, sha1, size)`. Checksum variants of one ROM
+/// (see [`crate::dat::DatRom::variants`]) are grouped by [`dat_rom_variants`].
+pub type DatRomTuple = (
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<u64>,
+);
+
+/// One tuple per checksum variant of `rom`.
+fn rom_variant_tuples(rom: &crate::dat::DatRom) -> Vec<DatRomTuple> {
+    rom.variants()
+        .into_iter()
+        .map(|variant| {
+            (
+                rom.name.clone(),
+                variant.crc32,
+                variant.md5,
+                variant.sha1,
+                variant.size,
+            )
+        })
+        .collect()
+}
+
+/// The checksum variants of each DAT ROM, one group per ROM, for
+/// [`generate_candidates_for_variants`]. ROMs that share a name stay separate.
+pub fn dat_rom_variants(roms: &[crate::dat::DatRom]) -> Vec<Vec<DatRomTuple>> {
+    roms.iter().map(rom_variant_tuples).collect()
+}
 
 #[derive(Debug, Clone, Serialize)]
 pub struct Candidate {
@@ -46,137 +79,284 @@ fn tokenize_title(input: &str) -> Vec<String> {
         .collect()
 }
 
-fn compare_match(a: &(FileRecord, f64), b: &(FileRecord, f64)) -> Ordering {
+fn compare_match(a: &(FileRecord, f64), b: &(FileRecord, f64), options: &MatchOptions) -> Ordering {
     let score_ord = b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal);
     if score_ord != Ordering::Equal {
         return score_ord;
     }
 
-    let ka = format!(
-        "{}::{}",
-        a.0.source.to_string_lossy(),
-        a.0.relative.to_string_lossy()
-    );
-    let kb = format!(
-        "{}::{}",
-        b.0.source.to_string_lossy(),
-        b.0.relative.to_string_lossy()
-    );
-    ka.cmp(&kb)
+    let input_ord = if options.priority_input {
+        input_precedence(&a.0).cmp(&input_precedence(&b.0))
+    } else {
+        Ordering::Equal
+    };
+    if input_ord != Ordering::Equal {
+        return input_ord;
+    }
+
+    // Files without a known mtime sort after those with one in either direction.
+    let mtime_ord = match options.tiebreak {
+        MtimeTiebreak::Path => Ordering::Equal,
+        MtimeTiebreak::Newest => b.0.modified.cmp(&a.0.modified),
+        MtimeTiebreak::Oldest => {
+            (a.0.modified.is_none(), a.0.modified).cmp(&(b.0.modified.is_none(), b.0.modified))
+        }
+    };
+    mtime_ord.then_with(|| a.0.identity().cmp(&b.0.identity()))
+}
+
+/// `--priority-input` sort key: earlier `-i` inputs first, files from no known input last.
+pub fn input_precedence(record: &FileRecord) -> (bool, Option<usize>) {
+    (record.input_index.is_none(), record.input_index)
 }
 
 /// Produce ranked candidate matches for each DAT ROM entry.
-pub fn generate_candidates(
-    dat_roms: &[(
-        String,
-        Option<String>,
-        Option<String>,
-        Option<String>,
-        Option<u64>,
-    )],
-    records: &[FileRecord],
-) -> Vec<Candidate> {
-    const MIN_SCORE: f64 = 25.0;
-    const SCORE_SIZE_EXACT: f64 = 700.0;
-    const SCORE_SIZE_ONLY: f64 = 20.0;
-    const SCORE_TITLE_EQUAL: f64 = 300.0;
-    const SCORE_TOKEN_SCALE: f64 = 300.0;
-    const SCORE_CRC32: f64 = 800.0;
-    const SCORE_MD5: f64 = 850.0;
-    const SCORE_SHA1: f64 = 900.0;
+pub fn generate_candidates(dat_roms: &[DatRomTuple], records: &[FileRecord]) -> Vec<Candidate> {
+    generate_candidates_with_case(dat_roms, records, true)
+}
 
-    // Parallelize across DAT ROM entries; preserve input order by using `par_iter()`
-    // on the slice and collecting the results. Each DAT entry's candidate
-    // generation remains deterministic: we compute scores and then sort.
-    dat_roms
-        .par_iter()
-        .map(|(name, crc32, md5, sha1, size)| {
-            let dat_stem = Path::new(name)
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("");
-            let dat_norm = crate::records::normalize_title(dat_stem);
-            let dat_tokens = tokenize_title(&dat_norm);
+/// Whether two normalized titles count as an exact title match.
+fn titles_equal(dat_norm: &str, rec_norm: &str, ignore_case: bool) -> bool {
+    if ignore_case {
+        dat_norm.to_lowercase() == rec_norm.to_lowercase()
+    } else {
+        dat_norm == rec_norm
+    }
+}
 
-            let mut matches = Vec::new();
+const MIN_SCORE: f64 = 25.0;
+const SCORE_SIZE_EXACT: f64 = 700.0;
+const SCORE_SIZE_ONLY: f64 = 20.0;
+const SCORE_TITLE_EQUAL: f64 = 300.0;
+const SCORE_TOKEN_SCALE: f64 = 300.0;
+const SCORE_CRC32: f64 = 800.0;
+const SCORE_MD5: f64 = 850.0;
+const SCORE_SHA1: f64 = 900.0;
+
+/// Matching knobs taken from the CLI: `--ignore-case`, `--tiebreak-mtime`,
+/// `--priority-input` and `--title-strip-regex`.
+#[derive(Debug, Clone, Default)]
+pub struct MatchOptions {
+    pub ignore_case: bool,
+    pub tiebreak: MtimeTiebreak,
+    pub priority_input: bool,
+    pub title_strip: Vec<Regex>,
+}
 
-            for record in records {
-                let mut score = 0.0;
-                let mut checksum_matched = false;
-
-                // Only consider a CRC32 match if the DAT also specifies a size
-                // and the sizes are equal. This avoids false positives where
-                // CRC32 collisions or truncated files could otherwise match.
-                if let (Some(dat_crc), Some(dat_size)) = (crc32.as_deref(), size) {
-                    if record
-                        .checksums
-                        .crc32
-                        .as_deref()
-                        .is_some_and(|c| c.eq_ignore_ascii_case(dat_crc))
-                        && record.size == *dat_size
-                    {
-                        score += SCORE_CRC32;
-                        checksum_matched = true;
-                    }
-                }
+impl MatchOptions {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            ignore_case: config.ignore_case,
+            tiebreak: config.tiebreak_mtime,
+            priority_input: config.priority_input,
+            title_strip: config.title_strip_patterns(),
+        }
+    }
+}
 
-                if let Some(dat_md5) = md5 {
-                    if record
-                        .checksums
-                        .md5
-                        .as_deref()
-                        .is_some_and(|c| c.eq_ignore_ascii_case(dat_md5))
-                    {
-                        score += SCORE_MD5;
-                        checksum_matched = true;
-                    }
-                }
+/// A DAT entry's normalized title, computed once and reused for every record.
+struct DatTitle<'a> {
+    name: &'a str,
+    norm: String,
+    tokens: Vec<String>,
+}
 
-                if let Some(dat_sha1) = sha1 {
-                    if record
-                        .checksums
-                        .sha1
-                        .as_deref()
-                        .is_some_and(|c| c.eq_ignore_ascii_case(dat_sha1))
-                    {
-                        score += SCORE_SHA1;
-                        checksum_matched = true;
-                    }
-                }
+impl<'a> DatTitle<'a> {
+    fn new(name: &'a str, options: &MatchOptions) -> Self {
+        let stem = Path::new(name)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("");
+        let norm = crate::records::normalize_title_with(stem, &options.title_strip);
+        let tokens = tokenize_title(&norm);
+        Self { name, norm, tokens }
+    }
+}
 
-                if let Some(dat_size) = size {
-                    if record.size == *dat_size {
-                        if let Some(name_str) = record.relative.file_name().and_then(|n| n.to_str())
-                        {
-                            if name_str == name {
-                                score += SCORE_SIZE_EXACT;
-                            } else {
-                                score += SCORE_SIZE_ONLY;
-                            }
-                        }
-                    }
-                }
+/// A record's normalized title, computed once and reused for every DAT entry.
+struct RecordTitle {
+    norm: String,
+    tokens: Vec<String>,
+}
 
-                if let Some(rec_stem) = record.relative.file_stem().and_then(|s| s.to_str()) {
-                    let rec_norm = crate::records::normalize_title(rec_stem);
-                    if !dat_norm.is_empty() && rec_norm == dat_norm {
-                        score += SCORE_TITLE_EQUAL;
-                    } else if !dat_tokens.is_empty() {
-                        let rec_tokens = tokenize_title(&rec_norm);
-                        if !rec_tokens.is_empty() {
-                            let dat_set: HashSet<_> = dat_tokens.iter().collect();
-                            let rec_set: HashSet<_> = rec_tokens.iter().collect();
-                            let inter = dat_set.intersection(&rec_set).count() as f64;
-                            let union = dat_set.union(&rec_set).count() as f64;
-                            if union > 0.0 {
-                                score += (inter / union) * SCORE_TOKEN_SCALE;
-                            }
-                        }
+impl RecordTitle {
+    /// `None` for records without a file stem to compare.
+    fn new(record: &FileRecord, options: &MatchOptions) -> Option<Self> {
+        let stem = record.relative.file_stem().and_then(|s| s.to_str())?;
+        let norm = crate::records::normalize_title_with(stem, &options.title_strip);
+        let tokens = tokenize_title(&norm);
+        Some(Self { norm, tokens })
+    }
+}
+
+/// How well one record fits one DAT ROM.
+struct RecordScore {
+    score: f64,
+    checksum_matched: bool,
+    /// 1.0 for an exact title match, else the token Jaccard overlap.
+    title_overlap: f64,
+}
+
+fn score_record(
+    variants: &[DatRomTuple],
+    title: &DatTitle,
+    record: &FileRecord,
+    record_title: Option<&RecordTitle>,
+    options: &MatchOptions,
+) -> RecordScore {
+    let mut score = 0.0;
+    let mut checksum_matched = false;
+
+    for (_, crc32, md5, sha1, size) in variants.iter() {
+        let mut variant_score = 0.0;
+        let mut variant_checksum_matched = false;
+
+        // Only consider a CRC32 match if the DAT also specifies a size
+        // and the sizes are equal. This avoids false positives where
+        // CRC32 collisions or truncated files could otherwise match.
+        if let (Some(dat_crc), Some(dat_size)) = (crc32.as_deref(), size) {
+            if record
+                .checksums
+                .crc32
+                .as_deref()
+                .is_some_and(|c| c.eq_ignore_ascii_case(dat_crc))
+                && record.size == *dat_size
+            {
+                variant_score += SCORE_CRC32;
+                variant_checksum_matched = true;
+            }
+        }
+
+        if let Some(dat_md5) = md5 {
+            if record
+                .checksums
+                .md5
+                .as_deref()
+                .is_some_and(|c| c.eq_ignore_ascii_case(dat_md5))
+            {
+                variant_score += SCORE_MD5;
+                variant_checksum_matched = true;
+            }
+        }
+
+        if let Some(dat_sha1) = sha1 {
+            if record
+                .checksums
+                .sha1
+                .as_deref()
+                .is_some_and(|c| c.eq_ignore_ascii_case(dat_sha1))
+            {
+                variant_score += SCORE_SHA1;
+                variant_checksum_matched = true;
+            }
+        }
+
+        if let Some(dat_size) = size {
+            if record.size == *dat_size {
+                if let Some(name_str) = record.relative.file_name().and_then(|n| n.to_str()) {
+                    if name_str == title.name {
+                        variant_score += SCORE_SIZE_EXACT;
+                    } else {
+                        variant_score += SCORE_SIZE_ONLY;
                     }
                 }
+            }
+        }
+
+        if (variant_checksum_matched, variant_score) > (checksum_matched, score) {
+            score = variant_score;
+            checksum_matched = variant_checksum_matched;
+        }
+    }
+
+    let mut title_overlap = 0.0;
+    if let Some(rec_title) = record_title {
+        if !title.norm.is_empty() && titles_equal(&title.norm, &rec_title.norm, options.ignore_case)
+        {
+            title_overlap = 1.0;
+            score += SCORE_TITLE_EQUAL;
+        } else if !title.tokens.is_empty() && !rec_title.tokens.is_empty() {
+            let dat_set: HashSet<_> = title.tokens.iter().collect();
+            let rec_set: HashSet<_> = rec_title.tokens.iter().collect();
+            let inter = dat_set.intersection(&rec_set).count() as f64;
+            let union = dat_set.union(&rec_set).count() as f64;
+            if union > 0.0 {
+                title_overlap = inter / union;
+                score += title_overlap * SCORE_TOKEN_SCALE;
+            }
+        }
+    }
+
+    RecordScore {
+        score,
+        checksum_matched,
+        title_overlap,
+    }
+}
+
+/// Like [`generate_candidates`], with control over whether exact-title comparison ignores case.
+pub fn generate_candidates_with_case(
+    dat_roms: &[DatRomTuple],
+    records: &[FileRecord],
+    ignore_case: bool,
+) -> Vec<Candidate> {
+    let options = MatchOptions {
+        ignore_case,
+        ..MatchOptions::default()
+    };
+    generate_candidates_with_options(dat_roms, records, &options)
+}
+
+/// Like [`generate_candidates_with_case`], with every [`MatchOptions`] knob.
+pub fn generate_candidates_with_options(
+    dat_roms: &[DatRomTuple],
+    records: &[FileRecord],
+    options: &MatchOptions,
+) -> Vec<Candidate> {
+    candidates_for_groups(&dat_roms.chunks(1).collect::<Vec<_>>(), records, options)
+}
+
+/// Like [`generate_candidates_with_options`] for DAT ROMs listing several checksum
+/// variants (see [`dat_rom_variants`]): one candidate per ROM, which a record matching
+/// any of its variants joins, scored by its best variant.
+pub fn generate_candidates_for_variants(
+    dat_roms: &[Vec<DatRomTuple>],
+    records: &[FileRecord],
+    options: &MatchOptions,
+) -> Vec<Candidate> {
+    let groups: Vec<&[DatRomTuple]> = dat_roms
+        .iter()
+        .map(Vec::as_slice)
+        .filter(|variants| !variants.is_empty())
+        .collect();
+    candidates_for_groups(&groups, records, options)
+}
 
-                if score >= MIN_SCORE {
-                    matches.push((record.clone(), score, checksum_matched));
+fn candidates_for_groups(
+    groups: &[&[DatRomTuple]],
+    records: &[FileRecord],
+    options: &MatchOptions,
+) -> Vec<Candidate> {
+    let record_titles: Vec<Option<RecordTitle>> = records
+        .par_iter()
+        .map(|record| RecordTitle::new(record, options))
+        .collect();
+
+    // Parallelize across DAT ROM entries; preserve input order by using `par_iter()`
+    // on the slice and collecting the results. Each DAT entry's candidate
+    // generation remains deterministic: we compute scores and then sort.
+    groups
+        .par_iter()
+        .map(|variants| {
+            let name = &variants[0].0;
+            let title = DatTitle::new(name, options);
+
+            let mut matches = Vec::new();
+
+            for (record, record_title) in records.iter().zip(&record_titles) {
+                let scored = score_record(variants, &title, record, record_title.as_ref(), options);
+                if scored.score >= MIN_SCORE {
+                    matches.push((record.clone(), scored.score, scored.checksum_matched));
                 }
             }
 
@@ -191,8 +371,8 @@ pub fn generate_candidates(
                 .map(|(rec, score, _)| (rec, score))
                 .collect();
 
-            checksum_matches.sort_by(compare_match);
-            fallback_matches.sort_by(compare_match);
+            checksum_matches.sort_by(|a, b| compare_match(a, b, options));
+            fallback_matches.sort_by(|a, b| compare_match(a, b, options));
 
             let ordered = if checksum_matches.is_empty() {
                 fallback_matches
@@ -208,72 +388,244 @@ pub fn generate_candidates(
         .collect()
 }
 
+/// The best-scoring record for a DAT ROM that found no match, with why it fell short.
+#[derive(Debug, Clone, Serialize)]
+pub struct NearMiss {
+    pub name: String,
+    pub source: std::path::PathBuf,
+    pub relative: std::path::PathBuf,
+    pub score: f64,
+    pub reasons: Vec<String>,
+    pub size_anomaly: Option<SizeAnomaly>,
+}
+
+/// A size that is a power-of-two multiple or fraction of the DAT size, which points to a
+/// bad dump rather than an unrelated file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SizeAnomaly {
+    Overdump,
+    Underdump,
+}
+
+impl SizeAnomaly {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SizeAnomaly::Overdump => "probable overdump",
+            SizeAnomaly::Underdump => "probable underdump",
+        }
+    }
+}
+
+/// Classify `file_size` against `dat_size`; `None` unless one is the other times a power
+/// of two.
+pub fn size_anomaly(file_size: u64, dat_size: u64) -> Option<SizeAnomaly> {
+    if file_size == 0 || dat_size == 0 || file_size == dat_size {
+        return None;
+    }
+    let (larger, smaller) = (file_size.max(dat_size), file_size.min(dat_size));
+    if larger % smaller != 0 || !(larger / smaller).is_power_of_two() {
+        return None;
+    }
+    Some(if file_size > dat_size {
+        SizeAnomaly::Overdump
+    } else {
+        SizeAnomaly::Underdump
+    })
+}
+
+/// Fewest points a record must score to be reported as a near miss: a size match, or about
+/// a twentieth of the title's words in common. Anything lower is an unrelated file.
+const NEAR_MISS_MIN_SCORE: f64 = 15.0;
+
+/// Most near misses reported for one DAT game, best first, so a game of many tracks does
+/// not flood the report.
+const NEAR_MISSES_PER_GAME: usize = 3;
+
+/// Surface the best record for every DAT ROM in `dat_roms`, including scores below the
+/// matching threshold, so the test command can explain why games went unmatched.
+/// Records scoring below `min_score` (and never below [`NEAR_MISS_MIN_SCORE`]) are not
+/// reported, nor more than [`NEAR_MISSES_PER_GAME`] ROMs of one game.
+pub fn near_misses(
+    dat_roms: &[crate::dat::DatRom],
+    records: &[FileRecord],
+    options: &MatchOptions,
+    min_score: f64,
+) -> Vec<NearMiss> {
+    let min_score = min_score.max(NEAR_MISS_MIN_SCORE);
+    let record_titles: Vec<Option<RecordTitle>> = records
+        .par_iter()
+        .map(|record| RecordTitle::new(record, options))
+        .collect();
+    let misses: Vec<(Option<&str>, NearMiss)> = dat_roms
+        .par_iter()
+        .filter_map(|rom| {
+            let variants = rom_variant_tuples(rom);
+            let title = DatTitle::new(&rom.name, options);
+            let (record, scored) = records
+                .iter()
+                .zip(&record_titles)
+                .map(|(record, record_title)| {
+                    let scored =
+                        score_record(&variants, &title, record, record_title.as_ref(), options);
+                    (record, scored)
+                })
+                .filter(|(_, scored)| scored.score >= min_score)
+                .min_by(|a, b| {
+                    b.1.score
+                        .partial_cmp(&a.1.score)
+                        .unwrap_or(Ordering::Equal)
+                        .then_with(|| a.0.identity().cmp(&b.0.identity()))
+                })?;
+            // Only a record carrying this ROM's title is a dump of it; a size that merely
+            // divides evenly says nothing about an unrelated file.
+            let anomaly = if scored.title_overlap >= 1.0 {
+                variants
+                    .iter()
+                    .filter_map(|v| v.4)
+                    .find_map(|dat_size| Some((size_anomaly(record.size, dat_size)?, dat_size)))
+            } else {
+                None
+            };
+            let miss = NearMiss {
+                name: title.name.to_string(),
+                source: record.source.clone(),
+                relative: record.relative.clone(),
+                score: scored.score,
+                reasons: near_miss_reasons(&variants, record, &scored, anomaly),
+                size_anomaly: anomaly.map(|(anomaly, _)| anomaly),
+            };
+            Some((rom.description.as_deref(), miss))
+        })
+        .collect();
+
+    // Keep each game's best few, reported in DAT order.
+    let mut by_game: HashMap<Option<&str>, Vec<usize>> = HashMap::new();
+    for (idx, (game, _)) in misses.iter().enumerate() {
+        by_game.entry(*game).or_default().push(idx);
+    }
+    let mut kept = vec![false; misses.len()];
+    for mut indexes in by_game.into_values() {
+        indexes.sort_by(|&a, &b| {
+            misses[b]
+                .1
+                .score
+                .partial_cmp(&misses[a].1.score)
+                .unwrap_or(Ordering::Equal)
+                .then(a.cmp(&b))
+        });
+        for idx in indexes.into_iter().take(NEAR_MISSES_PER_GAME) {
+            kept[idx] = true;
+        }
+    }
+    misses
+        .into_iter()
+        .zip(kept)
+        .filter_map(|((_, miss), kept)| kept.then_some(miss))
+        .collect()
+}
+
+fn near_miss_reasons(
+    variants: &[DatRomTuple],
+    record: &FileRecord,
+    scored: &RecordScore,
+    anomaly: Option<(SizeAnomaly, u64)>,
+) -> Vec<String> {
+    let mut reasons = Vec::new();
+    let dat_sizes: Vec<u64> = variants.iter().filter_map(|v| v.4).collect();
+    if let Some((anomaly, dat_size)) = anomaly {
+        reasons.push(format!(
+            "{} (DAT {}, file {})",
+            anomaly.label(),
+            dat_size,
+            record.size
+        ));
+    } else if !dat_sizes.is_empty() && !dat_sizes.contains(&record.size) {
+        reasons.push(format!(
+            "size mismatch (DAT {}, file {})",
+            dat_sizes[0], record.size
+        ));
+    }
+    if !scored.checksum_matched {
+        let comparable = variants.iter().any(|(_, crc32, md5, sha1, _)| {
+            (crc32.is_some() && record.checksums.crc32.is_some())
+                || (md5.is_some() && record.checksums.md5.is_some())
+                || (sha1.is_some() && record.checksums.sha1.is_some())
+        });
+        reasons.push(if comparable {
+            "checksum mismatch".to_string()
+        } else {
+            "checksum absent".to_string()
+        });
+    }
+    if scored.title_overlap < 1.0 {
+        reasons.push(format!(
+            "low token overlap ({:.0}%)",
+            scored.title_overlap * 100.0
+        ));
+    }
+    reasons
+}
+
 /// Build write-ready candidates by combining dat multi-file sets with available FileRecords.
 /// - `dat_sets` : map of set name -> Vec<dat rom names belonging to the set>
 /// - `dat_roms` : list of all dat roms as tuples (name, crc, md5, sha1, size)
 /// - `records` : scanned input file records
 pub fn build_write_candidates(
     dat_sets: &std::collections::HashMap<String, Vec<String>>,
-    dat_roms: &[(
-        String,
-        Option<String>,
-        Option<String>,
-        Option<String>,
-        Option<u64>,
-    )],
+    dat_roms: &[DatRomTuple],
     records: &[FileRecord],
     config: &Config,
 ) -> Vec<WriteCandidate> {
     let mut out = Vec::new();
     // Track which physical records have already been assigned to a part so we
     // don't reuse the same file for multiple dat entries (unless the user
-    // explicitly wants excess/incomplete sets). The key is the record's
-    // absolute source path joined with its relative path to be unique for
-    // in-archive entries.
+    // explicitly wants excess/incomplete sets). Records are keyed by
+    // `FileRecord::identity` so in-archive entries stay distinct.
     let mut used_records: std::collections::HashSet<String> = std::collections::HashSet::new();
 
-    // Build a lookup map for quick dat rom access by name
-    let mut dat_map: std::collections::HashMap<
-        String,
-        (Option<String>, Option<String>, Option<String>, Option<u64>),
-    > = std::collections::HashMap::new();
-    for (name, crc, md5, sha1, size) in dat_roms {
-        dat_map.insert(
-            name.clone(),
-            (crc.clone(), md5.clone(), sha1.clone(), *size),
-        );
+    // Build a lookup map for quick dat rom access by name, keeping every
+    // checksum variant listed for that name.
+    let mut dat_map: std::collections::HashMap<String, Vec<DatRomTuple>> =
+        std::collections::HashMap::new();
+    for rom in dat_roms {
+        dat_map.entry(rom.0.clone()).or_default().push(rom.clone());
     }
 
+    let hash_archives =
+        crate::candidate_archive_hasher::archive_hashing_enabled(dat_roms, records, config);
+    let options = MatchOptions::from_config(config);
+
     // For each set, attempt to find matching records for all parts
     for (set_name, parts) in dat_sets {
         let mut matched_files: Vec<FileRecord> = Vec::new();
         let mut files_map: std::collections::HashMap<String, FileRecord> =
             std::collections::HashMap::new();
+        let mut missing: Vec<String> = Vec::new();
         let mut all_found = true;
         for part in parts {
-            if let Some((crc, md5, sha1, size)) = dat_map.get(part) {
+            if let Some(variants) = dat_map.get(part) {
                 // Build candidate list prioritizing checksums including CHD-provided sha1/md5
-                let mut candidates = generate_candidates(
-                    &[(part.clone(), crc.clone(), md5.clone(), sha1.clone(), *size)],
+                let mut candidates = generate_candidates_for_variants(
+                    std::slice::from_ref(variants),
                     records,
+                    &options,
                 );
                 // Run conservative post-processing steps that may correct extensions
                 // or inspect archives. These are conditional on config flags so
                 // default tests and behavior are unchanged.
                 candidates = crate::candidate_extension::postprocess_candidates(candidates, config);
-                candidates =
-                    crate::candidate_archive_hasher::process_archive_hashes(candidates, config);
+                if hash_archives {
+                    candidates =
+                        crate::candidate_archive_hasher::process_archive_hashes(candidates, config);
+                }
                 // Try to pick the highest-ranked candidate that hasn't already
                 // been used for another part. This prevents a single file from
                 // being assigned to multiple parts within the same run.
                 let mut chosen_opt: Option<FileRecord> = None;
                 if let Some(c) = candidates.into_iter().next() {
                     for cand in c.matches.iter() {
-                        let key = format!(
-                            "{}::{}",
-                            cand.source.to_string_lossy(),
-                            cand.relative.to_string_lossy()
-                        );
+                        let key = cand.identity();
                         if !used_records.contains(&key) {
                             chosen_opt = Some(cand.clone());
                             used_records.insert(key);
@@ -289,6 +641,7 @@ pub fn build_write_candidates(
                 // If no direct candidate found, decide based on config
                 if config.allow_incomplete_sets {
                     // skip this part but continue building partial set
+                    missing.push(part.clone());
                     continue;
                 }
                 all_found = false;
@@ -302,8 +655,17 @@ pub fn build_write_candidates(
         if (!matched_files.is_empty() && (all_found || config.allow_incomplete_sets))
             || (matched_files.is_empty() && config.allow_excess_sets)
         {
+            if !missing.is_empty() {
+                vprintln!(
+                    config.verbose,
+                    1,
+                    "incomplete set {set_name}: missing {}",
+                    missing.join(", ")
+                );
+            }
             let mut wc = WriteCandidate::new(set_name.clone(), matched_files);
             wc.files_map = files_map;
+            wc.missing = missing;
             out.push(wc);
         }
     }
@@ -311,12 +673,71 @@ pub fn build_write_candidates(
     out
 }
 
+/// Every checksum variant of every DAT ROM, the flat `dat_roms` [`build_write_candidates`] takes.
+pub fn dat_rom_tuples(roms: &[crate::dat::DatRom]) -> Vec<DatRomTuple> {
+    roms.iter().flat_map(rom_variant_tuples).collect()
+}
+
+static DISC_TAG: once_cell::sync::Lazy<Regex> = once_cell::sync::Lazy::new(|| {
+    Regex::new(r"(?i)\s*\((?:disc|disk|cd)\s*(\d+)(?:\s*of\s*\d+)?\)").unwrap()
+});
+
+/// Split a file stem around its `(Disc N)` tag (also `Disk N`, `CD N`, `Disc N of M`):
+/// the title before the tag, the disc number, and whatever follows the tag.
+pub fn split_disc_tag(stem: &str) -> Option<(&str, u32, &str)> {
+    let caps = DISC_TAG.captures(stem)?;
+    let tag = caps.get(0)?;
+    let number = caps[1].parse().ok()?;
+    let title = stem[..tag.start()].trim_end();
+    (!title.is_empty()).then_some((title, number, &stem[tag.end()..]))
+}
+
+/// The game a DAT ROM belongs to: the game's name, else its description, else the ROM.
+pub fn dat_game(rom: &crate::dat::DatRom) -> String {
+    rom.set_info
+        .as_ref()
+        .map(|info| info.game.clone())
+        .filter(|game| !game.is_empty())
+        .or_else(|| rom.description.clone())
+        .unwrap_or_else(|| rom.name.clone())
+}
+
+/// Gather `(game, ROM name)` pairs into multi-file sets, as set name -> ROM names, for
+/// [`build_write_candidates`]: the ROMs of one game, with the numbered discs of a game
+/// (`Game (Disc 1)`, `Game (Disc 2)`) under the title they share. Sets of one ROM are left out.
+pub fn group_sets(
+    entries: impl IntoIterator<Item = (String, String)>,
+) -> HashMap<String, Vec<String>> {
+    let mut sets: HashMap<String, Vec<String>> = HashMap::new();
+    for (game, name) in entries {
+        let title = split_disc_tag(&game).map_or(game.as_str(), |(title, _, _)| title);
+        let parts = sets.entry(title.to_string()).or_default();
+        if !parts.contains(&name) {
+            parts.push(name);
+        }
+    }
+    sets.retain(|_, parts| parts.len() > 1);
+    sets
+}
+
+/// The multi-file sets of the DATs (see [`group_sets`]).
+pub fn dat_sets(roms: &[crate::dat::DatRom]) -> HashMap<String, Vec<String>> {
+    group_sets(roms.iter().map(|rom| (dat_game(rom), rom.name.clone())))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::types::FileRecord;
     use std::path::PathBuf;
 
+    fn ignore_case() -> MatchOptions {
+        MatchOptions {
+            ignore_case: true,
+            ..MatchOptions::default()
+        }
+    }
+
     fn make_rec(name: &str) -> FileRecord {
         FileRecord {
             source: PathBuf::from(name),
@@ -334,9 +755,158 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
+        }
+    }
+
+    #[test]
+    fn record_identity_keys_on_source_and_relative() {
+        let a = make_rec("game.bin");
+        let mut same = make_rec("game.bin");
+        same.size = 42;
+        let mut other_entry = make_rec("game.bin");
+        other_entry.relative = PathBuf::from("inner.bin");
+        let other_source = make_rec("other.bin");
+
+        assert_eq!(a.identity(), same.identity());
+        assert_ne!(a.identity(), other_entry.identity());
+        assert_ne!(a.identity(), other_source.identity());
+    }
+
+    fn dat_rom(name: &str, crc32: &str, size: u64, game: &str) -> crate::dat::DatRom {
+        crate::dat::DatRom {
+            name: name.to_string(),
+            description: Some(game.to_string()),
+            source_dat: PathBuf::from("test.dat"),
+            size: Some(size),
+            crc32: Some(crc32.to_string()),
+            md5: None,
+            sha1: None,
+            sha256: None,
+            match_reasons: None,
+            serial: None,
+            version: None,
+            region: None,
+            alternates: Vec::new(),
+            dat_name: None,
+            dat_description: None,
+            set_info: None,
         }
     }
 
+    #[test]
+    fn near_misses_report_sub_threshold_best_with_reasons() {
+        let dat_roms = vec![dat_rom(
+            "Sonic and Knuckles and Tails Adventure Deluxe Edition Plus (USA).md",
+            "12345678",
+            524288,
+            "Sonic",
+        )];
+        let dat = dat_rom_variants(&dat_roms);
+        let mut near = make_rec("Sonic Red Blue Green Cyan Pink Gold Grey Jade.md");
+        near.size = 1000;
+        near.checksums.crc32 = Some("deadbeef".to_string());
+        let unrelated = make_rec("Zelda.md");
+        let records = vec![unrelated, near];
+
+        assert!(
+            generate_candidates_for_variants(&dat, &records, &ignore_case())[0]
+                .matches
+                .is_empty()
+        );
+
+        let misses = near_misses(&dat_roms, &records, &ignore_case(), 0.0);
+        assert_eq!(misses.len(), 1);
+        let miss = &misses[0];
+        assert_eq!(
+            miss.relative,
+            PathBuf::from("Sonic Red Blue Green Cyan Pink Gold Grey Jade.md")
+        );
+        assert!(miss.score > 0.0 && miss.score < MIN_SCORE, "{}", miss.score);
+        assert_eq!(
+            miss.reasons,
+            vec![
+                "size mismatch (DAT 524288, file 1000)".to_string(),
+                "checksum mismatch".to_string(),
+                "low token overlap (6%)".to_string(),
+            ]
+        );
+
+        assert!(near_misses(&dat_roms, &records, &ignore_case(), MIN_SCORE).is_empty());
+        // a single shared word is below the near-miss floor whatever the caller asks for
+        let faint = vec![make_rec("Sonic.md")];
+        let words: Vec<String> = (1..24).map(|n| format!("Word{n}")).collect();
+        let distant = vec![dat_rom(
+            &format!("Sonic {} (USA).md", words.join(" ")),
+            "12345678",
+            524288,
+            "Sonic",
+        )];
+        assert!(near_misses(&distant, &faint, &ignore_case(), 0.0).is_empty());
+    }
+
+    #[test]
+    fn near_misses_report_at_most_a_few_roms_per_game() {
+        let dat_roms: Vec<_> = (1..=6)
+            .map(|track| {
+                dat_rom(
+                    &format!("Game (Track {track}).bin"),
+                    "12345678",
+                    1000,
+                    "Game",
+                )
+            })
+            .chain([dat_rom("Other.bin", "12345678", 1000, "Other")])
+            .collect();
+        let mut near = make_rec("Game (Track 9).bin");
+        near.size = 1000;
+        let mut other = make_rec("Other.bin");
+        other.size = 1000;
+
+        let misses = near_misses(&dat_roms, &[near, other], &ignore_case(), 0.0);
+        let names: Vec<_> = misses.iter().map(|miss| miss.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "Game (Track 1).bin",
+                "Game (Track 2).bin",
+                "Game (Track 3).bin",
+                "Other.bin"
+            ]
+        );
+    }
+
+    #[test]
+    fn doubled_size_is_flagged_as_a_probable_overdump() {
+        let dat = vec![dat_rom("Tetris (World).gb", "12345678", 32768, "Tetris")];
+        let mut doubled = make_rec("Tetris (World).gb");
+        doubled.size = 65536;
+        doubled.checksums.crc32 = Some("deadbeef".to_string());
+
+        let misses = near_misses(&dat, &[doubled], &ignore_case(), 0.0);
+        assert_eq!(misses.len(), 1);
+        assert_eq!(misses[0].size_anomaly, Some(SizeAnomaly::Overdump));
+        assert_eq!(
+            misses[0].reasons[0],
+            "probable overdump (DAT 32768, file 65536)"
+        );
+
+        // A doubled size only counts against the ROM the file is named after.
+        let mut other = make_rec("Tetris DX (World).gb");
+        other.size = 65536;
+        let misses = near_misses(&dat, &[other], &ignore_case(), 0.0);
+        assert_eq!(misses.len(), 1);
+        assert_eq!(misses[0].size_anomaly, None);
+
+        assert_eq!(size_anomaly(16384, 32768), Some(SizeAnomaly::Underdump));
+        assert_eq!(size_anomaly(49152, 32768), None);
+    }
+
     #[test]
     fn groups_similar_titles() {
         let records = vec![
@@ -371,6 +941,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
         let rec2 = FileRecord {
             source: PathBuf::from("b.bin"),
@@ -388,6 +964,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
 
         let dat_roms = vec![
@@ -455,6 +1037,120 @@ mod tests {
         );
     }
 
+    #[test]
+    fn title_strip_patterns_remove_custom_noise_before_matching() {
+        let mut repack = make_rec("b/Game {Repack}.bin");
+        repack.size = 100;
+        let mut other = make_rec("a/Game Other.bin");
+        other.size = 100;
+        let records = [repack, other];
+        let dat_roms = vec![("Game.bin".to_string(), None, None, None, Some(100u64))];
+
+        let plain = generate_candidates_with_options(&dat_roms, &records, &ignore_case());
+        assert_eq!(
+            plain[0].matches[0].source,
+            PathBuf::from("a/Game Other.bin")
+        );
+
+        let options = MatchOptions {
+            title_strip: vec![Regex::new(r"\{Repack\}").unwrap()],
+            ..ignore_case()
+        };
+        let stripped = generate_candidates_with_options(&dat_roms, &records, &options);
+        assert_eq!(
+            stripped[0].matches[0].source,
+            PathBuf::from("b/Game {Repack}.bin")
+        );
+    }
+
+    #[test]
+    fn tiebreak_mtime_orders_equal_scores_by_modification_time() {
+        use std::time::{Duration, SystemTime};
+
+        let mut older = make_rec("b/Game.bin");
+        older.size = 100;
+        older.modified = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000));
+        let mut newer = make_rec("a/Game.bin");
+        newer.size = 100;
+        newer.modified = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(2_000));
+        let records = [older, newer];
+        let dat_roms = vec![("Game.bin".to_string(), None, None, None, Some(100u64))];
+
+        let first = |tiebreak| {
+            let options = MatchOptions {
+                tiebreak,
+                ..ignore_case()
+            };
+            generate_candidates_with_options(&dat_roms, &records, &options)[0].matches[0]
+                .source
+                .clone()
+        };
+        assert_eq!(first(MtimeTiebreak::Path), PathBuf::from("a/Game.bin"));
+        assert_eq!(first(MtimeTiebreak::Oldest), PathBuf::from("b/Game.bin"));
+        assert_eq!(first(MtimeTiebreak::Newest), PathBuf::from("a/Game.bin"));
+
+        let mut records = records;
+        records[1].modified = Some(SystemTime::UNIX_EPOCH);
+        let newest = MatchOptions {
+            tiebreak: MtimeTiebreak::Newest,
+            ..ignore_case()
+        };
+        assert_eq!(
+            generate_candidates_with_options(&dat_roms, &records, &newest)[0].matches[0].source,
+            PathBuf::from("b/Game.bin")
+        );
+    }
+
+    #[test]
+    fn single_thread_matching_matches_default_pool() {
+        let records: Vec<FileRecord> = (0..32)
+            .map(|i| {
+                let mut rec = make_rec(&format!("Game {i} (USA).bin"));
+                rec.size = 100 + i;
+                rec
+            })
+            .collect();
+        let dat_roms: Vec<_> = (0..32)
+            .map(|i| (format!("Game {i}.bin"), None, None, None, Some(100 + i)))
+            .collect();
+
+        let config = crate::config::Config {
+            threads: Some(1),
+            hash_threads: Some(1),
+            scan_threads: Some(1),
+            archive_threads: None,
+            ..Default::default()
+        };
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(config.global_thread_count())
+            .build()
+            .unwrap();
+        assert_eq!(pool.current_num_threads(), 1);
+
+        let single = pool.install(|| generate_candidates(&dat_roms, &records));
+        let default = generate_candidates(&dat_roms, &records);
+        assert_eq!(
+            serde_json::to_value(&single).unwrap(),
+            serde_json::to_value(&default).unwrap()
+        );
+    }
+
+    #[test]
+    fn exact_title_bonus_ignores_case_by_default() {
+        let dat_norm = crate::records::normalize_title("Super Mario");
+        for variant in ["SUPER MARIO (USA)", "super mario (Europe)"] {
+            let rec_norm = crate::records::normalize_title(variant);
+            assert!(titles_equal(&dat_norm, &rec_norm, true), "{variant}");
+            assert!(!titles_equal(&dat_norm, &rec_norm, false), "{variant}");
+        }
+
+        let rec_upper = make_rec("SUPER MARIO (USA).bin");
+        let rec_lower = make_rec("super mario (Europe).bin");
+        let dat_roms = vec![("Super Mario.bin".to_string(), None, None, None, None)];
+        let candidates = generate_candidates(&dat_roms, &[rec_upper, rec_lower]);
+        assert_eq!(candidates[0].matches.len(), 2);
+    }
+
     #[test]
     fn checksum_preferred_over_title() {
         // rec_title better matches title, rec_checksum has checksum match
@@ -486,6 +1182,26 @@ mod tests {
         assert_eq!(norm, "Super Mario");
     }
 
+    #[test]
+    fn group_sets_gathers_numbered_discs_under_their_title() {
+        let entry = |game: &str, name: &str| (game.to_string(), name.to_string());
+        let sets = group_sets(vec![
+            entry("Game (USA) (Disc 1)", "Game (USA) (Disc 1).cue"),
+            entry("Game (USA) (Disc 1)", "Game (USA) (Disc 1).bin"),
+            entry("Game (USA) (Disc 2)", "Game (USA) (Disc 2).cue"),
+            entry("Other (USA)", "Other (USA).iso"),
+        ]);
+        assert_eq!(sets.len(), 1);
+        assert_eq!(
+            sets["Game (USA)"],
+            vec![
+                "Game (USA) (Disc 1).cue".to_string(),
+                "Game (USA) (Disc 1).bin".to_string(),
+                "Game (USA) (Disc 2).cue".to_string(),
+            ]
+        );
+    }
+
     #[test]
     fn build_write_candidates_combines_multi_file_set() {
         use std::collections::HashMap;
@@ -531,19 +1247,28 @@ mod tests {
             input: vec![],
             input_exclude: vec![],
             input_checksum_quick: false,
+            input_checksum_auto: false,
+            scan_files_only: false,
+            scan_archives_only: false,
             input_checksum_min: crate::types::Checksum::Crc32,
             input_checksum_max: None,
             input_checksum_archives: crate::types::ArchiveChecksumMode::Auto,
             dat: vec![],
             dat_exclude: vec![],
+            dat_url: vec![],
             dat_name_regex: None,
             dat_name_regex_exclude: None,
+            game_name_regex: None,
+            game_name_regex_exclude: None,
+            system: None,
+            dat_name_rewrite: None,
             dat_description_regex: None,
             dat_description_regex_exclude: None,
             dat_combine: false,
             dat_ignore_parent_clone: false,
             list_unmatched_dats: false,
             print_plan: true,
+            summary_only: false,
             enable_hasheous: false,
             igdb_client_id: None,
             igdb_client_secret: None,
@@ -554,24 +1279,37 @@ mod tests {
             patch_exclude: vec![],
             output: None,
             dir_mirror: false,
+            dir_mirror_root: false,
             dir_dat_mirror: false,
             dir_dat_name: false,
             dir_dat_description: false,
             dir_letter: false,
+            dir_region: false,
             dir_letter_count: None,
             dir_letter_limit: None,
             dir_letter_group: false,
+            dir_letter_ignore_articles: false,
             dir_game_subdir: crate::types::DirGameSubdirMode::Multiple,
             fix_extension: crate::types::FixExtensionMode::Auto,
+            normalize_extension_case: false,
+            shorten_paths: false,
             overwrite: false,
             overwrite_invalid: false,
+            on_collision: crate::types::CollisionPolicy::Skip,
+            quarantine: None,
+            no_space_check: false,
             move_delete_dirs: crate::types::MoveDeleteDirsMode::Auto,
+            extract_include: None,
+            extract_exclude: None,
             clean_exclude: vec![],
             clean_backup: None,
+            clean_backup_keep: None,
             clean_dry_run: false,
             zip_format: crate::types::ZipFormat::Torrentzip,
-            zip_exclude: None,
+            zip_exclude: vec![],
             zip_dat_name: false,
+            zip_comment: None,
+            to_chd: false,
             link_mode: crate::types::LinkMode::Hardlink,
             symlink_relative: false,
             header: None,
@@ -586,8 +1324,12 @@ mod tests {
             filter_regex: None,
             filter_regex_exclude: None,
             filter_language: None,
+            keep_languages: None,
+            keep_top_n: None,
             filter_region: None,
             filter_category_regex: None,
+            only_genre: None,
+            only_platform: None,
             no_bios: false,
             no_device: false,
             no_unlicensed: false,
@@ -599,16 +1341,46 @@ mod tests {
             no_prototype: false,
             no_program: false,
             verbose: 0,
+            no_color: false,
             quiet: 0,
             diag: false,
+            plan_diff: false,
+            region_map: None,
+            profile: false,
+            log_style: crate::types::LogStyle::Default,
+            exec: None,
             online_timeout_secs: Some(5),
             online_max_retries: Some(3),
             online_throttle_ms: None,
             cache_only: false,
             cache_db: None,
+            xattr_cache: false,
             hash_threads: None,
             scan_threads: None,
+            archive_threads: None,
             show_match_reasons: false,
+            min_score: None,
+            tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+            priority_input: false,
+            title_strip_regex: vec![],
+            ignore_case: true,
+            output_structure: None,
+            threads: None,
+            strict: false,
+            allow_no_matches: false,
+            dat_best_match: false,
+            dat_priority: Vec::new(),
+            dat_diff: None,
+            fixdat_format: None,
+            hash_algorithms: None,
+            hash_checkpoint_mib: None,
+            expect_crc32: None,
+            expect_md5: None,
+            expect_sha1: None,
+            expect_sha256: None,
+            max_decompressed_size: None,
+            skip_existing_checksum: false,
+            report_duplicates: false,
         };
 
         let out = build_write_candidates(&sets, &dat_roms, &[rec1.clone(), rec2.clone()], &cfg);
@@ -618,6 +1390,53 @@ mod tests {
         assert_eq!(wc.name, "Game (Multi)");
     }
 
+    #[test]
+    fn incomplete_sets_record_their_missing_parts() {
+        use std::collections::HashMap;
+
+        let mut rec1 = make_rec("game (disc 1).bin");
+        rec1.size = 100;
+        rec1.checksums.crc32 = Some("AAA".to_string());
+
+        let dat_roms = vec![
+            (
+                "game (disc 1).bin".to_string(),
+                Some("AAA".to_string()),
+                None,
+                None,
+                Some(100u64),
+            ),
+            (
+                "game (disc 2).bin".to_string(),
+                Some("BBB".to_string()),
+                None,
+                None,
+                Some(200u64),
+            ),
+        ];
+        let mut sets: HashMap<String, Vec<String>> = HashMap::new();
+        sets.insert(
+            "Game (Multi)".to_string(),
+            vec![
+                "game (disc 1).bin".to_string(),
+                "game (disc 2).bin".to_string(),
+            ],
+        );
+
+        let mut cfg = crate::config::Config::default();
+        let candidates = build_write_candidates(&sets, &dat_roms, &[rec1.clone()], &cfg);
+        assert!(
+            candidates.is_empty(),
+            "incomplete sets are dropped by default"
+        );
+
+        cfg.allow_incomplete_sets = true;
+        let candidates = build_write_candidates(&sets, &dat_roms, &[rec1], &cfg);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].files.len(), 1);
+        assert_eq!(candidates[0].missing, vec!["game (disc 2).bin".to_string()]);
+    }
+
     #[test]
     fn build_write_candidates_prefers_checksum_matches() {
         use std::collections::HashMap;
@@ -638,6 +1457,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
         let rec_checksum = FileRecord {
             source: PathBuf::from("Game.bin"),
@@ -655,6 +1480,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
 
         let dat_roms = vec![(
@@ -676,19 +1507,28 @@ mod tests {
             input: vec![],
             input_exclude: vec![],
             input_checksum_quick: false,
+            input_checksum_auto: false,
+            scan_files_only: false,
+            scan_archives_only: false,
             input_checksum_min: crate::types::Checksum::Crc32,
             input_checksum_max: None,
             input_checksum_archives: crate::types::ArchiveChecksumMode::Auto,
             dat: vec![],
             dat_exclude: vec![],
+            dat_url: vec![],
             dat_name_regex: None,
             dat_name_regex_exclude: None,
+            game_name_regex: None,
+            game_name_regex_exclude: None,
+            system: None,
+            dat_name_rewrite: None,
             dat_description_regex: None,
             dat_description_regex_exclude: None,
             dat_combine: false,
             dat_ignore_parent_clone: false,
             list_unmatched_dats: false,
             print_plan: true,
+            summary_only: false,
             enable_hasheous: false,
             igdb_client_id: None,
             igdb_client_secret: None,
@@ -699,24 +1539,37 @@ mod tests {
             patch_exclude: vec![],
             output: None,
             dir_mirror: false,
+            dir_mirror_root: false,
             dir_dat_mirror: false,
             dir_dat_name: false,
             dir_dat_description: false,
             dir_letter: false,
+            dir_region: false,
             dir_letter_count: None,
             dir_letter_limit: None,
             dir_letter_group: false,
+            dir_letter_ignore_articles: false,
             dir_game_subdir: crate::types::DirGameSubdirMode::Multiple,
             fix_extension: crate::types::FixExtensionMode::Auto,
+            normalize_extension_case: false,
+            shorten_paths: false,
             overwrite: false,
             overwrite_invalid: false,
+            on_collision: crate::types::CollisionPolicy::Skip,
+            quarantine: None,
+            no_space_check: false,
             move_delete_dirs: crate::types::MoveDeleteDirsMode::Auto,
+            extract_include: None,
+            extract_exclude: None,
             clean_exclude: vec![],
             clean_backup: None,
+            clean_backup_keep: None,
             clean_dry_run: false,
             zip_format: crate::types::ZipFormat::Torrentzip,
-            zip_exclude: None,
+            zip_exclude: vec![],
             zip_dat_name: false,
+            zip_comment: None,
+            to_chd: false,
             link_mode: crate::types::LinkMode::Hardlink,
             symlink_relative: false,
             header: None,
@@ -731,8 +1584,12 @@ mod tests {
             filter_regex: None,
             filter_regex_exclude: None,
             filter_language: None,
+            keep_languages: None,
+            keep_top_n: None,
             filter_region: None,
             filter_category_regex: None,
+            only_genre: None,
+            only_platform: None,
             no_bios: false,
             no_device: false,
             no_unlicensed: false,
@@ -744,16 +1601,46 @@ mod tests {
             no_prototype: false,
             no_program: false,
             verbose: 0,
+            no_color: false,
             quiet: 0,
             diag: false,
+            plan_diff: false,
+            region_map: None,
+            profile: false,
+            log_style: crate::types::LogStyle::Default,
+            exec: None,
             online_timeout_secs: Some(5),
             online_max_retries: Some(3),
             online_throttle_ms: None,
             cache_only: false,
             cache_db: None,
+            xattr_cache: false,
             hash_threads: None,
             scan_threads: None,
+            archive_threads: None,
             show_match_reasons: false,
+            min_score: None,
+            tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+            priority_input: false,
+            title_strip_regex: vec![],
+            ignore_case: true,
+            output_structure: None,
+            threads: None,
+            strict: false,
+            allow_no_matches: false,
+            dat_best_match: false,
+            dat_priority: Vec::new(),
+            dat_diff: None,
+            fixdat_format: None,
+            hash_algorithms: None,
+            hash_checkpoint_mib: None,
+            expect_crc32: None,
+            expect_md5: None,
+            expect_sha1: None,
+            expect_sha256: None,
+            max_decompressed_size: None,
+            skip_existing_checksum: false,
+            report_duplicates: false,
         };
 
         let out = build_write_candidates(
@@ -787,6 +1674,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
 
         // dat declares two parts both with same checksum
@@ -818,19 +1711,28 @@ mod tests {
             input: vec![],
             input_exclude: vec![],
             input_checksum_quick: false,
+            input_checksum_auto: false,
+            scan_files_only: false,
+            scan_archives_only: false,
             input_checksum_min: crate::types::Checksum::Crc32,
             input_checksum_max: None,
             input_checksum_archives: crate::types::ArchiveChecksumMode::Auto,
             dat: vec![],
             dat_exclude: vec![],
+            dat_url: vec![],
             dat_name_regex: None,
             dat_name_regex_exclude: None,
+            game_name_regex: None,
+            game_name_regex_exclude: None,
+            system: None,
+            dat_name_rewrite: None,
             dat_description_regex: None,
             dat_description_regex_exclude: None,
             dat_combine: false,
             dat_ignore_parent_clone: false,
             list_unmatched_dats: false,
             print_plan: true,
+            summary_only: false,
             enable_hasheous: false,
             igdb_client_id: None,
             igdb_client_secret: None,
@@ -841,24 +1743,37 @@ mod tests {
             patch_exclude: vec![],
             output: None,
             dir_mirror: false,
+            dir_mirror_root: false,
             dir_dat_mirror: false,
             dir_dat_name: false,
             dir_dat_description: false,
             dir_letter: false,
+            dir_region: false,
             dir_letter_count: None,
             dir_letter_limit: None,
             dir_letter_group: false,
+            dir_letter_ignore_articles: false,
             dir_game_subdir: crate::types::DirGameSubdirMode::Multiple,
             fix_extension: crate::types::FixExtensionMode::Auto,
+            normalize_extension_case: false,
+            shorten_paths: false,
             overwrite: false,
             overwrite_invalid: false,
+            on_collision: crate::types::CollisionPolicy::Skip,
+            quarantine: None,
+            no_space_check: false,
             move_delete_dirs: crate::types::MoveDeleteDirsMode::Auto,
+            extract_include: None,
+            extract_exclude: None,
             clean_exclude: vec![],
             clean_backup: None,
+            clean_backup_keep: None,
             clean_dry_run: false,
             zip_format: crate::types::ZipFormat::Torrentzip,
-            zip_exclude: None,
+            zip_exclude: vec![],
             zip_dat_name: false,
+            zip_comment: None,
+            to_chd: false,
             link_mode: crate::types::LinkMode::Hardlink,
             symlink_relative: false,
             header: None,
@@ -873,8 +1788,12 @@ mod tests {
             filter_regex: None,
             filter_regex_exclude: None,
             filter_language: None,
+            keep_languages: None,
+            keep_top_n: None,
             filter_region: None,
             filter_category_regex: None,
+            only_genre: None,
+            only_platform: None,
             no_bios: false,
             no_device: false,
             no_unlicensed: false,
@@ -886,16 +1805,46 @@ mod tests {
             no_prototype: false,
             no_program: false,
             verbose: 0,
+            no_color: false,
             quiet: 0,
             diag: false,
+            plan_diff: false,
+            region_map: None,
+            profile: false,
+            log_style: crate::types::LogStyle::Default,
+            exec: None,
             online_timeout_secs: Some(5),
             online_max_retries: Some(3),
             online_throttle_ms: None,
             cache_only: false,
             cache_db: None,
+            xattr_cache: false,
             hash_threads: None,
             scan_threads: None,
+            archive_threads: None,
             show_match_reasons: false,
+            min_score: None,
+            tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+            priority_input: false,
+            title_strip_regex: vec![],
+            ignore_case: true,
+            output_structure: None,
+            threads: None,
+            strict: false,
+            allow_no_matches: false,
+            dat_best_match: false,
+            dat_priority: Vec::new(),
+            dat_diff: None,
+            fixdat_format: None,
+            hash_algorithms: None,
+            hash_checkpoint_mib: None,
+            expect_crc32: None,
+            expect_md5: None,
+            expect_sha1: None,
+            expect_sha256: None,
+            max_decompressed_size: None,
+            skip_existing_checksum: false,
+            report_duplicates: false,
         };
 
         let out = build_write_candidates(&sets, &dat_roms, &[rec.clone()], &cfg);
@@ -929,6 +1878,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
 
         let rec_sha1 = FileRecord {
@@ -947,6 +1902,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
 
         let dats = vec![
@@ -993,6 +1954,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
         let rec2 = FileRecord {
             source: PathBuf::from("Alpha Gamma.bin"),
@@ -1010,6 +1977,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
 
         let dats = vec![(
@@ -1049,6 +2022,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
         let rec_b = FileRecord {
             source: PathBuf::from("/path/B/disc.bin"),
@@ -1066,6 +2045,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
 
         let dats = vec![(
@@ -1105,6 +2090,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
         let rec2 = FileRecord {
             source: PathBuf::from("D:/store2/game.bin"),
@@ -1122,6 +2113,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
 
         let dats = vec![("game.bin".to_string(), None, None, None, Some(50u64))];
@@ -1162,6 +2159,12 @@ mod tests {
                 derived_region: None,
                 derived_languages: Vec::new(),
                 scan_info: None,
+                output_name: None,
+                set_name: None,
+                input_root: None,
+                modified: None,
+                input_index: None,
+                fixed_extension: None,
             });
         }
 
@@ -1205,19 +2208,28 @@ mod tests {
             input: vec![],
             input_exclude: vec![],
             input_checksum_quick: false,
+            input_checksum_auto: false,
+            scan_files_only: false,
+            scan_archives_only: false,
             input_checksum_min: crate::types::Checksum::Crc32,
             input_checksum_max: None,
             input_checksum_archives: crate::types::ArchiveChecksumMode::Auto,
             dat: vec![],
             dat_exclude: vec![],
+            dat_url: vec![],
             dat_name_regex: None,
             dat_name_regex_exclude: None,
+            game_name_regex: None,
+            game_name_regex_exclude: None,
+            system: None,
+            dat_name_rewrite: None,
             dat_description_regex: None,
             dat_description_regex_exclude: None,
             dat_combine: false,
             dat_ignore_parent_clone: false,
             list_unmatched_dats: false,
             print_plan: true,
+            summary_only: false,
             enable_hasheous: false,
             igdb_client_id: None,
             igdb_client_secret: None,
@@ -1228,24 +2240,37 @@ mod tests {
             patch_exclude: vec![],
             output: None,
             dir_mirror: false,
+            dir_mirror_root: false,
             dir_dat_mirror: false,
             dir_dat_name: false,
             dir_dat_description: false,
             dir_letter: false,
+            dir_region: false,
             dir_letter_count: None,
             dir_letter_limit: None,
             dir_letter_group: false,
+            dir_letter_ignore_articles: false,
             dir_game_subdir: crate::types::DirGameSubdirMode::Multiple,
             fix_extension: crate::types::FixExtensionMode::Auto,
+            normalize_extension_case: false,
+            shorten_paths: false,
             overwrite: false,
             overwrite_invalid: false,
+            on_collision: crate::types::CollisionPolicy::Skip,
+            quarantine: None,
+            no_space_check: false,
             move_delete_dirs: crate::types::MoveDeleteDirsMode::Auto,
+            extract_include: None,
+            extract_exclude: None,
             clean_exclude: vec![],
             clean_backup: None,
+            clean_backup_keep: None,
             clean_dry_run: false,
             zip_format: crate::types::ZipFormat::Torrentzip,
-            zip_exclude: None,
+            zip_exclude: vec![],
             zip_dat_name: false,
+            zip_comment: None,
+            to_chd: false,
             link_mode: crate::types::LinkMode::Hardlink,
             symlink_relative: false,
             header: None,
@@ -1260,8 +2285,12 @@ mod tests {
             filter_regex: None,
             filter_regex_exclude: None,
             filter_language: None,
+            keep_languages: None,
+            keep_top_n: None,
             filter_region: None,
             filter_category_regex: None,
+            only_genre: None,
+            only_platform: None,
             no_bios: false,
             no_device: false,
             no_unlicensed: false,
@@ -1273,16 +2302,46 @@ mod tests {
             no_prototype: false,
             no_program: false,
             verbose: 0,
+            no_color: false,
             quiet: 0,
             diag: false,
+            plan_diff: false,
+            region_map: None,
+            profile: false,
+            log_style: crate::types::LogStyle::Default,
+            exec: None,
             online_timeout_secs: Some(5),
             online_max_retries: Some(3),
             online_throttle_ms: None,
             cache_only: false,
             cache_db: None,
+            xattr_cache: false,
             hash_threads: None,
             scan_threads: None,
+            archive_threads: None,
             show_match_reasons: false,
+            min_score: None,
+            tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+            priority_input: false,
+            title_strip_regex: vec![],
+            ignore_case: true,
+            output_structure: None,
+            threads: None,
+            strict: false,
+            allow_no_matches: false,
+            dat_best_match: false,
+            dat_priority: Vec::new(),
+            dat_diff: None,
+            fixdat_format: None,
+            hash_algorithms: None,
+            hash_checkpoint_mib: None,
+            expect_crc32: None,
+            expect_md5: None,
+            expect_sha1: None,
+            expect_sha256: None,
+            max_decompressed_size: None,
+            skip_existing_checksum: false,
+            report_duplicates: false,
         };
 
         let out = build_write_candidates(&sets, &dat_roms, &records, &cfg);
@@ -1319,6 +2378,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
         let rec_chd = FileRecord {
             source: PathBuf::from("/store/chd_game.chd"),
@@ -1336,6 +2401,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
 
         let dats = vec![(
@@ -1375,6 +2446,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
         let rec_checksum = FileRecord {
             source: PathBuf::from("/node/B/Game.bin"),
@@ -1392,6 +2469,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
 
         let dats = vec![(
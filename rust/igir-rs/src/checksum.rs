@@ -1,40 +1,36 @@
 use std::fs::File;
-use std::io::Read;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
 use std::sync::mpsc::Sender;
 
 use anyhow::Context;
+use clap::ValueEnum;
 use crc32fast::Hasher as Crc32;
 use md5::{Digest as Md5Digest, Md5};
-use sha1_smol::Sha1;
+use sha1::Sha1;
 use sha2::Sha256;
+use sha2::digest::common::hazmat::{SerializableState, SerializedState};
+use sha2::digest::typenum::Unsigned;
 
+use crate::cache::Cache;
 use crate::config::Config;
 use crate::progress::ProgressEvent;
 use crate::types::{Checksum, ChecksumSet};
 
 const STREAM_CHUNK_SIZE: usize = 512 * 1024; // 512 KiB chunks to better utilize network I/O
+/// Same as [`hash_reader`]; kept for existing callers.
 pub fn compute_checksums_stream<R: Read>(
-    mut reader: R,
+    reader: R,
     config: &Config,
 ) -> anyhow::Result<(ChecksumSet, u64)> {
-    let mut crc32 = None;
-    let mut md5 = None;
-    let mut sha1 = None;
-    let mut sha256 = None;
-
-    let targets = checksum_range(config.input_checksum_min, config.input_checksum_max);
-    let mut crc32h = targets
-        .iter()
-        .any(|t| *t == Checksum::Crc32)
-        .then(Crc32::new);
-    let mut md5h = targets.iter().any(|t| *t == Checksum::Md5).then(Md5::new);
-    let mut sha1h = targets.iter().any(|t| *t == Checksum::Sha1).then(Sha1::new);
-    let mut sha256h = targets
-        .iter()
-        .any(|t| *t == Checksum::Sha256)
-        .then(Sha256::new);
+    hash_reader(reader, config)
+}
 
+/// Hash everything `reader` yields (a network stream, stdin, an archive entry) with the
+/// checksums `config` selects, exactly as input files are hashed. Returns the checksums and
+/// the number of bytes read.
+pub fn hash_reader<R: Read>(mut reader: R, config: &Config) -> anyhow::Result<(ChecksumSet, u64)> {
+    let mut hashers = Hashers::new(&checksum_targets(config));
     let mut processed: u64 = 0;
     let mut buf = vec![0u8; STREAM_CHUNK_SIZE.min(64 * 1024)];
     loop {
@@ -43,58 +39,115 @@ pub fn compute_checksums_stream<R: Read>(
             break;
         }
         processed = processed.saturating_add(n as u64);
-        let slice = &buf[..n];
-        if let Some(h) = crc32h.as_mut() {
+        hashers.update(&buf[..n]);
+    }
+
+    Ok((hashers.finish(), processed))
+}
+
+/// One running hasher per requested checksum kind. Built from any set of kinds (a
+/// `--input-checksum-min/max` range or an explicit `--hash-algorithms` list, which need
+/// not be contiguous); kinds outside the set stay `None` in the result.
+struct Hashers {
+    crc32: Option<Crc32>,
+    md5: Option<Md5>,
+    sha1: Option<Sha1>,
+    sha256: Option<Sha256>,
+}
+
+impl Hashers {
+    fn new(targets: &[Checksum]) -> Self {
+        Self {
+            crc32: targets.contains(&Checksum::Crc32).then(Crc32::new),
+            md5: targets.contains(&Checksum::Md5).then(Md5::new),
+            sha1: targets.contains(&Checksum::Sha1).then(Sha1::new),
+            sha256: targets.contains(&Checksum::Sha256).then(Sha256::new),
+        }
+    }
+
+    fn update(&mut self, slice: &[u8]) {
+        if let Some(h) = self.crc32.as_mut() {
             h.update(slice);
         }
-        if let Some(h) = md5h.as_mut() {
+        if let Some(h) = self.md5.as_mut() {
             h.update(slice);
         }
-        if let Some(h) = sha1h.as_mut() {
+        if let Some(h) = self.sha1.as_mut() {
             h.update(slice);
         }
-        if let Some(h) = sha256h.as_mut() {
+        if let Some(h) = self.sha256.as_mut() {
             h.update(slice);
         }
     }
 
-    for target in targets {
-        match target {
-            Checksum::Crc32 => {
-                if let Some(h) = crc32h.take() {
-                    crc32 = Some(format!("{:08x}", h.finalize()));
-                }
-            }
-            Checksum::Md5 => {
-                if let Some(h) = md5h.take() {
-                    let digest = h.finalize();
-                    md5 = Some(format!("{:032x}", digest));
-                }
-            }
-            Checksum::Sha1 => {
-                if let Some(h) = sha1h.take() {
-                    let digest = h.digest();
-                    sha1 = Some(digest.to_string());
-                }
-            }
-            Checksum::Sha256 => {
-                if let Some(h) = sha256h.take() {
-                    sha256 = Some(format!("{:064x}", h.finalize()));
-                }
-            }
+    /// The running state of every hasher, in the layout [`Hashers::restore`] reads back:
+    /// the CRC32 so far, then the serialized MD5, SHA1 and SHA256 states of those computed.
+    fn save_state(&self) -> Vec<u8> {
+        let mut state = Vec::new();
+        if let Some(h) = &self.crc32 {
+            state.extend_from_slice(&h.clone().finalize().to_le_bytes());
         }
+        if let Some(h) = &self.md5 {
+            state.extend_from_slice(&h.serialize());
+        }
+        if let Some(h) = &self.sha1 {
+            state.extend_from_slice(&h.serialize());
+        }
+        if let Some(h) = &self.sha256 {
+            state.extend_from_slice(&h.serialize());
+        }
+        state
     }
 
-    Ok((
-        ChecksumSet {
+    /// Hashers for `targets` continuing from a [`Hashers::save_state`] taken after `len`
+    /// bytes; `None` when `state` was saved for other checksums or is damaged.
+    fn restore(targets: &[Checksum], mut state: &[u8], len: u64) -> Option<Self> {
+        fn take<H: SerializableState>(state: &mut &[u8]) -> Option<H> {
+            let (head, rest) = state.split_at_checked(H::SerializedStateSize::USIZE)?;
+            *state = rest;
+            H::deserialize(&SerializedState::<H>::try_from(head).ok()?).ok()
+        }
+        let crc32 = if targets.contains(&Checksum::Crc32) {
+            let (head, rest) = state.split_at_checked(4)?;
+            state = rest;
+            let crc = u32::from_le_bytes(head.try_into().ok()?);
+            Some(Crc32::new_with_initial_len(crc, len))
+        } else {
+            None
+        };
+        let md5 = if targets.contains(&Checksum::Md5) {
+            Some(take::<Md5>(&mut state)?)
+        } else {
+            None
+        };
+        let sha1 = if targets.contains(&Checksum::Sha1) {
+            Some(take::<Sha1>(&mut state)?)
+        } else {
+            None
+        };
+        let sha256 = if targets.contains(&Checksum::Sha256) {
+            Some(take::<Sha256>(&mut state)?)
+        } else {
+            None
+        };
+        state.is_empty().then_some(Self {
             crc32,
             md5,
             sha1,
             sha256,
-        },
-        processed,
-    ))
+        })
+    }
+
+    fn finish(self) -> ChecksumSet {
+        ChecksumSet {
+            crc32: self.crc32.map(|h| format!("{:08x}", h.finalize())),
+            md5: self.md5.map(|h| hex::encode(h.finalize())),
+            sha1: self.sha1.map(|h| hex::encode(h.finalize())),
+            sha256: self.sha256.map(|h| hex::encode(h.finalize())),
+        }
+    }
 }
+
 const MIN_PROGRESS_UPDATE: u64 = 64 * 1024;
 
 pub fn checksum_range(min: Checksum, max: Option<Checksum>) -> Vec<Checksum> {
@@ -116,8 +169,47 @@ pub fn checksum_range(min: Checksum, max: Option<Checksum>) -> Vec<Checksum> {
     checksums
 }
 
+/// Parse a comma-separated `--hash-algorithms` list into checksum kinds.
+pub fn parse_hash_algorithms(raw: &str) -> anyhow::Result<Vec<Checksum>> {
+    let mut algorithms = Vec::new();
+    for name in raw.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let algorithm = Checksum::from_str(name, true).map_err(|_| {
+            anyhow::anyhow!("unknown hash algorithm '{name}' (expected crc32, md5, sha1 or sha256)")
+        })?;
+        if !algorithms.contains(&algorithm) {
+            algorithms.push(algorithm);
+        }
+    }
+
+    if algorithms.is_empty() {
+        anyhow::bail!("--hash-algorithms requires at least one algorithm");
+    }
+
+    Ok(algorithms)
+}
+
+/// Checksums to compute for this run: the explicit `--hash-algorithms` list when given (in
+/// any order, gaps allowed), else the min/max range. Every hashing path consumes this set.
+pub fn checksum_targets(config: &Config) -> Vec<Checksum> {
+    config
+        .hash_algorithms
+        .clone()
+        .unwrap_or_else(|| checksum_range(config.input_checksum_min, config.input_checksum_max))
+}
+
 pub fn compute_checksums(path: &Path, config: &Config) -> anyhow::Result<ChecksumSet> {
-    compute_checksums_with_header(path, config, None, None)
+    compute_checksums_range(path, config, 0, None)
+}
+
+/// Hash `len` bytes of `path` starting at byte `start` (to the end of the file when `len`
+/// is `None`), so header-stripped payloads can be hashed without copying them first.
+pub fn compute_checksums_range(
+    path: &Path,
+    config: &Config,
+    start: u64,
+    len: Option<u64>,
+) -> anyhow::Result<ChecksumSet> {
+    compute_checksums_range_with_progress(path, config, start, len, None)
 }
 
 pub fn compute_checksums_with_header(
@@ -126,62 +218,40 @@ pub fn compute_checksums_with_header(
     header_size: Option<u64>,
     progress_sender: Option<Sender<ProgressEvent>>,
 ) -> anyhow::Result<ChecksumSet> {
-    let mut crc32 = None;
-    let mut md5 = None;
-    let mut sha1 = None;
-    let mut sha256 = None;
+    let file_len = std::fs::metadata(path)
+        .with_context(|| format!("opening file for checksum: {path:?}"))?
+        .len();
+    // Determine whether trimming should occur: only when header_size <= file_len
+    let trim = match header_size {
+        Some(s) if s > 0 && s <= file_len => s,
+        _ => 0,
+    };
+    compute_checksums_range_with_progress(path, config, trim, None, progress_sender)
+}
 
-    let targets = checksum_range(config.input_checksum_min, config.input_checksum_max);
+fn compute_checksums_range_with_progress(
+    path: &Path,
+    config: &Config,
+    start: u64,
+    len: Option<u64>,
+    progress_sender: Option<Sender<ProgressEvent>>,
+) -> anyhow::Result<ChecksumSet> {
     // Stream the file in chunks and update hashers incrementally.
     let mut file =
         File::open(path).with_context(|| format!("opening file for checksum: {path:?}"))?;
     let file_len = file.metadata().map(|m| m.len()).unwrap_or(0);
-
-    // Determine whether trimming should occur: only when header_size <= file_len
-    let trim = match header_size {
-        Some(s) if s > 0 && s <= file_len => Some(s),
-        _ => None,
-    };
-
-    // If trimming, consume header bytes first using the streaming buffer to avoid extra allocations.
+    let start = start.min(file_len);
+    let total_size = len.map_or(file_len - start, |l| l.min(file_len - start));
+    file.seek(SeekFrom::Start(start))
+        .with_context(|| format!("seeking to offset {start} for checksum: {path:?}"))?;
+    let mut file = file.take(total_size);
     let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
-    if let Some(mut remaining) = trim {
-        while remaining > 0 {
-            let to_read = std::cmp::min(remaining, buf.len() as u64) as usize;
-            let n = file.read(&mut buf[..to_read])?;
-            if n == 0 {
-                break;
-            }
-            remaining -= n as u64;
-        }
-    }
 
-    // Prepare hashers only for requested targets
-    let mut crc32h = if targets.iter().any(|t| *t == Checksum::Crc32) {
-        Some(Crc32::new())
-    } else {
-        None
-    };
-    let mut md5h = if targets.iter().any(|t| *t == Checksum::Md5) {
-        Some(Md5::new())
-    } else {
-        None
-    };
-    let mut sha1h = if targets.iter().any(|t| *t == Checksum::Sha1) {
-        Some(Sha1::new())
-    } else {
-        None
-    };
-    let mut sha256h = if targets.iter().any(|t| *t == Checksum::Sha256) {
-        Some(Sha256::new())
-    } else {
-        None
-    };
+    let mut hashers = Hashers::new(&checksum_targets(config));
 
     let mut bytes_read: u64 = 0;
     let mut last_reported: u64 = 0;
     let report_threshold: u64 = std::cmp::max(MIN_PROGRESS_UPDATE, (buf.len() as u64) / 2);
-    let total_size = file_len.saturating_sub(trim.unwrap_or(0));
     loop {
         let n = file.read(&mut buf)?;
         if n == 0 {
@@ -195,19 +265,7 @@ pub fn compute_checksums_with_header(
             }
             break;
         }
-        let slice = &buf[..n];
-        if let Some(h) = crc32h.as_mut() {
-            h.update(slice);
-        }
-        if let Some(h) = md5h.as_mut() {
-            h.update(slice);
-        }
-        if let Some(h) = sha1h.as_mut() {
-            h.update(slice);
-        }
-        if let Some(h) = sha256h.as_mut() {
-            h.update(slice);
-        }
+        hashers.update(&buf[..n]);
         bytes_read = bytes_read.saturating_add(n as u64);
         if let Some(tx) = &progress_sender {
             if bytes_read - last_reported >= report_threshold {
@@ -221,39 +279,105 @@ pub fn compute_checksums_with_header(
         }
     }
 
-    for target in targets {
-        match target {
-            Checksum::Crc32 => {
-                if let Some(h) = crc32h.take() {
-                    crc32 = Some(format!("{:08x}", h.finalize()));
-                }
-            }
-            Checksum::Md5 => {
-                if let Some(h) = md5h.take() {
-                    let digest = h.finalize();
-                    md5 = Some(format!("{:032x}", digest));
-                }
-            }
-            Checksum::Sha1 => {
-                if let Some(h) = sha1h.take() {
-                    let digest = h.digest();
-                    sha1 = Some(digest.to_string());
-                }
-            }
-            Checksum::Sha256 => {
-                if let Some(h) = sha256h.take() {
-                    sha256 = Some(format!("{:064x}", h.finalize()));
-                }
+    Ok(hashers.finish())
+}
+
+/// `--hash-checkpoint`: the `targets` checksums of `path` from byte `start` to the end,
+/// saving the running hasher state to `cache` every `interval` bytes so an interrupted
+/// hash of a huge image resumes from its last checkpoint.
+pub fn compute_checksums_checkpointed(
+    path: &Path,
+    start: u64,
+    targets: &[Checksum],
+    cache: &Cache,
+    interval: u64,
+    progress_sender: Option<Sender<ProgressEvent>>,
+) -> anyhow::Result<ChecksumSet> {
+    let file = File::open(path).with_context(|| format!("opening file for checksum: {path:?}"))?;
+    let metadata = file.metadata()?;
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos() as i64);
+    let stamp = (metadata.len(), mtime);
+    hash_checkpointed(
+        file,
+        path,
+        stamp,
+        start,
+        targets,
+        cache,
+        interval,
+        progress_sender,
+    )
+}
+
+/// Which checksums a saved hasher state covers, e.g. `crc32,md5,sha1`.
+fn checkpoint_targets_key(targets: &[Checksum]) -> String {
+    [
+        Checksum::Crc32,
+        Checksum::Md5,
+        Checksum::Sha1,
+        Checksum::Sha256,
+    ]
+    .into_iter()
+    .filter(|checksum| targets.contains(checksum))
+    .filter_map(|checksum| checksum.to_possible_value())
+    .map(|value| value.get_name().to_string())
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn hash_checkpointed<R: Read + Seek>(
+    mut reader: R,
+    path: &Path,
+    (size, mtime): (u64, i64),
+    start: u64,
+    targets: &[Checksum],
+    cache: &Cache,
+    interval: u64,
+    progress_sender: Option<Sender<ProgressEvent>>,
+) -> anyhow::Result<ChecksumSet> {
+    let start = start.min(size);
+    let key = checkpoint_targets_key(targets);
+    let (mut offset, mut hashers) = cache
+        .get_hash_checkpoint(path, size, mtime, start, &key)
+        .ok()
+        .flatten()
+        .filter(|(offset, _)| (start..=size).contains(offset))
+        .and_then(|(offset, state)| {
+            Some((offset, Hashers::restore(targets, &state, offset - start)?))
+        })
+        .unwrap_or_else(|| (start, Hashers::new(targets)));
+    reader.seek(SeekFrom::Start(offset))?;
+
+    let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+    let mut next_checkpoint = offset + interval;
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hashers.update(&buf[..n]);
+        offset += n as u64;
+        if offset >= next_checkpoint {
+            // Best effort: a failed write only means less progress is kept.
+            let state = hashers.save_state();
+            let _ = cache.set_hash_checkpoint(path, size, mtime, start, &key, (offset, &state));
+            next_checkpoint = offset + interval;
+            if let Some(tx) = &progress_sender {
+                let _ = tx.send(ProgressEvent::hashing(
+                    path.to_path_buf(),
+                    offset - start,
+                    Some(size - start),
+                ));
             }
         }
     }
-
-    Ok(ChecksumSet {
-        crc32,
-        md5,
-        sha1,
-        sha256,
-    })
+    let _ = cache.clear_hash_checkpoint(path);
+    Ok(hashers.finish())
 }
 
 /// Compute checksums from an in-memory buffer. Mirrors behavior of compute_checksums_with_header
@@ -263,41 +387,73 @@ pub fn compute_checksums_from_bytes(buf: &[u8], config: &Config) -> anyhow::Resu
     Ok(checksums)
 }
 
+/// `test --expect-<checksum>`: hash every input file and compare it with the trusted values,
+/// printing one PASS/FAIL line per file and checksum. Returns whether everything matched.
+pub fn verify_expected_checksums(config: &Config) -> anyhow::Result<bool> {
+    let mut all_match = true;
+    for path in &config.input {
+        if !path.is_file() {
+            anyhow::bail!(
+                "--expect-<checksum> needs regular input files: {}",
+                path.display()
+            );
+        }
+        let actual = compute_all_checksums(path)?;
+        for (kind, expected) in config.expected_checksums() {
+            let found = match kind {
+                Checksum::Crc32 => &actual.crc32,
+                Checksum::Md5 => &actual.md5,
+                Checksum::Sha1 => &actual.sha1,
+                Checksum::Sha256 => &actual.sha256,
+            }
+            .as_deref()
+            .unwrap_or_default();
+            let name = kind.to_possible_value().map(|v| v.get_name().to_string());
+            let name = name.unwrap_or_default();
+            if found.eq_ignore_ascii_case(expected.trim()) {
+                println!("PASS {} {name} {found}", path.display());
+            } else {
+                all_match = false;
+                println!(
+                    "FAIL {} {name} expected {} got {found}",
+                    path.display(),
+                    expected.trim()
+                );
+            }
+        }
+    }
+    Ok(all_match)
+}
+
 /// Compute all supported checksums for a file path (ignores `Config` settings).
 pub fn compute_all_checksums(path: &Path) -> anyhow::Result<ChecksumSet> {
+    compute_checksum_kinds(
+        path,
+        &[
+            Checksum::Crc32,
+            Checksum::Md5,
+            Checksum::Sha1,
+            Checksum::Sha256,
+        ],
+    )
+}
+
+/// Compute only the `kinds` checksums of a file path (ignores `Config` settings).
+pub fn compute_checksum_kinds(path: &Path, kinds: &[Checksum]) -> anyhow::Result<ChecksumSet> {
     let mut file =
         File::open(path).with_context(|| format!("opening file for checksum: {path:?}"))?;
 
-    // prepare hashers
-    let mut crc32h = Crc32::new();
-    let mut md5h = Md5::new();
-    let mut sha1h = Sha1::new();
-    let mut sha256h = Sha256::new();
-
+    let mut hashers = Hashers::new(kinds);
     let mut buf = [0u8; 64 * 1024];
     loop {
         let n = file.read(&mut buf)?;
         if n == 0 {
             break;
         }
-        let slice = &buf[..n];
-        crc32h.update(slice);
-        md5h.update(slice);
-        sha1h.update(slice);
-        sha256h.update(slice);
+        hashers.update(&buf[..n]);
     }
 
-    let crc32 = Some(format!("{:08x}", crc32h.finalize()));
-    let md5 = Some(format!("{:032x}", md5h.finalize()));
-    let sha1 = Some(sha1h.digest().to_string());
-    let sha256 = Some(format!("{:064x}", sha256h.finalize()));
-
-    Ok(ChecksumSet {
-        crc32,
-        md5,
-        sha1,
-        sha256,
-    })
+    Ok(hashers.finish())
 }
 
 #[cfg(test)]
@@ -314,6 +470,130 @@ mod tests {
         c
     }
 
+    /// Reads like the wrapped file but fails once `fail_at` bytes have been read, and counts
+    /// what it read.
+    struct Interrupted {
+        inner: std::io::Cursor<Vec<u8>>,
+        fail_at: u64,
+        read: u64,
+    }
+
+    impl Read for Interrupted {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.read >= self.fail_at {
+                return Err(std::io::Error::other("interrupted"));
+            }
+            let allowed = buf.len().min((self.fail_at - self.read) as usize);
+            let n = self.inner.read(&mut buf[..allowed])?;
+            self.read += n as u64;
+            Ok(n)
+        }
+    }
+
+    impl Seek for Interrupted {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn checkpointed_hashes_resume_after_an_interruption() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("disc.iso");
+        let data: Vec<u8> = (0..3_000_000u32).map(|i| (i % 241) as u8).collect();
+        std::fs::write(&path, &data).unwrap();
+        let cache = Cache::open(Some(&dir.path().join("cache.sqlite")), None).unwrap();
+        let stamp = (data.len() as u64, 7);
+        let interval = 1 << 20;
+        let reader = |fail_at| Interrupted {
+            inner: std::io::Cursor::new(data.clone()),
+            fail_at,
+            read: 0,
+        };
+        let full = compute_checksums_from_bytes(&data, &all_checksums_config()).unwrap();
+
+        for targets in [
+            vec![Checksum::Crc32],
+            vec![Checksum::Crc32, Checksum::Md5, Checksum::Sha1],
+            vec![Checksum::Sha256, Checksum::Md5],
+        ] {
+            let key = checkpoint_targets_key(&targets);
+            // The first run dies after 2.5 MB; its last checkpoint is at 2 MiB.
+            let mut first = reader(2_500_000);
+            assert!(
+                hash_checkpointed(
+                    &mut first, &path, stamp, 0, &targets, &cache, interval, None
+                )
+                .is_err()
+            );
+            let (offset, _) = cache
+                .get_hash_checkpoint(&path, stamp.0, stamp.1, 0, &key)
+                .unwrap()
+                .expect("checkpoint kept");
+            assert_eq!(offset, 2 << 20);
+
+            let mut resumed = reader(u64::MAX);
+            let sums = hash_checkpointed(
+                &mut resumed,
+                &path,
+                stamp,
+                0,
+                &targets,
+                &cache,
+                interval,
+                None,
+            )
+            .unwrap();
+            let wanted = |checksum, value: &Option<String>| {
+                value.clone().filter(|_| targets.contains(&checksum))
+            };
+            assert_eq!(
+                (sums.crc32, sums.md5, sums.sha1, sums.sha256),
+                (
+                    wanted(Checksum::Crc32, &full.crc32),
+                    wanted(Checksum::Md5, &full.md5),
+                    wanted(Checksum::Sha1, &full.sha1),
+                    wanted(Checksum::Sha256, &full.sha256),
+                ),
+                "{key}"
+            );
+            assert_eq!(
+                resumed.read,
+                data.len() as u64 - offset,
+                "only the rest is read"
+            );
+            assert!(
+                cache
+                    .get_hash_checkpoint(&path, stamp.0, stamp.1, 0, &key)
+                    .unwrap()
+                    .is_none()
+            );
+        }
+    }
+
+    #[test]
+    fn hash_reader_hashes_any_reader() {
+        let (sums, len) = hash_reader(
+            std::io::Cursor::new(b"abc".to_vec()),
+            &all_checksums_config(),
+        )
+        .unwrap();
+        assert_eq!(len, 3);
+        assert_eq!(sums.crc32.as_deref(), Some("352441c2"));
+        assert_eq!(
+            sums.md5.as_deref(),
+            Some("900150983cd24fb0d6963f7d28e17f72")
+        );
+        assert_eq!(
+            sums.sha1.as_deref(),
+            Some("a9993e364706816aba3e25717850c26c9cd0d89d")
+        );
+        assert_eq!(
+            sums.sha256.as_deref(),
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+    }
+
     #[test]
     fn trimmed_checksums_match_body_bytes() {
         let mut f = NamedTempFile::new().unwrap();
@@ -334,6 +614,32 @@ mod tests {
         assert_eq!(trimmed.sha256, from_bytes.sha256);
     }
 
+    #[test]
+    fn range_checksums_match_sliced_bytes() {
+        let mut f = NamedTempFile::new().unwrap();
+        let data: Vec<u8> = (0..=255u8).cycle().take(4096).collect();
+        f.write_all(&data).unwrap();
+        f.flush().unwrap();
+
+        let cfg = all_checksums_config();
+
+        let tail = compute_checksums_range(f.path(), &cfg, 16, None).unwrap();
+        let expected = compute_checksums_from_bytes(&data[16..], &cfg).unwrap();
+        assert_eq!(tail.crc32, expected.crc32);
+        assert_eq!(tail.md5, expected.md5);
+        assert_eq!(tail.sha1, expected.sha1);
+        assert_eq!(tail.sha256, expected.sha256);
+
+        let window = compute_checksums_range(f.path(), &cfg, 16, Some(100)).unwrap();
+        let expected = compute_checksums_from_bytes(&data[16..116], &cfg).unwrap();
+        assert_eq!(window.crc32, expected.crc32);
+        assert_eq!(window.sha256, expected.sha256);
+
+        let whole = compute_checksums(f.path(), &cfg).unwrap();
+        let expected = compute_checksums_from_bytes(&data, &cfg).unwrap();
+        assert_eq!(whole.sha1, expected.sha1);
+    }
+
     #[test]
     fn header_size_equal_file_results_in_empty_body_checksums() {
         let mut f = NamedTempFile::new().unwrap();
@@ -371,4 +677,50 @@ mod tests {
         assert_eq!(computed.sha1, full.sha1);
         assert_eq!(computed.sha256, full.sha256);
     }
+
+    #[test]
+    fn hash_algorithms_override_computes_only_listed_checksums() {
+        let mut f = NamedTempFile::new().unwrap();
+        f.write_all(b"explicit algorithms").unwrap();
+        f.flush().unwrap();
+
+        let mut cfg = all_checksums_config();
+        cfg.hash_algorithms = Some(parse_hash_algorithms("crc32,sha256").unwrap());
+
+        let computed = compute_checksums(f.path(), &cfg).unwrap();
+        let full =
+            compute_checksums_from_bytes(b"explicit algorithms", &all_checksums_config()).unwrap();
+
+        assert_eq!(computed.crc32, full.crc32);
+        assert!(computed.md5.is_none());
+        assert!(computed.sha1.is_none());
+        assert_eq!(computed.sha256, full.sha256);
+
+        assert!(parse_hash_algorithms("crc32,blake3").is_err());
+    }
+
+    #[test]
+    fn explicit_lists_with_gaps_hash_exactly_the_listed_checksums() {
+        let cfg = Config {
+            hash_algorithms: Some(parse_hash_algorithms("sha256, crc32").unwrap()),
+            hash_checkpoint_mib: None,
+            expect_crc32: None,
+            expect_md5: None,
+            expect_sha1: None,
+            expect_sha256: None,
+            ..Config::default()
+        };
+        assert_eq!(
+            checksum_targets(&cfg),
+            vec![Checksum::Sha256, Checksum::Crc32]
+        );
+
+        let (streamed, read) = hash_reader(&b"gaps"[..], &cfg).unwrap();
+        let full = compute_checksums_from_bytes(b"gaps", &all_checksums_config()).unwrap();
+        assert_eq!(read, 4);
+        assert_eq!(streamed.crc32, full.crc32);
+        assert!(streamed.md5.is_none());
+        assert!(streamed.sha1.is_none());
+        assert_eq!(streamed.sha256, full.sha256);
+    }
 }
@@ -1,9 +1,14 @@
-use clap::{ArgAction, Parser};
+use anyhow::{Context, bail};
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, CommandFactory, FromArgMatches, Parser};
+use std::ffi::OsString;
+use std::fs;
 use std::path::PathBuf;
 
 use crate::types::{
-    Action, ArchiveChecksumMode, Checksum, DirGameSubdirMode, FixExtensionMode, IgdbLookupMode,
-    LinkMode, MergeMode, MoveDeleteDirsMode, ZipFormat,
+    Action, ArchiveChecksumMode, Checksum, CollisionPolicy, DirGameSubdirMode, FixExtensionMode,
+    FixdatFormat, IgdbLookupMode, LinkMode, LogStyle, MergeMode, MoveDeleteDirsMode, MtimeTiebreak,
+    OutputStructure, ZipFormat,
 };
 
 #[derive(Parser, Debug, serde::Serialize)]
@@ -19,7 +24,8 @@ pub struct Cli {
     pub commands: Vec<Action>,
 
     // ROM input options
-    /// Path(s) to ROM files or archives (supports globbing)
+    /// Path(s) to ROM files or archives (supports globbing; `-` reads newline-delimited paths from stdin;
+    /// `archive.zip#member` selects a single zip entry)
     #[arg(short = 'i', long = "input", value_name = "PATH", action = ArgAction::Append)]
     pub input: Vec<PathBuf>,
 
@@ -31,6 +37,14 @@ pub struct Cli {
     #[arg(long = "input-checksum-quick")]
     pub input_checksum_quick: bool,
 
+    /// Only scan loose files, skipping every archive input
+    #[arg(long = "scan-files-only", conflicts_with = "scan_archives_only")]
+    pub scan_files_only: bool,
+
+    /// Only scan archives (zip, 7z, rar, tar, ...), skipping every loose file
+    #[arg(long = "scan-archives-only")]
+    pub scan_archives_only: bool,
+
     /// The minimum checksum level to calculate and use for matching
     #[arg(
         long = "input-checksum-min",
@@ -43,7 +57,43 @@ pub struct Cli {
     #[arg(long = "input-checksum-max", value_enum)]
     pub input_checksum_max: Option<Checksum>,
 
+    /// Raise the minimum checksum to the weakest one the DATs provide (bounded by
+    /// --input-checksum-max), skipping lower hashes that could never match
+    #[arg(long = "input-checksum-auto")]
+    pub input_checksum_auto: bool,
+
+    /// Explicit comma-separated list of checksums to calculate (e.g. crc32,sha256); used instead of
+    /// the min/max range, so it can't be combined with --input-checksum-min/max
+    #[arg(long = "hash-algorithms", value_name = "ALGOS")]
+    pub hash_algorithms: Option<String>,
+
+    /// Save the running hasher state of large files to the cache every this many MiB, so an
+    /// interrupted scan resumes hashing where it stopped
+    #[arg(long = "hash-checkpoint", value_name = "MIB")]
+    pub hash_checkpoint_mib: Option<u64>,
+
+    /// With `test`: fail unless every input's CRC32 equals this trusted value
+    #[arg(long = "expect-crc32", value_name = "HASH")]
+    pub expect_crc32: Option<String>,
+
+    /// With `test`: fail unless every input's MD5 equals this trusted value
+    #[arg(long = "expect-md5", value_name = "HASH")]
+    pub expect_md5: Option<String>,
+
+    /// With `test`: fail unless every input's SHA1 equals this trusted value
+    #[arg(long = "expect-sha1", value_name = "HASH")]
+    pub expect_sha1: Option<String>,
+
+    /// With `test`: fail unless every input's SHA256 equals this trusted value
+    #[arg(long = "expect-sha256", value_name = "HASH")]
+    pub expect_sha256: Option<String>,
+
+    /// Abort any archive entry that decompresses past this size (e.g. 4G) or expands implausibly
+    #[arg(long = "max-decompressed-size", value_name = "SIZE", value_parser = parse_byte_size)]
+    pub max_decompressed_size: Option<u64>,
+
     /// Calculate checksums of archive files themselves, allowing them to match files in DATs
+    /// (`never` treats archives as opaque files and skips scanning their members)
     #[arg(
         long = "input-checksum-archives",
         value_enum,
@@ -56,21 +106,53 @@ pub struct Cli {
     pub dat: Vec<PathBuf>,
     #[arg(long = "dat-exclude", value_name = "PATH", action = ArgAction::Append)]
     pub dat_exclude: Vec<PathBuf>,
+    /// Download a DAT (optionally gzip- or zip-compressed) from this URL, caching it under
+    /// the config directory, and load it alongside any --dat files
+    #[arg(long = "dat-url", value_name = "URL", action = ArgAction::Append)]
+    pub dat_url: Vec<String>,
     #[arg(long = "dat-name-regex", value_name = "REGEX")]
     pub dat_name_regex: Option<String>,
     #[arg(long = "dat-name-regex-exclude", value_name = "REGEX")]
     pub dat_name_regex_exclude: Option<String>,
+    /// Only keep DAT games whose name matches this regex (e.g. `Mario`); unlike
+    /// --dat-name-regex this filters games within each DAT
+    #[arg(
+        long = "game-name-regex",
+        alias = "dat-game-name-regex",
+        value_name = "REGEX"
+    )]
+    pub game_name_regex: Option<String>,
+    /// Drop DAT games whose name matches this regex
+    #[arg(
+        long = "game-name-regex-exclude",
+        alias = "dat-game-name-regex-exclude",
+        value_name = "REGEX"
+    )]
+    pub game_name_regex_exclude: Option<String>,
+    /// Only load DATs for this system, by name or alias (e.g. `GBA`, `SNES`, "Game Boy
+    /// Advance"); combines with --dat-name-regex
+    #[arg(long = "system", alias = "dat-filter-system", value_name = "SYSTEM")]
+    pub system: Option<String>,
+    /// Rewrite DAT names used for output folders with a regex and capture-group
+    /// replacement, e.g. `^Nintendo - (.+?) \(.*$=$1`
+    #[arg(long = "dat-name-rewrite", value_name = "REGEX=REPLACEMENT")]
+    pub dat_name_rewrite: Option<String>,
     #[arg(long = "dat-description-regex", value_name = "REGEX")]
     pub dat_description_regex: Option<String>,
     #[arg(long = "dat-description-regex-exclude", value_name = "REGEX")]
     pub dat_description_regex_exclude: Option<String>,
     #[arg(long = "dat-combine")]
     pub dat_combine: bool,
+    /// Treat every game as standalone, ignoring parent/clone relationships; rejected with
+    /// `--merge-roms merged` or `split`, which depend on them
     #[arg(long = "dat-ignore-parent-clone")]
     pub dat_ignore_parent_clone: bool,
     /// Include unmatched DAT entries in the printed execution plan JSON
     #[arg(long = "list-unmatched-dats")]
     pub list_unmatched_dats: bool,
+    /// Write the fixdat as a Logiqx or ClrMamePro DAT instead of JSON
+    #[arg(long = "fixdat-format", value_enum, value_name = "FORMAT")]
+    pub fixdat_format: Option<FixdatFormat>,
     /// Enable Hasheous lookups for unmatched ROMs
     #[arg(long = "enable-hasheous")]
     pub enable_hasheous: bool,
@@ -94,23 +176,80 @@ pub struct Cli {
     /// Path to sqlite cache DB file. If omitted a default is used inside the output or next to the binary.
     #[arg(long = "cache-db", value_name = "PATH")]
     pub cache_db: Option<PathBuf>,
+    /// Cache scanned checksums in `user.igir.*` extended attributes on the input files
+    /// (reused while size and mtime are unchanged); ignored where xattrs are unsupported
+    #[arg(long = "xattr-cache")]
+    pub xattr_cache: bool,
+    /// Number of threads for scanning, hashing and matching (defaults to logical CPU count)
+    #[arg(long = "threads", value_name = "N")]
+    pub threads: Option<usize>,
     /// Number of threads to use for hashing (overrides default of logical CPU count)
     #[arg(long = "hash-threads", value_name = "N")]
     pub hash_threads: Option<usize>,
     /// Number of threads to use for scanning (overrides default of logical CPU count)
     #[arg(long = "scan-threads", value_name = "N")]
     pub scan_threads: Option<usize>,
+    /// Number of archives to scan concurrently (defaults to the scan thread count)
+    #[arg(long = "archive-threads", value_name = "N")]
+    pub archive_threads: Option<usize>,
+    /// Compare titles case-insensitively when looking for exact title matches (pass `false` to disable)
+    #[arg(long = "ignore-case", value_name = "BOOL", default_value_t = true, action = ArgAction::Set)]
+    pub ignore_case: bool,
     /// Show per-DAT match reasons in the IGIR summary output
     #[arg(long = "show-match-reasons")]
     pub show_match_reasons: bool,
+    /// Lowest score a near-miss must reach to be reported for an unmatched DAT game (test command)
+    #[arg(long = "min-score", value_name = "SCORE")]
+    pub min_score: Option<f64>,
+    /// Order matches with equal scores, and inputs colliding on one output path, by file
+    /// modification time instead of by path
+    #[arg(
+        long = "tiebreak-mtime",
+        value_enum,
+        default_value_t = MtimeTiebreak::Path,
+    )]
+    pub tiebreak_mtime: MtimeTiebreak,
+    /// Break ties between equal-score matches by `-i` order: files from an earlier input
+    /// win over files from a later one
+    #[arg(long = "priority-input")]
+    pub priority_input: bool,
+    /// Regex removed from titles before matching, on top of the built-in `(...)`/`[...]`
+    /// stripping (e.g. `\{[^}]*\}` for uploader tags); repeatable
+    #[arg(long = "title-strip-regex", value_name = "REGEX", action = ArgAction::Append)]
+    pub title_strip_regex: Vec<String>,
+    /// Fail instead of warning when the loaded DATs can never match (e.g. they need stronger checksums)
+    #[arg(long = "strict")]
+    pub strict: bool,
+    /// Exit 0 even when DATs were loaded but no input matched them (otherwise exits with code 3)
+    #[arg(long = "allow-no-matches")]
+    pub allow_no_matches: bool,
+    /// When a file matches games in several DATs, keep only its single best DAT match
+    /// (highest score, then earliest-loaded DAT)
+    #[arg(long = "dat-best-match")]
+    pub dat_best_match: bool,
+    /// Rank DATs whose path matches REGEX with priority N when resolving the best DAT per file
+    /// (higher wins among equal match scores; repeatable, first matching rule applies)
+    #[arg(long = "dat-priority", value_name = "REGEX=N", action = ArgAction::Append)]
+    pub dat_priority: Vec<String>,
+    /// Print the games added, removed, or changed between two DAT files, as JSON
+    #[arg(long = "dat-diff", num_args = 2, value_names = ["OLD", "NEW"])]
+    pub dat_diff: Vec<PathBuf>,
     /// Only use cached Hasheous/IGDB results; never perform network lookups
     #[arg(long = "cache-only")]
     pub cache_only: bool,
+    /// Reuse cached checksums for unchanged files instead of rehashing them (report action)
+    #[arg(long = "skip-existing-checksum")]
+    pub skip_existing_checksum: bool,
+    /// Write duplicates.json listing every group of inputs sharing a checksum (report action)
+    #[arg(long = "report-duplicates")]
+    pub report_duplicates: bool,
     /// If set, save the provided or discovered IGDB client id/token to the persistent config file
     #[arg(long = "save-igdb-creds")]
     pub save_igdb_creds: bool,
 
     // Patch input options
+    /// IPS/IPS32 patch file(s) or glob; copy and move write a patched copy of the ROM whose
+    /// CRC32 appears in the patch file name, named after the patch
     #[arg(short = 'p', long = "patch", value_name = "PATH", action = ArgAction::Append)]
     pub patch: Vec<PathBuf>,
     #[arg(short = 'P', long = "patch-exclude", value_name = "PATH", action = ArgAction::Append)]
@@ -119,22 +258,41 @@ pub struct Cli {
     // ROM output path options
     #[arg(short = 'o', long = "output", value_name = "PATH")]
     pub output: Option<PathBuf>,
+    /// Directory layout under --output; when set it replaces the individual --dir-* options
+    #[arg(long = "output-structure", value_enum, value_name = "MODE")]
+    pub output_structure: Option<OutputStructure>,
+    /// Recreate each file's folder relative to the `-i` root it was found under
     #[arg(long = "dir-mirror")]
     pub dir_mirror: bool,
+    /// With --dir-mirror, start the mirrored path with the input root's own name so
+    /// different roots holding the same relative paths stay apart
+    #[arg(long = "dir-mirror-root")]
+    pub dir_mirror_root: bool,
     #[arg(long = "dir-dat-mirror")]
     pub dir_dat_mirror: bool,
+    /// Place outputs in a folder named after the matching DAT
     #[arg(short = 'D', long = "dir-dat-name")]
     pub dir_dat_name: bool,
+    /// Place outputs in a folder named after the matching DAT's header description
     #[arg(long = "dir-dat-description")]
     pub dir_dat_description: bool,
     #[arg(long = "dir-letter")]
     pub dir_letter: bool,
+    /// Place outputs in a folder named after the file's region (e.g. `EUR`), before any
+    /// letter folder; files without a detectable region go to `Unknown Region`
+    #[arg(long = "dir-region")]
+    pub dir_region: bool,
     #[arg(long = "dir-letter-count", value_name = "NUM")]
     pub dir_letter_count: Option<usize>,
     #[arg(long = "dir-letter-limit", value_name = "NUM")]
     pub dir_letter_limit: Option<usize>,
     #[arg(long = "dir-letter-group")]
     pub dir_letter_group: bool,
+    /// Ignore leading articles ("The", "A", "An", ...) when picking the --dir-letter bucket
+    #[arg(long = "dir-letter-ignore-articles")]
+    pub dir_letter_ignore_articles: bool,
+    /// Put multi-file games (`.m3u` sets) in a subdirectory: `multiple` only those, `always`
+    /// every game, `never` none (clashing names get a ` (N)` suffix)
     #[arg(
         long = "dir-game-subdir",
         value_enum,
@@ -149,10 +307,32 @@ pub struct Cli {
         default_value_t = FixExtensionMode::Auto,
     )]
     pub fix_extension: FixExtensionMode,
+    /// Lowercase the extension of output files (e.g. `GAME.SFC` -> `GAME.sfc`)
+    #[arg(long = "normalize-extension-case")]
+    pub normalize_extension_case: bool,
+    /// Shorten output directory names so full output paths fit in 260 characters (the
+    /// Windows MAX_PATH limit); without it, Windows runs fail before writing overlong paths
+    #[arg(long = "shorten-paths")]
+    pub shorten_paths: bool,
     #[arg(short = 'O', long = "overwrite")]
     pub overwrite: bool,
     #[arg(long = "overwrite-invalid")]
     pub overwrite_invalid: bool,
+    /// What to do when several inputs would be written to the same output path; paths are
+    /// compared case-insensitively since macOS and Windows filesystems fold case
+    #[arg(
+        long = "on-collision",
+        value_enum,
+        default_value_t = CollisionPolicy::Rename,
+    )]
+    pub on_collision: CollisionPolicy,
+    /// Move input files that match no DAT entry, and archives that fail `verify-zip`, into
+    /// this directory (keeping their path under the input) instead of leaving them in place
+    #[arg(long = "quarantine", value_name = "PATH")]
+    pub quarantine: Option<PathBuf>,
+    /// Skip the check that the output volume has room for the planned writes
+    #[arg(long = "no-space-check")]
+    pub no_space_check: bool,
 
     // move command options
     #[arg(
@@ -162,11 +342,22 @@ pub struct Cli {
     )]
     pub move_delete_dirs: MoveDeleteDirsMode,
 
+    // extract command options
+    /// Only write archive members whose path matches this glob during extract
+    #[arg(long = "extract-include", value_name = "GLOB")]
+    pub extract_include: Option<String>,
+    /// Skip archive members whose path matches this glob during extract
+    #[arg(long = "extract-exclude", value_name = "GLOB")]
+    pub extract_exclude: Option<String>,
+
     // clean command options
     #[arg(short = 'C', long = "clean-exclude", value_name = "PATH", action = ArgAction::Append)]
     pub clean_exclude: Vec<PathBuf>,
     #[arg(long = "clean-backup", value_name = "PATH")]
     pub clean_backup: Option<PathBuf>,
+    /// Number of dated --clean-backup run folders to keep; older ones are deleted
+    #[arg(long = "clean-backup-keep", value_name = "NUM")]
+    pub clean_backup_keep: Option<usize>,
     #[arg(long = "clean-dry-run")]
     pub clean_dry_run: bool,
 
@@ -177,10 +368,20 @@ pub struct Cli {
         default_value_t = ZipFormat::Torrentzip,
     )]
     pub zip_format: ZipFormat,
-    #[arg(short = 'Z', long = "zip-exclude", value_name = "GLOB")]
-    pub zip_exclude: Option<String>,
+    /// Leave files whose archive-relative path matches this glob (e.g. `docs/*`) unzipped;
+    /// can be repeated
+    #[arg(short = 'Z', long = "zip-exclude", value_name = "GLOB", action = ArgAction::Append)]
+    pub zip_exclude: Vec<String>,
     #[arg(long = "zip-dat-name")]
     pub zip_dat_name: bool,
+    /// Archive comment for zip outputs; not allowed with `--zip-format torrentzip`, whose
+    /// comment is fixed by the format
+    #[arg(long = "zip-comment", value_name = "TEXT")]
+    pub zip_comment: Option<String>,
+    /// After copy/move, convert each output `.cue` set into a `.chd` with `chdman createcd`,
+    /// removing the loose cue/bin files on success; skipped with a warning if `chdman` is missing
+    #[arg(long = "to-chd")]
+    pub to_chd: bool,
 
     // link command options
     #[arg(
@@ -193,24 +394,32 @@ pub struct Cli {
     pub symlink_relative: bool,
 
     // header options
+    /// Glob of header definition files (ClrMamePro `<detector>` XML) whose rules decide which
+    /// header to skip before hashing
     #[arg(long = "header", value_name = "GLOB")]
     pub header: Option<String>,
+    /// Comma-separated extensions whose headers are stripped before hashing (e.g. `a78`)
     #[arg(short = 'H', long = "remove-headers", value_name = "EXTENSIONS")]
     pub remove_headers: Option<String>,
 
     // trimmed ROM options
     #[arg(long = "trimmed-glob", value_name = "GLOB")]
     pub trimmed_glob: Option<String>,
+    /// Hash trimmed NDS ROMs found inside archives as their full-size padded dumps
     #[arg(long = "trim-scan-archives")]
     pub trim_scan_archives: bool,
 
     // ROM set options
+    /// ROM set merge type for parent/clone DATs: `split` leaves clones only the ROMs and
+    /// disks they add, `merged` folds clones into their parent's set; disks shared through
+    /// `merge` stay with the parent only
     #[arg(
         long = "merge-roms",
         value_enum,
         default_value_t = MergeMode::Fullnonmerged,
     )]
     pub merge_roms: MergeMode,
+    /// Write multi-disc games into one folder with `(Disc N)` names and a generated `.m3u`
     #[arg(long = "merge-discs")]
     pub merge_discs: bool,
     #[arg(long = "exclude-disks")]
@@ -227,10 +436,30 @@ pub struct Cli {
     pub filter_regex_exclude: Option<String>,
     #[arg(short = 'L', long = "filter-language", value_name = "LANGS")]
     pub filter_language: Option<String>,
+    /// Keep the best variant of each title for every listed language (e.g. `EN,FR`) instead of one per title
+    #[arg(long = "keep-languages", value_name = "LANGS")]
+    pub keep_languages: Option<String>,
+    /// Keep the N best-ranked variants of each title instead of only the single best
+    #[arg(long = "keep-top-n", value_name = "N")]
+    pub keep_top_n: Option<usize>,
     #[arg(short = 'R', long = "filter-region", value_name = "REGIONS")]
     pub filter_region: Option<String>,
+    /// JSON file mapping extra tag tokens to regions and languages, checked before the built-in
+    /// tables (e.g. `{"regions": {"Scandinavia": "EUR"}, "languages": {"Scandinavia": "SV"}}`)
+    #[arg(
+        long = "region-map",
+        alias = "normalize-region-map",
+        value_name = "JSON"
+    )]
+    pub region_map: Option<PathBuf>,
     #[arg(long = "filter-category-regex", value_name = "REGEX")]
     pub filter_category_regex: Option<String>,
+    /// Keep only ROMs whose derived genre matches one of these (comma-separated, case-insensitive)
+    #[arg(long = "only-genre", value_name = "GENRES")]
+    pub only_genre: Option<String>,
+    /// Keep only ROMs whose derived platform matches one of these (comma-separated, case-insensitive)
+    #[arg(long = "only-platform", value_name = "PLATFORMS")]
+    pub only_platform: Option<String>,
     #[arg(long = "no-bios")]
     pub no_bios: bool,
     #[arg(long = "no-device")]
@@ -255,12 +484,148 @@ pub struct Cli {
     // logging options (limited parity)
     #[arg(short = 'v', long = "verbose", action = ArgAction::Count)]
     pub verbose: u8,
+    /// Never color log output (color is already off when stderr is not a terminal)
+    #[arg(long = "no-color")]
+    pub no_color: bool,
+    /// Hide progress bars and the end-of-run summary; give it twice (`-qq`) to also
+    /// silence warnings
     #[arg(short = 'q', long = "quiet", action = ArgAction::Count)]
     pub quiet: u8,
     /// Print the execution plan JSON to stdout (opt-in)
     #[arg(long = "print-plan")]
     pub print_plan: bool,
+    /// Dry run for copy/move/link: print whether each planned write is `new`, `identical` to
+    /// the existing destination (by checksum) or would `overwrite` it, and write nothing.
+    /// Every other action that changes files (extract, zip, rename, clean) is skipped too
+    #[arg(long = "plan-diff")]
+    pub plan_diff: bool,
+    /// Print only the steps, files processed and run totals as JSON instead of the full plan
+    #[arg(long = "summary-only", conflicts_with = "print_plan")]
+    pub summary_only: bool,
     /// Enable diagnostic progress logging on the DIAG bar
     #[arg(long = "diag")]
     pub diag: bool,
+    /// Print wall-clock time spent in each phase (scanning, DAT loading, matching, each
+    /// action) when the run finishes
+    #[arg(long = "profile")]
+    pub profile: bool,
+    /// Layout of the end-of-run summary (`romvault` lists complete/partial DATs and
+    /// missing/fixed ROMs the way RomVault reports them)
+    #[arg(long = "log-style", value_enum, default_value_t = LogStyle::Default)]
+    pub log_style: LogStyle,
+    /// Command run after each file is written, e.g. `register {path} {crc32} {game}`; it is
+    /// split into arguments and started without a shell
+    #[arg(long = "exec", alias = "after-match-hook", value_name = "CMD")]
+    pub exec: Option<String>,
+
+    /// JSON or TOML (picked by a `.toml` extension) file of option values keyed by their
+    /// snake_case names (e.g. `dat_name_regex`); flags given on the command line override
+    /// the file
+    #[arg(long = "config", value_name = "FILE")]
+    #[serde(skip)]
+    pub config_file: Option<PathBuf>,
+}
+
+impl Cli {
+    /// Parse the process arguments, filling options left unset from the `--config` file.
+    pub fn parse_with_config_file() -> anyhow::Result<Self> {
+        Self::parse_from_with_config_file(std::env::args_os())
+    }
+
+    /// Parse `args`, appending the `--config` file's values for every option the command
+    /// line did not set so they go through the same value parsers as real flags.
+    pub fn parse_from_with_config_file<I, T>(args: I) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let mut args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+        let command = Self::command();
+        let matches = command.clone().get_matches_from(args.clone());
+        let Some(path) = matches.get_one::<PathBuf>("config_file") else {
+            return Ok(Self::from_arg_matches(&matches)?);
+        };
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        let is_toml = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+        let values: serde_json::Map<String, serde_json::Value> = if is_toml {
+            let table: toml::Table = toml::from_str(&raw)
+                .with_context(|| format!("config file {} is not valid TOML", path.display()))?;
+            serde_json::from_value(serde_json::to_value(table)?)?
+        } else {
+            serde_json::from_str(&raw)
+                .with_context(|| format!("config file {} is not a JSON object", path.display()))?
+        };
+        for (key, value) in &values {
+            let arg = command
+                .get_arguments()
+                .find(|arg| arg.get_id() == key.as_str() && key != "config_file")
+                .with_context(|| format!("unknown option '{key}' in {}", path.display()))?;
+            if matches.value_source(key) == Some(ValueSource::CommandLine) {
+                continue;
+            }
+            let extra = config_file_args(arg, value)
+                .with_context(|| format!("invalid value for '{key}' in {}", path.display()))?;
+            args.extend(extra);
+        }
+        Ok(Self::parse_from(args))
+    }
+}
+
+/// Render one config file entry as the command-line tokens that would set it.
+fn config_file_args(arg: &Arg, value: &serde_json::Value) -> anyhow::Result<Vec<OsString>> {
+    let flag = arg
+        .get_long()
+        .map(|long| OsString::from(format!("--{long}")));
+    let items = match value {
+        serde_json::Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    };
+    let mut tokens = Vec::new();
+    for item in items {
+        let text = match item {
+            serde_json::Value::Null | serde_json::Value::Bool(false) => continue,
+            serde_json::Value::Bool(true) if matches!(arg.get_action(), ArgAction::SetTrue) => {
+                tokens.extend(flag.clone());
+                continue;
+            }
+            serde_json::Value::Number(count) if matches!(arg.get_action(), ArgAction::Count) => {
+                let count = count.as_u64().context("expected a non-negative count")?;
+                for _ in 0..count {
+                    tokens.extend(flag.clone());
+                }
+                continue;
+            }
+            serde_json::Value::Bool(true) => "true".to_string(),
+            serde_json::Value::Number(number) => number.to_string(),
+            serde_json::Value::String(text) => text.clone(),
+            _ => bail!("expected a string, number, boolean or array of those"),
+        };
+        tokens.extend(flag.clone());
+        tokens.push(text.into());
+    }
+    Ok(tokens)
+}
+
+/// Parse a byte count with an optional binary `K`/`M`/`G`/`T` suffix (e.g. `512M`).
+fn parse_byte_size(raw: &str) -> Result<u64, String> {
+    let trimmed = raw.trim();
+    let upper = trimmed.to_ascii_uppercase();
+    let digits = upper.trim_end_matches('B');
+    let (number, shift) = match digits.chars().last() {
+        Some('K') => (&digits[..digits.len() - 1], 10),
+        Some('M') => (&digits[..digits.len() - 1], 20),
+        Some('G') => (&digits[..digits.len() - 1], 30),
+        Some('T') => (&digits[..digits.len() - 1], 40),
+        _ => (digits, 0),
+    };
+    let value: u64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid size '{trimmed}' (expected e.g. 1048576, 512M or 4G)"))?;
+    value
+        .checked_mul(1u64 << shift)
+        .ok_or_else(|| format!("size '{trimmed}' is too large"))
 }
@@ -3,15 +3,16 @@ use std::fs;
 use std::path::PathBuf;
 use std::time::Duration;
 
-use anyhow::anyhow;
+use anyhow::{Context, anyhow};
 use reqwest::blocking::Client;
 use serde::Deserialize;
 
 use crate::{
     cli::Cli,
     types::{
-        Action, ArchiveChecksumMode, Checksum, DirGameSubdirMode, FileRecord, FixExtensionMode,
-        IgdbLookupMode, LinkMode, MergeMode, MoveDeleteDirsMode, ZipFormat,
+        Action, ArchiveChecksumMode, Checksum, CollisionPolicy, DirGameSubdirMode, FileRecord,
+        FixExtensionMode, FixdatFormat, IgdbLookupMode, LinkMode, LogStyle, MergeMode,
+        MoveDeleteDirsMode, MtimeTiebreak, OutputStructure, ZipFormat,
     },
 };
 
@@ -21,19 +22,38 @@ pub struct Config {
     pub input: Vec<PathBuf>,
     pub input_exclude: Vec<PathBuf>,
     pub input_checksum_quick: bool,
+    pub input_checksum_auto: bool,
+    pub scan_files_only: bool,
+    pub scan_archives_only: bool,
     pub input_checksum_min: Checksum,
     pub input_checksum_max: Option<Checksum>,
+    /// `--hash-algorithms`, parsed once; replaces the min/max range when set.
+    pub hash_algorithms: Option<Vec<Checksum>>,
+    pub hash_checkpoint_mib: Option<u64>,
+    pub expect_crc32: Option<String>,
+    pub expect_md5: Option<String>,
+    pub expect_sha1: Option<String>,
+    pub expect_sha256: Option<String>,
+    pub max_decompressed_size: Option<u64>,
     pub input_checksum_archives: ArchiveChecksumMode,
     pub dat: Vec<PathBuf>,
     pub dat_exclude: Vec<PathBuf>,
+    pub dat_url: Vec<String>,
     pub dat_name_regex: Option<String>,
     pub dat_name_regex_exclude: Option<String>,
+    pub game_name_regex: Option<String>,
+    pub game_name_regex_exclude: Option<String>,
+    pub system: Option<String>,
+    pub dat_name_rewrite: Option<String>,
     pub dat_description_regex: Option<String>,
     pub dat_description_regex_exclude: Option<String>,
     pub dat_combine: bool,
     pub dat_ignore_parent_clone: bool,
     pub list_unmatched_dats: bool,
+    pub fixdat_format: Option<FixdatFormat>,
     pub print_plan: bool,
+    pub plan_diff: bool,
+    pub summary_only: bool,
     pub enable_hasheous: bool,
     pub igdb_client_id: Option<String>,
     #[serde(skip_serializing)]
@@ -44,25 +64,39 @@ pub struct Config {
     pub patch: Vec<PathBuf>,
     pub patch_exclude: Vec<PathBuf>,
     pub output: Option<PathBuf>,
+    pub output_structure: Option<OutputStructure>,
     pub dir_mirror: bool,
+    pub dir_mirror_root: bool,
     pub dir_dat_mirror: bool,
     pub dir_dat_name: bool,
     pub dir_dat_description: bool,
     pub dir_letter: bool,
+    pub dir_region: bool,
     pub dir_letter_count: Option<usize>,
     pub dir_letter_limit: Option<usize>,
     pub dir_letter_group: bool,
+    pub dir_letter_ignore_articles: bool,
     pub dir_game_subdir: DirGameSubdirMode,
     pub fix_extension: FixExtensionMode,
+    pub normalize_extension_case: bool,
+    pub shorten_paths: bool,
     pub overwrite: bool,
     pub overwrite_invalid: bool,
+    pub on_collision: CollisionPolicy,
+    pub quarantine: Option<PathBuf>,
+    pub no_space_check: bool,
     pub move_delete_dirs: MoveDeleteDirsMode,
+    pub extract_include: Option<String>,
+    pub extract_exclude: Option<String>,
     pub clean_exclude: Vec<PathBuf>,
     pub clean_backup: Option<PathBuf>,
+    pub clean_backup_keep: Option<usize>,
     pub clean_dry_run: bool,
     pub zip_format: ZipFormat,
-    pub zip_exclude: Option<String>,
+    pub zip_exclude: Vec<String>,
     pub zip_dat_name: bool,
+    pub zip_comment: Option<String>,
+    pub to_chd: bool,
     pub link_mode: LinkMode,
     pub symlink_relative: bool,
     pub header: Option<String>,
@@ -77,8 +111,13 @@ pub struct Config {
     pub filter_regex: Option<String>,
     pub filter_regex_exclude: Option<String>,
     pub filter_language: Option<String>,
+    pub keep_languages: Option<String>,
+    pub keep_top_n: Option<usize>,
     pub filter_region: Option<String>,
+    pub region_map: Option<crate::records::RegionMap>,
     pub filter_category_regex: Option<String>,
+    pub only_genre: Option<String>,
+    pub only_platform: Option<String>,
     pub no_bios: bool,
     pub no_device: bool,
     pub no_unlicensed: bool,
@@ -90,10 +129,26 @@ pub struct Config {
     pub no_prototype: bool,
     pub no_program: bool,
     pub verbose: u8,
+    pub no_color: bool,
     pub quiet: u8,
     pub diag: bool,
+    pub profile: bool,
+    pub log_style: LogStyle,
+    pub exec: Option<String>,
+    pub ignore_case: bool,
     pub show_match_reasons: bool,
+    pub min_score: Option<f64>,
+    pub tiebreak_mtime: MtimeTiebreak,
+    pub priority_input: bool,
+    pub title_strip_regex: Vec<String>,
+    pub strict: bool,
+    pub allow_no_matches: bool,
+    pub dat_best_match: bool,
+    pub dat_priority: Vec<String>,
+    pub dat_diff: Option<(PathBuf, PathBuf)>,
+    pub threads: Option<usize>,
     pub scan_threads: Option<usize>,
+    pub archive_threads: Option<usize>,
     // Online lookup tuning
     pub online_timeout_secs: Option<u64>,
     pub online_max_retries: Option<usize>,
@@ -102,10 +157,25 @@ pub struct Config {
     pub cache_only: bool,
     // Optional explicit cache DB path
     pub cache_db: Option<PathBuf>,
+    pub xattr_cache: bool,
+    pub skip_existing_checksum: bool,
+    pub report_duplicates: bool,
     pub hash_threads: Option<usize>,
 }
 
 impl Config {
+    /// Trusted checksums given via `--expect-<checksum>`, strongest last.
+    pub fn expected_checksums(&self) -> impl Iterator<Item = (Checksum, &str)> {
+        [
+            (Checksum::Crc32, &self.expect_crc32),
+            (Checksum::Md5, &self.expect_md5),
+            (Checksum::Sha1, &self.expect_sha1),
+            (Checksum::Sha256, &self.expect_sha256),
+        ]
+        .into_iter()
+        .filter_map(|(kind, value)| value.as_deref().map(|value| (kind, value)))
+    }
+
     fn validate_checksum_range(&self) -> anyhow::Result<()> {
         if let Some(max) = self.input_checksum_max {
             let min_rank = self.input_checksum_min.rank();
@@ -121,6 +191,19 @@ impl Config {
         Ok(())
     }
 
+    fn validate_hash_algorithms(&self) -> anyhow::Result<()> {
+        if self.hash_algorithms.is_some() {
+            // The explicit list replaces the range, so a range set alongside it would be ignored.
+            if self.input_checksum_min != Checksum::Crc32 || self.input_checksum_max.is_some() {
+                anyhow::bail!(
+                    "--hash-algorithms replaces --input-checksum-min/--input-checksum-max; pass either the explicit list or the range, not both"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
     fn validate_letter_strategy(&self) -> anyhow::Result<()> {
         if self.dir_letter_group && self.dir_letter_limit.is_none() {
             anyhow::bail!("dir-letter-group requires --dir-letter-limit to split ranges");
@@ -130,6 +213,10 @@ impl Config {
             anyhow::bail!("dir-letter-group requires --dir-letter to organize by letter");
         }
 
+        if self.dir_letter_ignore_articles && !self.dir_letter {
+            anyhow::bail!("dir-letter-ignore-articles requires --dir-letter to organize by letter");
+        }
+
         if self.dir_letter_limit.is_some() && !self.dir_letter {
             anyhow::bail!("dir-letter-limit requires --dir-letter to organize by letter");
         }
@@ -148,7 +235,7 @@ impl Config {
     }
 
     fn validate_commands(&self) -> anyhow::Result<()> {
-        if self.commands.is_empty() {
+        if self.commands.is_empty() && self.dat_diff.is_none() {
             anyhow::bail!("at least one command must be provided");
         }
 
@@ -167,7 +254,11 @@ impl Config {
             | Action::Fixdat
             | Action::Clean
             | Action::Report => true,
-            Action::Test => false,
+            Action::Rename
+            | Action::Test
+            | Action::VerifyZip
+            | Action::CleanEmptyDirs
+            | Action::Verify => false,
         });
 
         if needs_output && self.output.is_none() {
@@ -180,8 +271,109 @@ impl Config {
     pub fn validate(&self) -> anyhow::Result<()> {
         self.validate_commands()?;
         self.validate_checksum_range()?;
+        self.validate_hash_algorithms()?;
         self.validate_letter_strategy()?;
         self.validate_output_requirements()?;
+        if self.expected_checksums().next().is_some() && !self.commands.contains(&Action::Test) {
+            anyhow::bail!("--expect-crc32/md5/sha1/sha256 require the test command");
+        }
+        if self.hash_checkpoint_mib == Some(0) {
+            anyhow::bail!("--hash-checkpoint must be greater than zero");
+        }
+        if self.max_decompressed_size == Some(0) {
+            anyhow::bail!("--max-decompressed-size must be greater than zero");
+        }
+        if self.scan_files_only && self.scan_archives_only {
+            anyhow::bail!("--scan-files-only and --scan-archives-only are mutually exclusive");
+        }
+        if self.clean_backup_keep == Some(0) {
+            anyhow::bail!("--clean-backup-keep must be >= 1");
+        }
+        if self.dir_mirror_root && !self.dir_mirror {
+            anyhow::bail!("--dir-mirror-root requires --dir-mirror");
+        }
+        if self.clean_backup_keep.is_some() && self.clean_backup.is_none() {
+            anyhow::bail!("--clean-backup-keep requires --clean-backup");
+        }
+        if !self.dat_priority.is_empty() && !self.dat_best_match {
+            anyhow::bail!("--dat-priority requires --dat-best-match");
+        }
+        if self.dat_ignore_parent_clone {
+            let mode = match self.merge_roms {
+                MergeMode::Merged => Some("merged"),
+                MergeMode::Split => Some("split"),
+                MergeMode::Fullnonmerged | MergeMode::Nonmerged => None,
+            };
+            if let Some(mode) = mode {
+                anyhow::bail!(
+                    "--dat-ignore-parent-clone cannot be combined with --merge-roms {mode}: those sets are built from parent/clone relationships"
+                );
+            }
+        }
+        for pattern in &self.title_strip_regex {
+            regex::Regex::new(pattern)
+                .with_context(|| format!("invalid --title-strip-regex {pattern:?}"))?;
+        }
+        if let Some(url) = self
+            .dat_url
+            .iter()
+            .find(|url| !(url.starts_with("http://") || url.starts_with("https://")))
+        {
+            anyhow::bail!("--dat-url must be an http(s) URL, got {url:?}");
+        }
+        if let Some(rule) = &self.dat_name_rewrite {
+            crate::dat::parse_dat_name_rewrite(rule)?;
+        }
+        for rule in &self.dat_priority {
+            crate::dat::parse_dat_priority(rule)?;
+        }
+        for (flag, pattern) in [
+            ("--extract-include", &self.extract_include),
+            ("--extract-exclude", &self.extract_exclude),
+        ] {
+            if let Some(pattern) = pattern {
+                globset::Glob::new(pattern)
+                    .with_context(|| format!("invalid {flag} glob {pattern:?}"))?;
+            }
+        }
+        crate::utils::build_globset(&self.zip_exclude).context("invalid --zip-exclude glob")?;
+        if let Some(exec) = &self.exec {
+            let argv = crate::utils::split_command_line(exec)
+                .with_context(|| format!("invalid --exec command {exec:?}"))?;
+            if argv.is_empty() {
+                anyhow::bail!("--exec requires a command");
+            }
+        }
+        if let Some(comment) = &self.zip_comment {
+            if matches!(self.zip_format, ZipFormat::Torrentzip) {
+                anyhow::bail!(
+                    "--zip-comment cannot be used with --zip-format torrentzip, whose comment is fixed"
+                );
+            }
+            if comment.len() > usize::from(u16::MAX) {
+                anyhow::bail!("--zip-comment must be at most {} bytes", u16::MAX);
+            }
+        }
+        if self.to_chd
+            && !self
+                .commands
+                .iter()
+                .any(|action| matches!(action, Action::Copy | Action::Move))
+        {
+            anyhow::bail!("--to-chd requires the copy or move command");
+        }
+        if self.keep_top_n == Some(0) {
+            anyhow::bail!("--keep-top-n must be >= 1");
+        }
+        if self
+            .min_score
+            .is_some_and(|score| !score.is_finite() || score < 0.0)
+        {
+            anyhow::bail!("--min-score must be a non-negative number");
+        }
+        if self.threads == Some(0) {
+            anyhow::bail!("--threads must be >= 1");
+        }
         // Validate CLI-provided hash thread count (if any)
         if let Some(n) = self.hash_threads {
             if n == 0 {
@@ -193,9 +385,28 @@ impl Config {
                 anyhow::bail!("--scan-threads must be >= 1");
             }
         }
+        if self.archive_threads == Some(0) {
+            anyhow::bail!("--archive-threads must be >= 1");
+        }
         Ok(())
     }
 
+    /// Compiled `--title-strip-regex` patterns (already checked by `validate`).
+    pub fn title_strip_patterns(&self) -> Vec<regex::Regex> {
+        self.title_strip_regex
+            .iter()
+            .filter_map(|pattern| regex::Regex::new(pattern).ok())
+            .collect()
+    }
+
+    /// Size of the rayon global pool: the larger of the hash and scan thread counts.
+    pub fn global_thread_count(&self) -> usize {
+        let default_threads = num_cpus::get();
+        let hash_threads = self.hash_threads.unwrap_or(default_threads);
+        let scan_threads = self.scan_threads.unwrap_or(default_threads);
+        std::cmp::max(hash_threads, scan_threads).max(1)
+    }
+
     pub fn igdb_lookup_enabled(&self) -> bool {
         !matches!(self.igdb_mode, IgdbLookupMode::Off)
     }
@@ -329,19 +540,41 @@ impl TryFrom<Cli> for Config {
             input: cli.input,
             input_exclude: cli.input_exclude,
             input_checksum_quick: cli.input_checksum_quick,
+            input_checksum_auto: cli.input_checksum_auto,
+            scan_files_only: cli.scan_files_only,
+            scan_archives_only: cli.scan_archives_only,
             input_checksum_min: cli.input_checksum_min,
             input_checksum_max: cli.input_checksum_max,
+            hash_algorithms: cli
+                .hash_algorithms
+                .as_deref()
+                .map(crate::checksum::parse_hash_algorithms)
+                .transpose()?,
+            hash_checkpoint_mib: cli.hash_checkpoint_mib,
+            expect_crc32: cli.expect_crc32,
+            expect_md5: cli.expect_md5,
+            expect_sha1: cli.expect_sha1,
+            expect_sha256: cli.expect_sha256,
+            max_decompressed_size: cli.max_decompressed_size,
             input_checksum_archives: cli.input_checksum_archives,
             dat: cli.dat,
             dat_exclude: cli.dat_exclude,
+            dat_url: cli.dat_url,
             dat_name_regex: cli.dat_name_regex,
             dat_name_regex_exclude: cli.dat_name_regex_exclude,
+            game_name_regex: cli.game_name_regex,
+            game_name_regex_exclude: cli.game_name_regex_exclude,
+            system: cli.system,
+            dat_name_rewrite: cli.dat_name_rewrite,
             dat_description_regex: cli.dat_description_regex,
             dat_description_regex_exclude: cli.dat_description_regex_exclude,
             dat_combine: cli.dat_combine,
             dat_ignore_parent_clone: cli.dat_ignore_parent_clone,
             list_unmatched_dats: cli.list_unmatched_dats,
+            fixdat_format: cli.fixdat_format,
             print_plan: cli.print_plan,
+            plan_diff: cli.plan_diff,
+            summary_only: cli.summary_only,
             enable_hasheous: cli.enable_hasheous,
             igdb_client_id: effective_client_id.clone(),
             igdb_client_secret: effective_client_secret.clone(),
@@ -351,25 +584,39 @@ impl TryFrom<Cli> for Config {
             patch: cli.patch,
             patch_exclude: cli.patch_exclude,
             output: cli.output,
+            output_structure: cli.output_structure,
             dir_mirror: cli.dir_mirror,
+            dir_mirror_root: cli.dir_mirror_root,
             dir_dat_mirror: cli.dir_dat_mirror,
             dir_dat_name: cli.dir_dat_name,
             dir_dat_description: cli.dir_dat_description,
             dir_letter: cli.dir_letter,
+            dir_region: cli.dir_region,
             dir_letter_count: cli.dir_letter_count.or_else(|| cli.dir_letter.then_some(1)),
             dir_letter_limit: cli.dir_letter_limit,
             dir_letter_group: cli.dir_letter_group,
+            dir_letter_ignore_articles: cli.dir_letter_ignore_articles,
             dir_game_subdir: cli.dir_game_subdir,
             fix_extension: cli.fix_extension,
+            normalize_extension_case: cli.normalize_extension_case,
+            shorten_paths: cli.shorten_paths,
             overwrite: cli.overwrite,
             overwrite_invalid: cli.overwrite_invalid,
+            on_collision: cli.on_collision,
+            quarantine: cli.quarantine,
+            no_space_check: cli.no_space_check,
             move_delete_dirs: cli.move_delete_dirs,
+            extract_include: cli.extract_include,
+            extract_exclude: cli.extract_exclude,
             clean_exclude: cli.clean_exclude,
             clean_backup: cli.clean_backup,
+            clean_backup_keep: cli.clean_backup_keep,
             clean_dry_run: cli.clean_dry_run,
             zip_format: cli.zip_format,
             zip_exclude: cli.zip_exclude,
             zip_dat_name: cli.zip_dat_name,
+            zip_comment: cli.zip_comment,
+            to_chd: cli.to_chd,
             link_mode: cli.link_mode,
             symlink_relative: cli.symlink_relative,
             header: cli.header,
@@ -384,8 +631,17 @@ impl TryFrom<Cli> for Config {
             filter_regex: cli.filter_regex,
             filter_regex_exclude: cli.filter_regex_exclude,
             filter_language: cli.filter_language,
+            keep_languages: cli.keep_languages,
+            keep_top_n: cli.keep_top_n,
             filter_region: cli.filter_region,
+            region_map: cli
+                .region_map
+                .as_deref()
+                .map(crate::records::RegionMap::load)
+                .transpose()?,
             filter_category_regex: cli.filter_category_regex,
+            only_genre: cli.only_genre,
+            only_platform: cli.only_platform,
             no_bios: cli.no_bios,
             no_device: cli.no_device,
             no_unlicensed: cli.no_unlicensed,
@@ -397,16 +653,38 @@ impl TryFrom<Cli> for Config {
             no_prototype: cli.no_prototype,
             no_program: cli.no_program,
             verbose: cli.verbose,
+            no_color: cli.no_color,
             quiet: cli.quiet,
             diag: cli.diag,
+            profile: cli.profile,
+            log_style: cli.log_style,
+            exec: cli.exec,
             online_timeout_secs: Some(5),
             online_max_retries: Some(3),
             online_throttle_ms: None,
             cache_only: cli.cache_only,
             cache_db: cli.cache_db,
-            hash_threads: cli.hash_threads,
-            scan_threads: cli.scan_threads,
+            xattr_cache: cli.xattr_cache,
+            skip_existing_checksum: cli.skip_existing_checksum,
+            report_duplicates: cli.report_duplicates,
+            threads: cli.threads,
+            hash_threads: cli.hash_threads.or(cli.threads),
+            scan_threads: cli.scan_threads.or(cli.threads),
+            archive_threads: cli.archive_threads,
+            ignore_case: cli.ignore_case,
             show_match_reasons: cli.show_match_reasons,
+            min_score: cli.min_score,
+            tiebreak_mtime: cli.tiebreak_mtime,
+            priority_input: cli.priority_input,
+            title_strip_regex: cli.title_strip_regex,
+            strict: cli.strict,
+            allow_no_matches: cli.allow_no_matches,
+            dat_best_match: cli.dat_best_match,
+            dat_priority: cli.dat_priority,
+            dat_diff: match cli.dat_diff.as_slice() {
+                [old, new] => Some((old.clone(), new.clone())),
+                _ => None,
+            },
         };
 
         config.refresh_igdb_token_if_needed(token_from_cli)?;
@@ -414,7 +692,7 @@ impl TryFrom<Cli> for Config {
         if cli.save_igdb_creds || loaded_persisted {
             if let Err(err) = config.persist_igdb_creds() {
                 if config.verbose > 0 {
-                    eprintln!("warning: unable to persist IGDB credentials: {}", err);
+                    vwarn!("unable to persist IGDB credentials: {}", err);
                 }
             }
         }
@@ -432,19 +710,28 @@ impl Default for Config {
             input: vec![],
             input_exclude: vec![],
             input_checksum_quick: false,
+            input_checksum_auto: false,
+            scan_files_only: false,
+            scan_archives_only: false,
             input_checksum_min: crate::types::Checksum::Crc32,
             input_checksum_max: None,
             input_checksum_archives: crate::types::ArchiveChecksumMode::Auto,
             dat: vec![],
             dat_exclude: vec![],
+            dat_url: vec![],
             dat_name_regex: None,
             dat_name_regex_exclude: None,
+            game_name_regex: None,
+            game_name_regex_exclude: None,
+            system: None,
+            dat_name_rewrite: None,
             dat_description_regex: None,
             dat_description_regex_exclude: None,
             dat_combine: false,
             dat_ignore_parent_clone: false,
             list_unmatched_dats: false,
             print_plan: true,
+            summary_only: false,
             enable_hasheous: false,
             igdb_client_id: None,
             igdb_client_secret: None,
@@ -455,24 +742,37 @@ impl Default for Config {
             patch_exclude: vec![],
             output: None,
             dir_mirror: false,
+            dir_mirror_root: false,
             dir_dat_mirror: false,
             dir_dat_name: false,
             dir_dat_description: false,
             dir_letter: false,
+            dir_region: false,
             dir_letter_count: None,
             dir_letter_limit: None,
             dir_letter_group: false,
+            dir_letter_ignore_articles: false,
             dir_game_subdir: crate::types::DirGameSubdirMode::Multiple,
             fix_extension: crate::types::FixExtensionMode::Auto,
+            normalize_extension_case: false,
+            shorten_paths: false,
             overwrite: false,
             overwrite_invalid: false,
+            on_collision: crate::types::CollisionPolicy::Rename,
+            quarantine: None,
+            no_space_check: false,
             move_delete_dirs: crate::types::MoveDeleteDirsMode::Auto,
+            extract_include: None,
+            extract_exclude: None,
             clean_exclude: vec![],
             clean_backup: None,
+            clean_backup_keep: None,
             clean_dry_run: false,
             zip_format: crate::types::ZipFormat::Torrentzip,
-            zip_exclude: None,
+            zip_exclude: vec![],
             zip_dat_name: false,
+            zip_comment: None,
+            to_chd: false,
             link_mode: crate::types::LinkMode::Hardlink,
             symlink_relative: false,
             header: None,
@@ -487,8 +787,12 @@ impl Default for Config {
             filter_regex: None,
             filter_regex_exclude: None,
             filter_language: None,
+            keep_languages: None,
+            keep_top_n: None,
             filter_region: None,
             filter_category_regex: None,
+            only_genre: None,
+            only_platform: None,
             no_bios: false,
             no_device: false,
             no_unlicensed: false,
@@ -500,16 +804,46 @@ impl Default for Config {
             no_prototype: false,
             no_program: false,
             verbose: 0,
+            no_color: false,
             quiet: 0,
             diag: false,
+            plan_diff: false,
+            region_map: None,
+            profile: false,
+            log_style: crate::types::LogStyle::Default,
+            exec: None,
             show_match_reasons: false,
+            min_score: None,
+            tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+            priority_input: false,
+            title_strip_regex: vec![],
+            ignore_case: true,
+            output_structure: None,
+            threads: None,
+            strict: false,
+            allow_no_matches: false,
+            dat_best_match: false,
+            dat_priority: Vec::new(),
+            dat_diff: None,
+            fixdat_format: None,
+            hash_algorithms: None,
+            hash_checkpoint_mib: None,
+            expect_crc32: None,
+            expect_md5: None,
+            expect_sha1: None,
+            expect_sha256: None,
+            max_decompressed_size: None,
+            skip_existing_checksum: false,
+            report_duplicates: false,
             online_timeout_secs: Some(5),
             online_max_retries: Some(3),
             online_throttle_ms: None,
             cache_only: false,
             cache_db: None,
+            xattr_cache: false,
             hash_threads: None,
             scan_threads: None,
+            archive_threads: None,
         }
     }
 }
@@ -538,6 +872,16 @@ fn persisted_config_path() -> anyhow::Result<PathBuf> {
     anyhow::bail!("unable to determine a config path for persisted credentials")
 }
 
+/// Directory holding the persisted config file; downloaded `--dat-url` DATs are cached
+/// beneath it.
+pub(crate) fn config_dir() -> anyhow::Result<PathBuf> {
+    let path = persisted_config_path()?;
+    Ok(path
+        .parent()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(".")))
+}
+
 #[derive(Deserialize)]
 struct IgdbTokenResponse {
     access_token: String,
@@ -576,13 +920,21 @@ mod tests {
             input: vec![PathBuf::from("/tmp/file.bin")],
             input_exclude: vec![],
             input_checksum_quick: false,
+            input_checksum_auto: false,
+            scan_files_only: false,
+            scan_archives_only: false,
             input_checksum_min: Checksum::Crc32,
             input_checksum_max: None,
             input_checksum_archives: ArchiveChecksumMode::Auto,
             dat: vec![],
             dat_exclude: vec![],
+            dat_url: vec![],
             dat_name_regex: None,
             dat_name_regex_exclude: None,
+            game_name_regex: None,
+            game_name_regex_exclude: None,
+            system: None,
+            dat_name_rewrite: None,
             dat_description_regex: None,
             dat_description_regex_exclude: None,
             dat_combine: false,
@@ -597,24 +949,37 @@ mod tests {
             patch_exclude: vec![],
             output: None,
             dir_mirror: false,
+            dir_mirror_root: false,
             dir_dat_mirror: false,
             dir_dat_name: false,
             dir_dat_description: false,
             dir_letter: false,
+            dir_region: false,
             dir_letter_count: None,
             dir_letter_limit: None,
             dir_letter_group: false,
+            dir_letter_ignore_articles: false,
             dir_game_subdir: DirGameSubdirMode::Multiple,
             fix_extension: FixExtensionMode::Auto,
+            normalize_extension_case: false,
+            shorten_paths: false,
             overwrite: false,
             overwrite_invalid: false,
+            on_collision: crate::types::CollisionPolicy::Skip,
+            quarantine: None,
+            no_space_check: false,
             move_delete_dirs: MoveDeleteDirsMode::Auto,
+            extract_include: None,
+            extract_exclude: None,
             clean_exclude: vec![],
             clean_backup: None,
+            clean_backup_keep: None,
             clean_dry_run: false,
             zip_format: ZipFormat::Torrentzip,
-            zip_exclude: None,
+            zip_exclude: vec![],
             zip_dat_name: false,
+            zip_comment: None,
+            to_chd: false,
             link_mode: LinkMode::Hardlink,
             symlink_relative: false,
             header: None,
@@ -629,8 +994,12 @@ mod tests {
             filter_regex: None,
             filter_regex_exclude: None,
             filter_language: None,
+            keep_languages: None,
+            keep_top_n: None,
             filter_region: None,
             filter_category_regex: None,
+            only_genre: None,
+            only_platform: None,
             no_bios: false,
             no_device: false,
             no_unlicensed: false,
@@ -642,15 +1011,47 @@ mod tests {
             no_prototype: false,
             no_program: false,
             verbose: 0,
+            no_color: false,
             quiet: 0,
             diag: false,
+            plan_diff: false,
+            region_map: None,
+            profile: false,
+            log_style: crate::types::LogStyle::Default,
+            exec: None,
+            config_file: None,
             cache_only: false,
             cache_db: None,
+            xattr_cache: false,
             hash_threads: None,
             scan_threads: None,
+            archive_threads: None,
             show_match_reasons: false,
+            min_score: None,
+            tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+            priority_input: false,
+            title_strip_regex: vec![],
+            ignore_case: true,
+            output_structure: None,
+            threads: None,
+            strict: false,
+            allow_no_matches: false,
+            dat_best_match: false,
+            dat_priority: Vec::new(),
+            dat_diff: Vec::new(),
+            fixdat_format: None,
+            hash_algorithms: None,
+            hash_checkpoint_mib: None,
+            expect_crc32: None,
+            expect_md5: None,
+            expect_sha1: None,
+            expect_sha256: None,
+            max_decompressed_size: None,
+            skip_existing_checksum: false,
+            report_duplicates: false,
             save_igdb_creds: false,
             print_plan,
+            summary_only: false,
         }
     }
 
@@ -671,6 +1072,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         }
     }
 
@@ -703,13 +1110,21 @@ mod tests {
             input: vec![],
             input_exclude: vec![],
             input_checksum_quick: false,
+            input_checksum_auto: false,
+            scan_files_only: false,
+            scan_archives_only: false,
             input_checksum_min: Checksum::Crc32,
             input_checksum_max: None,
             input_checksum_archives: ArchiveChecksumMode::Auto,
             dat: vec![],
             dat_exclude: vec![],
+            dat_url: vec![],
             dat_name_regex: None,
             dat_name_regex_exclude: None,
+            game_name_regex: None,
+            game_name_regex_exclude: None,
+            system: None,
+            dat_name_rewrite: None,
             dat_description_regex: None,
             dat_description_regex_exclude: None,
             dat_combine: false,
@@ -724,24 +1139,37 @@ mod tests {
             patch_exclude: vec![],
             output: None,
             dir_mirror: false,
+            dir_mirror_root: false,
             dir_dat_mirror: false,
             dir_dat_name: false,
             dir_dat_description: false,
             dir_letter: false,
+            dir_region: false,
             dir_letter_count: None,
             dir_letter_limit: None,
             dir_letter_group: false,
+            dir_letter_ignore_articles: false,
             dir_game_subdir: DirGameSubdirMode::Multiple,
             fix_extension: FixExtensionMode::Auto,
+            normalize_extension_case: false,
+            shorten_paths: false,
             overwrite: false,
             overwrite_invalid: false,
+            on_collision: crate::types::CollisionPolicy::Skip,
+            quarantine: None,
+            no_space_check: false,
             move_delete_dirs: MoveDeleteDirsMode::Auto,
+            extract_include: None,
+            extract_exclude: None,
             clean_exclude: vec![],
             clean_backup: None,
+            clean_backup_keep: None,
             clean_dry_run: false,
             zip_format: ZipFormat::Torrentzip,
-            zip_exclude: None,
+            zip_exclude: vec![],
             zip_dat_name: false,
+            zip_comment: None,
+            to_chd: false,
             link_mode: LinkMode::Hardlink,
             symlink_relative: false,
             header: None,
@@ -756,8 +1184,12 @@ mod tests {
             filter_regex: None,
             filter_regex_exclude: None,
             filter_language: None,
+            keep_languages: None,
+            keep_top_n: None,
             filter_region: None,
             filter_category_regex: None,
+            only_genre: None,
+            only_platform: None,
             no_bios: false,
             no_device: false,
             no_unlicensed: false,
@@ -769,14 +1201,46 @@ mod tests {
             no_prototype: false,
             no_program: false,
             verbose: 0,
+            no_color: false,
             quiet: 0,
             diag: false,
+            plan_diff: false,
+            region_map: None,
+            profile: false,
+            log_style: crate::types::LogStyle::Default,
+            exec: None,
+            config_file: None,
             print_plan: false,
+            summary_only: false,
             cache_only: false,
             cache_db: None,
+            xattr_cache: false,
             hash_threads: None,
             scan_threads: None,
+            archive_threads: None,
             show_match_reasons: false,
+            min_score: None,
+            tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+            priority_input: false,
+            title_strip_regex: vec![],
+            ignore_case: true,
+            output_structure: None,
+            threads: None,
+            strict: false,
+            allow_no_matches: false,
+            dat_best_match: false,
+            dat_priority: Vec::new(),
+            dat_diff: Vec::new(),
+            fixdat_format: None,
+            hash_algorithms: None,
+            hash_checkpoint_mib: None,
+            expect_crc32: None,
+            expect_md5: None,
+            expect_sha1: None,
+            expect_sha256: None,
+            max_decompressed_size: None,
+            skip_existing_checksum: false,
+            report_duplicates: false,
             save_igdb_creds: false,
         };
 
@@ -791,13 +1255,21 @@ mod tests {
             input: vec![PathBuf::from("/tmp/file.bin")],
             input_exclude: vec![],
             input_checksum_quick: false,
+            input_checksum_auto: false,
+            scan_files_only: false,
+            scan_archives_only: false,
             input_checksum_min: Checksum::Sha1,
             input_checksum_max: Some(Checksum::Md5),
             input_checksum_archives: ArchiveChecksumMode::Auto,
             dat: vec![],
             dat_exclude: vec![],
+            dat_url: vec![],
             dat_name_regex: None,
             dat_name_regex_exclude: None,
+            game_name_regex: None,
+            game_name_regex_exclude: None,
+            system: None,
+            dat_name_rewrite: None,
             dat_description_regex: None,
             dat_description_regex_exclude: None,
             dat_combine: false,
@@ -812,24 +1284,37 @@ mod tests {
             patch_exclude: vec![],
             output: Some(PathBuf::from("out")),
             dir_mirror: false,
+            dir_mirror_root: false,
             dir_dat_mirror: false,
             dir_dat_name: false,
             dir_dat_description: false,
             dir_letter: false,
+            dir_region: false,
             dir_letter_count: None,
             dir_letter_limit: None,
             dir_letter_group: false,
+            dir_letter_ignore_articles: false,
             dir_game_subdir: DirGameSubdirMode::Multiple,
             fix_extension: FixExtensionMode::Auto,
+            normalize_extension_case: false,
+            shorten_paths: false,
             overwrite: false,
             overwrite_invalid: false,
+            on_collision: crate::types::CollisionPolicy::Skip,
+            quarantine: None,
+            no_space_check: false,
             move_delete_dirs: MoveDeleteDirsMode::Auto,
+            extract_include: None,
+            extract_exclude: None,
             clean_exclude: vec![],
             clean_backup: None,
+            clean_backup_keep: None,
             clean_dry_run: false,
             zip_format: ZipFormat::Torrentzip,
-            zip_exclude: None,
+            zip_exclude: vec![],
             zip_dat_name: false,
+            zip_comment: None,
+            to_chd: false,
             link_mode: LinkMode::Hardlink,
             symlink_relative: false,
             header: None,
@@ -844,8 +1329,12 @@ mod tests {
             filter_regex: None,
             filter_regex_exclude: None,
             filter_language: None,
+            keep_languages: None,
+            keep_top_n: None,
             filter_region: None,
             filter_category_regex: None,
+            only_genre: None,
+            only_platform: None,
             no_bios: false,
             no_device: false,
             no_unlicensed: false,
@@ -857,14 +1346,46 @@ mod tests {
             no_prototype: false,
             no_program: false,
             verbose: 0,
+            no_color: false,
             quiet: 0,
             diag: false,
+            plan_diff: false,
+            region_map: None,
+            profile: false,
+            log_style: crate::types::LogStyle::Default,
+            exec: None,
+            config_file: None,
             print_plan: false,
+            summary_only: false,
             cache_only: false,
             cache_db: None,
+            xattr_cache: false,
             hash_threads: None,
             scan_threads: None,
+            archive_threads: None,
             show_match_reasons: false,
+            min_score: None,
+            tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+            priority_input: false,
+            title_strip_regex: vec![],
+            ignore_case: true,
+            output_structure: None,
+            threads: None,
+            strict: false,
+            allow_no_matches: false,
+            dat_best_match: false,
+            dat_priority: Vec::new(),
+            dat_diff: Vec::new(),
+            fixdat_format: None,
+            hash_algorithms: None,
+            hash_checkpoint_mib: None,
+            expect_crc32: None,
+            expect_md5: None,
+            expect_sha1: None,
+            expect_sha256: None,
+            max_decompressed_size: None,
+            skip_existing_checksum: false,
+            report_duplicates: false,
             save_igdb_creds: false,
         };
 
@@ -879,13 +1400,21 @@ mod tests {
             input: vec![PathBuf::from("/tmp/file.bin")],
             input_exclude: vec![],
             input_checksum_quick: false,
+            input_checksum_auto: false,
+            scan_files_only: false,
+            scan_archives_only: false,
             input_checksum_min: Checksum::Crc32,
             input_checksum_max: None,
             input_checksum_archives: ArchiveChecksumMode::Auto,
             dat: vec![],
             dat_exclude: vec![],
+            dat_url: vec![],
             dat_name_regex: None,
             dat_name_regex_exclude: None,
+            game_name_regex: None,
+            game_name_regex_exclude: None,
+            system: None,
+            dat_name_rewrite: None,
             dat_description_regex: None,
             dat_description_regex_exclude: None,
             dat_combine: false,
@@ -900,24 +1429,37 @@ mod tests {
             patch_exclude: vec![],
             output: Some(PathBuf::from("out")),
             dir_mirror: false,
+            dir_mirror_root: false,
             dir_dat_mirror: false,
             dir_dat_name: false,
             dir_dat_description: false,
             dir_letter: false,
+            dir_region: false,
             dir_letter_count: None,
             dir_letter_limit: None,
             dir_letter_group: true,
+            dir_letter_ignore_articles: false,
             dir_game_subdir: DirGameSubdirMode::Multiple,
             fix_extension: FixExtensionMode::Auto,
+            normalize_extension_case: false,
+            shorten_paths: false,
             overwrite: false,
             overwrite_invalid: false,
+            on_collision: crate::types::CollisionPolicy::Skip,
+            quarantine: None,
+            no_space_check: false,
             move_delete_dirs: MoveDeleteDirsMode::Auto,
+            extract_include: None,
+            extract_exclude: None,
             clean_exclude: vec![],
             clean_backup: None,
+            clean_backup_keep: None,
             clean_dry_run: false,
             zip_format: ZipFormat::Torrentzip,
-            zip_exclude: None,
+            zip_exclude: vec![],
             zip_dat_name: false,
+            zip_comment: None,
+            to_chd: false,
             link_mode: LinkMode::Hardlink,
             symlink_relative: false,
             header: None,
@@ -932,8 +1474,12 @@ mod tests {
             filter_regex: None,
             filter_regex_exclude: None,
             filter_language: None,
+            keep_languages: None,
+            keep_top_n: None,
             filter_region: None,
             filter_category_regex: None,
+            only_genre: None,
+            only_platform: None,
             no_bios: false,
             no_device: false,
             no_unlicensed: false,
@@ -945,14 +1491,46 @@ mod tests {
             no_prototype: false,
             no_program: false,
             verbose: 0,
+            no_color: false,
             quiet: 0,
             diag: false,
+            plan_diff: false,
+            region_map: None,
+            profile: false,
+            log_style: crate::types::LogStyle::Default,
+            exec: None,
+            config_file: None,
             print_plan: false,
+            summary_only: false,
             cache_only: false,
             cache_db: None,
+            xattr_cache: false,
             hash_threads: None,
             scan_threads: None,
+            archive_threads: None,
             show_match_reasons: false,
+            min_score: None,
+            tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+            priority_input: false,
+            title_strip_regex: vec![],
+            ignore_case: true,
+            output_structure: None,
+            threads: None,
+            strict: false,
+            allow_no_matches: false,
+            dat_best_match: false,
+            dat_priority: Vec::new(),
+            dat_diff: Vec::new(),
+            fixdat_format: None,
+            hash_algorithms: None,
+            hash_checkpoint_mib: None,
+            expect_crc32: None,
+            expect_md5: None,
+            expect_sha1: None,
+            expect_sha256: None,
+            max_decompressed_size: None,
+            skip_existing_checksum: false,
+            report_duplicates: false,
             save_igdb_creds: false,
         };
 
@@ -967,13 +1545,21 @@ mod tests {
             input: vec![PathBuf::from("/tmp/file.bin")],
             input_exclude: vec![],
             input_checksum_quick: false,
+            input_checksum_auto: false,
+            scan_files_only: false,
+            scan_archives_only: false,
             input_checksum_min: Checksum::Crc32,
             input_checksum_max: None,
             input_checksum_archives: ArchiveChecksumMode::Auto,
             dat: vec![],
             dat_exclude: vec![],
+            dat_url: vec![],
             dat_name_regex: None,
             dat_name_regex_exclude: None,
+            game_name_regex: None,
+            game_name_regex_exclude: None,
+            system: None,
+            dat_name_rewrite: None,
             dat_description_regex: None,
             dat_description_regex_exclude: None,
             dat_combine: false,
@@ -988,24 +1574,37 @@ mod tests {
             patch_exclude: vec![],
             output: Some(PathBuf::from("out")),
             dir_mirror: false,
+            dir_mirror_root: false,
             dir_dat_mirror: false,
             dir_dat_name: false,
             dir_dat_description: false,
             dir_letter: true,
+            dir_region: false,
             dir_letter_count: None,
             dir_letter_limit: Some(0),
             dir_letter_group: false,
+            dir_letter_ignore_articles: false,
             dir_game_subdir: DirGameSubdirMode::Multiple,
             fix_extension: FixExtensionMode::Auto,
+            normalize_extension_case: false,
+            shorten_paths: false,
             overwrite: false,
             overwrite_invalid: false,
+            on_collision: crate::types::CollisionPolicy::Skip,
+            quarantine: None,
+            no_space_check: false,
             move_delete_dirs: MoveDeleteDirsMode::Auto,
+            extract_include: None,
+            extract_exclude: None,
             clean_exclude: vec![],
             clean_backup: None,
+            clean_backup_keep: None,
             clean_dry_run: false,
             zip_format: ZipFormat::Torrentzip,
-            zip_exclude: None,
+            zip_exclude: vec![],
             zip_dat_name: false,
+            zip_comment: None,
+            to_chd: false,
             link_mode: LinkMode::Hardlink,
             symlink_relative: false,
             header: None,
@@ -1020,8 +1619,12 @@ mod tests {
             filter_regex: None,
             filter_regex_exclude: None,
             filter_language: None,
+            keep_languages: None,
+            keep_top_n: None,
             filter_region: None,
             filter_category_regex: None,
+            only_genre: None,
+            only_platform: None,
             no_bios: false,
             no_device: false,
             no_unlicensed: false,
@@ -1033,14 +1636,46 @@ mod tests {
             no_prototype: false,
             no_program: false,
             verbose: 0,
+            no_color: false,
             quiet: 0,
             diag: false,
+            plan_diff: false,
+            region_map: None,
+            profile: false,
+            log_style: crate::types::LogStyle::Default,
+            exec: None,
+            config_file: None,
             print_plan: false,
+            summary_only: false,
             cache_only: false,
             cache_db: None,
+            xattr_cache: false,
             hash_threads: None,
             scan_threads: None,
+            archive_threads: None,
             show_match_reasons: false,
+            min_score: None,
+            tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+            priority_input: false,
+            title_strip_regex: vec![],
+            ignore_case: true,
+            output_structure: None,
+            threads: None,
+            strict: false,
+            allow_no_matches: false,
+            dat_best_match: false,
+            dat_priority: Vec::new(),
+            dat_diff: Vec::new(),
+            fixdat_format: None,
+            hash_algorithms: None,
+            hash_checkpoint_mib: None,
+            expect_crc32: None,
+            expect_md5: None,
+            expect_sha1: None,
+            expect_sha256: None,
+            max_decompressed_size: None,
+            skip_existing_checksum: false,
+            report_duplicates: false,
             save_igdb_creds: false,
         };
 
@@ -1055,13 +1690,21 @@ mod tests {
             input: vec![PathBuf::from("/tmp/file.bin")],
             input_exclude: vec![],
             input_checksum_quick: false,
+            input_checksum_auto: false,
+            scan_files_only: false,
+            scan_archives_only: false,
             input_checksum_min: Checksum::Crc32,
             input_checksum_max: None,
             input_checksum_archives: ArchiveChecksumMode::Auto,
             dat: vec![],
             dat_exclude: vec![],
+            dat_url: vec![],
             dat_name_regex: None,
             dat_name_regex_exclude: None,
+            game_name_regex: None,
+            game_name_regex_exclude: None,
+            system: None,
+            dat_name_rewrite: None,
             dat_description_regex: None,
             dat_description_regex_exclude: None,
             dat_combine: false,
@@ -1076,24 +1719,37 @@ mod tests {
             patch_exclude: vec![],
             output: Some(PathBuf::from("out")),
             dir_mirror: false,
+            dir_mirror_root: false,
             dir_dat_mirror: false,
             dir_dat_name: false,
             dir_dat_description: false,
             dir_letter: true,
+            dir_region: false,
             dir_letter_count: None,
             dir_letter_limit: None,
             dir_letter_group: false,
+            dir_letter_ignore_articles: false,
             dir_game_subdir: DirGameSubdirMode::Multiple,
             fix_extension: FixExtensionMode::Auto,
+            normalize_extension_case: false,
+            shorten_paths: false,
             overwrite: false,
             overwrite_invalid: false,
+            on_collision: crate::types::CollisionPolicy::Skip,
+            quarantine: None,
+            no_space_check: false,
             move_delete_dirs: MoveDeleteDirsMode::Auto,
+            extract_include: None,
+            extract_exclude: None,
             clean_exclude: vec![],
             clean_backup: None,
+            clean_backup_keep: None,
             clean_dry_run: false,
             zip_format: ZipFormat::Torrentzip,
-            zip_exclude: None,
+            zip_exclude: vec![],
             zip_dat_name: false,
+            zip_comment: None,
+            to_chd: false,
             link_mode: LinkMode::Hardlink,
             symlink_relative: false,
             header: None,
@@ -1108,8 +1764,12 @@ mod tests {
             filter_regex: None,
             filter_regex_exclude: None,
             filter_language: None,
+            keep_languages: None,
+            keep_top_n: None,
             filter_region: None,
             filter_category_regex: None,
+            only_genre: None,
+            only_platform: None,
             no_bios: false,
             no_device: false,
             no_unlicensed: false,
@@ -1121,14 +1781,46 @@ mod tests {
             no_prototype: false,
             no_program: false,
             verbose: 0,
+            no_color: false,
             quiet: 0,
             diag: false,
+            plan_diff: false,
+            region_map: None,
+            profile: false,
+            log_style: crate::types::LogStyle::Default,
+            exec: None,
+            config_file: None,
             print_plan: false,
+            summary_only: false,
             cache_only: false,
             cache_db: None,
+            xattr_cache: false,
             hash_threads: None,
             scan_threads: None,
+            archive_threads: None,
             show_match_reasons: false,
+            min_score: None,
+            tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+            priority_input: false,
+            title_strip_regex: vec![],
+            ignore_case: true,
+            output_structure: None,
+            threads: None,
+            strict: false,
+            allow_no_matches: false,
+            dat_best_match: false,
+            dat_priority: Vec::new(),
+            dat_diff: Vec::new(),
+            fixdat_format: None,
+            hash_algorithms: None,
+            hash_checkpoint_mib: None,
+            expect_crc32: None,
+            expect_md5: None,
+            expect_sha1: None,
+            expect_sha256: None,
+            max_decompressed_size: None,
+            skip_existing_checksum: false,
+            report_duplicates: false,
             save_igdb_creds: false,
         };
 
@@ -1143,13 +1835,21 @@ mod tests {
             input: vec![PathBuf::from("/tmp/file.bin")],
             input_exclude: vec![],
             input_checksum_quick: false,
+            input_checksum_auto: false,
+            scan_files_only: false,
+            scan_archives_only: false,
             input_checksum_min: Checksum::Crc32,
             input_checksum_max: None,
             input_checksum_archives: ArchiveChecksumMode::Auto,
             dat: vec![],
             dat_exclude: vec![],
+            dat_url: vec![],
             dat_name_regex: None,
             dat_name_regex_exclude: None,
+            game_name_regex: None,
+            game_name_regex_exclude: None,
+            system: None,
+            dat_name_rewrite: None,
             dat_description_regex: None,
             dat_description_regex_exclude: None,
             dat_combine: false,
@@ -1164,24 +1864,37 @@ mod tests {
             patch_exclude: vec![],
             output: None,
             dir_mirror: false,
+            dir_mirror_root: false,
             dir_dat_mirror: false,
             dir_dat_name: false,
             dir_dat_description: false,
             dir_letter: false,
+            dir_region: false,
             dir_letter_count: None,
             dir_letter_limit: None,
             dir_letter_group: false,
+            dir_letter_ignore_articles: false,
             dir_game_subdir: DirGameSubdirMode::Multiple,
             fix_extension: FixExtensionMode::Auto,
+            normalize_extension_case: false,
+            shorten_paths: false,
             overwrite: false,
             overwrite_invalid: false,
+            on_collision: crate::types::CollisionPolicy::Skip,
+            quarantine: None,
+            no_space_check: false,
             move_delete_dirs: MoveDeleteDirsMode::Auto,
+            extract_include: None,
+            extract_exclude: None,
             clean_exclude: vec![],
             clean_backup: None,
+            clean_backup_keep: None,
             clean_dry_run: false,
             zip_format: ZipFormat::Torrentzip,
-            zip_exclude: None,
+            zip_exclude: vec![],
             zip_dat_name: false,
+            zip_comment: None,
+            to_chd: false,
             link_mode: LinkMode::Hardlink,
             symlink_relative: false,
             header: None,
@@ -1196,8 +1909,12 @@ mod tests {
             filter_regex: None,
             filter_regex_exclude: None,
             filter_language: None,
+            keep_languages: None,
+            keep_top_n: None,
             filter_region: None,
             filter_category_regex: None,
+            only_genre: None,
+            only_platform: None,
             no_bios: false,
             no_device: false,
             no_unlicensed: false,
@@ -1209,14 +1926,46 @@ mod tests {
             no_prototype: false,
             no_program: false,
             verbose: 0,
+            no_color: false,
             quiet: 0,
             diag: false,
+            plan_diff: false,
+            region_map: None,
+            profile: false,
+            log_style: crate::types::LogStyle::Default,
+            exec: None,
+            config_file: None,
             print_plan: false,
+            summary_only: false,
             cache_only: false,
             cache_db: None,
+            xattr_cache: false,
             hash_threads: None,
             scan_threads: None,
+            archive_threads: None,
             show_match_reasons: false,
+            min_score: None,
+            tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+            priority_input: false,
+            title_strip_regex: vec![],
+            ignore_case: true,
+            output_structure: None,
+            threads: None,
+            strict: false,
+            allow_no_matches: false,
+            dat_best_match: false,
+            dat_priority: Vec::new(),
+            dat_diff: Vec::new(),
+            fixdat_format: None,
+            hash_algorithms: None,
+            hash_checkpoint_mib: None,
+            expect_crc32: None,
+            expect_md5: None,
+            expect_sha1: None,
+            expect_sha256: None,
+            max_decompressed_size: None,
+            skip_existing_checksum: false,
+            report_duplicates: false,
             save_igdb_creds: false,
         };
 
@@ -1232,13 +1981,21 @@ mod tests {
                 input: vec![PathBuf::from("/tmp/file.bin")],
                 input_exclude: vec![],
                 input_checksum_quick: false,
+                input_checksum_auto: false,
+                scan_files_only: false,
+                scan_archives_only: false,
                 input_checksum_min: Checksum::Crc32,
                 input_checksum_max: None,
                 input_checksum_archives: ArchiveChecksumMode::Auto,
                 dat: vec![],
                 dat_exclude: vec![],
+                dat_url: vec![],
                 dat_name_regex: None,
                 dat_name_regex_exclude: None,
+                game_name_regex: None,
+                game_name_regex_exclude: None,
+                system: None,
+                dat_name_rewrite: None,
                 dat_description_regex: None,
                 dat_description_regex_exclude: None,
                 dat_combine: false,
@@ -1253,24 +2010,37 @@ mod tests {
                 patch_exclude: vec![],
                 output: None,
                 dir_mirror: false,
+                dir_mirror_root: false,
                 dir_dat_mirror: false,
                 dir_dat_name: false,
                 dir_dat_description: false,
                 dir_letter: false,
+                dir_region: false,
                 dir_letter_count: None,
                 dir_letter_limit: None,
                 dir_letter_group: false,
+                dir_letter_ignore_articles: false,
                 dir_game_subdir: DirGameSubdirMode::Multiple,
                 fix_extension: FixExtensionMode::Auto,
+                normalize_extension_case: false,
+                shorten_paths: false,
                 overwrite: false,
                 overwrite_invalid: false,
+                on_collision: crate::types::CollisionPolicy::Skip,
+                quarantine: None,
+                no_space_check: false,
                 move_delete_dirs: MoveDeleteDirsMode::Auto,
+                extract_include: None,
+                extract_exclude: None,
                 clean_exclude: vec![],
                 clean_backup: None,
+                clean_backup_keep: None,
                 clean_dry_run: false,
                 zip_format: ZipFormat::Torrentzip,
-                zip_exclude: None,
+                zip_exclude: vec![],
                 zip_dat_name: false,
+                zip_comment: None,
+                to_chd: false,
                 link_mode: LinkMode::Hardlink,
                 symlink_relative: false,
                 header: None,
@@ -1285,8 +2055,12 @@ mod tests {
                 filter_regex: None,
                 filter_regex_exclude: None,
                 filter_language: None,
+                keep_languages: None,
+                keep_top_n: None,
                 filter_region: None,
                 filter_category_regex: None,
+                only_genre: None,
+                only_platform: None,
                 no_bios: false,
                 no_device: false,
                 no_unlicensed: false,
@@ -1298,15 +2072,47 @@ mod tests {
                 no_prototype: false,
                 no_program: false,
                 verbose: 0,
+                no_color: false,
                 quiet: 0,
                 diag: false,
+                plan_diff: false,
+                region_map: None,
+                profile: false,
+                log_style: crate::types::LogStyle::Default,
+                exec: None,
+                config_file: None,
                 cache_only: false,
                 cache_db: None,
+                xattr_cache: false,
                 hash_threads: None,
                 scan_threads: None,
+                archive_threads: None,
                 show_match_reasons: false,
+                min_score: None,
+                tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+                priority_input: false,
+                title_strip_regex: vec![],
+                ignore_case: true,
+                output_structure: None,
+                threads: None,
+                strict: false,
+                allow_no_matches: false,
+                dat_best_match: false,
+                dat_priority: Vec::new(),
+                dat_diff: Vec::new(),
+                fixdat_format: None,
+                hash_algorithms: None,
+                hash_checkpoint_mib: None,
+                expect_crc32: None,
+                expect_md5: None,
+                expect_sha1: None,
+                expect_sha256: None,
+                max_decompressed_size: None,
+                skip_existing_checksum: false,
+                report_duplicates: false,
                 save_igdb_creds: false,
                 print_plan,
+                summary_only: false,
             }
         }
 
@@ -1317,6 +2123,49 @@ mod tests {
         assert!(opt_in_cfg.print_plan);
     }
 
+    #[test]
+    fn threads_sets_both_pools_unless_overridden() {
+        let mut cli = make_cli(false);
+        cli.threads = Some(1);
+        let config = Config::try_from(cli).expect("config");
+        assert_eq!(config.hash_threads, Some(1));
+        assert_eq!(config.scan_threads, Some(1));
+        assert_eq!(config.global_thread_count(), 1);
+
+        let mut cli = make_cli(false);
+        cli.threads = Some(2);
+        cli.scan_threads = Some(4);
+        let config = Config::try_from(cli).expect("config");
+        assert_eq!(config.hash_threads, Some(2));
+        assert_eq!(config.scan_threads, Some(4));
+        assert_eq!(config.global_thread_count(), 4);
+
+        let mut cli = make_cli(false);
+        cli.threads = Some(0);
+        assert!(Config::try_from(cli).is_err());
+    }
+
+    #[test]
+    fn zip_comment_is_rejected_for_torrentzip() {
+        let mut cli = make_cli(false);
+        cli.zip_comment = Some("from my collection".to_string());
+        assert!(Config::try_from(cli).is_err());
+
+        let mut cli = make_cli(false);
+        cli.zip_comment = Some("from my collection".to_string());
+        cli.zip_format = ZipFormat::Deflate;
+        let config = Config::try_from(cli).expect("config");
+        assert_eq!(config.zip_comment.as_deref(), Some("from my collection"));
+    }
+
+    #[test]
+    fn scan_files_only_and_scan_archives_only_conflict() {
+        let mut cli = make_cli(false);
+        cli.scan_files_only = true;
+        cli.scan_archives_only = true;
+        assert!(Config::try_from(cli).is_err());
+    }
+
     #[test]
     fn errors_when_hash_threads_zero() {
         let mut cli = make_cli(false);
@@ -1324,4 +2173,115 @@ mod tests {
         let result = Config::try_from(cli);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn config_file_values_are_overridden_by_command_line_flags() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("igir.json");
+        std::fs::write(
+            &path,
+            r#"{
+                "commands": ["copy"],
+                "input": ["/roms/in"],
+                "output": "/roms/from-file",
+                "dat_name_regex": "Nintendo",
+                "overwrite": true,
+                "verbose": 2
+            }"#,
+        )
+        .unwrap();
+
+        let cli = Cli::parse_from_with_config_file([
+            "igir".as_ref(),
+            "--config".as_ref(),
+            path.as_os_str(),
+            "--output".as_ref(),
+            std::ffi::OsStr::new("/roms/from-cli"),
+        ])
+        .expect("cli");
+        let config = Config::try_from(cli).expect("config");
+
+        assert_eq!(config.commands, vec![Action::Copy]);
+        assert_eq!(config.input, vec![PathBuf::from("/roms/in")]);
+        assert_eq!(config.output, Some(PathBuf::from("/roms/from-cli")));
+        assert_eq!(config.dat_name_regex.as_deref(), Some("Nintendo"));
+        assert!(config.overwrite);
+        assert_eq!(config.verbose, 2);
+    }
+
+    #[test]
+    fn toml_config_file_is_read_by_its_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("igir.toml");
+        std::fs::write(
+            &path,
+            r#"
+commands = ["copy"]
+input = ["/roms/in"]
+output = "/roms/from-file"
+dat_name_regex = "Nintendo"
+overwrite = true
+verbose = 2
+"#,
+        )
+        .unwrap();
+
+        let cli = Cli::parse_from_with_config_file([
+            "igir".as_ref(),
+            "--config".as_ref(),
+            path.as_os_str(),
+            "--output".as_ref(),
+            std::ffi::OsStr::new("/roms/from-cli"),
+        ])
+        .expect("cli");
+        let config = Config::try_from(cli).expect("config");
+
+        assert_eq!(config.commands, vec![Action::Copy]);
+        assert_eq!(config.input, vec![PathBuf::from("/roms/in")]);
+        assert_eq!(config.output, Some(PathBuf::from("/roms/from-cli")));
+        assert_eq!(config.dat_name_regex.as_deref(), Some("Nintendo"));
+        assert!(config.overwrite);
+        assert_eq!(config.verbose, 2);
+    }
+
+    #[test]
+    fn dat_ignore_parent_clone_rejects_merge_modes_that_need_parents() {
+        for mode in [MergeMode::Merged, MergeMode::Split] {
+            let mut cli = make_cli(false);
+            cli.dat_ignore_parent_clone = true;
+            cli.merge_roms = mode;
+            let err = Config::try_from(cli).unwrap_err().to_string();
+            assert!(err.contains("--dat-ignore-parent-clone"), "{err}");
+        }
+
+        for mode in [MergeMode::Fullnonmerged, MergeMode::Nonmerged] {
+            let mut cli = make_cli(false);
+            cli.dat_ignore_parent_clone = true;
+            cli.merge_roms = mode;
+            assert!(Config::try_from(cli).is_ok());
+        }
+    }
+
+    #[test]
+    fn hash_algorithms_conflict_with_an_explicit_checksum_range() {
+        let mut cli = make_cli(false);
+        cli.hash_algorithms = Some("crc32,sha256".to_string());
+        cli.input_checksum_max = Some(Checksum::Sha1);
+        let err = Config::try_from(cli).unwrap_err().to_string();
+        assert!(err.contains("--hash-algorithms"), "{err}");
+
+        let mut cli = make_cli(false);
+        cli.hash_algorithms = Some("sha1".to_string());
+        cli.input_checksum_min = Checksum::Md5;
+        assert!(Config::try_from(cli).is_err());
+
+        let mut cli = make_cli(false);
+        cli.hash_algorithms = Some("crc32,sha256".to_string());
+        assert!(Config::try_from(cli).is_ok());
+
+        let mut cli = make_cli(false);
+        cli.input_checksum_min = Checksum::Md5;
+        cli.input_checksum_max = Some(Checksum::Sha256);
+        assert!(Config::try_from(cli).is_ok());
+    }
 }
@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::PathBuf;
 
 use anyhow::Context;
@@ -13,10 +13,12 @@ use std::thread::sleep;
 use std::time::{Duration, Instant};
 
 use crate::cache;
+use crate::checksum::checksum_targets;
 use crate::config::Config;
 use crate::progress::ProgressReporter;
 use crate::records::collect_files;
-use crate::types::FileRecord;
+use crate::types::{Checksum, FileRecord};
+use clap::ValueEnum;
 use rayon::prelude::*;
 use std::sync::mpsc;
 
@@ -127,6 +129,139 @@ pub struct DatRom {
     pub sha256: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub match_reasons: Option<Vec<String>>,
+    /// Game serial (e.g. `SLUS-00594`) as listed by Redump-style DATs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serial: Option<String>,
+    /// Game version (e.g. `1.1`) as listed by Redump-style DATs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Game region (e.g. `USA`) as listed by DATs with a `<region>` element.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Additional checksum variants (e.g. TOSEC headered and headerless hashes);
+    /// the fields above hold the first variant.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub alternates: Vec<DatRomVariant>,
+    /// Name of the DAT after `--dat-name-rewrite`; unset means the DAT file stem.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dat_name: Option<String>,
+    /// Description from the DAT header, used by `--dir-dat-description`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dat_description: Option<String>,
+    /// Parent/clone details from Logiqx/MAME DATs, used by `--merge-roms`.
+    #[serde(skip)]
+    pub set_info: Option<DatSetInfo>,
+}
+
+/// Where a DAT entry sits in a parent/clone family.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DatSetInfo {
+    /// The game's `name` attribute (`description` may hold its `<description>` instead).
+    pub game: String,
+    /// The game's `cloneof` parent.
+    pub parent: Option<String>,
+    /// The entry's `merge` attribute: the name it has in the parent set.
+    pub merge: Option<String>,
+    /// A `<disk>` (CHD) entry rather than a `<rom>`.
+    pub disk: bool,
+}
+
+/// Most numbered checksum keys (`crc2`, `crc3`, ...) read for one ROM.
+const MAX_ROM_VARIANTS: usize = 16;
+
+/// One size/checksum combination a DAT lists for a ROM.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct DatRomVariant {
+    pub size: Option<u64>,
+    pub crc32: Option<String>,
+    pub md5: Option<String>,
+    pub sha1: Option<String>,
+    pub sha256: Option<String>,
+}
+
+impl DatRom {
+    /// Name of the DAT this entry came from: the `--dat-name-rewrite` result when
+    /// set, otherwise the DAT file stem.
+    pub fn source_dat_name(&self) -> String {
+        self.dat_name.clone().unwrap_or_else(|| {
+            self.source_dat
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default()
+        })
+    }
+
+    /// The primary checksums followed by any alternates. Alternates that omit a
+    /// size inherit the primary size.
+    pub fn variants(&self) -> Vec<DatRomVariant> {
+        let primary = DatRomVariant {
+            size: self.size,
+            crc32: self.crc32.clone(),
+            md5: self.md5.clone(),
+            sha1: self.sha1.clone(),
+            sha256: self.sha256.clone(),
+        };
+        let mut out = vec![primary];
+        out.extend(self.alternates.iter().map(|alt| DatRomVariant {
+            size: alt.size.or(self.size),
+            ..alt.clone()
+        }));
+        out
+    }
+
+    /// Record a `size`/`crc`/`md5`/`sha1`/`sha256` attribute. Repeated keys
+    /// (`crc="..." crc="..."`) and numbered keys (`crc2="..."`) fill
+    /// successive variants so each hash stays paired with its own size.
+    fn set_rom_attribute(&mut self, key: &str, value: &str, seen: &mut HashMap<String, usize>) {
+        let key = key.to_ascii_lowercase();
+        // `crc32` is the spelled-out `crc`, not its 32nd variant.
+        let key = if key == "crc32" { "crc" } else { key.as_str() };
+        let Some((base, suffix)) = ["sha256", "sha1", "md5", "crc", "size"]
+            .into_iter()
+            .find_map(|base| key.strip_prefix(base).map(|suffix| (base, suffix)))
+            .filter(|(_, suffix)| suffix.bytes().all(|b| b.is_ascii_digit()))
+        else {
+            return;
+        };
+        let slot = if suffix.is_empty() {
+            let count = seen.entry(base.to_string()).or_default();
+            *count += 1;
+            *count - 1
+        } else {
+            match suffix.parse::<usize>() {
+                Ok(n @ 1..=MAX_ROM_VARIANTS) => n - 1,
+                _ => return,
+            }
+        };
+        let (size, crc32, md5, sha1, sha256) = if slot == 0 {
+            (
+                &mut self.size,
+                &mut self.crc32,
+                &mut self.md5,
+                &mut self.sha1,
+                &mut self.sha256,
+            )
+        } else {
+            if self.alternates.len() < slot {
+                self.alternates.resize_with(slot, DatRomVariant::default);
+            }
+            let alt = &mut self.alternates[slot - 1];
+            (
+                &mut alt.size,
+                &mut alt.crc32,
+                &mut alt.md5,
+                &mut alt.sha1,
+                &mut alt.sha256,
+            )
+        };
+        match base {
+            "size" => *size = value.parse().ok(),
+            "crc" => *crc32 = Some(value.to_ascii_uppercase()),
+            "md5" => *md5 = Some(value.to_ascii_lowercase()),
+            "sha1" => *sha1 = Some(value.to_ascii_lowercase()),
+            _ => *sha256 = Some(value.to_ascii_lowercase()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -173,6 +308,9 @@ pub fn load_dat_roms(
 
     let mut resolved: Vec<std::path::PathBuf> = Vec::new();
     let mut missing_explicit: Vec<std::path::PathBuf> = Vec::new();
+    for url in &config.dat_url {
+        resolved.push(fetch_remote_dat(url, config)?);
+    }
     for dat_arg in &config.dat {
         let s = dat_arg.to_string_lossy();
         // If the path exists as given, use it (expanding directories)
@@ -267,124 +405,7 @@ pub fn load_dat_roms(
             jobs_clone
                 .par_iter()
                 .for_each_with(tx.clone(), |s, dat_path| {
-                    let res: anyhow::Result<(PathBuf, Vec<DatRom>)> = (|| {
-                        let mut reader = Reader::from_file(dat_path).with_context(|| {
-                            format!("unable to open DAT file: {}", dat_path.to_string_lossy())
-                        })?;
-                        reader.config_mut().trim_text(true);
-                        let mut buf = Vec::new();
-
-                        let mut roms_local: Vec<DatRom> = Vec::new();
-                        let mut current_description: Option<String> = None;
-                        let mut in_description = false;
-
-                        loop {
-                            match reader.read_event_into(&mut buf) {
-                                Ok(Event::Start(ref e))
-                                    if e.name().as_ref() == b"game"
-                                        || e.name().as_ref() == b"machine" =>
-                                {
-                                    current_description = e
-                                        .attributes()
-                                        .filter_map(Result::ok)
-                                        .find(|a| a.key.as_ref() == b"name")
-                                        .and_then(|a| String::from_utf8(a.value.into_owned()).ok());
-                                }
-                                Ok(Event::Start(ref e)) if e.name().as_ref() == b"description" => {
-                                    in_description = true;
-                                }
-                                Ok(Event::Text(e)) if in_description => {
-                                    current_description =
-                                        Some(e.unescape().unwrap_or_default().to_string());
-                                    in_description = false;
-                                }
-                                Ok(Event::Empty(ref e)) if e.name().as_ref() == b"rom" => {
-                                    let mut rom = DatRom {
-                                        name: String::new(),
-                                        description: current_description.clone(),
-                                        source_dat: dat_path.clone(),
-                                        size: None,
-                                        crc32: None,
-                                        md5: None,
-                                        sha1: None,
-                                        sha256: None,
-                                        match_reasons: None,
-                                    };
-
-                                    for attr in e.attributes().flatten() {
-                                        let key = attr.key.as_ref();
-                                        let value =
-                                            String::from_utf8_lossy(&attr.value).to_string();
-                                        match key {
-                                            b"name" | b"NAME" => rom.name = value,
-                                            b"size" | b"SIZE" => rom.size = value.parse().ok(),
-                                            b"crc" | b"CRC" => {
-                                                rom.crc32 = Some(value.to_ascii_uppercase())
-                                            }
-                                            b"md5" | b"MD5" => {
-                                                rom.md5 = Some(value.to_ascii_lowercase())
-                                            }
-                                            b"sha1" | b"SHA1" => {
-                                                rom.sha1 = Some(value.to_ascii_lowercase())
-                                            }
-                                            b"sha256" | b"SHA256" => {
-                                                rom.sha256 = Some(value.to_ascii_lowercase())
-                                            }
-                                            _ => {}
-                                        }
-                                    }
-
-                                    rom.match_reasons = None;
-                                    roms_local.push(rom);
-                                }
-                                // Also accept <rom ...>start</rom> style elements where attributes are on Start
-                                Ok(Event::Start(ref e)) if e.name().as_ref() == b"rom" => {
-                                    let mut rom = DatRom {
-                                        name: String::new(),
-                                        description: current_description.clone(),
-                                        source_dat: dat_path.clone(),
-                                        size: None,
-                                        crc32: None,
-                                        md5: None,
-                                        sha1: None,
-                                        sha256: None,
-                                        match_reasons: None,
-                                    };
-
-                                    for attr in e.attributes().flatten() {
-                                        let key = attr.key.as_ref();
-                                        let value =
-                                            String::from_utf8_lossy(&attr.value).to_string();
-                                        match key {
-                                            b"name" | b"NAME" => rom.name = value,
-                                            b"size" | b"SIZE" => rom.size = value.parse().ok(),
-                                            b"crc" | b"CRC" => {
-                                                rom.crc32 = Some(value.to_ascii_uppercase())
-                                            }
-                                            b"md5" | b"MD5" => {
-                                                rom.md5 = Some(value.to_ascii_lowercase())
-                                            }
-                                            b"sha1" | b"SHA1" => {
-                                                rom.sha1 = Some(value.to_ascii_lowercase())
-                                            }
-                                            b"sha256" | b"SHA256" => {
-                                                rom.sha256 = Some(value.to_ascii_lowercase())
-                                            }
-                                            _ => {}
-                                        }
-                                    }
-
-                                    rom.match_reasons = None;
-                                    roms_local.push(rom);
-                                }
-                                Ok(Event::Eof) => break,
-                                _ => {}
-                            }
-                            buf.clear();
-                        }
-
-                        Ok((dat_path.clone(), roms_local))
-                    })();
+                    let res = parse_dat_file(dat_path).map(|roms| (dat_path.clone(), roms));
                     let _ = s.send(res);
                 });
         });
@@ -392,12 +413,12 @@ pub fn load_dat_roms(
         // Receive parsed DAT results and update progress on the main thread as each DAT completes
         for _ in 0..job_count {
             match rx.recv() {
-                Ok(Ok((dat_path, mut parsed))) => {
+                Ok(Ok((dat_path, parsed))) => {
                     parsed_count += 1;
                     if let Some(p) = progress {
                         p.advance_dat_loading(parsed_count, Some(&dat_path));
                     }
-                    roms.append(&mut parsed);
+                    roms.extend(crate::merge::merge_sets(parsed, &config.merge_roms));
                 }
                 Ok(Err(e)) => {
                     // worker signalled an error parsing a DAT
@@ -420,10 +441,796 @@ pub fn load_dat_roms(
         p.finish_dat_loading(parsed_count);
     }
 
+    let name_regex = |flag: &str, pattern: Option<&str>| {
+        pattern
+            .map(|p| regex::Regex::new(p).with_context(|| format!("invalid {flag} {p:?}")))
+            .transpose()
+    };
+    let include = name_regex("--dat-name-regex", config.dat_name_regex.as_deref())?;
+    let exclude = name_regex(
+        "--dat-name-regex-exclude",
+        config.dat_name_regex_exclude.as_deref(),
+    )?;
+    let system = config.system.as_deref().map(system_name_regex);
+    if include.is_some() || exclude.is_some() || system.is_some() {
+        roms.retain(|rom| {
+            let name = rom.source_dat_name();
+            include.as_ref().is_none_or(|re| re.is_match(&name))
+                && system.as_ref().is_none_or(|re| re.is_match(&name))
+                && !exclude.as_ref().is_some_and(|re| re.is_match(&name))
+        });
+    }
+
+    let game_include = name_regex("--game-name-regex", config.game_name_regex.as_deref())?;
+    let game_exclude = name_regex(
+        "--game-name-regex-exclude",
+        config.game_name_regex_exclude.as_deref(),
+    )?;
+    if game_include.is_some() || game_exclude.is_some() {
+        roms.retain(|rom| {
+            let game = rom.description.as_deref().unwrap_or(&rom.name);
+            game_include.as_ref().is_none_or(|re| re.is_match(game))
+                && !game_exclude.as_ref().is_some_and(|re| re.is_match(game))
+        });
+    }
+
+    if let Some(rule) = config.dat_name_rewrite.as_deref() {
+        let (pattern, replacement) = parse_dat_name_rewrite(rule)?;
+        for rom in &mut roms {
+            let name = rom.source_dat_name();
+            if pattern.is_match(&name) {
+                rom.dat_name = Some(pattern.replace(&name, replacement.as_str()).into_owned());
+            }
+        }
+    }
+
     Ok(roms)
 }
 
+/// `--system` aliases and the system name No-Intro/Redump DAT names use for them.
+const SYSTEM_ALIASES: &[(&str, &str)] = &[
+    ("gb", "Game Boy"),
+    ("gbc", "Game Boy Color"),
+    ("gba", "Game Boy Advance"),
+    ("nes", "Nintendo Entertainment System"),
+    ("fds", "Family Computer Disk System"),
+    ("snes", "Super Nintendo Entertainment System"),
+    ("n64", "Nintendo 64"),
+    ("nds", "Nintendo DS"),
+    ("3ds", "Nintendo 3DS"),
+    ("vb", "Virtual Boy"),
+    ("sms", "Master System - Mark III"),
+    ("gg", "Game Gear"),
+    ("md", "Mega Drive - Genesis"),
+    ("genesis", "Mega Drive - Genesis"),
+    ("32x", "32X"),
+    ("psx", "PlayStation"),
+    ("ps1", "PlayStation"),
+    ("ps2", "PlayStation 2"),
+    ("psp", "PlayStation Portable"),
+    ("pce", "PC Engine - TurboGrafx-16"),
+    ("lynx", "Lynx"),
+    ("ngp", "Neo Geo Pocket"),
+    ("ngpc", "Neo Geo Pocket Color"),
+    ("ws", "WonderSwan"),
+    ("wsc", "WonderSwan Color"),
+];
+
+/// Regex matching DAT names for `system` (an alias from [`SYSTEM_ALIASES`] or a full
+/// system name). The name must be a whole ` - `-separated segment, so `Game Boy` does not
+/// also select `Game Boy Advance` DATs.
+pub fn system_name_regex(system: &str) -> regex::Regex {
+    let name = SYSTEM_ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(system.trim()))
+        .map_or(system.trim(), |(_, name)| name);
+    let pattern = format!(r"(?i)(?:^|\s-\s){}(?:$|\s-\s|\s*\()", regex::escape(name));
+    regex::Regex::new(&pattern).expect("escaped system name is a valid regex")
+}
+
+/// Parse a `--dat-name-rewrite REGEX=REPLACEMENT` rule. The split happens at the
+/// last `=`, as with `--dat-priority`.
+pub fn parse_dat_name_rewrite(rule: &str) -> anyhow::Result<(regex::Regex, String)> {
+    let (pattern, replacement) = rule.rsplit_once('=').with_context(|| {
+        format!("--dat-name-rewrite '{rule}' must be in the form REGEX=REPLACEMENT")
+    })?;
+    let pattern = regex::Regex::new(pattern)
+        .with_context(|| format!("--dat-name-rewrite '{rule}' has an invalid regex"))?;
+    Ok((pattern, replacement.to_string()))
+}
+
+/// Download a `--dat-url` DAT into `<config dir>/dats` and return the cached path.
+/// Uses the online timeout/retry settings; when every attempt fails, a copy cached by an
+/// earlier run is used instead.
+pub fn fetch_remote_dat(url: &str, config: &Config) -> anyhow::Result<PathBuf> {
+    let name = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("remote.dat");
+    let dir = crate::config::config_dir()?.join("dats");
+    let cached = dir.join(format!("{:08x}-{name}", crc32fast::hash(url.as_bytes())));
+
+    let client = Client::builder()
+        .timeout(Duration::from_secs(config.online_timeout_secs.unwrap_or(5)))
+        .build()?;
+    let max_attempts = std::cmp::max(1, config.online_max_retries.unwrap_or(3));
+    let mut last_error = None;
+    for attempt in 0..max_attempts {
+        match client
+            .get(url)
+            .send()
+            .and_then(|resp| resp.error_for_status())
+            .and_then(|resp| resp.bytes())
+        {
+            Ok(body) => {
+                std::fs::create_dir_all(&dir)
+                    .with_context(|| format!("creating DAT cache dir {:?}", dir))?;
+                crate::utils::write_atomically(&cached, |tmp| Ok(std::fs::write(tmp, &body)?))?;
+                vprintln!(config.verbose, 1, "DAT {} -> {:?}", url, cached);
+                return Ok(cached);
+            }
+            Err(e) => {
+                vprintln!(
+                    config.verbose,
+                    1,
+                    "DAT download error for {} (attempt {}): {}",
+                    url,
+                    attempt + 1,
+                    e
+                );
+                last_error = Some(e);
+                if attempt + 1 < max_attempts {
+                    match config.online_throttle_ms {
+                        Some(ms) => sleep(Duration::from_millis(ms)),
+                        None => sleep(Duration::from_millis(250 * (1 << attempt))),
+                    }
+                }
+            }
+        }
+    }
+
+    if cached.is_file() {
+        vwarn!("could not download {url}; using cached copy {cached:?}");
+        return Ok(cached);
+    }
+    match last_error {
+        Some(e) => Err(e).with_context(|| format!("downloading DAT from {url}")),
+        None => anyhow::bail!("downloading DAT from {url} failed"),
+    }
+}
+
+/// Unwrap gzip- or zip-compressed DAT contents; from a zip the first `.dat`/`.xml`
+/// member is used, else its first file. Plain DATs are returned unchanged.
+fn decompress_dat_bytes(bytes: Vec<u8>, dat_path: &std::path::Path) -> anyhow::Result<Vec<u8>> {
+    use std::io::Read;
+
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut out = Vec::new();
+        flate2::read::MultiGzDecoder::new(bytes.as_slice())
+            .read_to_end(&mut out)
+            .with_context(|| format!("decompressing gzip DAT {:?}", dat_path))?;
+        return Ok(out);
+    }
+    if bytes.starts_with(b"PK\x03\x04") {
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))
+            .with_context(|| format!("opening zipped DAT {:?}", dat_path))?;
+        let names: Vec<String> = archive
+            .file_names()
+            .filter(|name| !name.ends_with('/'))
+            .map(str::to_string)
+            .collect();
+        let member = names
+            .iter()
+            .find(|name| {
+                let lower = name.to_ascii_lowercase();
+                lower.ends_with(".dat") || lower.ends_with(".xml")
+            })
+            .or_else(|| names.first())
+            .with_context(|| format!("zipped DAT {:?} has no files", dat_path))?;
+        let mut out = Vec::new();
+        archive.by_name(member)?.read_to_end(&mut out)?;
+        return Ok(out);
+    }
+    Ok(bytes)
+}
+
+/// Parse a single Logiqx XML or ClrMamePro DAT file (optionally gzip- or zip-compressed)
+/// into its rom entries.
+pub fn parse_dat_file(dat_path: &std::path::Path) -> anyhow::Result<Vec<DatRom>> {
+    let bytes = std::fs::read(dat_path)
+        .with_context(|| format!("unable to open DAT file: {}", dat_path.to_string_lossy()))?;
+    let bytes = decompress_dat_bytes(bytes, dat_path)?;
+    // anything that doesn't start with markup is treated as a ClrMamePro DAT
+    if bytes
+        .iter()
+        .find(|b| !b.is_ascii_whitespace())
+        .is_some_and(|b| *b != b'<')
+    {
+        let text = String::from_utf8_lossy(&bytes);
+        return Ok(parse_clrmamepro(&text, dat_path));
+    }
+    let mut reader = Reader::from_reader(bytes.as_slice());
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+
+    let mut roms_local: Vec<DatRom> = Vec::new();
+    let mut current_description: Option<String> = None;
+    let mut in_description = false;
+    // Redump DATs list <serial>/<version>/<region> per game, possibly after the roms
+    let mut current_serial: Option<String> = None;
+    let mut current_version: Option<String> = None;
+    let mut current_region: Option<String> = None;
+    let mut in_serial = false;
+    let mut in_version = false;
+    let mut in_region = false;
+    let mut game_start = 0usize;
+    let mut in_header = false;
+    let mut dat_description: Option<String> = None;
+    let mut current_game = String::new();
+    let mut current_parent: Option<String> = None;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e))
+                if e.name().as_ref() == b"game" || e.name().as_ref() == b"machine" =>
+            {
+                let attribute = |name: &[u8]| {
+                    e.attributes()
+                        .filter_map(Result::ok)
+                        .find(|a| a.key.as_ref() == name)
+                        .and_then(|a| String::from_utf8(a.value.into_owned()).ok())
+                };
+                current_description = attribute(b"name");
+                current_game = current_description.clone().unwrap_or_default();
+                current_parent = attribute(b"cloneof");
+                current_serial = None;
+                current_version = None;
+                current_region = None;
+                game_start = roms_local.len();
+            }
+            Ok(Event::End(ref e))
+                if e.name().as_ref() == b"game" || e.name().as_ref() == b"machine" =>
+            {
+                for rom in &mut roms_local[game_start..] {
+                    if rom.serial.is_none() {
+                        rom.serial = current_serial.clone();
+                    }
+                    if rom.version.is_none() {
+                        rom.version = current_version.clone();
+                    }
+                    if rom.region.is_none() {
+                        rom.region = current_region.clone();
+                    }
+                }
+                game_start = roms_local.len();
+            }
+            Ok(Event::Start(ref e)) if e.name().as_ref() == b"header" => {
+                in_header = true;
+            }
+            Ok(Event::End(ref e)) if e.name().as_ref() == b"header" => {
+                in_header = false;
+            }
+            Ok(Event::Start(ref e)) if e.name().as_ref() == b"description" => {
+                in_description = true;
+            }
+            Ok(Event::Start(ref e)) if e.name().as_ref() == b"serial" => {
+                in_serial = true;
+            }
+            Ok(Event::Start(ref e)) if e.name().as_ref() == b"version" => {
+                in_version = true;
+            }
+            Ok(Event::Start(ref e)) if e.name().as_ref() == b"region" => {
+                in_region = true;
+            }
+            Ok(Event::Text(e)) if in_description => {
+                let text = e.unescape().unwrap_or_default().to_string();
+                if in_header {
+                    dat_description = Some(text);
+                } else {
+                    current_description = Some(text);
+                }
+                in_description = false;
+            }
+            Ok(Event::Text(e)) if in_serial || in_version || in_region => {
+                let value = e.unescape().unwrap_or_default().trim().to_string();
+                let value = (!value.is_empty()).then_some(value);
+                if in_serial {
+                    current_serial = value;
+                } else if in_version {
+                    current_version = value;
+                } else {
+                    current_region = value;
+                }
+                in_serial = false;
+                in_version = false;
+                in_region = false;
+            }
+            Ok(Event::Empty(ref e))
+                if e.name().as_ref() == b"rom" || e.name().as_ref() == b"disk" =>
+            {
+                let disk = e.name().as_ref() == b"disk";
+                let mut rom = DatRom {
+                    name: String::new(),
+                    description: current_description.clone(),
+                    source_dat: dat_path.to_path_buf(),
+                    size: None,
+                    crc32: None,
+                    md5: None,
+                    sha1: None,
+                    sha256: None,
+                    match_reasons: None,
+                    serial: current_serial.clone(),
+                    version: current_version.clone(),
+                    region: current_region.clone(),
+                    alternates: Vec::new(),
+                    dat_name: None,
+                    dat_description: None,
+                    set_info: Some(DatSetInfo {
+                        game: current_game.clone(),
+                        parent: current_parent.clone(),
+                        merge: None,
+                        disk,
+                    }),
+                };
+
+                // Duplicate attributes are how some TOSEC DATs list
+                // checksum variants, so don't reject them.
+                let mut seen = HashMap::new();
+                for attr in e.attributes().with_checks(false).flatten() {
+                    let key = String::from_utf8_lossy(attr.key.as_ref());
+                    let value = String::from_utf8_lossy(&attr.value).to_string();
+                    if key.eq_ignore_ascii_case("name") {
+                        rom.name = value;
+                    } else if key.eq_ignore_ascii_case("merge") {
+                        if let Some(info) = rom.set_info.as_mut() {
+                            info.merge = Some(value);
+                        }
+                    } else {
+                        rom.set_rom_attribute(&key, &value, &mut seen);
+                    }
+                }
+                // Disks are dumped as CHDs named after the entry.
+                if disk {
+                    rom.name.push_str(".chd");
+                    if let Some(merge) = rom.set_info.as_mut().and_then(|i| i.merge.as_mut()) {
+                        merge.push_str(".chd");
+                    }
+                }
+
+                rom.match_reasons = None;
+                roms_local.push(rom);
+            }
+            // Also accept <rom ...>start</rom> style elements where attributes are on Start
+            Ok(Event::Start(ref e))
+                if e.name().as_ref() == b"rom" || e.name().as_ref() == b"disk" =>
+            {
+                let disk = e.name().as_ref() == b"disk";
+                let mut rom = DatRom {
+                    name: String::new(),
+                    description: current_description.clone(),
+                    source_dat: dat_path.to_path_buf(),
+                    size: None,
+                    crc32: None,
+                    md5: None,
+                    sha1: None,
+                    sha256: None,
+                    match_reasons: None,
+                    serial: current_serial.clone(),
+                    version: current_version.clone(),
+                    region: current_region.clone(),
+                    alternates: Vec::new(),
+                    dat_name: None,
+                    dat_description: None,
+                    set_info: Some(DatSetInfo {
+                        game: current_game.clone(),
+                        parent: current_parent.clone(),
+                        merge: None,
+                        disk,
+                    }),
+                };
+
+                // Duplicate attributes are how some TOSEC DATs list
+                // checksum variants, so don't reject them.
+                let mut seen = HashMap::new();
+                for attr in e.attributes().with_checks(false).flatten() {
+                    let key = String::from_utf8_lossy(attr.key.as_ref());
+                    let value = String::from_utf8_lossy(&attr.value).to_string();
+                    if key.eq_ignore_ascii_case("name") {
+                        rom.name = value;
+                    } else if key.eq_ignore_ascii_case("merge") {
+                        if let Some(info) = rom.set_info.as_mut() {
+                            info.merge = Some(value);
+                        }
+                    } else {
+                        rom.set_rom_attribute(&key, &value, &mut seen);
+                    }
+                }
+                // Disks are dumped as CHDs named after the entry.
+                if disk {
+                    rom.name.push_str(".chd");
+                    if let Some(merge) = rom.set_info.as_mut().and_then(|i| i.merge.as_mut()) {
+                        merge.push_str(".chd");
+                    }
+                }
+
+                rom.match_reasons = None;
+                roms_local.push(rom);
+            }
+            Ok(Event::Eof) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    for rom in &mut roms_local {
+        rom.dat_description = dat_description.clone();
+    }
+    Ok(roms_local)
+}
+
+/// The rom entries loaded from one DAT file.
+#[derive(Debug, Clone)]
+pub struct DatFile {
+    pub path: PathBuf,
+    pub roms: Vec<DatRom>,
+}
+
+impl DatFile {
+    pub fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            roms: parse_dat_file(path)?,
+        })
+    }
+
+    /// Group rom entries by game name (the `<game name>`/description the rom
+    /// was listed under, falling back to the rom name).
+    fn games(&self) -> BTreeMap<&str, Vec<&DatRom>> {
+        let mut games: BTreeMap<&str, Vec<&DatRom>> = BTreeMap::new();
+        for rom in &self.roms {
+            let game = rom.description.as_deref().unwrap_or(&rom.name);
+            games.entry(game).or_default().push(rom);
+        }
+        games
+    }
+}
+
+/// A game present in both DATs whose roms differ.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct DatGameChange {
+    pub game: String,
+    /// Rom names that were added, removed, or whose size/checksums changed.
+    pub roms: Vec<String>,
+}
+
+/// Differences between two versions of a DAT (`--dat-diff`).
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct DatDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<DatGameChange>,
+}
+
+/// Compare two DATs by game name. A renamed game is reported as one removal
+/// and one addition.
+pub fn diff(old: &DatFile, new: &DatFile) -> DatDiff {
+    fn rom_fingerprints<'a>(roms: &[&'a DatRom]) -> BTreeMap<&'a str, Vec<DatRomVariant>> {
+        let mut map: BTreeMap<&str, Vec<DatRomVariant>> = BTreeMap::new();
+        for rom in roms {
+            map.entry(rom.name.as_str())
+                .or_default()
+                .extend(rom.variants().into_iter().map(|variant| DatRomVariant {
+                    crc32: variant.crc32.map(|crc| crc.to_ascii_lowercase()),
+                    ..variant
+                }));
+        }
+        map
+    }
+
+    let old_games = old.games();
+    let new_games = new.games();
+    let mut diff = DatDiff::default();
+
+    for (game, new_roms) in &new_games {
+        let Some(old_roms) = old_games.get(game) else {
+            diff.added.push(game.to_string());
+            continue;
+        };
+        let old_prints = rom_fingerprints(old_roms);
+        let new_prints = rom_fingerprints(new_roms);
+        let roms: BTreeSet<&str> = old_prints
+            .keys()
+            .chain(new_prints.keys())
+            .filter(|name| old_prints.get(*name) != new_prints.get(*name))
+            .copied()
+            .collect();
+        if !roms.is_empty() {
+            diff.changed.push(DatGameChange {
+                game: game.to_string(),
+                roms: roms.into_iter().map(str::to_string).collect(),
+            });
+        }
+    }
+    diff.removed = old_games
+        .keys()
+        .filter(|game| !new_games.contains_key(*game))
+        .map(|game| game.to_string())
+        .collect();
+
+    diff
+}
+
+#[derive(Debug, PartialEq)]
+enum CmpToken {
+    Open,
+    Close,
+    Word(String),
+}
+
+fn tokenize_clrmamepro(text: &str) -> Vec<CmpToken> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => tokens.push(CmpToken::Open),
+            ')' => tokens.push(CmpToken::Close),
+            '"' => {
+                let mut word = String::new();
+                for inner in chars.by_ref() {
+                    if inner == '"' {
+                        break;
+                    }
+                    word.push(inner);
+                }
+                tokens.push(CmpToken::Word(word));
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut word = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || next == '(' || next == ')' {
+                        break;
+                    }
+                    word.push(next);
+                    chars.next();
+                }
+                tokens.push(CmpToken::Word(word));
+            }
+        }
+    }
+
+    tokens
+}
+
+/// Parse a ClrMamePro-format DAT (`game ( name "..." rom ( name "..." size N crc ... ) )`).
+pub fn parse_clrmamepro(text: &str, source_dat: &std::path::Path) -> Vec<DatRom> {
+    let tokens = tokenize_clrmamepro(text);
+    let mut roms = Vec::new();
+
+    let mut depth = 0usize;
+    let mut in_game = false;
+    let mut in_header = false;
+    let mut dat_description: Option<String> = None;
+    let mut game_name: Option<String> = None;
+    let mut game_description: Option<String> = None;
+    let mut game_serial: Option<String> = None;
+    let mut game_version: Option<String> = None;
+    let mut game_start = 0usize;
+    let mut rom: Option<DatRom> = None;
+    let mut rom_keys_seen: HashMap<String, usize> = HashMap::new();
+
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            CmpToken::Open => depth += 1,
+            CmpToken::Close => {
+                if depth == 2 {
+                    if let Some(done) = rom.take() {
+                        roms.push(done);
+                    }
+                } else if depth == 1 {
+                    for done in &mut roms[game_start..] {
+                        done.serial = game_serial.clone();
+                        done.version = game_version.clone();
+                    }
+                    in_game = false;
+                }
+                depth = depth.saturating_sub(1);
+            }
+            CmpToken::Word(key) => match tokens.get(i + 1) {
+                Some(CmpToken::Open) => {
+                    if depth == 0 {
+                        in_game = matches!(key.as_str(), "game" | "machine" | "resource");
+                        in_header = key == "clrmamepro";
+                        game_name = None;
+                        game_description = None;
+                        game_serial = None;
+                        game_version = None;
+                        game_start = roms.len();
+                    } else if depth == 1 && in_game && key == "rom" {
+                        rom_keys_seen.clear();
+                        rom = Some(DatRom {
+                            name: String::new(),
+                            description: game_description.clone().or_else(|| game_name.clone()),
+                            source_dat: source_dat.to_path_buf(),
+                            size: None,
+                            crc32: None,
+                            md5: None,
+                            sha1: None,
+                            sha256: None,
+                            match_reasons: None,
+                            serial: None,
+                            version: None,
+                            region: None,
+                            alternates: Vec::new(),
+                            dat_name: None,
+                            dat_description: None,
+                            set_info: None,
+                        });
+                    }
+                }
+                Some(CmpToken::Word(value)) => {
+                    if let Some(current) = rom.as_mut().filter(|_| depth == 2) {
+                        if key == "name" {
+                            current.name = value.clone();
+                        } else {
+                            current.set_rom_attribute(key, value, &mut rom_keys_seen);
+                        }
+                    } else if depth == 1 && in_game {
+                        match key.as_str() {
+                            "name" => game_name = Some(value.clone()),
+                            "description" => game_description = Some(value.clone()),
+                            "serial" => game_serial = Some(value.clone()),
+                            "version" => game_version = Some(value.clone()),
+                            _ => {}
+                        }
+                    } else if depth == 1 && in_header && key == "description" {
+                        dat_description = Some(value.clone());
+                    }
+                    i += 2;
+                    continue;
+                }
+                _ => {}
+            },
+        }
+        i += 1;
+    }
+
+    for rom in &mut roms {
+        rom.dat_description = dat_description.clone();
+    }
+    roms
+}
+
+/// `--input-checksum-auto`: the minimum checksum worth computing for these DATs, i.e. the
+/// weakest checksum any of their ROMs lists, never below `--input-checksum-min` nor above
+/// `--input-checksum-max`. Without DAT checksums the configured minimum is kept.
+pub fn auto_checksum_min(dat_roms: &[DatRom], config: &Config) -> Checksum {
+    let weakest = dat_roms
+        .iter()
+        .flat_map(|rom| rom.variants())
+        .filter_map(|variant| {
+            [
+                (Checksum::Crc32, variant.crc32.is_some()),
+                (Checksum::Md5, variant.md5.is_some()),
+                (Checksum::Sha1, variant.sha1.is_some()),
+                (Checksum::Sha256, variant.sha256.is_some()),
+            ]
+            .into_iter()
+            .find_map(|(checksum, present)| present.then_some(checksum))
+        })
+        .min_by_key(Checksum::rank);
+    let mut min = match weakest {
+        Some(weakest) if weakest.rank() > config.input_checksum_min.rank() => weakest,
+        _ => config.input_checksum_min,
+    };
+    if let Some(max) = config.input_checksum_max
+        && max.rank() < min.rank()
+    {
+        min = max;
+    }
+    min
+}
+
+/// The configuration inputs are scanned with: `--input-checksum-auto` applied, and SHA1
+/// computed alongside weaker checksums unless `--input-checksum-max` says otherwise.
+/// Checks about which checksums a run computes must use this rather than the raw config.
+pub fn effective_scan_config(dat_roms: &[DatRom], config: &Config) -> Config {
+    let mut scan_config = config.clone();
+    if config.input_checksum_auto && config.hash_algorithms.is_none() {
+        scan_config.input_checksum_min = auto_checksum_min(dat_roms, config);
+    }
+    if scan_config.input_checksum_max.is_none()
+        && scan_config.input_checksum_min.rank() < Checksum::Sha1.rank()
+    {
+        scan_config.input_checksum_max = Some(Checksum::Sha1);
+    }
+    scan_config
+}
+
+/// Describe DATs containing ROMs that only list checksums stronger than this run computes,
+/// since those entries can never be matched.
+pub fn dat_checksum_warnings(dat_roms: &[DatRom], config: &Config) -> Vec<String> {
+    let targets = checksum_targets(config);
+    let mut required: std::collections::BTreeMap<&std::path::Path, Checksum> =
+        std::collections::BTreeMap::new();
+
+    for rom in dat_roms {
+        let available: Vec<Checksum> = [
+            (Checksum::Crc32, rom.crc32.is_some()),
+            (Checksum::Md5, rom.md5.is_some()),
+            (Checksum::Sha1, rom.sha1.is_some()),
+            (Checksum::Sha256, rom.sha256.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(checksum, present)| present.then_some(checksum))
+        .collect();
+
+        if available.is_empty() || available.iter().any(|c| targets.contains(c)) {
+            continue;
+        }
+
+        let weakest = available[0];
+        let needed = required.entry(rom.source_dat.as_path()).or_insert(weakest);
+        if weakest.rank() > needed.rank() {
+            *needed = weakest;
+        }
+    }
+
+    let computed = targets
+        .iter()
+        .filter_map(|c| c.to_possible_value().map(|v| v.get_name().to_string()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let quick = crate::archives::quick_crc_only(config);
+    required
+        .into_iter()
+        .map(|(dat, needed)| {
+            let needed = needed
+                .to_possible_value()
+                .map(|v| v.get_name().to_string())
+                .unwrap_or_default();
+            let name = dat
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| dat.to_string_lossy().to_string());
+            if quick {
+                format!(
+                    "DAT {name} requires {needed} checksums but --input-checksum-quick only reads CRC32 from archive headers; drop --input-checksum-quick and raise --input-checksum-max to match it"
+                )
+            } else {
+                format!(
+                    "DAT {name} requires {needed} checksums but only {computed} are computed; raise --input-checksum-max to match it"
+                )
+            }
+        })
+        .collect()
+}
+
+/// Warn about (or with `--strict`, reject) DATs that can never match the computed checksums.
+pub fn check_dat_checksum_coverage(dat_roms: &[DatRom], config: &Config) -> anyhow::Result<()> {
+    let warnings = dat_checksum_warnings(dat_roms, config);
+    if config.strict && !warnings.is_empty() {
+        anyhow::bail!(warnings.join("\n"));
+    }
+    for warning in &warnings {
+        vwarn!("{warning}");
+    }
+    Ok(())
+}
+
 fn match_reasons_for_record(record: &FileRecord, dat: &DatRom) -> Vec<String> {
+    dat.variants()
+        .iter()
+        .map(|variant| variant_match_reasons(record, &dat.name, variant))
+        .find(|reasons| !reasons.is_empty())
+        .unwrap_or_default()
+}
+
+fn variant_match_reasons(record: &FileRecord, name: &str, dat: &DatRomVariant) -> Vec<String> {
     let mut reasons: Vec<String> = Vec::new();
 
     if let Some(sha1) = &dat.sha1 {
@@ -456,8 +1263,8 @@ fn match_reasons_for_record(record: &FileRecord, dat: &DatRom) -> Vec<String> {
     // Size+name match (DAT provided size and filename matches exactly)
     if let Some(dat_size) = dat.size {
         if record.size == dat_size {
-            if let Some(name) = record.relative.file_name().and_then(|n| n.to_str()) {
-                if name == dat.name {
+            if let Some(file_name) = record.relative.file_name().and_then(|n| n.to_str()) {
+                if file_name == name {
                     reasons.push("size+name".to_string());
                 }
             }
@@ -494,25 +1301,27 @@ impl DatIndex {
     pub(crate) fn from_dats(dats: &[DatRom]) -> Self {
         let mut index = DatIndex::default();
         for (idx, dat) in dats.iter().enumerate() {
-            if let Some(sha1) = &dat.sha1 {
-                index.sha1.entry(sha1.clone()).or_default().push(idx);
-            }
-            if let Some(sha256) = &dat.sha256 {
-                index.sha256.entry(sha256.clone()).or_default().push(idx);
-            }
-            if let Some(md5) = &dat.md5 {
-                index.md5.entry(md5.clone()).or_default().push(idx);
-            }
-            if let (Some(crc), Some(size)) = (dat.crc32.as_deref(), dat.size) {
-                let key = (crc.to_ascii_uppercase(), size);
-                index.crc_size.entry(key).or_default().push(idx);
-            }
-            if let Some(size) = dat.size {
-                index
-                    .size_name
-                    .entry((size, dat.name.clone()))
-                    .or_default()
-                    .push(idx);
+            for variant in dat.variants() {
+                if let Some(sha1) = &variant.sha1 {
+                    index.sha1.entry(sha1.clone()).or_default().push(idx);
+                }
+                if let Some(sha256) = &variant.sha256 {
+                    index.sha256.entry(sha256.clone()).or_default().push(idx);
+                }
+                if let Some(md5) = &variant.md5 {
+                    index.md5.entry(md5.clone()).or_default().push(idx);
+                }
+                if let (Some(crc), Some(size)) = (variant.crc32.as_deref(), variant.size) {
+                    let key = (crc.to_ascii_uppercase(), size);
+                    index.crc_size.entry(key).or_default().push(idx);
+                }
+                if let Some(size) = variant.size {
+                    index
+                        .size_name
+                        .entry((size, dat.name.clone()))
+                        .or_default()
+                        .push(idx);
+                }
             }
         }
         index
@@ -632,21 +1441,33 @@ impl RecordIndex {
         let mut candidates = Vec::new();
         let mut seen = HashSet::new();
 
-        Self::collect(&self.sha1, dat.sha1.as_deref(), &mut seen, &mut candidates);
-        Self::collect(
-            &self.sha256,
-            dat.sha256.as_deref(),
-            &mut seen,
-            &mut candidates,
-        );
-        Self::collect(&self.md5, dat.md5.as_deref(), &mut seen, &mut candidates);
-        if let (Some(crc), Some(size)) = (dat.crc32.as_deref(), dat.size) {
-            let key = (crc.to_ascii_uppercase(), size);
-            Self::collect_tuple_crc(&self.crc_size, &key, &mut seen, &mut candidates);
-        }
-        if let Some(size) = dat.size {
-            let key = (size, dat.name.clone());
-            Self::collect_tuple_size_name(&self.size_name, &key, &mut seen, &mut candidates);
+        for variant in dat.variants() {
+            Self::collect(
+                &self.sha1,
+                variant.sha1.as_deref(),
+                &mut seen,
+                &mut candidates,
+            );
+            Self::collect(
+                &self.sha256,
+                variant.sha256.as_deref(),
+                &mut seen,
+                &mut candidates,
+            );
+            Self::collect(
+                &self.md5,
+                variant.md5.as_deref(),
+                &mut seen,
+                &mut candidates,
+            );
+            if let (Some(crc), Some(size)) = (variant.crc32.as_deref(), variant.size) {
+                let key = (crc.to_ascii_uppercase(), size);
+                Self::collect_tuple_crc(&self.crc_size, &key, &mut seen, &mut candidates);
+            }
+            if let Some(size) = variant.size {
+                let key = (size, dat.name.clone());
+                Self::collect_tuple_size_name(&self.size_name, &key, &mut seen, &mut candidates);
+            }
         }
 
         candidates
@@ -730,6 +1551,111 @@ pub fn partition_dat_matches(
     (matched, unmatched)
 }
 
+/// Weight of each match reason when ranking DATs, mirroring the checksum and
+/// size+name scores used by candidate generation.
+fn match_reason_score(reason: &str) -> u32 {
+    match reason {
+        "sha256" => 950,
+        "sha1" => 900,
+        "md5" => 850,
+        "crc32+size" => 800,
+        "size+name" => 700,
+        _ => 0,
+    }
+}
+
+/// A `--dat-priority REGEX=N` rule: DATs whose path matches `pattern` rank
+/// with `priority` when breaking ties between equally scored matches.
+#[derive(Debug, Clone)]
+pub struct DatPriority {
+    pub pattern: regex::Regex,
+    pub priority: i64,
+}
+
+/// Parse a `REGEX=N` rule. The split happens at the last `=` so the pattern
+/// itself may contain `=`.
+pub fn parse_dat_priority(rule: &str) -> anyhow::Result<DatPriority> {
+    let (pattern, priority) = rule
+        .rsplit_once('=')
+        .with_context(|| format!("--dat-priority '{rule}' must be in the form REGEX=N"))?;
+    let priority = priority
+        .trim()
+        .parse::<i64>()
+        .with_context(|| format!("--dat-priority '{rule}' has an invalid priority"))?;
+    let pattern = regex::Regex::new(pattern)
+        .with_context(|| format!("--dat-priority '{rule}' has an invalid regex"))?;
+    Ok(DatPriority { pattern, priority })
+}
+
+/// Priority of the DAT `dat` was loaded from: the first matching rule wins,
+/// DATs no rule matches rank 0.
+fn dat_priority_for(dat: &DatRom, priorities: &[DatPriority]) -> i64 {
+    let source = dat.source_dat.to_string_lossy();
+    priorities
+        .iter()
+        .find(|rule| rule.pattern.is_match(&source))
+        .map_or(0, |rule| rule.priority)
+}
+
+/// Return the index of the single best DAT entry for `record`: highest match
+/// score first, then the highest `--dat-priority`, then the earliest-loaded DAT.
+fn best_dat_index_for_record(
+    record: &FileRecord,
+    dat_roms: &[DatRom],
+    dat_index: &DatIndex,
+    priorities: &[DatPriority],
+) -> Option<usize> {
+    dat_index
+        .match_candidates(record)
+        .into_iter()
+        .filter_map(|idx| {
+            let score: u32 = match_reasons_for_record(record, &dat_roms[idx])
+                .iter()
+                .map(|reason| match_reason_score(reason))
+                .sum();
+            (score > 0).then_some((score, idx))
+        })
+        .min_by_key(|(score, idx)| {
+            (
+                std::cmp::Reverse(*score),
+                std::cmp::Reverse(dat_priority_for(&dat_roms[*idx], priorities)),
+                *idx,
+            )
+        })
+        .map(|(_, idx)| idx)
+}
+
+/// Resolve overlapping DATs so each record keeps only its single best DAT
+/// match (`--dat-best-match`). Entries that some record matched but that lost
+/// to a better match everywhere are dropped; unmatched entries are kept so
+/// fixdat/report output still lists them.
+pub fn select_best_dat_matches(
+    records: &[FileRecord],
+    dat_roms: &[DatRom],
+    priorities: &[DatPriority],
+) -> Vec<DatRom> {
+    let dat_index = DatIndex::from_dats(dat_roms);
+    let mut winners: HashSet<usize> = HashSet::new();
+    let mut matched: HashSet<usize> = HashSet::new();
+    for record in records {
+        matched.extend(
+            dat_index
+                .match_candidates(record)
+                .into_iter()
+                .filter(|&idx| !match_reasons_for_record(record, &dat_roms[idx]).is_empty()),
+        );
+        if let Some(idx) = best_dat_index_for_record(record, dat_roms, &dat_index, priorities) {
+            winners.insert(idx);
+        }
+    }
+    dat_roms
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| winners.contains(idx) || !matched.contains(idx))
+        .map(|(_, dat)| dat.clone())
+        .collect()
+}
+
 pub fn dat_unmatched(records: &[FileRecord], dat_roms: &[DatRom]) -> (Vec<DatRom>, usize) {
     let (matched, unmatched) = partition_dat_matches(records, dat_roms);
     let matched_count = matched.len();
@@ -1627,6 +2553,13 @@ mod dat_combine_tests {
                 sha1: None,
                 sha256: None,
                 match_reasons: None,
+                serial: None,
+                version: None,
+                region: None,
+                alternates: Vec::new(),
+                dat_name: None,
+                dat_description: None,
+                set_info: None,
             },
             DatRom {
                 name: "Game Disc 2".to_string(),
@@ -1638,6 +2571,13 @@ mod dat_combine_tests {
                 sha1: None,
                 sha256: None,
                 match_reasons: None,
+                serial: None,
+                version: None,
+                region: None,
+                alternates: Vec::new(),
+                dat_name: None,
+                dat_description: None,
+                set_info: None,
             },
         ];
 
@@ -1668,6 +2608,13 @@ mod dat_tests {
                 sha1: None,
                 sha256: None,
                 match_reasons: None,
+                serial: None,
+                version: None,
+                region: None,
+                alternates: Vec::new(),
+                dat_name: None,
+                dat_description: None,
+                set_info: None,
             },
             DatRom {
                 name: "Game Disc 2".to_string(),
@@ -1679,6 +2626,13 @@ mod dat_tests {
                 sha1: None,
                 sha256: None,
                 match_reasons: None,
+                serial: None,
+                version: None,
+                region: None,
+                alternates: Vec::new(),
+                dat_name: None,
+                dat_description: None,
+                set_info: None,
             },
             DatRom {
                 name: "OtherGame (1)".to_string(),
@@ -1690,6 +2644,13 @@ mod dat_tests {
                 sha1: None,
                 sha256: None,
                 match_reasons: None,
+                serial: None,
+                version: None,
+                region: None,
+                alternates: Vec::new(),
+                dat_name: None,
+                dat_description: None,
+                set_info: None,
             },
         ];
 
@@ -1719,6 +2680,12 @@ mod dat_tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
         // dat with sha1 should match
         let dat = DatRom {
@@ -1731,6 +2698,13 @@ mod dat_tests {
             sha1: Some("sha1val".to_string()),
             sha256: None,
             match_reasons: None,
+            serial: None,
+            version: None,
+            region: None,
+            alternates: Vec::new(),
+            dat_name: None,
+            dat_description: None,
+            set_info: None,
         };
         assert!(rom_matches(&rec, &dat));
         // dat with md5 should match
@@ -1744,6 +2718,13 @@ mod dat_tests {
             sha1: None,
             sha256: None,
             match_reasons: None,
+            serial: None,
+            version: None,
+            region: None,
+            alternates: Vec::new(),
+            dat_name: None,
+            dat_description: None,
+            set_info: None,
         };
         assert!(rom_matches(&rec, &dat2));
         // dat with crc matching case-insensitive
@@ -1757,6 +2738,13 @@ mod dat_tests {
             sha1: None,
             sha256: None,
             match_reasons: None,
+            serial: None,
+            version: None,
+            region: None,
+            alternates: Vec::new(),
+            dat_name: None,
+            dat_description: None,
+            set_info: None,
         };
         assert!(rom_matches(&rec, &dat3));
     }
@@ -1780,6 +2768,12 @@ mod dat_tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
         let dat = DatRom {
             name: "game.bin".to_string(),
@@ -1791,10 +2785,383 @@ mod dat_tests {
             sha1: None,
             sha256: None,
             match_reasons: None,
+            serial: None,
+            version: None,
+            region: None,
+            alternates: Vec::new(),
+            dat_name: None,
+            dat_description: None,
+            set_info: None,
         };
         assert!(rom_matches(&rec, &dat));
     }
 
+    #[test]
+    fn dat_best_match_keeps_only_the_preferred_dat_per_file() {
+        use crate::types::{ChecksumSet, FileRecord, OutputStructure};
+        let rec = FileRecord {
+            source: PathBuf::from("game.bin"),
+            relative: PathBuf::from("game.bin"),
+            size: 10,
+            checksums: ChecksumSet {
+                crc32: Some("abcd1234".to_string()),
+                md5: None,
+                sha1: Some("sha1val".to_string()),
+                sha256: None,
+            },
+            letter_dir: None,
+            derived_platform: None,
+            derived_genres: Vec::new(),
+            derived_region: None,
+            derived_languages: Vec::new(),
+            scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
+        };
+        let dat = |game: &str, source: &str| DatRom {
+            name: game.to_string(),
+            description: None,
+            source_dat: PathBuf::from(source),
+            size: Some(10),
+            crc32: None,
+            md5: None,
+            sha1: Some("sha1val".to_string()),
+            sha256: None,
+            match_reasons: None,
+            serial: None,
+            version: None,
+            region: None,
+            alternates: Vec::new(),
+            dat_name: None,
+            dat_description: None,
+            set_info: None,
+        };
+        let unrelated = DatRom {
+            sha1: Some("other".to_string()),
+            ..dat("Other", "Secondary.dat")
+        };
+        let dats = vec![
+            dat("Primary Game", "Primary.dat"),
+            dat("Secondary Game", "Secondary.dat"),
+            unrelated,
+        ];
+
+        // Equal scores: the earlier-loaded DAT wins and the loser is dropped,
+        // while entries no file matched are kept.
+        let best = select_best_dat_matches(std::slice::from_ref(&rec), &dats, &[]);
+        let names: Vec<&str> = best.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["Primary Game", "Other"]);
+        let cfg = Config {
+            output: Some(PathBuf::from("/out")),
+            output_structure: Some(OutputStructure::Dat),
+            ..Config::default()
+        };
+        assert_eq!(
+            crate::records::resolve_output_path_with_dats(&rec, &cfg, Some(&best)),
+            PathBuf::from("/out/Primary/game.bin")
+        );
+
+        // A stronger match in a later DAT outranks load order.
+        let stronger = DatRom {
+            crc32: Some("ABCD1234".to_string()),
+            ..dat("Secondary Game", "Secondary.dat")
+        };
+        let best = select_best_dat_matches(
+            std::slice::from_ref(&rec),
+            &[dat("Primary Game", "Primary.dat"), stronger],
+            &[],
+        );
+        assert_eq!(best.len(), 1);
+        assert_eq!(best[0].name, "Secondary Game");
+    }
+
+    #[test]
+    fn dat_name_rewrite_shortens_the_dat_output_folder() {
+        use crate::types::{ChecksumSet, FileRecord, OutputStructure};
+        let dir = tempfile::tempdir().unwrap();
+        let dat_path = dir.path().join("Nintendo - Game Boy (20240101-000000).dat");
+        std::fs::write(
+            &dat_path,
+            r#"<?xml version="1.0"?><datafile><game name="Tetris (World)"><rom name="Tetris (World).gb" size="4" crc="12345678"/></game></datafile>"#,
+        )
+        .unwrap();
+        let rec = FileRecord {
+            source: PathBuf::from("Tetris (World).gb"),
+            relative: PathBuf::from("Tetris (World).gb"),
+            size: 4,
+            checksums: ChecksumSet {
+                crc32: Some("12345678".to_string()),
+                md5: None,
+                sha1: None,
+                sha256: None,
+            },
+            letter_dir: None,
+            derived_platform: None,
+            derived_genres: Vec::new(),
+            derived_region: None,
+            derived_languages: Vec::new(),
+            scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
+        };
+        let mut cfg = Config {
+            dat: vec![dat_path],
+            output: Some(PathBuf::from("/out")),
+            output_structure: Some(OutputStructure::Dat),
+            ..Config::default()
+        };
+
+        let dats = load_dat_roms(&cfg, None).unwrap();
+        assert_eq!(
+            crate::records::resolve_output_path_with_dats(&rec, &cfg, Some(&dats)),
+            PathBuf::from("/out/Nintendo - Game Boy (20240101-000000)/Tetris (World).gb")
+        );
+
+        cfg.dat_name_rewrite = Some(r"^Nintendo - (.+?) \(.*$=$1".to_string());
+        let dats = load_dat_roms(&cfg, None).unwrap();
+        assert_eq!(dats[0].dat_name.as_deref(), Some("Game Boy"));
+        assert_eq!(
+            crate::records::resolve_output_path_with_dats(&rec, &cfg, Some(&dats)),
+            PathBuf::from("/out/Game Boy/Tetris (World).gb")
+        );
+
+        assert!(parse_dat_name_rewrite("no-separator").is_err());
+        assert!(parse_dat_name_rewrite("(=x").is_err());
+    }
+
+    #[test]
+    fn auto_checksum_min_skips_checksums_a_sha1_only_dat_never_lists() {
+        let dir = tempfile::tempdir().unwrap();
+        let dat_path = dir.path().join("sha1.dat");
+        std::fs::write(
+            &dat_path,
+            format!(
+                r#"<?xml version="1.0"?><datafile><game name="Game"><rom name="Game.bin" size="4" sha1="{}"/></game></datafile>"#,
+                "a".repeat(40)
+            ),
+        )
+        .unwrap();
+        let rom = dir.path().join("Game.bin");
+        std::fs::write(&rom, b"game").unwrap();
+
+        let mut cfg = Config {
+            dat: vec![dat_path],
+            input_checksum_auto: true,
+            ..Config::default()
+        };
+        let dats = load_dat_roms(&cfg, None).unwrap();
+        cfg.input_checksum_min = auto_checksum_min(&dats, &cfg);
+        assert_eq!(cfg.input_checksum_min, Checksum::Sha1);
+        let checksums = crate::checksum::compute_checksums(&rom, &cfg).unwrap();
+        assert!(checksums.crc32.is_none());
+        assert!(checksums.sha1.is_some());
+
+        let bounded = Config {
+            input_checksum_max: Some(Checksum::Md5),
+            ..Config::default()
+        };
+        assert_eq!(auto_checksum_min(&dats, &bounded), Checksum::Md5);
+    }
+
+    #[test]
+    fn system_filter_keeps_only_dats_for_that_system() {
+        let dir = tempfile::tempdir().unwrap();
+        for (system, game) in [
+            ("Game Boy Advance", "Advance Game"),
+            ("Game Boy", "Handheld Game"),
+            ("Super Nintendo Entertainment System", "Console Game"),
+        ] {
+            std::fs::write(
+                dir.path()
+                    .join(format!("Nintendo - {system} (20240101-000000).dat")),
+                format!(
+                    r#"<?xml version="1.0"?><datafile><game name="{game}"><rom name="{game}.bin" size="4" crc="12345678"/></game></datafile>"#
+                ),
+            )
+            .unwrap();
+        }
+
+        let mut cfg = Config {
+            dat: vec![dir.path().to_path_buf()],
+            system: Some("GBA".to_string()),
+            ..Config::default()
+        };
+        let dats = load_dat_roms(&cfg, None).unwrap();
+        let names: Vec<&str> = dats.iter().map(|rom| rom.name.as_str()).collect();
+        assert_eq!(names, vec!["Advance Game.bin"]);
+
+        cfg.system = Some("Game Boy".to_string());
+        let dats = load_dat_roms(&cfg, None).unwrap();
+        let names: Vec<&str> = dats.iter().map(|rom| rom.name.as_str()).collect();
+        assert_eq!(names, vec!["Handheld Game.bin"]);
+    }
+
+    #[test]
+    fn game_name_regex_keeps_only_matching_games_of_a_dat() {
+        let dir = tempfile::tempdir().unwrap();
+        let dat_path = dir.path().join("snes.dat");
+        let games = [
+            "Super Mario World (USA)",
+            "Mario Paint (USA)",
+            "F-Zero (USA)",
+        ];
+        let body: String = games
+            .iter()
+            .enumerate()
+            .map(|(idx, game)| {
+                format!(r#"<game name="{game}"><rom name="{game}.sfc" size="4" crc="0000000{idx}"/></game>"#)
+            })
+            .collect();
+        std::fs::write(
+            &dat_path,
+            format!(r#"<?xml version="1.0"?><datafile>{body}</datafile>"#),
+        )
+        .unwrap();
+
+        let mut cfg = Config {
+            dat: vec![dat_path],
+            game_name_regex: Some("Mario".to_string()),
+            ..Config::default()
+        };
+        let dats = load_dat_roms(&cfg, None).unwrap();
+        let names: Vec<&str> = dats.iter().map(|rom| rom.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["Super Mario World (USA).sfc", "Mario Paint (USA).sfc"]
+        );
+
+        cfg.game_name_regex_exclude = Some("Paint".to_string());
+        let dats = load_dat_roms(&cfg, None).unwrap();
+        let names: Vec<&str> = dats.iter().map(|rom| rom.name.as_str()).collect();
+        assert_eq!(names, vec!["Super Mario World (USA).sfc"]);
+    }
+
+    #[test]
+    fn dir_dat_description_sanitizes_illegal_path_characters() {
+        use crate::types::{ChecksumSet, FileRecord};
+        let dir = tempfile::tempdir().unwrap();
+        let dat_path = dir.path().join("gb.dat");
+        std::fs::write(
+            &dat_path,
+            r#"<?xml version="1.0"?><datafile><header><name>gb</name><description>Nintendo: Game Boy? *Verified*</description></header><game name="Tetris (World)"><rom name="Tetris (World).gb" size="4" crc="12345678"/></game></datafile>"#,
+        )
+        .unwrap();
+        let rec = FileRecord {
+            source: PathBuf::from("Tetris (World).gb"),
+            relative: PathBuf::from("Tetris (World).gb"),
+            size: 4,
+            checksums: ChecksumSet {
+                crc32: Some("12345678".to_string()),
+                md5: None,
+                sha1: None,
+                sha256: None,
+            },
+            letter_dir: None,
+            derived_platform: None,
+            derived_genres: Vec::new(),
+            derived_region: None,
+            derived_languages: Vec::new(),
+            scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
+        };
+        let cfg = Config {
+            dat: vec![dat_path],
+            output: Some(PathBuf::from("/out")),
+            dir_dat_description: true,
+            ..Config::default()
+        };
+
+        let dats = load_dat_roms(&cfg, None).unwrap();
+        assert_eq!(
+            dats[0].dat_description.as_deref(),
+            Some("Nintendo: Game Boy? *Verified*")
+        );
+        let expected = PathBuf::from("/out/Nintendo_ Game Boy_ _Verified_/Tetris (World).gb");
+        for _ in 0..2 {
+            assert_eq!(
+                crate::records::resolve_output_path_with_dats(&rec, &cfg, Some(&dats)),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn dat_priority_flips_the_winner_for_an_ambiguous_file() {
+        use crate::types::{ChecksumSet, FileRecord};
+        let rec = FileRecord {
+            source: PathBuf::from("disc.bin"),
+            relative: PathBuf::from("disc.bin"),
+            size: 10,
+            checksums: ChecksumSet {
+                crc32: None,
+                md5: None,
+                sha1: Some("sha1val".to_string()),
+                sha256: None,
+            },
+            letter_dir: None,
+            derived_platform: None,
+            derived_genres: Vec::new(),
+            derived_region: None,
+            derived_languages: Vec::new(),
+            scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
+        };
+        let dat = |game: &str, source: &str| DatRom {
+            name: game.to_string(),
+            description: None,
+            source_dat: PathBuf::from(source),
+            size: Some(10),
+            crc32: None,
+            md5: None,
+            sha1: Some("sha1val".to_string()),
+            sha256: None,
+            match_reasons: None,
+            serial: None,
+            version: None,
+            region: None,
+            alternates: Vec::new(),
+            dat_name: None,
+            dat_description: None,
+            set_info: None,
+        };
+        let dats = vec![
+            dat("No-Intro Disc", "dats/No-Intro - Sony.dat"),
+            dat("Redump Disc", "dats/Redump - Sony.dat"),
+        ];
+
+        let best = select_best_dat_matches(std::slice::from_ref(&rec), &dats, &[]);
+        assert_eq!(best.len(), 1);
+        assert_eq!(best[0].name, "No-Intro Disc");
+
+        let rules = vec![parse_dat_priority("(?i)redump=10").unwrap()];
+        let best = select_best_dat_matches(std::slice::from_ref(&rec), &dats, &rules);
+        assert_eq!(best.len(), 1);
+        assert_eq!(best[0].name, "Redump Disc");
+
+        assert!(parse_dat_priority("redump").is_err());
+        assert!(parse_dat_priority("redump=high").is_err());
+        assert!(parse_dat_priority("(=1").is_err());
+        assert_eq!(parse_dat_priority("a=b=-2").unwrap().priority, -2);
+    }
+
     #[test]
     fn normalize_name_drops_revision_tokens() {
         let plan = normalize_name_with_keywords("Donkey Kong Rev 1 (USA).sfc");
@@ -1875,6 +3242,12 @@ mod dat_tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         };
         let dats = vec![
             DatRom {
@@ -1887,6 +3260,13 @@ mod dat_tests {
                 sha1: None,
                 sha256: None,
                 match_reasons: None,
+                serial: None,
+                version: None,
+                region: None,
+                alternates: Vec::new(),
+                dat_name: None,
+                dat_description: None,
+                set_info: None,
             },
             DatRom {
                 name: "b.bin".to_string(),
@@ -1898,6 +3278,13 @@ mod dat_tests {
                 sha1: None,
                 sha256: None,
                 match_reasons: None,
+                serial: None,
+                version: None,
+                region: None,
+                alternates: Vec::new(),
+                dat_name: None,
+                dat_description: None,
+                set_info: None,
             },
         ];
         let (unmatched, matched) = dat_unmatched(&[rec], &dats);
@@ -1920,6 +3307,13 @@ mod dat_tests {
                 sha1: None,
                 sha256: None,
                 match_reasons: None,
+                serial: None,
+                version: None,
+                region: None,
+                alternates: Vec::new(),
+                dat_name: None,
+                dat_description: None,
+                set_info: None,
             },
             DatRom {
                 name: "Game - Disc 1".to_string(),
@@ -1931,6 +3325,13 @@ mod dat_tests {
                 sha1: None,
                 sha256: None,
                 match_reasons: None,
+                serial: None,
+                version: None,
+                region: None,
+                alternates: Vec::new(),
+                dat_name: None,
+                dat_description: None,
+                set_info: None,
             },
         ];
         let grouped = group_multi_file_roms(&roms);
@@ -421,6 +421,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         }
     }
 
@@ -436,6 +442,13 @@ mod tests {
             sha1: None,
             sha256: None,
             match_reasons: None,
+            serial: None,
+            version: None,
+            region: None,
+            alternates: Vec::new(),
+            dat_name: None,
+            dat_description: None,
+            set_info: None,
         };
 
         let derived = romm_from_dat(&dat);
@@ -469,6 +482,13 @@ mod tests {
             sha1: Some("deadbeef".to_string()),
             sha256: None,
             match_reasons: None,
+            serial: None,
+            version: None,
+            region: None,
+            alternates: Vec::new(),
+            dat_name: None,
+            dat_description: None,
+            set_info: None,
         };
 
         assert!(!record_is_cartridge_based(&record, Some(&[dat])));
@@ -1,12 +1,20 @@
-// Lightweight verbosity-gated logging helper used throughout the crate.
+// Lightweight verbosity-gated logging helper used throughout the crate; the
+// level also picks the WARN/INFO/DEBUG/TRACE prefix (see `log::LogLevel`).
 macro_rules! vprintln {
 	($verbose:expr, $level:expr, $($arg:tt)*) => {
 		if $verbose >= $level {
-			eprintln!($($arg)*);
+			$crate::log::emit($level, format_args!($($arg)*));
 		}
 	};
 }
 
+// Warning shown whatever the verbosity, with the WARN prefix; `-qq` silences it.
+macro_rules! vwarn {
+	($($arg:tt)*) => {
+		$crate::log::warn(format_args!($($arg)*))
+	};
+}
+
 // Public library re-exports for integration tests and external use.
 pub mod actions;
 pub mod archives;
@@ -20,6 +28,8 @@ pub mod config;
 pub mod dat;
 pub mod game_console;
 pub mod igdb_platform_map;
+pub mod log;
+pub mod merge;
 pub mod patch;
 pub mod patch_apply;
 pub mod progress;
@@ -30,5 +40,6 @@ pub mod torrentzip_zip64;
 pub mod types;
 pub mod utils;
 pub mod write_candidate;
+pub mod xattr_cache;
 
 // Keep main.rs thin and have it call into the library functions.
@@ -0,0 +1,165 @@
+use std::fmt;
+use std::io::{IsTerminal, Write, stderr};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(test)]
+use std::cell::Cell;
+
+static COLOR_DISABLED: AtomicBool = AtomicBool::new(false);
+static WARNINGS_SILENCED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(test)]
+thread_local! {
+    static FORCE_COLOR_TTY: Cell<Option<bool>> = const { Cell::new(None) };
+}
+
+/// Severity shown in front of `vprintln!` output, derived from the verbosity
+/// level the message is gated on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    /// Map a `-v` count to a level: 0 always prints as a warning, 1 is `-v`,
+    /// 2 is `-vv`, anything higher is trace output.
+    pub fn from_verbosity(level: u8) -> Self {
+        match level {
+            0 => LogLevel::Warn,
+            1 => LogLevel::Info,
+            2 => LogLevel::Debug,
+            _ => LogLevel::Trace,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            LogLevel::Warn => "WARN",
+            LogLevel::Info => "INFO",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Trace => "TRACE",
+        }
+    }
+
+    fn ansi_color(self) -> &'static str {
+        match self {
+            LogLevel::Warn => "\x1b[33m",
+            LogLevel::Info => "\x1b[32m",
+            LogLevel::Debug => "\x1b[36m",
+            LogLevel::Trace => "\x1b[90m",
+        }
+    }
+}
+
+/// Turn off ANSI colors for the rest of the process (`--no-color`).
+pub fn disable_color() {
+    COLOR_DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Drop warnings for the rest of the process (`-qq`).
+pub fn silence_warnings() {
+    WARNINGS_SILENCED.store(true, Ordering::Relaxed);
+}
+
+fn warnings_silenced() -> bool {
+    WARNINGS_SILENCED.load(Ordering::Relaxed)
+}
+
+fn color_enabled() -> bool {
+    if COLOR_DISABLED.load(Ordering::Relaxed) {
+        return false;
+    }
+    #[cfg(test)]
+    {
+        if let Some(flag) = FORCE_COLOR_TTY.with(|cell| cell.get()) {
+            return flag;
+        }
+    }
+    stderr().is_terminal()
+}
+
+#[cfg(test)]
+pub(crate) fn force_color_tty_for_tests(flag: Option<bool>) {
+    FORCE_COLOR_TTY.with(|cell| cell.set(flag));
+}
+
+/// Write one log line to `out`, prefixed with its level and colored when
+/// stderr is a terminal.
+pub fn write_line<W: Write>(out: &mut W, level: LogLevel, args: fmt::Arguments) {
+    let _ = if color_enabled() {
+        writeln!(
+            out,
+            "{}{:<5}\x1b[0m {}",
+            level.ansi_color(),
+            level.label(),
+            args
+        )
+    } else {
+        writeln!(out, "{:<5} {}", level.label(), args)
+    };
+}
+
+/// Backend of `vprintln!`: write a leveled line to stderr. Level 0 lines are warnings
+/// and are dropped once [`silence_warnings`] was called.
+pub fn emit(level: u8, args: fmt::Arguments) {
+    emit_to(&mut stderr().lock(), LogLevel::from_verbosity(level), args);
+}
+
+fn emit_to<W: Write>(out: &mut W, level: LogLevel, args: fmt::Arguments) {
+    if level == LogLevel::Warn && warnings_silenced() {
+        return;
+    }
+    write_line(out, level, args);
+}
+
+/// Backend of `vwarn!`: write a `WARN` line to stderr unless warnings are silenced.
+pub fn warn(args: fmt::Arguments) {
+    emit(0, args);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(level: LogLevel, message: &str) -> String {
+        let mut out = Vec::new();
+        write_line(&mut out, level, format_args!("{message}"));
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn warnings_are_prefixed_and_uncolored_without_a_tty() {
+        force_color_tty_for_tests(Some(false));
+        assert_eq!(
+            line(LogLevel::from_verbosity(0), "disk almost full"),
+            "WARN  disk almost full\n"
+        );
+        assert_eq!(line(LogLevel::from_verbosity(2), "x"), "DEBUG x\n");
+        assert_eq!(line(LogLevel::from_verbosity(7), "x"), "TRACE x\n");
+
+        force_color_tty_for_tests(Some(true));
+        let colored = line(LogLevel::Warn, "disk almost full");
+        assert!(colored.starts_with("\x1b[33mWARN"));
+        assert!(colored.ends_with("\x1b[0m disk almost full\n"));
+        force_color_tty_for_tests(None);
+    }
+
+    #[test]
+    fn silenced_warnings_are_dropped_but_other_levels_stay() {
+        force_color_tty_for_tests(Some(false));
+        let emitted = |level: LogLevel| {
+            let mut out = Vec::new();
+            emit_to(&mut out, level, format_args!("x"));
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(emitted(LogLevel::Warn), "WARN  x\n");
+        silence_warnings();
+        assert_eq!(emitted(LogLevel::Warn), "");
+        assert_eq!(emitted(LogLevel::Info), "INFO  x\n");
+        WARNINGS_SILENCED.store(false, Ordering::Relaxed);
+        force_color_tty_for_tests(None);
+    }
+}
@@ -1,27 +1,56 @@
-use clap::Parser;
-
 use igir::actions::perform_actions;
 use igir::cli::Cli;
 use igir::config::Config;
-use num_cpus;
+use igir::dat::{self, DatFile};
+use igir::types::{Action, EXIT_NO_MATCHES};
 use rayon::ThreadPoolBuilder;
 
 fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+    let cli = Cli::parse_with_config_file()?;
     let config = Config::try_from(cli)?;
+    if config.no_color {
+        igir::log::disable_color();
+    }
+    if config.quiet > 1 {
+        igir::log::silence_warnings();
+    }
 
-    // Initialize Rayon global thread pool using the configured thread counts.
-    // Use the larger of the configured hash and scan thread counts (or CPU count by default)
-    let default_threads = num_cpus::get();
-    let hash_threads = config.hash_threads.unwrap_or(default_threads);
-    let scan_threads = config.scan_threads.unwrap_or(default_threads);
-    let threads = std::cmp::max(hash_threads, scan_threads);
-    let _ = ThreadPoolBuilder::new().num_threads(threads).build_global();
+    // Initialize Rayon global thread pool using the configured thread counts so
+    // matching (`par_iter` in candidates) follows --threads as well.
+    let _ = ThreadPoolBuilder::new()
+        .num_threads(config.global_thread_count())
+        .build_global();
+
+    if let Some((old, new)) = &config.dat_diff {
+        let diff = dat::diff(&DatFile::load(old)?, &DatFile::load(new)?);
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+        if config.commands.is_empty() {
+            return Ok(());
+        }
+    }
+
+    if config.expected_checksums().next().is_some() {
+        if !igir::checksum::verify_expected_checksums(&config)? {
+            std::process::exit(1);
+        }
+        if config.commands.iter().all(|c| *c == Action::Test) {
+            return Ok(());
+        }
+    }
 
     let plan = perform_actions(&config)?;
-    if config.print_plan {
+    if config.summary_only {
+        println!("{}", serde_json::to_string_pretty(&plan.summary_view())?);
+    } else if config.print_plan {
         println!("{}", serde_json::to_string_pretty(&plan)?);
     }
 
+    if plan.matched_nothing() && !config.allow_no_matches {
+        eprintln!(
+            "error: no input files matched the loaded DATs (use --allow-no-matches to exit 0)"
+        );
+        std::process::exit(EXIT_NO_MATCHES);
+    }
+
     Ok(())
 }
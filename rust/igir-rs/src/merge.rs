@@ -0,0 +1,200 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::dat::DatRom;
+use crate::types::MergeMode;
+
+/// Apply `--merge-roms` to the entries of one DAT.
+///
+/// `split` drops the clone entries whose `merge` attribute names an entry of the parent,
+/// leaving each clone with only what it adds. `merged` also folds the remaining clone
+/// entries into the parent's set, so each family is written as one set. Disks follow the
+/// same rule: a clone disk that references a parent disk is only kept by the parent, while
+/// a clone-only disk (no `merge`, or one naming a disk the parent lacks) is never dropped.
+/// Parent entries are always kept. `nonmerged` and `fullnonmerged` write every set whole.
+pub fn merge_sets(roms: Vec<DatRom>, mode: &MergeMode) -> Vec<DatRom> {
+    let merged = match mode {
+        MergeMode::Merged => true,
+        MergeMode::Split => false,
+        MergeMode::Fullnonmerged | MergeMode::Nonmerged => return roms,
+    };
+
+    // Entry names of every game, keyed by (disk, name), and each game's description.
+    let mut entries: HashMap<String, HashSet<(bool, String)>> = HashMap::new();
+    let mut descriptions: HashMap<String, Option<String>> = HashMap::new();
+    for rom in &roms {
+        if let Some(info) = &rom.set_info {
+            entries
+                .entry(info.game.clone())
+                .or_default()
+                .insert((info.disk, rom.name.clone()));
+            descriptions
+                .entry(info.game.clone())
+                .or_insert_with(|| rom.description.clone());
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut out = Vec::with_capacity(roms.len());
+    for mut rom in roms {
+        if let Some(info) = rom.set_info.as_mut()
+            && let Some(parent) = info
+                .parent
+                .clone()
+                .filter(|parent| parent != &info.game && entries.contains_key(parent))
+        {
+            let in_parent = info
+                .merge
+                .as_ref()
+                .is_some_and(|merge| entries[&parent].contains(&(info.disk, merge.clone())));
+            if in_parent {
+                continue;
+            }
+            if merged {
+                info.game = parent.clone();
+                rom.description = descriptions[&parent].clone();
+            }
+        }
+        // Clones often repeat an entry the family already has, without a `merge` attribute.
+        if merged
+            && let Some(info) = &rom.set_info
+            && !seen.insert((
+                info.game.clone(),
+                rom.name.clone(),
+                rom.crc32.clone(),
+                rom.md5.clone(),
+                rom.sha1.clone(),
+            ))
+        {
+            continue;
+        }
+        out.push(rom);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dat::DatSetInfo;
+    use std::path::PathBuf;
+
+    fn entry(
+        game: &str,
+        parent: Option<&str>,
+        name: &str,
+        sha1: &str,
+        merge: Option<&str>,
+    ) -> DatRom {
+        let disk = name.ends_with(".chd");
+        DatRom {
+            name: name.to_string(),
+            description: Some(format!("{game} description")),
+            source_dat: PathBuf::from("mame.dat"),
+            size: (!disk).then_some(16),
+            crc32: None,
+            md5: None,
+            sha1: Some(sha1.to_string()),
+            sha256: None,
+            match_reasons: None,
+            serial: None,
+            version: None,
+            region: None,
+            alternates: Vec::new(),
+            dat_name: None,
+            dat_description: None,
+            set_info: Some(DatSetInfo {
+                game: game.to_string(),
+                parent: parent.map(str::to_string),
+                merge: merge.map(str::to_string),
+                disk,
+            }),
+        }
+    }
+
+    fn family() -> Vec<DatRom> {
+        vec![
+            entry("kinst", None, "kinst.u98", "a1", None),
+            entry("kinst", None, "kinst.chd", "d1", None),
+            entry(
+                "kinst2",
+                Some("kinst"),
+                "kinst.u98",
+                "a1",
+                Some("kinst.u98"),
+            ),
+            // Shared with the parent through `merge`: referenced, not duplicated.
+            entry(
+                "kinst2",
+                Some("kinst"),
+                "kinst.chd",
+                "d1",
+                Some("kinst.chd"),
+            ),
+            // Only the clone has this disk.
+            entry("kinst2", Some("kinst"), "kinst2.chd", "d2", None),
+            // Repeats the parent disk without saying so.
+            entry("kinst2", Some("kinst"), "kinst.chd", "d1", None),
+            // A `merge` naming a disk the parent lacks leaves the disk with the clone.
+            entry(
+                "kinst2",
+                Some("kinst"),
+                "extra.chd",
+                "d3",
+                Some("missing.chd"),
+            ),
+        ]
+    }
+
+    fn sets(roms: &[DatRom]) -> Vec<(String, String)> {
+        roms.iter()
+            .map(|rom| {
+                (
+                    rom.description.clone().unwrap_or_default(),
+                    rom.name.clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn merged_sets_keep_parent_disks_and_clone_only_disks() {
+        let merged = merge_sets(family(), &MergeMode::Merged);
+        assert_eq!(
+            sets(&merged),
+            vec![
+                ("kinst description".to_string(), "kinst.u98".to_string()),
+                ("kinst description".to_string(), "kinst.chd".to_string()),
+                ("kinst description".to_string(), "kinst2.chd".to_string()),
+                ("kinst description".to_string(), "extra.chd".to_string()),
+            ]
+        );
+        assert!(
+            merged
+                .iter()
+                .all(|rom| rom.set_info.as_ref().unwrap().game == "kinst")
+        );
+    }
+
+    #[test]
+    fn split_sets_keep_only_what_the_clone_adds() {
+        let split = merge_sets(family(), &MergeMode::Split);
+        assert_eq!(
+            sets(&split),
+            vec![
+                ("kinst description".to_string(), "kinst.u98".to_string()),
+                ("kinst description".to_string(), "kinst.chd".to_string()),
+                ("kinst2 description".to_string(), "kinst2.chd".to_string()),
+                // Without `merge` only a merged set can tell it is the parent's.
+                ("kinst2 description".to_string(), "kinst.chd".to_string()),
+                ("kinst2 description".to_string(), "extra.chd".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn non_merged_sets_are_left_whole() {
+        for mode in [MergeMode::Nonmerged, MergeMode::Fullnonmerged] {
+            assert_eq!(merge_sets(family(), &mode).len(), family().len());
+        }
+    }
+}
@@ -78,3 +78,36 @@ pub fn guess_patch_type(entry: &PatchEntry) -> Option<&'static str> {
         _ => None,
     }
 }
+
+/// The CRC32 of the ROM a patch applies to, taken from an 8-digit hex word in the patch
+/// file name (e.g. `Game (Translated) 1a2b3c4d.ips`), lowercased.
+pub fn patch_target_crc32(entry: &PatchEntry) -> Option<String> {
+    let stem = entry.path.file_stem()?.to_str()?;
+    stem.split(|c: char| !c.is_ascii_alphanumeric())
+        .find(|word| word.len() == 8 && word.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(str::to_ascii_lowercase)
+}
+
+/// File name for the patched copy of `target`: the patch file name without its CRC32
+/// word, keeping the extension of `target`.
+pub fn patched_file_name(entry: &PatchEntry, target: &std::path::Path) -> String {
+    let stem = entry
+        .path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = match patch_target_crc32(entry) {
+        Some(crc) => {
+            let start = stem.to_ascii_lowercase().find(&crc).unwrap_or(0);
+            let mut name = stem.clone();
+            name.replace_range(start..start + crc.len(), "");
+            name.split_whitespace().collect::<Vec<_>>().join(" ")
+        }
+        None => stem,
+    };
+    let name = name.trim_matches(|c: char| c == '-' || c == '_' || c.is_whitespace());
+    match target.extension() {
+        Some(ext) => format!("{name}.{}", ext.to_string_lossy()),
+        None => name.to_string(),
+    }
+}
@@ -1,11 +1,141 @@
 use std::path::Path;
 
-/// Stubbed patch application API.
+use anyhow::bail;
+
+use crate::progress::{BackgroundTask, ProgressReporter};
+
+const IPS_HEADER: &[u8] = b"PATCH";
+const IPS_EOF: &[u8] = b"EOF";
+
+/// Apply the patch at `patch_path` to `source`.
 ///
-/// Current implementation is a placeholder that returns Ok(None) which indicates
-/// "not applied / not implemented yet". Future work: implement IPS/BPS/UPS
-/// applying logic here or call into a dedicated crate.
-pub fn apply_patch_to_bytes(_patch_path: &Path, _source: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
-    // TODO: implement patch formats (IPS, BPS, UPS, IPS32, etc.) or integrate a crate.
-    Ok(None)
+/// Returns Ok(None) for formats that are not implemented yet (BPS, UPS);
+/// IPS/IPS32 patches are applied in memory.
+pub fn apply_patch_to_bytes(patch_path: &Path, source: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+    apply_patch_to_bytes_with_progress(patch_path, source, None)
+}
+
+/// Like [`apply_patch_to_bytes`], reporting per-patch byte progress through the
+/// `[PATCH]` background task when a reporter is available. The caller finishes the task
+/// once every patch is written.
+pub fn apply_patch_to_bytes_with_progress(
+    patch_path: &Path,
+    source: &[u8],
+    progress: Option<&ProgressReporter>,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let ext = patch_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    let offset_width = match ext.as_str() {
+        "ips" => 3,
+        "ips32" => 4,
+        // TODO: implement BPS/UPS or integrate a crate.
+        _ => return Ok(None),
+    };
+    let patch = std::fs::read(patch_path)?;
+    let patched = apply_ips(&patch, source, offset_width, |done| {
+        if let Some(p) = progress {
+            p.update_patch_progress(patch_path, done, Some(patch.len() as u64));
+        }
+    });
+    if let Some(p) = progress {
+        p.tick_background_task(BackgroundTask::Patch, 1, Some(patch_path));
+    }
+    patched.map(Some)
+}
+
+/// Apply an IPS patch whose record offsets are `offset_width` bytes wide
+/// (3 for IPS, 4 for IPS32). `on_progress` receives the number of patch
+/// bytes consumed after each record.
+fn apply_ips<F>(
+    patch: &[u8],
+    source: &[u8],
+    offset_width: usize,
+    mut on_progress: F,
+) -> anyhow::Result<Vec<u8>>
+where
+    F: FnMut(u64),
+{
+    if !patch.starts_with(IPS_HEADER) {
+        bail!("not an IPS patch: missing PATCH header");
+    }
+    let eof_marker: &[u8] = if offset_width == 4 { b"EEOF" } else { IPS_EOF };
+    let mut out = source.to_vec();
+    let mut pos = IPS_HEADER.len();
+
+    let take = |pos: &mut usize, len: usize| -> anyhow::Result<&[u8]> {
+        let end = pos.checked_add(len).filter(|end| *end <= patch.len());
+        let Some(end) = end else {
+            bail!("truncated IPS patch at offset {}", pos);
+        };
+        let slice = &patch[*pos..end];
+        *pos = end;
+        Ok(slice)
+    };
+    let be = |bytes: &[u8]| bytes.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize);
+
+    loop {
+        if patch[pos..].starts_with(eof_marker) {
+            pos += eof_marker.len();
+            break;
+        }
+        let offset = be(take(&mut pos, offset_width)?);
+        let size = be(take(&mut pos, 2)?);
+        let (data, run) = if size == 0 {
+            let count = be(take(&mut pos, 2)?);
+            let value = take(&mut pos, 1)?[0];
+            (None, (count, value))
+        } else {
+            (Some(take(&mut pos, size)?), (size, 0))
+        };
+        let end = offset + run.0;
+        if out.len() < end {
+            out.resize(end, 0);
+        }
+        match data {
+            Some(bytes) => out[offset..end].copy_from_slice(bytes),
+            None => out[offset..end].fill(run.1),
+        }
+        on_progress(pos as u64);
+    }
+
+    // Optional truncation length follows the EOF marker.
+    if patch.len() - pos == 3 {
+        out.truncate(be(&patch[pos..]));
+    }
+    on_progress(patch.len() as u64);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn ips_patch() -> Vec<u8> {
+        let mut patch = IPS_HEADER.to_vec();
+        // overwrite two bytes at 0x000001
+        patch.extend_from_slice(&[0x00, 0x00, 0x01, 0x00, 0x02, 0xAA, 0xBB]);
+        // RLE: four 0xCC bytes at 0x000006, growing the target
+        patch.extend_from_slice(&[0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x04, 0xCC]);
+        patch.extend_from_slice(IPS_EOF);
+        patch
+    }
+
+    #[test]
+    fn ips_patches_overwrite_and_grow_the_source() {
+        let mut file = tempfile::Builder::new().suffix(".ips").tempfile().unwrap();
+        file.write_all(&ips_patch()).unwrap();
+        file.flush().unwrap();
+
+        let patched = apply_patch_to_bytes(file.path(), &[0u8; 4]).unwrap();
+        assert_eq!(
+            patched,
+            Some(vec![
+                0x00, 0xAA, 0xBB, 0x00, 0x00, 0x00, 0xCC, 0xCC, 0xCC, 0xCC
+            ])
+        );
+    }
 }
@@ -36,15 +36,19 @@ fn action_label(action: &Action) -> String {
     match action {
         Action::Copy => "COPY",
         Action::Move => "MOVE",
+        Action::Rename => "RENAME",
         Action::Link => "LINK",
         Action::Extract => "EXTRACT",
         Action::Zip => "ZIP",
         Action::Playlist => "PLAYLIST",
         Action::Test => "TEST",
+        Action::VerifyZip => "VERIFY",
         Action::Dir2dat => "DIR2DAT",
         Action::Fixdat => "FIXDAT",
         Action::Clean => "CLEAN",
+        Action::CleanEmptyDirs => "CLEAN-DIRS",
         Action::Report => "REPORT",
+        Action::Verify => "VERIFY-DAT",
     }
     .to_string()
 }
@@ -154,12 +158,13 @@ impl DetailPanelState {
     }
 }
 
-#[derive(Clone, Copy, Hash, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
 pub enum BackgroundTask {
     Checksums,
     Cache,
     NetLookup,
     Diag,
+    Patch,
 }
 
 impl BackgroundTask {
@@ -169,6 +174,7 @@ impl BackgroundTask {
             BackgroundTask::Cache => "[CACHE]",
             BackgroundTask::NetLookup => "[NET ]",
             BackgroundTask::Diag => "[DIAG]",
+            BackgroundTask::Patch => "[PATCH]",
         }
     }
 
@@ -178,6 +184,7 @@ impl BackgroundTask {
             BackgroundTask::Cache => "entries",
             BackgroundTask::NetLookup => "requests",
             BackgroundTask::Diag => "queued",
+            BackgroundTask::Patch => "patches",
         }
     }
 
@@ -281,6 +288,8 @@ pub struct ProgressReporter {
     diag_phase_bars: RefCell<HashMap<String, ProgressBar>>,
     item_bytes: RefCell<HashMap<BackgroundTask, HashMap<PathBuf, ItemBytesState>>>,
     action_item_bytes: RefCell<HashMap<PathBuf, ItemBytesState>>,
+    #[cfg(test)]
+    task_events: RefCell<Vec<(BackgroundTask, &'static str)>>,
 }
 
 impl ProgressReporter {
@@ -344,6 +353,8 @@ impl ProgressReporter {
             spinner
         };
         bar.set_prefix(task.prefix().to_string());
+        #[cfg(test)]
+        self.task_events.borrow_mut().push((task, "begin"));
         let state = BackgroundTaskState {
             task,
             bar,
@@ -530,6 +541,21 @@ impl ProgressReporter {
         });
     }
 
+    /// Report byte-level progress for a patch being applied to `target`.
+    /// The `[PATCH]` task is created on first use; callers finish it with
+    /// [`ProgressReporter::finish_background_task`] once the patch is written.
+    pub fn update_patch_progress(&self, target: &Path, bytes_done: u64, total: Option<u64>) {
+        if !self.enabled {
+            return;
+        }
+        self.note_item_bytes(BackgroundTask::Patch, target, bytes_done, total);
+        let hint_text = self.format_hint(Some(target));
+        self.with_background_task(BackgroundTask::Patch, None, |state| {
+            state.last_hint = hint_text.clone();
+            state.bar.set_message(state.update_message());
+        });
+    }
+
     pub fn update_action_item_bytes(&self, path: &Path, bytes_done: u64, total: Option<u64>) {
         if !self.enabled {
             return;
@@ -598,6 +624,8 @@ impl ProgressReporter {
         if let Some(state) = self.background_tasks.borrow_mut().remove(&task) {
             state.bar.finish_and_clear();
             self.multi.remove(&state.bar);
+            #[cfg(test)]
+            self.task_events.borrow_mut().push((task, "finish"));
         }
         self.item_bytes.borrow_mut().remove(&task);
     }
@@ -644,7 +672,7 @@ impl ProgressReporter {
         self.set_panel_section(DetailSection::Action, text, reset_elapsed);
     }
 
-    fn log_summary(&self, message: String) {
+    pub fn log_summary(&self, message: String) {
         if !self.enabled {
             return;
         }
@@ -662,6 +690,17 @@ impl ProgressReporter {
         });
     }
 
+    #[cfg(test)]
+    pub(crate) fn scan_totals_for_tests(&self) -> (Option<u64>, Option<u64>) {
+        // `scan_total` is cleared when scanning finishes; the bar keeps its length.
+        (self.scanning_bar.length(), self.scan_total_bytes.get())
+    }
+
+    #[cfg(test)]
+    pub(crate) fn task_events_for_tests(&self) -> Vec<(BackgroundTask, &'static str)> {
+        self.task_events.borrow().clone()
+    }
+
     #[cfg(test)]
     pub(crate) fn diag_last_hint_for_tests(&self) -> Option<String> {
         self.background_tasks
@@ -724,6 +763,8 @@ impl ProgressReporter {
             finalized: Cell::new(false),
             item_bytes: RefCell::new(HashMap::new()),
             action_item_bytes: RefCell::new(HashMap::new()),
+            #[cfg(test)]
+            task_events: RefCell::new(Vec::new()),
         })
     }
 
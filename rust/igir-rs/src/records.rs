@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -9,22 +9,31 @@ use num_cpus;
 use regex::Regex;
 use walkdir::WalkDir;
 
-use crate::archives::scan_zip_entries;
-use crate::checksum::compute_checksums_with_header;
+use crate::archives::{
+    is_archive_path, scan_archive_entries, scan_zip_member, scans_archive_members,
+};
+use crate::cache::Cache;
+use crate::checksum::{
+    checksum_targets, compute_checksums_checkpointed, compute_checksums_with_header,
+};
 use crate::config::Config;
 use crate::game_console;
 use crate::progress::{BackgroundTask, ProgressEvent, ProgressReporter};
+use crate::roms::header_def::{HeaderDetector, detect_header_size, load_header_definitions};
+use crate::roms::rom_scanner::RomInfo;
 use crate::roms::{chd, rom_scanner::scan as scan_rom};
 use crate::types::{
-    ArchiveChecksumMode, DirGameSubdirMode, FileCollection, FileRecord, SkipReason, SkippedFile,
+    ArchiveChecksumMode, DirGameSubdirMode, FileCollection, FileRecord, OutputStructure,
+    SkipReason, SkippedFile,
 };
 use crate::utils::build_globset;
+use crate::xattr_cache;
 use rayon::prelude::*;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::mpsc::{self, TryRecvError};
 use std::thread::JoinHandle;
-use std::time::Instant;
+use std::time::{Instant, SystemTime};
 fn note_scan_progress(
     progress: Option<&ProgressReporter>,
     counter: &mut usize,
@@ -59,6 +68,7 @@ struct JobResult {
     path: PathBuf,
     checksums: Option<crate::types::ChecksumSet>,
     size: u64,
+    modified: Option<SystemTime>,
     rom_info: Option<crate::roms::rom_scanner::RomInfo>,
     extra_records: Vec<FileRecord>,
 }
@@ -109,6 +119,12 @@ fn handle_checksum_result(
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: job.rom_info,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: job.modified,
+            input_index: None,
+            fixed_extension: None,
         });
 
         if let Some(p) = progress {
@@ -133,6 +149,9 @@ fn handle_checksum_result(
     }
 
     if !job.extra_records.is_empty() {
+        for record in job.extra_records.iter_mut() {
+            record.modified = job.modified;
+        }
         records.extend(job.extra_records.into_iter());
     }
 
@@ -205,6 +224,53 @@ impl Drop for WorkerGuard {
     }
 }
 
+/// The deepest input directory containing `source`, or the file's own directory when it
+/// was passed directly as an input.
+fn input_root_for(source: &Path, inputs: &[PathBuf]) -> Option<PathBuf> {
+    inputs
+        .iter()
+        .filter(|input| input.as_path() != source && source.starts_with(input))
+        .max_by_key(|input| input.components().count())
+        .cloned()
+        .or_else(|| source.parent().map(Path::to_path_buf))
+}
+
+/// Position of the first input (in the order given) that is or contains `source`.
+fn input_index_for(source: &Path, inputs: &[PathBuf]) -> Option<usize> {
+    inputs.iter().position(|input| source.starts_with(input))
+}
+
+/// Replace a `-` input with the newline-delimited paths read from `stdin`, so
+/// `find ... | igir copy -i - -o out` works alongside other `-i` arguments.
+/// Stdin is only read when `-` is present, and only once.
+fn expand_stdin_inputs<R: std::io::BufRead>(
+    raw_inputs: &[PathBuf],
+    stdin: R,
+) -> anyhow::Result<Vec<PathBuf>> {
+    if !raw_inputs.iter().any(|input| input.as_os_str() == "-") {
+        return Ok(raw_inputs.to_vec());
+    }
+    let mut piped = Vec::new();
+    for line in stdin.lines() {
+        let line = line.context("reading input paths from stdin")?;
+        let line = line.trim_end_matches('\r');
+        if !line.trim().is_empty() {
+            piped.push(PathBuf::from(line));
+        }
+    }
+    let mut expanded = Vec::new();
+    let mut consumed = false;
+    for input in raw_inputs {
+        if input.as_os_str() != "-" {
+            expanded.push(input.clone());
+        } else if !consumed {
+            expanded.append(&mut piped);
+            consumed = true;
+        }
+    }
+    Ok(expanded)
+}
+
 fn expand_inputs(raw_inputs: &[PathBuf]) -> anyhow::Result<Vec<PathBuf>> {
     let mut expanded = Vec::new();
     for input in raw_inputs {
@@ -219,9 +285,167 @@ fn expand_inputs(raw_inputs: &[PathBuf]) -> anyhow::Result<Vec<PathBuf>> {
     Ok(expanded)
 }
 
+/// Cache to save `--hash-checkpoint` progress in, for files longer than one checkpoint
+/// interval.
+fn hash_checkpoint_cache(config: &Config, size: u64) -> Option<(Cache, u64)> {
+    let interval = config.hash_checkpoint_mib?.saturating_mul(1 << 20);
+    if size <= interval {
+        return None;
+    }
+    let cache = Cache::open(config.cache_db.as_ref(), None).ok()?;
+    Some((cache, interval))
+}
+
+/// [`scan_rom`] with the `--header` definitions applied: a matching detector decides the
+/// header size skipped when hashing, overriding the built-in guesses. Atari 7800 headers are
+/// only skipped when `a78` is listed in `--remove-headers`.
+fn scan_rom_with_headers(
+    path: &Path,
+    headers: &[HeaderDetector],
+    config: &Config,
+) -> Option<RomInfo> {
+    let mut info = scan_rom(path).ok()?;
+    if info.header_name == Some("A78") && !removes_header(config, "a78") {
+        info.trimmed_size += info.header_size.take().unwrap_or(0);
+    }
+    if let Some(header) = detect_header_size(headers, path) {
+        let size = info.trimmed_size + info.header_size.unwrap_or(0);
+        info.header_size = Some(header);
+        info.trimmed_size = size.saturating_sub(header);
+    }
+    Some(info)
+}
+
+/// Whether `--remove-headers` lists `extension` (comma-separated, case-insensitive, with or
+/// without the leading dot).
+fn removes_header(config: &Config, extension: &str) -> bool {
+    config.remove_headers.as_deref().is_some_and(|list| {
+        list.split(',')
+            .map(|ext| ext.trim().trim_start_matches('.'))
+            .any(|ext| ext.eq_ignore_ascii_case(extension))
+    })
+}
+
+/// Drop inputs that resolve to a path already listed, so overlapping globs and paths
+/// (`-i '*.zip' -i game.zip`) scan each file once. The first occurrence keeps its position.
+/// Inputs that only overlap through a directory are caught per file by `first_sighting`.
+fn dedup_inputs(inputs: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    inputs
+        .into_iter()
+        .filter(|input| seen.insert(fs::canonicalize(input).unwrap_or_else(|_| input.clone())))
+        .collect()
+}
+
+/// Record `path` as scanned, returning false when it (by canonical path) already was, so
+/// a file reached through several inputs (`-i dir -i dir/game.bin`) is scanned once.
+fn first_sighting(seen: &mut HashSet<PathBuf>, path: &Path) -> bool {
+    seen.insert(fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()))
+}
+
+/// Split an `archive.zip#member` input into the archive path and member name. Paths that
+/// exist as given are never split, so file names containing `#` keep working.
+fn split_archive_member(input: &Path) -> Option<(PathBuf, String)> {
+    if input.exists() {
+        return None;
+    }
+    let raw = input.to_str()?;
+    raw.match_indices('#')
+        .map(|(idx, _)| (&raw[..idx], &raw[idx + 1..]))
+        .find(|(archive, member)| !member.is_empty() && Path::new(archive).is_file())
+        .map(|(archive, member)| (PathBuf::from(archive), member.replace('\\', "/")))
+}
+
+/// Replace `.m3u` inputs with the files they list. Relative entries resolve against the
+/// playlist's directory; the returned map records which set (playlist stem) each member belongs to.
+fn expand_playlists(
+    inputs: Vec<PathBuf>,
+) -> anyhow::Result<(Vec<PathBuf>, HashMap<PathBuf, String>)> {
+    let mut expanded = Vec::new();
+    let mut sets = HashMap::new();
+    for input in inputs {
+        let is_playlist = input
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("m3u"));
+        if !is_playlist || !input.is_file() {
+            expanded.push(input);
+            continue;
+        }
+
+        let text =
+            fs::read_to_string(&input).with_context(|| format!("reading playlist: {input:?}"))?;
+        let set_name = input
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let base = input.parent().unwrap_or(Path::new(""));
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let member = base.join(line);
+            sets.insert(member.clone(), set_name.clone());
+            expanded.push(member);
+        }
+    }
+    Ok((expanded, sets))
+}
+
+fn unreadable_input(root: &Path, err: walkdir::Error) -> SkippedFile {
+    SkippedFile {
+        path: err.path().unwrap_or(root).to_path_buf(),
+        reason: SkipReason::Unreadable,
+        detail: Some(err.to_string()),
+    }
+}
+
+/// One-line report of unreadable inputs: the count plus the first few paths.
+fn unreadable_summary(unreadable: &[SkippedFile]) -> String {
+    const PREVIEW: usize = 3;
+    let preview: Vec<String> = unreadable
+        .iter()
+        .take(PREVIEW)
+        .map(|entry| entry.path.display().to_string())
+        .collect();
+    let more = unreadable.len().saturating_sub(PREVIEW);
+    format!(
+        "Skipped {} unreadable input path{}: {}{}",
+        unreadable.len(),
+        if unreadable.len() == 1 { "" } else { "s" },
+        preview.join(", "),
+        if more > 0 {
+            format!(" (+{more} more)")
+        } else {
+            String::new()
+        }
+    )
+}
+
+/// The `--fix-extension` candidate for a loose file, read from its first bytes; whether it
+/// applies is decided against the output name. Archive members have no file of their own
+/// and keep their name.
+fn sniff_fixed_extension(record: &FileRecord, config: &Config) -> Option<String> {
+    if !record.source.ends_with(&record.relative) {
+        return None;
+    }
+    crate::candidate_extension::sniffed_extension(&record.source, &config.fix_extension)
+        .map(str::to_string)
+}
+
+/// Whether `--scan-files-only` / `--scan-archives-only` leave `path` out of the scan.
+fn skipped_by_scan_mode(path: &Path, config: &Config) -> bool {
+    if config.scan_files_only {
+        is_archive_path(path)
+    } else {
+        config.scan_archives_only && !is_archive_path(path)
+    }
+}
+
 fn count_total_files_and_bytes(
     inputs: &[PathBuf],
     exclude: &Option<GlobSet>,
+    config: &Config,
 ) -> anyhow::Result<(usize, u64)> {
     let mut total = 0usize;
     let mut bytes = 0u64;
@@ -233,6 +457,7 @@ fn count_total_files_and_bytes(
             if exclude
                 .as_ref()
                 .is_some_and(|set| set.is_match(path.to_string_lossy().as_ref()))
+                || skipped_by_scan_mode(path, config)
             {
                 continue;
             }
@@ -251,6 +476,7 @@ fn count_total_files_and_bytes(
                 if exclude
                     .as_ref()
                     .is_some_and(|set| set.is_match(entry_path.to_string_lossy().as_ref()))
+                    || skipped_by_scan_mode(entry_path, config)
                 {
                     continue;
                 }
@@ -268,14 +494,36 @@ fn count_total_files_and_bytes(
 pub fn collect_files(
     config: &Config,
     progress: Option<&ProgressReporter>,
+) -> anyhow::Result<FileCollection> {
+    collect_files_with_stdin(config, progress, std::io::stdin().lock())
+}
+
+/// Like [`collect_files`], reading the paths for a `-` input from `stdin`
+/// instead of the process's standard input.
+pub fn collect_files_with_stdin<R: std::io::BufRead>(
+    config: &Config,
+    progress: Option<&ProgressReporter>,
+    stdin: R,
 ) -> anyhow::Result<FileCollection> {
     let exclude = build_globset(&config.input_exclude)?;
     let mut records = Vec::new();
     let mut scanned_total = 0usize;
     let mut scanned_bytes = 0u64;
 
-    let expanded_inputs = expand_inputs(&config.input)?;
-    let (total_count, total_bytes) = count_total_files_and_bytes(&expanded_inputs, &exclude)?;
+    let mut member_records = Vec::new();
+    let mut inputs = Vec::new();
+    for input in expand_stdin_inputs(&config.input, stdin)? {
+        match split_archive_member(&input) {
+            Some((archive, member)) => {
+                member_records.push(scan_zip_member(&archive, &member, config)?);
+            }
+            None => inputs.push(input),
+        }
+    }
+    let (expanded_inputs, playlist_sets) = expand_playlists(expand_inputs(&inputs)?)?;
+    let expanded_inputs = dedup_inputs(expanded_inputs);
+    let (total_count, total_bytes) =
+        count_total_files_and_bytes(&expanded_inputs, &exclude, config)?;
     let total_hint = if total_count > 0 {
         Some(total_count)
     } else {
@@ -296,28 +544,28 @@ pub fn collect_files(
     #[derive(Clone, Copy)]
     enum JobKind {
         RegularFile,
-        Archive { compute_archive_checksum: bool },
+        Archive,
     }
 
     impl JobKind {
-        fn should_hash(&self) -> bool {
-            match self {
-                JobKind::RegularFile => true,
-                JobKind::Archive {
-                    compute_archive_checksum,
-                } => *compute_archive_checksum,
-            }
-        }
-
         fn is_archive(&self) -> bool {
-            matches!(self, JobKind::Archive { .. })
+            matches!(self, JobKind::Archive)
         }
     }
 
+    // `--input-checksum-archives never` treats archives as opaque files: they are hashed
+    // like any other file and their members are never scanned.
+    let scan_archive_members = config.input_checksum_archives != ArchiveChecksumMode::Never;
+    let header_definitions = match config.header.as_deref() {
+        Some(pattern) => load_header_definitions(pattern)?,
+        None => Vec::new(),
+    };
+
     #[derive(Clone)]
     struct Job {
         path: PathBuf,
         size: u64,
+        modified: Option<SystemTime>,
         rom_info: Option<crate::roms::rom_scanner::RomInfo>,
         kind: JobKind,
     }
@@ -325,72 +573,120 @@ pub fn collect_files(
     let (result_tx, result_rx) = mpsc::channel::<ChecksumJobResult>();
     let (progress_tx, progress_rx) = mpsc::channel::<ProgressEvent>();
     let (job_tx, job_rx) = mpsc::channel::<Job>();
+    // Archives get their own queue and pool (`--archive-threads`) so several archives are
+    // scanned at once without starving plain-file hashing. Workers only report progress
+    // through `progress_tx`; the `ProgressReporter` is driven from this thread.
+    let (archive_tx, archive_rx) = mpsc::channel::<Job>();
+    let default_threads = num_cpus::get();
+    let hash_parallelism = config.hash_threads.unwrap_or(default_threads).max(1);
+    let scan_parallelism = config.scan_threads.unwrap_or(default_threads).max(1);
+    let archive_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(config.archive_threads.unwrap_or(scan_parallelism).max(1))
+        .thread_name(|idx| format!("igir-archive-{idx}"))
+        .build()
+        .context("building archive scan thread pool")?;
     let worker_config = config.clone();
-    let worker_handle = WorkerGuard::new(std::thread::spawn(move || {
-        job_rx.into_iter().par_bridge().for_each(|job| {
-            let sender = result_tx.clone();
-            let progress_sender = progress_tx.clone();
-            let res: ChecksumJobResult = (|| {
-                let Job {
-                    path,
-                    size,
-                    rom_info,
-                    kind,
-                } = job;
-
-                let mut extra_records = Vec::new();
-                if kind.is_archive() {
-                    let mut inner = scan_zip_entries(
+    let process_job = Arc::new(
+        move |job: Job, progress_sender: mpsc::Sender<ProgressEvent>| -> ChecksumJobResult {
+            let Job {
+                path,
+                size,
+                modified,
+                rom_info,
+                kind,
+            } = job;
+
+            let mut extra_records = Vec::new();
+            if kind.is_archive() {
+                let mut inner =
+                    scan_archive_entries(&path, &worker_config, Some(progress_sender.clone()))?;
+                for record in inner.iter_mut() {
+                    record.source = path.clone();
+                }
+                extra_records.extend(inner);
+            }
+
+            let chd_checksums = rom_info
+                .as_ref()
+                .filter(|info| info.is_chd)
+                .and_then(|_| chd::embedded_checksums(&path, &worker_config));
+            let header_size = rom_info.as_ref().and_then(|info| info.header_size);
+            let xattr_cached = worker_config
+                .xattr_cache
+                .then(|| {
+                    xattr_cache::read_checksums(
+                        &path,
+                        size,
+                        modified,
+                        header_size,
+                        &checksum_targets(&worker_config),
+                    )
+                })
+                .flatten();
+            let checksums = if chd_checksums.is_some() {
+                chd_checksums
+            } else if let Some(cached) = xattr_cached {
+                let _ =
+                    progress_sender.send(ProgressEvent::hashing(path.clone(), size, Some(size)));
+                Some(cached)
+            } else {
+                let computed = match hash_checkpoint_cache(&worker_config, size) {
+                    Some((cache, interval)) => compute_checksums_checkpointed(
+                        &path,
+                        header_size.filter(|&header| header <= size).unwrap_or(0),
+                        &checksum_targets(&worker_config),
+                        &cache,
+                        interval,
+                        Some(progress_sender.clone()),
+                    )?,
+                    None => compute_checksums_with_header(
                         &path,
                         &worker_config,
+                        header_size,
                         Some(progress_sender.clone()),
-                    )?;
-                    for record in inner.iter_mut() {
-                        record.source = path.clone();
-                    }
-                    extra_records.extend(inner);
-                }
-
-                let checksums = if kind.should_hash() {
-                    let progress_clone = progress_sender.clone();
-                    if let Some(ref info) = rom_info {
-                        Some(compute_checksums_with_header(
-                            &path,
-                            &worker_config,
-                            info.header_size,
-                            Some(progress_clone),
-                        )?)
-                    } else {
-                        Some(compute_checksums_with_header(
-                            &path,
-                            &worker_config,
-                            None,
-                            Some(progress_clone),
-                        )?)
-                    }
-                } else {
-                    None
+                    )?,
                 };
+                if worker_config.xattr_cache {
+                    xattr_cache::write_checksums(&path, size, modified, header_size, &computed);
+                }
+                Some(computed)
+            };
 
-                Ok(JobResult {
-                    path,
-                    checksums,
-                    size,
-                    rom_info,
-                    extra_records,
+            Ok(JobResult {
+                path,
+                checksums,
+                size,
+                modified,
+                rom_info,
+                extra_records,
+            })
+        },
+    );
+    let spawn_worker = |jobs: mpsc::Receiver<Job>, pool: Option<rayon::ThreadPool>| {
+        let result_tx = result_tx.clone();
+        let progress_tx = progress_tx.clone();
+        let process_job = Arc::clone(&process_job);
+        WorkerGuard::new(std::thread::spawn(move || {
+            let run = || {
+                jobs.into_iter().par_bridge().for_each(|job| {
+                    let _ = result_tx.send(process_job(job, progress_tx.clone()));
                 })
-            })();
-            let _ = sender.send(res);
-        });
-    }));
-    let mut worker_handle = Some(worker_handle);
+            };
+            match pool {
+                Some(pool) => pool.install(run),
+                None => run(),
+            }
+        }))
+    };
+    let mut worker_handles = vec![
+        spawn_worker(job_rx, None),
+        spawn_worker(archive_rx, Some(archive_pool)),
+    ];
+    drop((result_tx, progress_tx));
 
     let mut jobs_enqueued = 0usize;
     let mut completed_files = 0usize;
     let mut worker_err: Option<anyhow::Error> = None;
-    let default_threads = num_cpus::get();
-    let hash_parallelism = config.hash_threads.unwrap_or(default_threads).max(1);
-    let scan_parallelism = config.scan_threads.unwrap_or(default_threads).max(1);
     // Allow a deep queue of checksum jobs so network I/O stays saturated. We still
     // cap the backlog to avoid unbounded memory growth, but the limit is high
     // enough that scanning rarely blocks unless hashes are extremely slow.
@@ -470,7 +766,10 @@ pub fn collect_files(
             }
         };
 
-    'scan: for matched in expanded_inputs {
+    // Directory entries WalkDir could not read (e.g. permission denied on a subtree).
+    let mut unreadable: Vec<SkippedFile> = Vec::new();
+    let mut seen_files = HashSet::new();
+    'scan: for matched in expanded_inputs.clone() {
         drain_progress_updates(scanned_total, scanned_bytes);
         if let Err(e) =
             drain_nonblocking_results(&result_rx, &mut records, progress, &mut completed_files)
@@ -484,10 +783,13 @@ pub fn collect_files(
             if exclude
                 .as_ref()
                 .is_some_and(|set| set.is_match(matched.to_string_lossy().as_ref()))
+                || skipped_by_scan_mode(&matched, config)
+                || !first_sighting(&mut seen_files, &matched)
             {
                 continue;
             }
             let file_size = metadata.len();
+            let modified = metadata.modified().ok();
             note_scan_progress(
                 progress,
                 &mut scanned_total,
@@ -496,64 +798,60 @@ pub fn collect_files(
                 file_size,
             );
 
-            if let Some(ext) = matched.extension().and_then(|s| s.to_str()) {
-                if ext.eq_ignore_ascii_case("zip") {
-                    let compute_archive_checksum =
-                        config.input_checksum_archives != ArchiveChecksumMode::Never;
-                    if job_tx
-                        .send(Job {
-                            path: matched.clone(),
-                            size: file_size,
-                            rom_info: None,
-                            kind: JobKind::Archive {
-                                compute_archive_checksum,
-                            },
-                        })
-                        .is_err()
-                    {
-                        worker_err = Some(anyhow::anyhow!(
-                            "checksum worker stopped while queuing jobs"
-                        ));
-                        break 'scan;
+            if scan_archive_members && scans_archive_members(&matched) {
+                if archive_tx
+                    .send(Job {
+                        path: matched.clone(),
+                        size: file_size,
+                        modified,
+                        rom_info: None,
+                        kind: JobKind::Archive,
+                    })
+                    .is_err()
+                {
+                    worker_err = Some(anyhow::anyhow!(
+                        "checksum worker stopped while queuing jobs"
+                    ));
+                    break 'scan;
+                }
+                jobs_enqueued = jobs_enqueued.saturating_add(1);
+                metrics.queued.fetch_add(1, Ordering::Relaxed);
+                drain_progress_updates(scanned_total, scanned_bytes);
+                if let Err(e) = drain_nonblocking_results(
+                    &result_rx,
+                    &mut records,
+                    progress,
+                    &mut completed_files,
+                ) {
+                    worker_err = Some(e);
+                    break 'scan;
+                }
+                match maybe_apply_backpressure(
+                    jobs_enqueued,
+                    &mut completed_files,
+                    max_in_flight,
+                    &result_rx,
+                    &mut records,
+                    progress,
+                ) {
+                    Ok(true) => {
+                        drain_progress_updates(scanned_total, scanned_bytes);
                     }
-                    jobs_enqueued = jobs_enqueued.saturating_add(1);
-                    metrics.queued.fetch_add(1, Ordering::Relaxed);
-                    drain_progress_updates(scanned_total, scanned_bytes);
-                    if let Err(e) = drain_nonblocking_results(
-                        &result_rx,
-                        &mut records,
-                        progress,
-                        &mut completed_files,
-                    ) {
+                    Ok(false) => {}
+                    Err(e) => {
                         worker_err = Some(e);
                         break 'scan;
                     }
-                    match maybe_apply_backpressure(
-                        jobs_enqueued,
-                        &mut completed_files,
-                        max_in_flight,
-                        &result_rx,
-                        &mut records,
-                        progress,
-                    ) {
-                        Ok(true) => {
-                            drain_progress_updates(scanned_total, scanned_bytes);
-                        }
-                        Ok(false) => {}
-                        Err(e) => {
-                            worker_err = Some(e);
-                            break 'scan;
-                        }
-                    }
-                    continue;
                 }
+                continue;
             }
 
-            let rom_info = scan_rom(&matched).ok();
+            let rom_info = scan_rom_with_headers(&matched, &header_definitions, config);
             if job_tx
                 .send(Job {
                     path: matched.clone(),
                     size: file_size,
+                    modified,
                     rom_info: rom_info.clone(),
                     kind: JobKind::RegularFile,
                 })
@@ -595,7 +893,11 @@ pub fn collect_files(
 
         for entry in WalkDir::new(&matched)
             .into_iter()
-            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                entry
+                    .map_err(|err| unreadable.push(unreadable_input(&matched, err)))
+                    .ok()
+            })
             .filter(|e| e.file_type().is_file())
         {
             let path = entry.into_path();
@@ -609,13 +911,16 @@ pub fn collect_files(
             if exclude
                 .as_ref()
                 .is_some_and(|set| set.is_match(path.to_string_lossy().as_ref()))
+                || skipped_by_scan_mode(&path, config)
+                || !first_sighting(&mut seen_files, &path)
             {
                 continue;
             }
 
-            let file_size = fs::metadata(&path)
-                .with_context(|| format!("reading input: {path:?}"))?
-                .len();
+            let metadata =
+                fs::metadata(&path).with_context(|| format!("reading input: {path:?}"))?;
+            let file_size = metadata.len();
+            let modified = metadata.modified().ok();
 
             note_scan_progress(
                 progress,
@@ -625,65 +930,61 @@ pub fn collect_files(
                 file_size,
             );
 
-            let rom_info = scan_rom(&path).ok();
-
-            if let Some(ext) = path.extension().and_then(|s| s.to_str()) {
-                if ext.eq_ignore_ascii_case("zip") {
-                    let compute_archive_checksum =
-                        config.input_checksum_archives != ArchiveChecksumMode::Never;
-                    if job_tx
-                        .send(Job {
-                            path: path.clone(),
-                            size: file_size,
-                            rom_info: None,
-                            kind: JobKind::Archive {
-                                compute_archive_checksum,
-                            },
-                        })
-                        .is_err()
-                    {
-                        worker_err = Some(anyhow::anyhow!(
-                            "checksum worker stopped while queuing jobs"
-                        ));
-                        break 'scan;
+            let rom_info = scan_rom_with_headers(&path, &header_definitions, config);
+
+            if scan_archive_members && scans_archive_members(&path) {
+                if archive_tx
+                    .send(Job {
+                        path: path.clone(),
+                        size: file_size,
+                        modified,
+                        rom_info: None,
+                        kind: JobKind::Archive,
+                    })
+                    .is_err()
+                {
+                    worker_err = Some(anyhow::anyhow!(
+                        "checksum worker stopped while queuing jobs"
+                    ));
+                    break 'scan;
+                }
+                jobs_enqueued = jobs_enqueued.saturating_add(1);
+                metrics.queued.fetch_add(1, Ordering::Relaxed);
+                drain_progress_updates(scanned_total, scanned_bytes);
+                if let Err(e) = drain_nonblocking_results(
+                    &result_rx,
+                    &mut records,
+                    progress,
+                    &mut completed_files,
+                ) {
+                    worker_err = Some(e);
+                    break 'scan;
+                }
+                match maybe_apply_backpressure(
+                    jobs_enqueued,
+                    &mut completed_files,
+                    max_in_flight,
+                    &result_rx,
+                    &mut records,
+                    progress,
+                ) {
+                    Ok(true) => {
+                        drain_progress_updates(scanned_total, scanned_bytes);
                     }
-                    jobs_enqueued = jobs_enqueued.saturating_add(1);
-                    metrics.queued.fetch_add(1, Ordering::Relaxed);
-                    drain_progress_updates(scanned_total, scanned_bytes);
-                    if let Err(e) = drain_nonblocking_results(
-                        &result_rx,
-                        &mut records,
-                        progress,
-                        &mut completed_files,
-                    ) {
+                    Ok(false) => {}
+                    Err(e) => {
                         worker_err = Some(e);
                         break 'scan;
                     }
-                    match maybe_apply_backpressure(
-                        jobs_enqueued,
-                        &mut completed_files,
-                        max_in_flight,
-                        &result_rx,
-                        &mut records,
-                        progress,
-                    ) {
-                        Ok(true) => {
-                            drain_progress_updates(scanned_total, scanned_bytes);
-                        }
-                        Ok(false) => {}
-                        Err(e) => {
-                            worker_err = Some(e);
-                            break 'scan;
-                        }
-                    }
-                    continue;
                 }
+                continue;
             }
 
             if job_tx
                 .send(Job {
                     path: path.clone(),
                     size: file_size,
+                    modified,
                     rom_info: rom_info.clone(),
                     kind: JobKind::RegularFile,
                 })
@@ -728,6 +1029,7 @@ pub fn collect_files(
     // reflect actual progress.
 
     drop(job_tx);
+    drop(archive_tx);
 
     if worker_err.is_none() {
         while completed_files < jobs_enqueued {
@@ -752,20 +1054,14 @@ pub fn collect_files(
 
     drain_progress_updates(scanned_total, scanned_bytes);
 
-    if let Some(err) = worker_err {
-        if let Some(handle) = worker_handle.take() {
-            handle
-                .join()
-                .map_err(|e| anyhow::anyhow!("checksum worker panicked: {e:?}"))?;
-        }
-        return Err(err);
-    }
-
-    if let Some(handle) = worker_handle.take() {
+    for handle in worker_handles.drain(..) {
         handle
             .join()
             .map_err(|e| anyhow::anyhow!("checksum worker panicked: {e:?}"))?;
     }
+    if let Some(err) = worker_err {
+        return Err(err);
+    }
 
     if let Some(p) = progress {
         p.finish_scanning(scanned_total);
@@ -773,10 +1069,34 @@ pub fn collect_files(
         p.finish_background_task(crate::progress::BackgroundTask::Diag);
     }
 
-    let mut skipped = Vec::new();
+    if !unreadable.is_empty() {
+        let message = unreadable_summary(&unreadable);
+        if config.strict {
+            anyhow::bail!("{message} (--strict)");
+        }
+        match progress {
+            Some(p) => p.log_summary(message),
+            None => vwarn!("{message}"),
+        }
+    }
+
+    records.extend(member_records);
+    for record in records.iter_mut() {
+        record.set_name = playlist_sets.get(&record.source).cloned();
+        record.input_root = input_root_for(&record.source, &expanded_inputs);
+        record.input_index = input_index_for(&record.source, &expanded_inputs);
+    }
+
+    let mut skipped = unreadable;
     records = apply_filters(records, config, &mut skipped)?;
 
-    annotate_locale_metadata(&mut records);
+    if !matches!(config.fix_extension, crate::types::FixExtensionMode::Never) {
+        records.par_iter_mut().for_each(|record| {
+            record.fixed_extension = sniff_fixed_extension(record, config);
+        });
+    }
+
+    annotate_locale_metadata(&mut records, config.region_map.as_ref());
 
     if config.dir_letter {
         assign_letter_dirs(&mut records, config)?;
@@ -785,6 +1105,21 @@ pub fn collect_files(
     Ok(FileCollection { records, skipped })
 }
 
+/// Give records unique file names when they all land in one output directory: with
+/// `--output-structure flat`, or with `--dir-game-subdir never` for multi-file games.
+/// Run it on the records that will actually be written, after every filter, so dropped
+/// records do not push ` (N)` suffixes onto the ones that remain.
+pub(crate) fn assign_output_names(records: &mut [FileRecord], config: &Config) {
+    // Without game subdirectories, members of multi-file games share the output dir with
+    // everything else and may need a disambiguating name.
+    let flat_games = config.output_structure.is_none()
+        && matches!(config.dir_game_subdir, DirGameSubdirMode::Never)
+        && records.iter().any(|r| r.set_name.is_some());
+    if config.output_structure == Some(OutputStructure::Flat) || flat_games {
+        assign_flat_output_names(records);
+    }
+}
+
 #[cfg(test)]
 mod chd_integration_tests {
     use super::*;
@@ -813,19 +1148,28 @@ mod chd_integration_tests {
             input: vec![PathBuf::from(f.path())],
             input_exclude: vec![],
             input_checksum_quick: false,
+            input_checksum_auto: false,
+            scan_files_only: false,
+            scan_archives_only: false,
             input_checksum_min: crate::types::Checksum::Crc32,
             input_checksum_max: None,
             input_checksum_archives: crate::types::ArchiveChecksumMode::Auto,
             dat: vec![],
             dat_exclude: vec![],
+            dat_url: vec![],
             dat_name_regex: None,
             dat_name_regex_exclude: None,
+            game_name_regex: None,
+            game_name_regex_exclude: None,
+            system: None,
+            dat_name_rewrite: None,
             dat_description_regex: None,
             dat_description_regex_exclude: None,
             dat_combine: false,
             dat_ignore_parent_clone: false,
             list_unmatched_dats: false,
             print_plan: true,
+            summary_only: false,
             enable_hasheous: false,
             igdb_client_id: None,
             igdb_client_secret: None,
@@ -834,31 +1178,68 @@ mod chd_integration_tests {
             igdb_mode: crate::types::IgdbLookupMode::BestEffort,
             cache_only: false,
             cache_db: None,
+            xattr_cache: false,
             hash_threads: None,
             scan_threads: None,
+            archive_threads: None,
             show_match_reasons: false,
+            min_score: None,
+            tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+            priority_input: false,
+            title_strip_regex: vec![],
+            ignore_case: true,
+            output_structure: None,
+            threads: None,
+            strict: false,
+            allow_no_matches: false,
+            dat_best_match: false,
+            dat_priority: Vec::new(),
+            dat_diff: None,
+            fixdat_format: None,
+            hash_algorithms: None,
+            hash_checkpoint_mib: None,
+            expect_crc32: None,
+            expect_md5: None,
+            expect_sha1: None,
+            expect_sha256: None,
+            max_decompressed_size: None,
+            skip_existing_checksum: false,
+            report_duplicates: false,
             patch: vec![],
             patch_exclude: vec![],
             output: None,
             dir_mirror: false,
+            dir_mirror_root: false,
             dir_dat_mirror: false,
             dir_dat_name: false,
             dir_dat_description: false,
             dir_letter: false,
+            dir_region: false,
             dir_letter_count: None,
             dir_letter_limit: None,
             dir_letter_group: false,
+            dir_letter_ignore_articles: false,
             dir_game_subdir: crate::types::DirGameSubdirMode::Multiple,
             fix_extension: crate::types::FixExtensionMode::Auto,
+            normalize_extension_case: false,
+            shorten_paths: false,
             overwrite: false,
             overwrite_invalid: false,
+            on_collision: crate::types::CollisionPolicy::Skip,
+            quarantine: None,
+            no_space_check: false,
             move_delete_dirs: crate::types::MoveDeleteDirsMode::Auto,
+            extract_include: None,
+            extract_exclude: None,
             clean_exclude: vec![],
             clean_backup: None,
+            clean_backup_keep: None,
             clean_dry_run: false,
             zip_format: crate::types::ZipFormat::Torrentzip,
-            zip_exclude: None,
+            zip_exclude: vec![],
             zip_dat_name: false,
+            zip_comment: None,
+            to_chd: false,
             link_mode: crate::types::LinkMode::Hardlink,
             symlink_relative: false,
             header: None,
@@ -873,8 +1254,12 @@ mod chd_integration_tests {
             filter_regex: None,
             filter_regex_exclude: None,
             filter_language: None,
+            keep_languages: None,
+            keep_top_n: None,
             filter_region: None,
             filter_category_regex: None,
+            only_genre: None,
+            only_platform: None,
             no_bios: false,
             no_device: false,
             no_unlicensed: false,
@@ -886,8 +1271,14 @@ mod chd_integration_tests {
             no_prototype: false,
             no_program: false,
             verbose: 0,
+            no_color: false,
             quiet: 0,
             diag: false,
+            plan_diff: false,
+            region_map: None,
+            profile: false,
+            log_style: crate::types::LogStyle::Default,
+            exec: None,
             online_timeout_secs: Some(5),
             online_max_retries: Some(3),
             online_throttle_ms: None,
@@ -919,19 +1310,28 @@ mod chd_integration_tests {
             input: vec![PathBuf::from(f.path())],
             input_exclude: vec![],
             input_checksum_quick: false,
+            input_checksum_auto: false,
+            scan_files_only: false,
+            scan_archives_only: false,
             input_checksum_min: crate::types::Checksum::Sha1,
             input_checksum_max: None,
             input_checksum_archives: crate::types::ArchiveChecksumMode::Auto,
             dat: vec![],
             dat_exclude: vec![],
+            dat_url: vec![],
             dat_name_regex: None,
             dat_name_regex_exclude: None,
+            game_name_regex: None,
+            game_name_regex_exclude: None,
+            system: None,
+            dat_name_rewrite: None,
             dat_description_regex: None,
             dat_description_regex_exclude: None,
             dat_combine: false,
             dat_ignore_parent_clone: false,
             list_unmatched_dats: false,
             print_plan: true,
+            summary_only: false,
             enable_hasheous: false,
             igdb_client_id: None,
             igdb_client_secret: None,
@@ -940,31 +1340,68 @@ mod chd_integration_tests {
             igdb_mode: crate::types::IgdbLookupMode::BestEffort,
             cache_only: false,
             cache_db: None,
+            xattr_cache: false,
             hash_threads: None,
             scan_threads: None,
+            archive_threads: None,
             show_match_reasons: false,
+            min_score: None,
+            tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+            priority_input: false,
+            title_strip_regex: vec![],
+            ignore_case: true,
+            output_structure: None,
+            threads: None,
+            strict: false,
+            allow_no_matches: false,
+            dat_best_match: false,
+            dat_priority: Vec::new(),
+            dat_diff: None,
+            fixdat_format: None,
+            hash_algorithms: None,
+            hash_checkpoint_mib: None,
+            expect_crc32: None,
+            expect_md5: None,
+            expect_sha1: None,
+            expect_sha256: None,
+            max_decompressed_size: None,
+            skip_existing_checksum: false,
+            report_duplicates: false,
             patch: vec![],
             patch_exclude: vec![],
             output: None,
             dir_mirror: false,
+            dir_mirror_root: false,
             dir_dat_mirror: false,
             dir_dat_name: false,
             dir_dat_description: false,
             dir_letter: false,
+            dir_region: false,
             dir_letter_count: None,
             dir_letter_limit: None,
             dir_letter_group: false,
+            dir_letter_ignore_articles: false,
             dir_game_subdir: crate::types::DirGameSubdirMode::Multiple,
             fix_extension: crate::types::FixExtensionMode::Auto,
+            normalize_extension_case: false,
+            shorten_paths: false,
             overwrite: false,
             overwrite_invalid: false,
+            on_collision: crate::types::CollisionPolicy::Skip,
+            quarantine: None,
+            no_space_check: false,
             move_delete_dirs: crate::types::MoveDeleteDirsMode::Auto,
+            extract_include: None,
+            extract_exclude: None,
             clean_exclude: vec![],
             clean_backup: None,
+            clean_backup_keep: None,
             clean_dry_run: false,
             zip_format: crate::types::ZipFormat::Torrentzip,
-            zip_exclude: None,
+            zip_exclude: vec![],
             zip_dat_name: false,
+            zip_comment: None,
+            to_chd: false,
             link_mode: crate::types::LinkMode::Hardlink,
             symlink_relative: false,
             header: None,
@@ -979,8 +1416,12 @@ mod chd_integration_tests {
             filter_regex: None,
             filter_regex_exclude: None,
             filter_language: None,
+            keep_languages: None,
+            keep_top_n: None,
             filter_region: None,
             filter_category_regex: None,
+            only_genre: None,
+            only_platform: None,
             no_bios: false,
             no_device: false,
             no_unlicensed: false,
@@ -992,8 +1433,14 @@ mod chd_integration_tests {
             no_prototype: false,
             no_program: false,
             verbose: 0,
+            no_color: false,
             quiet: 0,
             diag: false,
+            plan_diff: false,
+            region_map: None,
+            profile: false,
+            log_style: crate::types::LogStyle::Default,
+            exec: None,
             online_timeout_secs: Some(5),
             online_max_retries: Some(3),
             online_throttle_ms: None,
@@ -1071,6 +1518,57 @@ fn assign_letter_dirs(records: &mut [FileRecord], config: &Config) -> anyhow::Re
     Ok(())
 }
 
+/// Give every record a unique file name for flat output, appending ` (N)` to later
+/// records whose base name (compared case-insensitively) is already taken.
+fn assign_flat_output_names(records: &mut [FileRecord]) {
+    fn base_name(record: &FileRecord) -> String {
+        record
+            .relative
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| record.relative.to_string_lossy().to_string())
+    }
+
+    let mut order: Vec<usize> = (0..records.len()).collect();
+    order.sort_by(|a, b| {
+        (&records[*a].relative, &records[*a].source)
+            .cmp(&(&records[*b].relative, &records[*b].source))
+    });
+
+    let mut taken: HashSet<String> = records
+        .iter()
+        .map(|r| base_name(r).to_lowercase())
+        .collect();
+    let mut claimed: HashSet<String> = HashSet::new();
+
+    for idx in order {
+        let name = base_name(&records[idx]);
+        if claimed.insert(name.to_lowercase()) {
+            records[idx].output_name = Some(name);
+            continue;
+        }
+
+        let path = Path::new(&name);
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| name.clone());
+        let extension = path.extension().map(|e| e.to_string_lossy().to_string());
+        let mut counter = 1;
+        let unique = loop {
+            let candidate = match &extension {
+                Some(ext) => format!("{stem} ({counter}).{ext}"),
+                None => format!("{stem} ({counter})"),
+            };
+            if taken.insert(candidate.to_lowercase()) {
+                break candidate;
+            }
+            counter += 1;
+        };
+        records[idx].output_name = Some(unique);
+    }
+}
+
 fn letter_key(record: &FileRecord, config: &Config) -> String {
     let count = config.dir_letter_count.unwrap_or(1);
     let relative_str = record.relative.to_string_lossy();
@@ -1086,6 +1584,11 @@ fn letter_key(record: &FileRecord, config: &Config) -> String {
                 .and_then(|s| s.to_str())
                 .unwrap_or(relative_str.as_ref())
         });
+    let candidate = if config.dir_letter_ignore_articles {
+        strip_leading_article(candidate)
+    } else {
+        candidate
+    };
 
     let mut key = candidate
         .chars()
@@ -1117,6 +1620,27 @@ fn letter_key(record: &FileRecord, config: &Config) -> String {
     }
 }
 
+/// Leading articles skipped by `--dir-letter-ignore-articles`. German "Die" is left out since
+/// it collides with English titles ("Die Hard").
+const LEADING_ARTICLES: &[&str] = &[
+    "The ", "A ", "An ", "Der ", "Das ", "Le ", "La ", "Les ", "L'", "El ", "Los ", "Las ", "Il ",
+    "Lo ", "Gli ",
+];
+
+fn strip_leading_article(name: &str) -> &str {
+    for article in LEADING_ARTICLES {
+        if let Some(prefix) = name.get(..article.len())
+            && prefix.eq_ignore_ascii_case(article)
+        {
+            let rest = name[article.len()..].trim_start();
+            if !rest.is_empty() {
+                return rest;
+            }
+        }
+    }
+    name
+}
+
 fn apply_filters(
     records: Vec<FileRecord>,
     config: &Config,
@@ -1188,10 +1712,78 @@ fn filter_by_regex(
     Ok(kept)
 }
 
+/// Apply `--only-genre` / `--only-platform` using the derived metadata. These run after
+/// online enrichment, so they are not part of `apply_filters`.
+pub fn filter_by_metadata(
+    records: Vec<FileRecord>,
+    config: &Config,
+    skipped: &mut Vec<SkippedFile>,
+) -> Vec<FileRecord> {
+    let genres = parse_list(config.only_genre.as_deref());
+    let platforms: Vec<String> = parse_list(config.only_platform.as_deref())
+        .into_iter()
+        .flat_map(|value| {
+            let token = game_console::romm_from_platform_name(&value);
+            std::iter::once(value).chain(token)
+        })
+        .collect();
+    if genres.is_empty() && platforms.is_empty() {
+        return records;
+    }
+
+    let mut kept = Vec::new();
+
+    for record in records {
+        if !genres.is_empty()
+            && !record
+                .derived_genres
+                .iter()
+                .any(|genre| genres.iter().any(|g| g.eq_ignore_ascii_case(genre.trim())))
+        {
+            skipped.push(SkippedFile {
+                path: record.relative.clone(),
+                reason: SkipReason::Metadata,
+                detail: Some(format!(
+                    "genres {:?} not in --only-genre '{}'",
+                    record.derived_genres,
+                    config.only_genre.as_deref().unwrap_or_default()
+                )),
+            });
+            continue;
+        }
+
+        if !platforms.is_empty() {
+            let platform = record
+                .derived_platform
+                .clone()
+                .or_else(|| game_console::romm_from_extension(&record.relative));
+            let matches = platform
+                .as_deref()
+                .is_some_and(|p| platforms.iter().any(|want| want.eq_ignore_ascii_case(p)));
+            if !matches {
+                skipped.push(SkippedFile {
+                    path: record.relative.clone(),
+                    reason: SkipReason::Metadata,
+                    detail: Some(format!(
+                        "platform {} not in --only-platform '{}'",
+                        platform.as_deref().unwrap_or("unknown"),
+                        config.only_platform.as_deref().unwrap_or_default()
+                    )),
+                });
+                continue;
+            }
+        }
+
+        kept.push(record);
+    }
+
+    kept
+}
+
 #[derive(Clone)]
 struct CandidateRecord {
     record: FileRecord,
-    region: Option<String>,
+    regions: Vec<String>,
     languages: Vec<String>,
     // title field unused in current logic; keep for future use
     #[allow(dead_code)]
@@ -1199,6 +1791,8 @@ struct CandidateRecord {
     quality: QualityInfo,
     revision: RevisionRank,
     set_info: Option<SetInfo>,
+    // Remaining members of an `.m3u` set; they are kept or skipped together with `record`.
+    set_members: Vec<FileRecord>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -1282,13 +1876,14 @@ impl RevisionRank {
 
 #[derive(Clone, Debug)]
 struct VariantMeta {
-    region: Option<String>,
+    regions: Vec<String>,
     languages: Vec<String>,
     quality: QualityInfo,
     revision: RevisionRank,
     set_info: Option<SetInfo>,
     region_rank: usize,
     language_rank: usize,
+    matched_region: Option<String>,
     matched_language: Option<String>,
 }
 
@@ -1299,38 +1894,66 @@ fn filter_by_region_and_language(
 ) -> Vec<FileRecord> {
     let region_preferences = parse_list(config.filter_region.as_deref());
     let language_preferences = parse_list(config.filter_language.as_deref());
-
-    if region_preferences.is_empty() && language_preferences.is_empty() {
+    let keep_languages = parse_list(config.keep_languages.as_deref());
+    let keep_top_n = config.keep_top_n.unwrap_or(1);
+    let title_strip = config.title_strip_patterns();
+
+    if region_preferences.is_empty()
+        && language_preferences.is_empty()
+        && keep_languages.is_empty()
+        && config.keep_top_n.is_none()
+    {
         return records;
     }
 
     let mut grouped: HashMap<String, Vec<CandidateRecord>> = HashMap::new();
 
+    // Files listed by one `.m3u` compete as a single candidate named after the playlist.
+    let mut sets: Vec<(String, Vec<FileRecord>)> = Vec::new();
+    let mut singles = Vec::new();
     for record in records {
-        let name = record
+        match record.set_name.clone() {
+            Some(set_name) => match sets.iter_mut().find(|(name, _)| *name == set_name) {
+                Some((_, members)) => members.push(record),
+                None => sets.push((set_name, vec![record])),
+            },
+            None => singles.push((None, vec![record])),
+        }
+    }
+    let units = sets
+        .into_iter()
+        .map(|(name, members)| (Some(name), members))
+        .chain(singles);
+
+    for (set_name, mut members) in units {
+        let record = members.remove(0);
+        let stem = record
             .relative
             .file_stem()
             .and_then(|s| s.to_str())
-            .unwrap_or_default();
+            .unwrap_or_default()
+            .to_string();
+        let name = set_name.as_deref().unwrap_or(&stem);
         let tags = extract_tags(name);
-        let region = detect_region(&tags);
-        let languages = detect_languages(&tags);
+        let regions = detect_regions(&tags, config.region_map.as_ref());
+        let languages = detect_languages(&tags, config.region_map.as_ref());
         let quality = detect_quality(&tags);
         let revision = detect_revision(&tags);
         let set_info = detect_set_info(&tags);
-        let title = normalize_title(name);
+        let title = normalize_title_with(name, &title_strip);
 
         grouped
             .entry(title.clone())
             .or_default()
             .push(CandidateRecord {
                 record,
-                region,
+                regions,
                 languages,
                 title,
                 quality,
                 revision,
                 set_info,
+                set_members: members,
             });
     }
 
@@ -1340,49 +1963,77 @@ fn filter_by_region_and_language(
         candidates
             .sort_by(|a, b| compare_candidates(a, b, &region_preferences, &language_preferences));
 
-        let mut kept_variant: Option<VariantMeta> = None;
+        let is_acceptable = |cand: &CandidateRecord| {
+            preferences_satisfied(
+                preference_rank(&cand.regions, &region_preferences) < region_preferences.len(),
+                preference_rank(&cand.languages, &language_preferences)
+                    < language_preferences.len(),
+                !region_preferences.is_empty(),
+                !language_preferences.is_empty(),
+            )
+        };
+        // With --keep-languages the title keeps one variant per listed language; variants in
+        // none of those languages only compete when no listed language is available.
+        let language_class =
+            |cand: &CandidateRecord| matched_preference(&cand.languages, &keep_languages);
+        let has_language_class = candidates
+            .iter()
+            .any(|cand| is_acceptable(cand) && language_class(cand).is_some());
+
+        // Best kept variant and number of kept variants per language class.
+        let mut kept_variants: HashMap<Option<String>, (VariantMeta, usize)> = HashMap::new();
+        let mut first_kept: Option<VariantMeta> = None;
 
         for cand in candidates {
-            let region_rank = preference_rank(cand.region.as_deref(), &region_preferences);
-            let lang_rank = language_rank(&cand.languages, &language_preferences);
-            let matched_lang = matched_language(&cand.languages, &language_preferences);
+            let region_rank = preference_rank(&cand.regions, &region_preferences);
+            let lang_rank = preference_rank(&cand.languages, &language_preferences);
+            let matched_region = matched_preference(&cand.regions, &region_preferences);
+            let matched_lang = matched_preference(&cand.languages, &language_preferences);
+            let class = language_class(&cand);
 
             let meta = VariantMeta {
-                region: cand.region.clone(),
+                regions: cand.regions.clone(),
                 languages: cand.languages.clone(),
                 quality: cand.quality.clone(),
                 revision: cand.revision.clone(),
                 set_info: cand.set_info.clone(),
                 region_rank,
                 language_rank: lang_rank,
+                matched_region,
                 matched_language: matched_lang,
             };
 
             let mut detail = None;
-            let region_match = region_rank < region_preferences.len();
-            let language_match = lang_rank < language_preferences.len();
-
-            let acceptable = preferences_satisfied(
-                region_match,
-                language_match,
-                !region_preferences.is_empty(),
-                !language_preferences.is_empty(),
-            );
-
-            if acceptable && kept_variant.is_none() {
-                kept_variant = Some(meta.clone());
+            let acceptable = is_acceptable(&cand);
+            let class_open = kept_variants
+                .get(&class)
+                .is_none_or(|(_, count)| *count < keep_top_n)
+                && (class.is_some() || !has_language_class);
+
+            if acceptable && class_open {
+                first_kept.get_or_insert_with(|| meta.clone());
+                kept_variants.entry(class).or_insert_with(|| (meta, 0)).1 += 1;
                 kept.push(cand.record);
+                kept.extend(cand.set_members);
                 continue;
             }
 
             if acceptable {
-                if let Some(best) = &kept_variant {
+                if let Some(best) = kept_variants
+                    .get(&class)
+                    .map(|(best, _)| best)
+                    .or(first_kept.as_ref())
+                {
                     detail = build_skip_detail(&meta, best).or_else(|| {
                         Some("another variant provided a closer region/language match".to_string())
                     });
                 }
             } else {
-                let region_info = meta.region.clone().unwrap_or_else(|| "unknown".to_string());
+                let region_info = if meta.regions.is_empty() {
+                    "unknown".to_string()
+                } else {
+                    meta.regions.join(", ")
+                };
                 let lang_info = if meta.languages.is_empty() {
                     "unknown".to_string()
                 } else {
@@ -1394,11 +2045,13 @@ fn filter_by_region_and_language(
                 ));
             }
 
-            skipped.push(SkippedFile {
-                path: cand.record.relative.clone(),
-                reason: SkipReason::RegionLanguage,
-                detail,
-            });
+            for member in std::iter::once(&cand.record).chain(&cand.set_members) {
+                skipped.push(SkippedFile {
+                    path: member.relative.clone(),
+                    reason: SkipReason::RegionLanguage,
+                    detail: detail.clone(),
+                });
+            }
         }
     }
 
@@ -1425,15 +2078,15 @@ fn compare_candidates(
     region_preferences: &[String],
     language_preferences: &[String],
 ) -> std::cmp::Ordering {
-    let region_rank_a = preference_rank(a.region.as_deref(), region_preferences);
-    let region_rank_b = preference_rank(b.region.as_deref(), region_preferences);
+    let region_rank_a = preference_rank(&a.regions, region_preferences);
+    let region_rank_b = preference_rank(&b.regions, region_preferences);
 
     if region_rank_a != region_rank_b {
         return region_rank_a.cmp(&region_rank_b);
     }
 
-    let lang_rank_a = language_rank(&a.languages, language_preferences);
-    let lang_rank_b = language_rank(&b.languages, language_preferences);
+    let lang_rank_a = preference_rank(&a.languages, language_preferences);
+    let lang_rank_b = preference_rank(&b.languages, language_preferences);
 
     if lang_rank_a != lang_rank_b {
         return lang_rank_a.cmp(&lang_rank_b);
@@ -1466,22 +2119,17 @@ fn compare_set_info(a: Option<&SetInfo>, b: Option<&SetInfo>) -> std::cmp::Order
     }
 }
 
-fn preference_rank(value: Option<&str>, preferences: &[String]) -> usize {
-    value
-        .and_then(|v| preferences.iter().position(|pref| pref == v))
-        .unwrap_or(preferences.len())
-}
-
-fn language_rank(languages: &[String], preferences: &[String]) -> usize {
+/// Rank a set of detected values by the highest user preference present in it.
+fn preference_rank(values: &[String], preferences: &[String]) -> usize {
     preferences
         .iter()
-        .position(|pref| languages.iter().any(|lang| lang == pref))
+        .position(|pref| values.iter().any(|value| value == pref))
         .unwrap_or(preferences.len())
 }
 
-fn matched_language(languages: &[String], preferences: &[String]) -> Option<String> {
+fn matched_preference(values: &[String], preferences: &[String]) -> Option<String> {
     for pref in preferences {
-        if languages.iter().any(|lang| lang == pref) {
+        if values.iter().any(|value| value == pref) {
             return Some(pref.clone());
         }
     }
@@ -1557,6 +2205,17 @@ fn extract_tags(name: &str) -> Vec<TagSegment> {
 }
 
 pub(crate) fn normalize_title(name: &str) -> String {
+    normalize_title_with(name, &[])
+}
+
+/// [`normalize_title`] after first removing every match of the `--title-strip-regex`
+/// patterns in `strip`.
+pub(crate) fn normalize_title_with(name: &str, strip: &[Regex]) -> String {
+    let mut name = name.to_string();
+    for pattern in strip {
+        name = pattern.replace_all(&name, "").into_owned();
+    }
+
     let mut clean = String::new();
     let mut depth = 0usize;
 
@@ -1584,28 +2243,67 @@ pub(crate) fn normalize_title(name: &str) -> String {
         .to_string()
 }
 
-fn detect_region(tags: &[TagSegment]) -> Option<String> {
-    for tag in tags {
-        for token in tag_tokens(&tag.value) {
-            if let Some(region) = normalize_region_token(&token) {
-                return Some(region.to_string());
-            }
-        }
-    }
+/// User tokens loaded by `--region-map`, consulted before the built-in region and language
+/// tables. The JSON file holds `{"regions": {"Scandinavia": "EUR"}, "languages": {...}}`;
+/// tokens are single words as they appear in tags and are matched case-insensitively.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct RegionMap {
+    #[serde(default)]
+    pub regions: HashMap<String, String>,
+    #[serde(default)]
+    pub languages: HashMap<String, String>,
+}
 
-    None
+impl RegionMap {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("reading region map: {}", path.display()))?;
+        let map: RegionMap = serde_json::from_str(&raw)
+            .with_context(|| format!("parsing region map: {}", path.display()))?;
+        let upper = |table: HashMap<String, String>| {
+            table
+                .into_iter()
+                .map(|(token, value)| (token.trim().to_uppercase(), value.trim().to_uppercase()))
+                .collect()
+        };
+        Ok(RegionMap {
+            regions: upper(map.regions),
+            languages: upper(map.languages),
+        })
+    }
+}
+
+fn detect_regions(tags: &[TagSegment], map: Option<&RegionMap>) -> Vec<String> {
+    let mut regions = Vec::new();
+
+    for tag in tags {
+        for token in tag_tokens(&tag.value) {
+            let region = map
+                .and_then(|map| map.regions.get(&token).cloned())
+                .or_else(|| normalize_region_token(&token).map(str::to_string));
+            if let Some(region) = region
+                && !regions.contains(&region)
+            {
+                regions.push(region);
+            }
+        }
+    }
+
+    regions
 }
 
-fn detect_languages(tags: &[TagSegment]) -> Vec<String> {
+fn detect_languages(tags: &[TagSegment], map: Option<&RegionMap>) -> Vec<String> {
     let mut langs = Vec::new();
 
     for tag in tags {
         for token in tag_tokens(&tag.value) {
-            if let Some(lang) = normalize_language_token(&token) {
-                let lang = lang.to_string();
-                if !langs.contains(&lang) {
-                    langs.push(lang);
-                }
+            let lang = map
+                .and_then(|map| map.languages.get(&token).cloned())
+                .or_else(|| normalize_language_token(&token).map(str::to_string));
+            if let Some(lang) = lang
+                && !langs.contains(&lang)
+            {
+                langs.push(lang);
             }
         }
     }
@@ -1902,12 +2600,14 @@ fn build_skip_detail(candidate: &VariantMeta, winner: &VariantMeta) -> Option<St
 
     if winner.region_rank < candidate.region_rank {
         let winner_region = winner
-            .region
+            .matched_region
             .clone()
+            .or_else(|| winner.regions.first().cloned())
             .unwrap_or_else(|| "unknown".to_string());
         let candidate_region = candidate
-            .region
+            .matched_region
             .clone()
+            .or_else(|| candidate.regions.first().cloned())
             .unwrap_or_else(|| "unknown".to_string());
         parts.push(format!(
             "preferred region {winner_region} over {candidate_region}"
@@ -2025,6 +2725,15 @@ pub fn resolve_output_path(record: &FileRecord, config: &Config) -> PathBuf {
     resolve_output_path_with_dats(record, config, None)
 }
 
+/// The part of `--output` before its first `{token}` component, which is the same for
+/// every record; the whole path when it has no tokens.
+pub(crate) fn output_static_prefix(output: &Path) -> PathBuf {
+    output
+        .components()
+        .take_while(|component| !component.as_os_str().to_string_lossy().contains('{'))
+        .collect()
+}
+
 /// Resolve output path, optionally using DAT entries to prefer DAT-derived RomM tokens.
 pub fn resolve_output_path_with_dats(
     record: &FileRecord,
@@ -2072,15 +2781,55 @@ pub fn resolve_output_path_with_dats(
         base = PathBuf::from(base.to_string_lossy().replace("{language}", &replacement));
     }
 
-    if config.dir_mirror {
-        if let Some(parent) = record.relative.parent() {
-            base = base.join(parent);
-        }
+    if base.to_string_lossy().contains("{serial}") {
+        let replacement = resolve_serial_token(record, dats);
+        base = PathBuf::from(base.to_string_lossy().replace("{serial}", &replacement));
     }
 
-    if config.dir_letter {
-        if let Some(letter) = &record.letter_dir {
-            base = base.join(letter);
+    match config.output_structure {
+        Some(OutputStructure::Flat) => {}
+        Some(OutputStructure::Mirror) => base = base.join(mirrored_dir(record, config)),
+        Some(OutputStructure::Dat) => {
+            let dat_dir = dats
+                .and_then(|dats| crate::dat::find_dat_for_record(record, dats))
+                .map(|dat| dat.source_dat_name());
+            if let Some(dir) = dat_dir.as_deref().and_then(sanitize_path_segment) {
+                base = base.join(dir);
+            }
+        }
+        None => {
+            if config.dir_dat_name || config.dir_dat_description {
+                let dat = dats.and_then(|dats| crate::dat::find_dat_for_record(record, dats));
+                if config.dir_dat_name
+                    && let Some(dir) = dat
+                        .as_ref()
+                        .and_then(|dat| sanitize_path_segment(&dat.source_dat_name()))
+                {
+                    base = base.join(dir);
+                }
+                if config.dir_dat_description
+                    && let Some(dir) = dat
+                        .as_ref()
+                        .and_then(|dat| dat.dat_description.as_deref())
+                        .and_then(sanitize_path_segment)
+                {
+                    base = base.join(dir);
+                }
+            }
+
+            if config.dir_mirror {
+                base = base.join(mirrored_dir(record, config));
+            }
+
+            if config.dir_region {
+                base = base.join(region_directory(record, config));
+            }
+
+            if config.dir_letter {
+                if let Some(letter) = &record.letter_dir {
+                    base = base.join(letter);
+                }
+            }
         }
     }
 
@@ -2114,18 +2863,148 @@ pub fn resolve_output_path_with_dats(
         base = PathBuf::from(base_str);
     }
 
-    if matches!(config.dir_game_subdir, DirGameSubdirMode::Always) {
-        if let Some(stem) = record.relative.file_stem().and_then(|s| s.to_str()) {
-            base = base.join(stem);
+    // A game's subdirectory is its `.m3u` set name, or the file stem for single-file games.
+    if config.output_structure.is_none() {
+        let stem = record.relative.file_stem().and_then(|s| s.to_str());
+        let subdir = match config.dir_game_subdir {
+            DirGameSubdirMode::Never => None,
+            DirGameSubdirMode::Multiple => record.set_name.as_deref(),
+            DirGameSubdirMode::Always => record.set_name.as_deref().or(stem),
+        };
+        if let Some(subdir) = subdir.and_then(sanitize_path_segment) {
+            base = base.join(subdir);
         }
     }
 
-    base.join(
-        record
-            .relative
-            .file_name()
-            .unwrap_or_else(|| record.relative.as_os_str()),
-    )
+    let mut target = match &record.output_name {
+        Some(name) => base.join(name),
+        _ => base.join(
+            record
+                .relative
+                .file_name()
+                .unwrap_or_else(|| record.relative.as_os_str()),
+        ),
+    };
+
+    // Decided on the name written, which may be a DAT name rather than the input's.
+    if let Some(ext) = &record.fixed_extension
+        && crate::candidate_extension::fixes_extension(ext, &target, &config.fix_extension)
+    {
+        target.set_extension(ext);
+    }
+
+    if config.normalize_extension_case {
+        target = lowercase_extension(target);
+    }
+    if config.shorten_paths {
+        let root_depth = config
+            .output
+            .as_deref()
+            .unwrap_or(Path::new("output"))
+            .components()
+            .count();
+        // The limit applies to the absolute path the OS sees, not a relative `--output`.
+        let length = |path: &Path| path.to_string_lossy().chars().count();
+        let prefix = std::path::absolute(&target)
+            .map(|absolute| length(&absolute).saturating_sub(length(&target)))
+            .unwrap_or(0);
+        target = shorten_output_path(target, root_depth, MAX_OUTPUT_PATH.saturating_sub(prefix));
+    }
+    target
+}
+
+/// Folder `--dir-mirror` recreates under the output: the source's folder relative to the
+/// input root it was scanned from (prefixed with the root's name for `--dir-mirror-root`),
+/// plus any folder inside an archive. Records without a root mirror `relative` alone.
+fn mirrored_dir(record: &FileRecord, config: &Config) -> PathBuf {
+    let inner = record.relative.parent().unwrap_or(Path::new(""));
+    let Some(root) = record.input_root.as_deref() else {
+        return inner.to_path_buf();
+    };
+    let mut dir = PathBuf::new();
+    if config.dir_mirror_root
+        && let Some(name) = root.file_name()
+    {
+        dir.push(name);
+    }
+    if let Some(folder) = record
+        .source
+        .parent()
+        .and_then(|parent| parent.strip_prefix(root).ok())
+    {
+        dir.push(folder);
+    }
+    // Archive members also keep their folder inside the archive.
+    if !record.source.ends_with(&record.relative) {
+        dir.push(inner);
+    }
+    dir
+}
+
+/// Longest output path `--shorten-paths` produces and the preflight allows on Windows
+/// (`MAX_PATH`).
+pub const MAX_OUTPUT_PATH: usize = 260;
+
+/// Directory names are never cut below this many characters, hash suffix included.
+const MIN_SHORTENED_COMPONENT: usize = 12;
+
+/// Truncate the directories between the first `root_depth` components and the file name,
+/// longest first, until `path` fits in `limit` characters. A cut directory ends in `~` and
+/// a short hash of its full name, so names sharing a prefix stay distinct. Deterministic:
+/// the same path always shortens the same way. Paths that cannot fit are returned as short
+/// as allowed.
+pub(crate) fn shorten_output_path(path: PathBuf, root_depth: usize, limit: usize) -> PathBuf {
+    let length = |path: &Path| path.to_string_lossy().chars().count();
+    if length(&path) <= limit {
+        return path;
+    }
+    let components: Vec<std::ffi::OsString> = path
+        .components()
+        .map(|c| c.as_os_str().to_os_string())
+        .collect();
+    if components.len() < root_depth + 2 {
+        return path;
+    }
+    let dirs = root_depth..components.len() - 1;
+    let longest = components[dirs.clone()]
+        .iter()
+        .map(|c| c.to_string_lossy().chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut shortest = path.clone();
+    for cap in (MIN_SHORTENED_COMPONENT..longest).rev() {
+        let candidate: PathBuf = components
+            .iter()
+            .enumerate()
+            .map(|(idx, component)| {
+                let name = component.to_string_lossy();
+                if dirs.contains(&idx) && name.chars().count() > cap {
+                    let hash = crc32fast::hash(name.as_bytes()) & 0xff_ffff;
+                    let cut: String = name.chars().take(cap - 7).collect();
+                    // Windows drops trailing dots and spaces from directory names.
+                    let cut = cut.trim_end_matches([' ', '.']);
+                    std::ffi::OsString::from(format!("{cut}~{hash:06x}"))
+                } else {
+                    component.clone()
+                }
+            })
+            .collect();
+        if length(&candidate) <= limit {
+            return candidate;
+        }
+        shortest = candidate;
+    }
+    shortest
+}
+
+fn lowercase_extension(path: PathBuf) -> PathBuf {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.chars().any(|c| c.is_uppercase()) => {
+            path.with_extension(ext.to_lowercase())
+        }
+        _ => path,
+    }
 }
 
 /// Return a RomM platform token for a filename based on its extension.
@@ -2169,6 +3048,41 @@ fn resolve_region_token(record: &FileRecord) -> String {
     sanitize_path_segment(&candidate).unwrap_or_else(|| FALLBACK.to_string())
 }
 
+/// Folder for `--dir-region`. A multi-region name such as `(USA, Europe)` goes under the
+/// region ranked highest by `--filter-region`, else the first one listed; names without a
+/// region fall back to the record's derived region, then to `Unknown Region`.
+fn region_directory(record: &FileRecord, config: &Config) -> String {
+    const FALLBACK: &str = "Unknown Region";
+    let name = record
+        .relative
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let preferences = parse_list(config.filter_region.as_deref());
+    detect_regions(&extract_tags(&name), config.region_map.as_ref())
+        .into_iter()
+        .min_by_key(|region| {
+            preferences
+                .iter()
+                .position(|pref| pref == region)
+                .unwrap_or(usize::MAX)
+        })
+        .or_else(|| record.derived_region.clone())
+        .and_then(|region| sanitize_path_segment(&region))
+        .unwrap_or_else(|| FALLBACK.to_string())
+}
+
+fn resolve_serial_token(record: &FileRecord, dats: Option<&[crate::dat::DatRom]>) -> String {
+    const FALLBACK: &str = "unknown-serial";
+    let candidate = dats
+        .and_then(|dats| crate::dat::find_dat_for_record(record, dats))
+        .and_then(|dat| dat.serial)
+        .map(|val| val.trim().to_string())
+        .filter(|val| !val.is_empty())
+        .unwrap_or_else(|| FALLBACK.to_string());
+    sanitize_path_segment(&candidate).unwrap_or_else(|| FALLBACK.to_string())
+}
+
 fn resolve_language_token(record: &FileRecord) -> String {
     const FALLBACK: &str = "unknown-language";
     let candidate = record
@@ -2186,20 +3100,36 @@ fn resolve_language_token(record: &FileRecord) -> String {
     sanitize_path_segment(&candidate).unwrap_or_else(|| FALLBACK.to_string())
 }
 
+/// Device names Windows refuses as file or directory names, with or without an extension.
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Turn a DAT name, description or token value into a single directory name. Characters
+/// that are illegal on any supported platform become `_`, so the same input maps to the
+/// same directory everywhere.
 fn sanitize_path_segment(input: &str) -> Option<String> {
     let mut cleaned = String::new();
     for ch in input.trim().chars() {
         match ch {
-            '/' | '\\' => cleaned.push('_'),
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => cleaned.push('_'),
             c if c.is_control() => continue,
             _ => cleaned.push(ch),
         }
     }
-    let normalized = cleaned
+    let mut normalized = cleaned
         .trim_matches('.')
         .trim()
         .trim_matches('-')
         .to_string();
+    let stem_len = normalized.split('.').next().unwrap_or_default().len();
+    if WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(&normalized[..stem_len]))
+    {
+        normalized.insert(stem_len, '_');
+    }
     if normalized.is_empty() {
         None
     } else {
@@ -2207,13 +3137,13 @@ fn sanitize_path_segment(input: &str) -> Option<String> {
     }
 }
 
-fn annotate_locale_metadata(records: &mut [FileRecord]) {
+fn annotate_locale_metadata(records: &mut [FileRecord], map: Option<&RegionMap>) {
     for record in records {
-        populate_locale_tokens(record);
+        populate_locale_tokens(record, map);
     }
 }
 
-pub(crate) fn populate_locale_tokens(record: &mut FileRecord) {
+pub(crate) fn populate_locale_tokens(record: &mut FileRecord, map: Option<&RegionMap>) {
     let needs_region = record.derived_region.is_none();
     let needs_languages = record.derived_languages.is_empty();
     if !needs_region && !needs_languages {
@@ -2234,10 +3164,10 @@ pub(crate) fn populate_locale_tokens(record: &mut FileRecord) {
 
     let tags = extract_tags(&name);
     if needs_region && record.derived_region.is_none() {
-        record.derived_region = detect_region(&tags);
+        record.derived_region = detect_regions(&tags, map).into_iter().next();
     }
     if needs_languages && record.derived_languages.is_empty() {
-        record.derived_languages = detect_languages(&tags);
+        record.derived_languages = detect_languages(&tags, map);
     }
 }
 
@@ -2273,6 +3203,12 @@ mod tests {
             derived_region: None,
             derived_languages: Vec::new(),
             scan_info: None,
+            output_name: None,
+            set_name: None,
+            input_root: None,
+            modified: None,
+            input_index: None,
+            fixed_extension: None,
         }
     }
 
@@ -2282,19 +3218,28 @@ mod tests {
             input: vec![],
             input_exclude: vec![],
             input_checksum_quick: false,
+            input_checksum_auto: false,
+            scan_files_only: false,
+            scan_archives_only: false,
             input_checksum_min: Checksum::Crc32,
             input_checksum_max: None,
             input_checksum_archives: ArchiveChecksumMode::Auto,
             dat: vec![],
             dat_exclude: vec![],
+            dat_url: vec![],
             dat_name_regex: None,
             dat_name_regex_exclude: None,
+            game_name_regex: None,
+            game_name_regex_exclude: None,
+            system: None,
+            dat_name_rewrite: None,
             dat_description_regex: None,
             dat_description_regex_exclude: None,
             dat_combine: false,
             dat_ignore_parent_clone: false,
             list_unmatched_dats: false,
             print_plan: true,
+            summary_only: false,
             enable_hasheous: false,
             igdb_client_id: None,
             igdb_client_secret: None,
@@ -2303,31 +3248,68 @@ mod tests {
             igdb_mode: crate::types::IgdbLookupMode::BestEffort,
             cache_only: false,
             cache_db: None,
+            xattr_cache: false,
             hash_threads: None,
             scan_threads: None,
+            archive_threads: None,
             show_match_reasons: false,
+            min_score: None,
+            tiebreak_mtime: crate::types::MtimeTiebreak::Path,
+            priority_input: false,
+            title_strip_regex: vec![],
+            ignore_case: true,
+            output_structure: None,
+            threads: None,
+            strict: false,
+            allow_no_matches: false,
+            dat_best_match: false,
+            dat_priority: Vec::new(),
+            dat_diff: None,
+            fixdat_format: None,
+            hash_algorithms: None,
+            hash_checkpoint_mib: None,
+            expect_crc32: None,
+            expect_md5: None,
+            expect_sha1: None,
+            expect_sha256: None,
+            max_decompressed_size: None,
+            skip_existing_checksum: false,
+            report_duplicates: false,
             patch: vec![],
             patch_exclude: vec![],
             output: None,
             dir_mirror: false,
+            dir_mirror_root: false,
             dir_dat_mirror: false,
             dir_dat_name: false,
             dir_dat_description: false,
             dir_letter: false,
+            dir_region: false,
             dir_letter_count: None,
             dir_letter_limit: None,
             dir_letter_group: false,
+            dir_letter_ignore_articles: false,
             dir_game_subdir: DirGameSubdirMode::Multiple,
             fix_extension: FixExtensionMode::Auto,
+            normalize_extension_case: false,
+            shorten_paths: false,
             overwrite: false,
             overwrite_invalid: false,
+            on_collision: crate::types::CollisionPolicy::Skip,
+            quarantine: None,
+            no_space_check: false,
             move_delete_dirs: MoveDeleteDirsMode::Auto,
+            extract_include: None,
+            extract_exclude: None,
             clean_exclude: vec![],
             clean_backup: None,
+            clean_backup_keep: None,
             clean_dry_run: false,
             zip_format: ZipFormat::Torrentzip,
-            zip_exclude: None,
+            zip_exclude: vec![],
             zip_dat_name: false,
+            zip_comment: None,
+            to_chd: false,
             link_mode: LinkMode::Hardlink,
             symlink_relative: false,
             header: None,
@@ -2342,8 +3324,12 @@ mod tests {
             filter_regex: None,
             filter_regex_exclude: None,
             filter_language: language.map(|s| s.to_string()),
+            keep_languages: None,
+            keep_top_n: None,
             filter_region: region.map(|s| s.to_string()),
             filter_category_regex: None,
+            only_genre: None,
+            only_platform: None,
             no_bios: false,
             no_device: false,
             no_unlicensed: false,
@@ -2355,8 +3341,14 @@ mod tests {
             no_prototype: false,
             no_program: false,
             verbose: 0,
+            no_color: false,
             quiet: 0,
             diag: false,
+            plan_diff: false,
+            region_map: None,
+            profile: false,
+            log_style: crate::types::LogStyle::Default,
+            exec: None,
             online_timeout_secs: None,
             online_max_retries: None,
             online_throttle_ms: None,
@@ -2388,6 +3380,94 @@ mod tests {
         );
     }
 
+    #[test]
+    fn multi_region_release_ranks_by_top_preference() {
+        let config = test_config(Some("EUR,USA"), None);
+        let records = vec![
+            dummy_record("Sonic the Hedgehog (USA).md"),
+            dummy_record("Sonic the Hedgehog (USA, Europe).md"),
+        ];
+
+        let mut skipped = Vec::new();
+        let filtered = filter_by_region_and_language(records, &config, &mut skipped);
+
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(
+            filtered[0].relative,
+            PathBuf::from("Sonic the Hedgehog (USA, Europe).md")
+        );
+        assert_eq!(
+            detect_regions(&extract_tags("Sonic the Hedgehog (USA, Europe).md"), None),
+            vec!["USA".to_string(), "EUR".to_string()]
+        );
+        let detail = skipped[0].detail.as_deref().expect("detail present");
+        assert!(detail.contains("preferred region EUR over USA"));
+    }
+
+    #[test]
+    fn keep_languages_keeps_best_variant_per_language() {
+        let mut config = test_config(Some("EUR,USA"), None);
+        config.keep_languages = Some("EN,FR".to_string());
+        let records = vec![
+            dummy_record("Asterix (Europe) (En).nes"),
+            dummy_record("Asterix (USA) (En).nes"),
+            dummy_record("Asterix (France) (Fr).nes"),
+            dummy_record("Asterix (Germany) (De).nes"),
+        ];
+
+        let mut skipped = Vec::new();
+        let mut kept: Vec<PathBuf> = filter_by_region_and_language(records, &config, &mut skipped)
+            .into_iter()
+            .map(|r| r.relative)
+            .collect();
+        kept.sort();
+
+        assert_eq!(
+            kept,
+            vec![
+                PathBuf::from("Asterix (Europe) (En).nes"),
+                PathBuf::from("Asterix (France) (Fr).nes"),
+            ]
+        );
+        let mut skipped_paths: Vec<PathBuf> = skipped.into_iter().map(|s| s.path).collect();
+        skipped_paths.sort();
+        assert_eq!(
+            skipped_paths,
+            vec![
+                PathBuf::from("Asterix (Germany) (De).nes"),
+                PathBuf::from("Asterix (USA) (En).nes"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keep_top_n_keeps_the_best_ranked_variants() {
+        let mut config = test_config(Some("USA,EUR,JPN"), None);
+        config.keep_top_n = Some(2);
+        let records = vec![
+            dummy_record("Tetris (Japan).gb"),
+            dummy_record("Tetris (Europe).gb"),
+            dummy_record("Tetris (USA).gb"),
+        ];
+
+        let mut skipped = Vec::new();
+        let mut kept: Vec<PathBuf> = filter_by_region_and_language(records, &config, &mut skipped)
+            .into_iter()
+            .map(|r| r.relative)
+            .collect();
+        kept.sort();
+
+        assert_eq!(
+            kept,
+            vec![
+                PathBuf::from("Tetris (Europe).gb"),
+                PathBuf::from("Tetris (USA).gb"),
+            ]
+        );
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].path, PathBuf::from("Tetris (Japan).gb"));
+    }
+
     #[test]
     fn skips_titles_without_preferred_regions_or_languages() {
         let config = test_config(Some("EUR,USA"), Some("EN"));
@@ -2429,6 +3509,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn only_platform_keeps_matching_derived_platforms() {
+        let mut cfg = test_config(None, None);
+        cfg.only_platform = Some("SNES, gba".to_string());
+        let mut derived = dummy_record("Sonic.bin");
+        derived.derived_platform = Some("genesis-slash-megadrive".to_string());
+        let records = vec![
+            dummy_record("Super Mario World.sfc"),
+            dummy_record("Metroid Fusion.gba"),
+            dummy_record("Pokemon Red.gb"),
+            derived,
+        ];
+        let mut skipped = Vec::new();
+        let kept = filter_by_metadata(records, &cfg, &mut skipped);
+        let names: Vec<_> = kept.iter().map(|r| r.relative.clone()).collect();
+        assert_eq!(
+            names,
+            vec![
+                PathBuf::from("Super Mario World.sfc"),
+                PathBuf::from("Metroid Fusion.gba")
+            ]
+        );
+        assert_eq!(skipped.len(), 2);
+        assert!(skipped.iter().all(|s| s.reason == SkipReason::Metadata));
+    }
+
+    #[test]
+    fn only_genre_matches_any_derived_genre_case_insensitively() {
+        let mut cfg = test_config(None, None);
+        cfg.only_genre = Some("rpg,Platformer".to_string());
+        let mut rpg = dummy_record("Chrono Trigger.sfc");
+        rpg.derived_genres = vec!["Adventure".to_string(), "RPG".to_string()];
+        let mut platformer = dummy_record("Super Mario World.sfc");
+        platformer.derived_genres = vec!["platformer".to_string()];
+        let mut racing = dummy_record("F-Zero.sfc");
+        racing.derived_genres = vec!["Racing".to_string()];
+        let unknown = dummy_record("Unknown.sfc");
+        let records = vec![rpg, platformer, racing, unknown];
+        let mut skipped = Vec::new();
+        let kept = filter_by_metadata(records, &cfg, &mut skipped);
+        assert_eq!(kept.len(), 2);
+        assert_eq!(skipped.len(), 2);
+        assert!(
+            skipped
+                .iter()
+                .any(|s| s.detail.as_deref().is_some_and(|d| d.contains("Racing")))
+        );
+    }
+
     #[test]
     fn skip_detail_mentions_quality_differences() {
         let config = test_config(Some("USA"), None);
@@ -2475,19 +3604,42 @@ mod tests {
     #[test]
     fn detect_region_and_language_from_country_names() {
         let tags = extract_tags("Ape Escape (France).chd");
-        let region = detect_region(&tags);
-        let languages = detect_languages(&tags);
+        let regions = detect_regions(&tags, None);
+        let languages = detect_languages(&tags, None);
 
-        assert_eq!(region.as_deref(), Some("EUR"));
+        assert_eq!(regions, vec!["EUR".to_string()]);
         assert!(languages.iter().any(|lang| lang == "FR"));
     }
 
+    #[test]
+    fn region_map_teaches_extra_region_and_language_tokens() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("regions.json");
+        fs::write(
+            &path,
+            r#"{"regions": {"Scandinavia": "Nordic"}, "languages": {"scandinavia": "sv"}}"#,
+        )
+        .unwrap();
+        let map = RegionMap::load(&path).unwrap();
+
+        let tags = extract_tags("Game (Scandinavia).bin");
+        assert!(detect_regions(&tags, None).is_empty());
+        assert_eq!(
+            detect_regions(&tags, Some(&map)),
+            vec!["NORDIC".to_string()]
+        );
+        assert_eq!(detect_languages(&tags, Some(&map)), vec!["SV".to_string()]);
+
+        let tags = extract_tags("Game (Europe).bin");
+        assert_eq!(detect_regions(&tags, Some(&map)), vec!["EUR".to_string()]);
+    }
+
     #[test]
     fn detect_languages_handles_no_intro_european_tokens() {
         let tags = extract_tags(
             "2 Games in 1 - Finding Nemo + Finding Nemo - The Continuing Adventures (Europe) (Es,It+En,Es,It,Sv,Da)",
         );
-        let languages = detect_languages(&tags);
+        let languages = detect_languages(&tags, None);
 
         assert!(
             languages.contains(&"SV".to_string()),
@@ -2556,6 +3708,13 @@ mod tests {
             sha1: Some("deadbeefdeadbeefdeadbeefdeadbeefdeadbeef".to_string()),
             sha256: None,
             match_reasons: None,
+            serial: None,
+            version: None,
+            region: None,
+            alternates: Vec::new(),
+            dat_name: None,
+            dat_description: None,
+            set_info: None,
         };
 
         let out = resolve_output_path_with_dats(&rec, &cfg, Some(&[dat.clone()]));
@@ -2603,6 +3762,926 @@ mod tests {
         assert_eq!(parent, Path::new("/out/Action"));
     }
 
+    #[test]
+    fn dir_mirror_is_relative_to_each_input_root() {
+        let input = tempfile::tempdir().unwrap();
+        let roots = [input.path().join("a/roms"), input.path().join("b/games")];
+        for root in &roots {
+            std::fs::create_dir_all(root.join("sub")).unwrap();
+            std::fs::write(root.join("sub/Game.bin"), root.to_string_lossy().as_bytes()).unwrap();
+        }
+
+        let outputs = |dir_mirror_root: bool| {
+            let mut cfg = test_config(None, None);
+            cfg.input = roots.to_vec();
+            cfg.output = Some(PathBuf::from("/out"));
+            cfg.dir_mirror = true;
+            cfg.dir_mirror_root = dir_mirror_root;
+            let mut collection = collect_files(&cfg, None).unwrap();
+            assign_output_names(&mut collection.records, &cfg);
+            let mut outputs: Vec<PathBuf> = collection
+                .records
+                .iter()
+                .map(|rec| resolve_output_path(rec, &cfg))
+                .collect();
+            outputs.sort();
+            outputs
+        };
+
+        assert_eq!(
+            outputs(false),
+            vec![
+                PathBuf::from("/out/sub/Game.bin"),
+                PathBuf::from("/out/sub/Game.bin"),
+            ]
+        );
+        assert_eq!(
+            outputs(true),
+            vec![
+                PathBuf::from("/out/games/sub/Game.bin"),
+                PathBuf::from("/out/roms/sub/Game.bin"),
+            ]
+        );
+    }
+
+    #[test]
+    fn flat_output_structure_places_nested_inputs_side_by_side() {
+        let input = tempfile::tempdir().unwrap();
+        for dir in ["disc-a", "disc-b/extra"] {
+            std::fs::create_dir_all(input.path().join(dir)).unwrap();
+            std::fs::write(input.path().join(dir).join("Game.bin"), dir.as_bytes()).unwrap();
+        }
+
+        let mut cfg = test_config(None, None);
+        cfg.input = vec![input.path().to_path_buf()];
+        cfg.output = Some(PathBuf::from("/out"));
+        cfg.output_structure = Some(OutputStructure::Flat);
+        cfg.dir_mirror = true;
+
+        let mut collection = collect_files(&cfg, None).unwrap();
+        assign_output_names(&mut collection.records, &cfg);
+        let mut outputs: Vec<PathBuf> = collection
+            .records
+            .iter()
+            .map(|rec| resolve_output_path(rec, &cfg))
+            .collect();
+        outputs.sort();
+
+        assert_eq!(
+            outputs,
+            vec![
+                PathBuf::from("/out/Game (1).bin"),
+                PathBuf::from("/out/Game.bin"),
+            ]
+        );
+
+        // Names are given to what is left after later filtering, not to every scanned file.
+        let mut remaining = collect_files(&cfg, None).unwrap().records;
+        remaining.truncate(1);
+        assign_output_names(&mut remaining, &cfg);
+        assert_eq!(
+            resolve_output_path(&remaining[0], &cfg),
+            PathBuf::from("/out/Game.bin")
+        );
+    }
+
+    #[test]
+    fn dir_game_subdir_never_writes_multi_file_games_flat() {
+        let input = tempfile::tempdir().unwrap();
+        let mut playlists = Vec::new();
+        for game in ["Alpha (USA)", "Beta (USA)"] {
+            let dir = input.path().join(game);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("disc1.bin"), format!("{game} one")).unwrap();
+            std::fs::write(dir.join("disc2.bin"), format!("{game} two")).unwrap();
+            let playlist = dir.join(format!("{game}.m3u"));
+            std::fs::write(&playlist, "disc1.bin\ndisc2.bin\n").unwrap();
+            playlists.push(playlist);
+        }
+
+        let outputs = |mode: DirGameSubdirMode| {
+            let mut cfg = test_config(None, None);
+            cfg.input = playlists.clone();
+            cfg.output = Some(PathBuf::from("/out"));
+            cfg.dir_game_subdir = mode;
+            let mut collection = collect_files(&cfg, None).unwrap();
+            assign_output_names(&mut collection.records, &cfg);
+            let mut outputs: Vec<PathBuf> = collection
+                .records
+                .iter()
+                .map(|rec| resolve_output_path(rec, &cfg))
+                .collect();
+            outputs.sort();
+            outputs
+        };
+
+        assert_eq!(
+            outputs(DirGameSubdirMode::Multiple),
+            vec![
+                PathBuf::from("/out/Alpha (USA)/disc1.bin"),
+                PathBuf::from("/out/Alpha (USA)/disc2.bin"),
+                PathBuf::from("/out/Beta (USA)/disc1.bin"),
+                PathBuf::from("/out/Beta (USA)/disc2.bin"),
+            ]
+        );
+        assert_eq!(
+            outputs(DirGameSubdirMode::Never),
+            vec![
+                PathBuf::from("/out/disc1 (1).bin"),
+                PathBuf::from("/out/disc1.bin"),
+                PathBuf::from("/out/disc2 (1).bin"),
+                PathBuf::from("/out/disc2.bin"),
+            ]
+        );
+    }
+
+    #[test]
+    fn m3u_input_scans_members_as_one_set() {
+        let input = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(input.path().join("discs")).unwrap();
+        std::fs::write(input.path().join("Game (USA) (Disc 1).bin"), b"disc one").unwrap();
+        std::fs::write(
+            input.path().join("discs").join("Game (USA) (Disc 2).bin"),
+            b"disc two",
+        )
+        .unwrap();
+        let playlist = input.path().join("Game (USA).m3u");
+        std::fs::write(
+            &playlist,
+            "#EXTM3U\nGame (USA) (Disc 1).bin\n\ndiscs/Game (USA) (Disc 2).bin\n",
+        )
+        .unwrap();
+
+        let mut cfg = test_config(Some("USA"), None);
+        cfg.input = vec![playlist];
+
+        let collection = collect_files(&cfg, None).unwrap();
+        let mut names: Vec<String> = collection
+            .records
+            .iter()
+            .map(|rec| rec.relative.to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["Game (USA) (Disc 1).bin", "Game (USA) (Disc 2).bin"]
+        );
+        assert!(collection.skipped.is_empty(), "{:?}", collection.skipped);
+        assert!(
+            collection
+                .records
+                .iter()
+                .all(|rec| rec.set_name.as_deref() == Some("Game (USA)"))
+        );
+    }
+
+    #[test]
+    fn dir_letter_ignore_articles_buckets_by_first_significant_word() {
+        let mut cfg = test_config(None, None);
+        cfg.output = Some(PathBuf::from("/out"));
+        cfg.dir_letter = true;
+
+        let fresh = || {
+            vec![
+                dummy_record("The Legend of Zelda (USA).sfc"),
+                dummy_record("Tetris (World).gb"),
+                dummy_record("An American Tail (USA).nes"),
+            ]
+        };
+        let letters = |records: &[FileRecord]| -> Vec<String> {
+            records
+                .iter()
+                .map(|r| r.letter_dir.clone().unwrap_or_default())
+                .collect()
+        };
+
+        let mut records = fresh();
+        assign_letter_dirs(&mut records, &cfg).unwrap();
+        assert_eq!(letters(&records), vec!["T", "T", "A"]);
+
+        cfg.dir_letter_ignore_articles = true;
+        let mut records = fresh();
+        assign_letter_dirs(&mut records, &cfg).unwrap();
+        assert_eq!(letters(&records), vec!["L", "T", "A"]);
+        assert_eq!(
+            resolve_output_path(&records[0], &cfg),
+            PathBuf::from("/out/L/The Legend of Zelda (USA).sfc")
+        );
+    }
+
+    #[test]
+    fn normalize_extension_case_lowercases_only_the_extension() {
+        let mut cfg = test_config(None, None);
+        cfg.output = Some(PathBuf::from("/OUT"));
+        let rec = dummy_record("GAME.SFC");
+        assert_eq!(
+            resolve_output_path(&rec, &cfg),
+            PathBuf::from("/OUT/GAME.SFC")
+        );
+
+        cfg.normalize_extension_case = true;
+        assert_eq!(
+            resolve_output_path(&rec, &cfg),
+            PathBuf::from("/OUT/GAME.sfc")
+        );
+        assert_eq!(
+            resolve_output_path(&dummy_record("Already.zip"), &cfg),
+            PathBuf::from("/OUT/Already.zip")
+        );
+    }
+
+    #[test]
+    fn stdin_input_lists_paths_alongside_other_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let piped_a = dir.path().join("piped-a.bin");
+        let piped_b = dir.path().join("piped-b.bin");
+        let listed = tempfile::tempdir().unwrap();
+        fs::write(&piped_a, b"a").unwrap();
+        fs::write(&piped_b, b"bb").unwrap();
+        fs::write(listed.path().join("listed.bin"), b"ccc").unwrap();
+
+        let mut cfg = test_config(None, None);
+        cfg.input = vec![PathBuf::from("-"), listed.path().to_path_buf()];
+        let stdin = format!("{}\n\n{}\n", piped_a.display(), piped_b.display());
+
+        let collection = collect_files_with_stdin(&cfg, None, stdin.as_bytes()).unwrap();
+        let mut sources: Vec<PathBuf> = collection
+            .records
+            .iter()
+            .map(|rec| rec.source.clone())
+            .collect();
+        sources.sort();
+        let mut expected = vec![piped_a, piped_b, listed.path().join("listed.bin")];
+        expected.sort();
+        assert_eq!(sources, expected);
+    }
+
+    #[test]
+    fn scan_pre_pass_feeds_totals_to_the_progress_reporter() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.bin"), b"aaaa").unwrap();
+        fs::write(dir.path().join("b.bin"), b"bb").unwrap();
+        let mut cfg = test_config(None, None);
+        cfg.input = vec![dir.path().to_path_buf()];
+
+        crate::progress::force_progress_tty_for_tests(Some(true));
+        let progress = ProgressReporter::maybe_new(&cfg).expect("progress reporter");
+        collect_files_with_stdin(&cfg, Some(&progress), std::io::empty()).unwrap();
+        assert_eq!(progress.scan_totals_for_tests(), (Some(2), Some(6)));
+        drop(progress);
+        crate::progress::force_progress_tty_for_tests(None);
+    }
+
+    #[test]
+    fn chd_sha1_fast_path_uses_the_embedded_header_sha1() {
+        let dir = tempfile::tempdir().unwrap();
+        let embedded = "0123456789abcdef0123456789abcdef01234567";
+        // CHD v5 header: magic, length, version, then the SHA1 at 0x54; hunk data follows.
+        let mut chd = vec![0u8; 124];
+        chd[..8].copy_from_slice(b"MComprHD");
+        chd[8..12].copy_from_slice(&124u32.to_be_bytes());
+        chd[12..16].copy_from_slice(&5u32.to_be_bytes());
+        chd[0x20..0x28].copy_from_slice(&4096u64.to_be_bytes());
+        for (idx, byte) in chd[0x54..0x68].iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&embedded[idx * 2..idx * 2 + 2], 16).unwrap();
+        }
+        chd.extend(std::iter::repeat_n(0xA5, 4096));
+        let path = dir.path().join("disc.chd");
+        fs::write(&path, &chd).unwrap();
+
+        let mut cfg = test_config(None, None);
+        cfg.input = vec![dir.path().to_path_buf()];
+        cfg.input_checksum_min = Checksum::Sha1;
+
+        let collection = collect_files_with_stdin(&cfg, None, std::io::empty()).unwrap();
+        let record = collection
+            .records
+            .iter()
+            .find(|rec| rec.source == path)
+            .expect("chd record");
+        assert_eq!(record.checksums.sha1.as_deref(), Some(embedded));
+        assert_eq!(record.checksums.crc32, None);
+        assert_eq!(record.checksums.md5, None);
+        assert_ne!(
+            record.checksums.sha1,
+            crate::checksum::compute_all_checksums(&path).unwrap().sha1
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn xattr_cache_reuses_checksums_from_a_previous_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Game.bin");
+        fs::write(&path, b"xattr payload").unwrap();
+        let mut cfg = test_config(None, None);
+        cfg.input = vec![dir.path().to_path_buf()];
+        cfg.xattr_cache = true;
+
+        let scan = |cfg: &Config| {
+            collect_files_with_stdin(cfg, None, std::io::empty())
+                .unwrap()
+                .records[0]
+                .checksums
+                .crc32
+                .clone()
+        };
+        let hashed = scan(&cfg);
+        let metadata = fs::metadata(&path).unwrap();
+        let (size, modified) = (metadata.len(), metadata.modified().ok());
+        if crate::xattr_cache::read_checksums(&path, size, modified, None, &[Checksum::Crc32])
+            .is_none()
+        {
+            eprintln!("user xattrs unsupported here; skipping");
+            return;
+        }
+
+        // A planted value proves the second scan read the attribute instead of hashing.
+        let planted = ChecksumSet {
+            crc32: Some("deadbeef".to_string()),
+            md5: None,
+            sha1: None,
+            sha256: None,
+        };
+        crate::xattr_cache::write_checksums(&path, size, modified, None, &planted);
+        assert_eq!(scan(&cfg).as_deref(), Some("deadbeef"));
+
+        // Restamping without a kind removes its earlier value instead of re-validating it.
+        let with_md5 = ChecksumSet {
+            md5: Some("00".repeat(16)),
+            ..planted.clone()
+        };
+        crate::xattr_cache::write_checksums(&path, size, modified, None, &with_md5);
+        crate::xattr_cache::write_checksums(&path, size, modified, None, &planted);
+        assert!(
+            crate::xattr_cache::read_checksums(&path, size, modified, None, &[Checksum::Md5])
+                .is_none()
+        );
+
+        // A changed mtime invalidates the cached checksums.
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap();
+        assert_eq!(scan(&cfg), hashed);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_subdirectories_are_reported_as_skipped() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("ok.bin"), b"ok").unwrap();
+        let locked = dir.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        fs::write(locked.join("hidden.bin"), b"hidden").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        if fs::read_dir(&locked).is_ok() {
+            // Running as root: permissions are not enforced, nothing to observe.
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+            return;
+        }
+
+        let mut cfg = test_config(None, None);
+        cfg.input = vec![dir.path().to_path_buf()];
+        let collection = collect_files_with_stdin(&cfg, None, std::io::empty());
+        cfg.strict = true;
+        let strict = collect_files_with_stdin(&cfg, None, std::io::empty());
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let collection = collection.unwrap();
+        assert_eq!(collection.records.len(), 1);
+        let unreadable: Vec<&SkippedFile> = collection
+            .skipped
+            .iter()
+            .filter(|s| s.reason == SkipReason::Unreadable)
+            .collect();
+        assert_eq!(unreadable.len(), 1);
+        assert_eq!(unreadable[0].path, locked);
+        let err = strict.unwrap_err().to_string();
+        assert!(
+            err.contains("Skipped 1 unreadable input path"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn dir_region_places_files_under_their_region_before_the_letter() {
+        let mut cfg = test_config(None, None);
+        cfg.output = Some(PathBuf::from("/out"));
+        cfg.dir_region = true;
+        cfg.dir_letter = true;
+
+        let mut europe = dummy_record("Sonic (Europe).md");
+        europe.letter_dir = Some("S".to_string());
+        assert_eq!(
+            resolve_output_path(&europe, &cfg),
+            PathBuf::from("/out/EUR/S/Sonic (Europe).md")
+        );
+
+        let mut multi = dummy_record("Tetris (USA, Europe).gb");
+        multi.letter_dir = Some("T".to_string());
+        assert_eq!(
+            resolve_output_path(&multi, &cfg),
+            PathBuf::from("/out/USA/T/Tetris (USA, Europe).gb")
+        );
+        cfg.filter_region = Some("EUR".to_string());
+        assert_eq!(
+            resolve_output_path(&multi, &cfg),
+            PathBuf::from("/out/EUR/T/Tetris (USA, Europe).gb")
+        );
+
+        let mut unknown = dummy_record("Homebrew.gb");
+        unknown.letter_dir = Some("H".to_string());
+        assert_eq!(
+            resolve_output_path(&unknown, &cfg),
+            PathBuf::from("/out/Unknown Region/H/Homebrew.gb")
+        );
+    }
+
+    #[test]
+    fn archive_member_input_selects_a_single_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("collection.zip");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&archive).unwrap());
+        for (name, bytes) in [("roms/game.sfc", b"game"), ("roms/else.sfc", b"else")] {
+            writer
+                .start_file(name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            std::io::Write::write_all(&mut writer, bytes).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let mut cfg = test_config(None, None);
+        cfg.input = vec![PathBuf::from(format!(
+            "{}#roms/game.sfc",
+            archive.display()
+        ))];
+        let collection = collect_files_with_stdin(&cfg, None, std::io::empty()).unwrap();
+        assert_eq!(collection.records.len(), 1);
+        let record = &collection.records[0];
+        assert_eq!(record.source, archive);
+        assert_eq!(record.relative, PathBuf::from("roms/game.sfc"));
+        assert_eq!(record.size, 4);
+        assert_eq!(
+            record.checksums.crc32,
+            crate::checksum::compute_checksums_from_bytes(b"game", &cfg)
+                .unwrap()
+                .crc32
+        );
+
+        cfg.input = vec![PathBuf::from(format!(
+            "{}#roms/missing.sfc",
+            archive.display()
+        ))];
+        let err = collect_files_with_stdin(&cfg, None, std::io::empty()).unwrap_err();
+        assert!(
+            err.to_string().contains("\"roms/missing.sfc\" not found"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn archive_threads_scan_every_entry_of_every_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut expected = Vec::new();
+        for archive in 0..4 {
+            let file = fs::File::create(dir.path().join(format!("set{archive}.zip"))).unwrap();
+            let mut writer = zip::ZipWriter::new(file);
+            for entry in 0..3 {
+                let name = format!("set{archive}-rom{entry}.bin");
+                writer
+                    .start_file(name.as_str(), zip::write::SimpleFileOptions::default())
+                    .unwrap();
+                std::io::Write::write_all(&mut writer, name.as_bytes()).unwrap();
+                expected.push((format!("set{archive}.zip"), name));
+            }
+            writer.finish().unwrap();
+        }
+
+        let mut cfg = test_config(None, None);
+        cfg.input = vec![dir.path().to_path_buf()];
+        cfg.archive_threads = Some(2);
+
+        let collection = collect_files_with_stdin(&cfg, None, std::io::empty()).unwrap();
+        let mut entries: Vec<(String, String)> = collection
+            .records
+            .iter()
+            .filter(|rec| !rec.source.ends_with(&rec.relative))
+            .map(|rec| {
+                (
+                    rec.source
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .to_string(),
+                    rec.relative.to_string_lossy().to_string(),
+                )
+            })
+            .collect();
+        entries.sort();
+        expected.sort();
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn priority_input_prefers_files_from_earlier_inputs() {
+        use crate::candidates::{MatchOptions, generate_candidates_with_options};
+
+        let dir = tempfile::tempdir().unwrap();
+        let verified = dir.path().join("verified");
+        let another = dir.path().join("another");
+        for input in [&verified, &another] {
+            fs::create_dir_all(input).unwrap();
+            fs::write(input.join("Game.bin"), b"same game").unwrap();
+        }
+
+        let mut cfg = test_config(None, None);
+        cfg.input = vec![verified.clone(), another.clone()];
+        let records = collect_files_with_stdin(&cfg, None, std::io::empty())
+            .unwrap()
+            .records;
+        let dat_roms = vec![("Game.bin".to_string(), None, None, None, Some(9u64))];
+
+        let best = |options: &MatchOptions| {
+            generate_candidates_with_options(&dat_roms, &records, options)[0].matches[0]
+                .source
+                .clone()
+        };
+        assert_eq!(best(&MatchOptions::default()), another.join("Game.bin"));
+        let priority = MatchOptions {
+            priority_input: true,
+            ..MatchOptions::default()
+        };
+        assert_eq!(best(&priority), verified.join("Game.bin"));
+    }
+
+    #[test]
+    fn header_definitions_decide_the_header_skipped_before_hashing() {
+        let dir = tempfile::tempdir().unwrap();
+        let defs = dir.path().join("headers");
+        fs::create_dir_all(&defs).unwrap();
+        fs::write(
+            defs.join("custom.xml"),
+            r#"<?xml version="1.0"?>
+<detector>
+  <name>Custom</name>
+  <rule start_offset="20" end_offset="EOF" operation="none">
+    <data offset="0" value="43555354" result="true"/>
+  </rule>
+</detector>"#,
+        )
+        .unwrap();
+        let roms = dir.path().join("roms");
+        fs::create_dir_all(&roms).unwrap();
+        let mut headered = b"CUST".to_vec();
+        headered.resize(0x20, 0xAA);
+        headered.extend_from_slice(b"the actual rom data");
+        fs::write(roms.join("game.bin"), &headered).unwrap();
+        fs::write(roms.join("plain.bin"), b"the actual rom data").unwrap();
+
+        let mut cfg = test_config(None, None);
+        cfg.input = vec![roms.clone()];
+        cfg.header = Some(defs.join("*.xml").to_string_lossy().to_string());
+        let collection = collect_files_with_stdin(&cfg, None, std::io::empty()).unwrap();
+        let crc = |name: &str| {
+            collection
+                .records
+                .iter()
+                .find(|rec| rec.relative == Path::new(name))
+                .and_then(|rec| rec.checksums.crc32.clone())
+                .unwrap()
+        };
+        assert_eq!(crc("game.bin"), crc("plain.bin"));
+    }
+
+    #[test]
+    fn a78_headers_are_stripped_only_when_listed_in_remove_headers() {
+        let dir = tempfile::tempdir().unwrap();
+        let payload: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        let mut headered = vec![0u8; 128];
+        headered[0] = 1;
+        headered[1..10].copy_from_slice(b"ATARI7800");
+        headered.extend_from_slice(&payload);
+        fs::write(dir.path().join("game.a78"), &headered).unwrap();
+        fs::write(dir.path().join("clean.bin"), &payload).unwrap();
+
+        let scan = |remove_headers: Option<&str>| {
+            let mut cfg = test_config(None, None);
+            cfg.input = vec![dir.path().to_path_buf()];
+            cfg.remove_headers = remove_headers.map(str::to_string);
+            collect_files_with_stdin(&cfg, None, std::io::empty())
+                .unwrap()
+                .records
+        };
+        let find = |records: &[FileRecord], name: &str| {
+            records
+                .iter()
+                .find(|rec| rec.relative == Path::new(name))
+                .cloned()
+                .unwrap()
+        };
+
+        let records = scan(Some("nes,.A78"));
+        let game = find(&records, "game.a78");
+        assert_eq!(
+            game.checksums.crc32,
+            find(&records, "clean.bin").checksums.crc32
+        );
+        assert_eq!(game.scan_info.and_then(|info| info.header_size), Some(128));
+
+        let records = scan(None);
+        let game = find(&records, "game.a78");
+        assert_ne!(
+            game.checksums.crc32,
+            find(&records, "clean.bin").checksums.crc32
+        );
+        assert_eq!(game.scan_info.and_then(|info| info.header_size), None);
+    }
+
+    #[test]
+    fn overlapping_inputs_scan_each_file_once() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("game.bin"), b"game").unwrap();
+        fs::write(dir.path().join("other.bin"), b"other").unwrap();
+
+        let mut cfg = test_config(None, None);
+        cfg.input = vec![
+            dir.path().join("*.bin"),
+            dir.path().join("game.bin"),
+            dir.path().join(".").join("game.bin"),
+        ];
+        let collection = collect_files_with_stdin(&cfg, None, std::io::empty()).unwrap();
+        let mut names: Vec<String> = collection
+            .records
+            .iter()
+            .map(|rec| rec.relative.to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["game.bin", "other.bin"]);
+    }
+
+    #[test]
+    fn files_inside_an_input_directory_are_not_scanned_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in");
+        fs::create_dir(&input).unwrap();
+        fs::write(input.join("g.bin"), b"game").unwrap();
+
+        let mut cfg = test_config(None, None);
+        cfg.input = vec![input.clone(), input.join("g.bin")];
+        let collection = collect_files_with_stdin(&cfg, None, std::io::empty()).unwrap();
+        assert_eq!(collection.records.len(), 1);
+    }
+
+    #[test]
+    fn hash_checkpoint_resumes_with_the_default_checksums() {
+        /// Fails once `fail_at` bytes have been read.
+        struct Interrupted(std::io::Cursor<Vec<u8>>, u64);
+        impl std::io::Read for Interrupted {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let left = self.1.saturating_sub(self.0.position()) as usize;
+                if left == 0 {
+                    return Err(std::io::Error::other("interrupted"));
+                }
+                let len = buf.len().min(left);
+                self.0.read(&mut buf[..len])
+            }
+        }
+        impl std::io::Seek for Interrupted {
+            fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+                self.0.seek(pos)
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in");
+        fs::create_dir(&input).unwrap();
+        let path = input.join("disc.iso");
+        let data: Vec<u8> = (0..3_000_000u32).map(|i| (i % 251) as u8).collect();
+        fs::write(&path, &data).unwrap();
+        let mut cfg = test_config(None, None);
+        cfg.input = vec![input.clone()];
+        cfg.cache_db = Some(dir.path().join("cache.sqlite"));
+        cfg.hash_checkpoint_mib = Some(1);
+        let cfg = crate::dat::effective_scan_config(&[], &cfg);
+        let targets = checksum_targets(&cfg);
+        assert_eq!(targets.len(), 3, "crc32 through sha1 by default");
+
+        // An earlier scan died after 2.5 MB and left a checkpoint behind.
+        let cache = Cache::open(cfg.cache_db.as_ref(), None).unwrap();
+        let mtime = fs::metadata(&path)
+            .unwrap()
+            .modified()
+            .unwrap()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as i64;
+        let stamp = (data.len() as u64, mtime);
+        let interrupted = Interrupted(std::io::Cursor::new(data.clone()), 2_500_000);
+        let sums = crate::checksum::hash_checkpointed(
+            interrupted,
+            &path,
+            stamp,
+            0,
+            &targets,
+            &cache,
+            1 << 20,
+            None,
+        );
+        assert!(sums.is_err());
+        let key = "crc32,md5,sha1";
+        assert!(
+            cache
+                .get_hash_checkpoint(&path, stamp.0, stamp.1, 0, key)
+                .unwrap()
+                .is_some()
+        );
+
+        let collection = collect_files_with_stdin(&cfg, None, std::io::empty()).unwrap();
+        let record = &collection.records[0];
+        let full = crate::checksum::compute_checksums_from_bytes(&data, &cfg).unwrap();
+        assert_eq!(record.checksums.crc32, full.crc32);
+        assert_eq!(record.checksums.md5, full.md5);
+        assert_eq!(record.checksums.sha1, full.sha1);
+        // Only the checkpointed path clears what the interrupted scan saved.
+        assert!(
+            cache
+                .get_hash_checkpoint(&path, stamp.0, stamp.1, 0, key)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn scan_mode_flags_record_only_their_category() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("Loose.bin"), b"loose").unwrap();
+        let mut writer =
+            zip::ZipWriter::new(fs::File::create(dir.path().join("Packed.zip")).unwrap());
+        writer
+            .start_file("Packed.bin", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        std::io::Write::write_all(&mut writer, b"packed").unwrap();
+        writer.finish().unwrap();
+        fs::write(dir.path().join("Other.7z"), b"not really 7z").unwrap();
+
+        let sources = |cfg: &Config| {
+            let collection = collect_files_with_stdin(cfg, None, std::io::empty()).unwrap();
+            let mut names: Vec<String> = collection
+                .records
+                .iter()
+                .map(|rec| {
+                    rec.source
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .to_string()
+                })
+                .collect();
+            names.sort();
+            names.dedup();
+            names
+        };
+
+        let mut cfg = test_config(None, None);
+        cfg.input = vec![dir.path().to_path_buf()];
+        cfg.scan_files_only = true;
+        assert_eq!(sources(&cfg), vec!["Loose.bin"]);
+
+        cfg.scan_files_only = false;
+        cfg.scan_archives_only = true;
+        assert_eq!(sources(&cfg), vec!["Other.7z", "Packed.zip"]);
+    }
+
+    #[test]
+    fn never_checksum_archives_records_zips_as_opaque_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Game.zip");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        for name in ["Game (Track 1).bin", "Game (Track 2).bin"] {
+            writer
+                .start_file(name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            std::io::Write::write_all(&mut writer, name.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let mut cfg = test_config(None, None);
+        cfg.input = vec![dir.path().to_path_buf()];
+        cfg.input_checksum_archives = ArchiveChecksumMode::Never;
+
+        let collection = collect_files_with_stdin(&cfg, None, std::io::empty()).unwrap();
+        assert_eq!(collection.records.len(), 1);
+        let record = &collection.records[0];
+        assert_eq!(record.source, path);
+        assert_eq!(record.relative, PathBuf::from("Game.zip"));
+        assert_eq!(record.size, fs::metadata(&path).unwrap().len());
+        let expected = format!("{:08x}", crc32fast::hash(&fs::read(&path).unwrap()));
+        assert_eq!(
+            record
+                .checksums
+                .crc32
+                .as_deref()
+                .map(str::to_ascii_lowercase),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn fix_extension_auto_renames_only_contradicting_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cfg = test_config(None, None);
+        cfg.output = Some(PathBuf::from("/out"));
+
+        let loose = |name: &str, bytes: &[u8]| {
+            let path = dir.path().join(name);
+            fs::write(&path, bytes).unwrap();
+            let mut rec = dummy_record(name);
+            rec.source = path;
+            rec
+        };
+
+        let mut zip_bytes = Vec::new();
+        {
+            let mut writer = zip::ZipWriter::new(std::io::Cursor::new(&mut zip_bytes));
+            writer
+                .start_file("inner.rom", zip::write::SimpleFileOptions::default())
+                .unwrap();
+            std::io::Write::write_all(&mut writer, b"payload").unwrap();
+            writer.finish().unwrap();
+        }
+        let mislabeled = loose("Game.bin", &zip_bytes);
+        let mut snes = vec![0u8; 1024];
+        snes[0] = 0x78;
+        let correct = loose("Game.sfc", &snes);
+
+        let fixed = |rec: &FileRecord, cfg: &Config| {
+            let mut rec = rec.clone();
+            rec.fixed_extension = sniff_fixed_extension(&rec, cfg);
+            resolve_output_path(&rec, cfg)
+        };
+
+        cfg.fix_extension = FixExtensionMode::Auto;
+        assert_eq!(fixed(&mislabeled, &cfg), PathBuf::from("/out/Game.zip"));
+        assert_eq!(fixed(&correct, &cfg), PathBuf::from("/out/Game.sfc"));
+
+        cfg.fix_extension = FixExtensionMode::Always;
+        assert_eq!(fixed(&correct, &cfg), PathBuf::from("/out/Game.smc"));
+
+        cfg.fix_extension = FixExtensionMode::Never;
+        assert_eq!(fixed(&mislabeled, &cfg), PathBuf::from("/out/Game.bin"));
+
+        // The extension is decided once; moving the source away must not change the output path.
+        cfg.fix_extension = FixExtensionMode::Auto;
+        let mut moved = mislabeled.clone();
+        moved.fixed_extension = sniff_fixed_extension(&moved, &cfg);
+        fs::remove_file(&moved.source).unwrap();
+        assert_eq!(
+            resolve_output_path(&moved, &cfg),
+            PathBuf::from("/out/Game.zip")
+        );
+    }
+
+    #[test]
+    fn fix_extension_auto_keeps_32x_and_decides_on_the_output_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cfg = test_config(None, None);
+        cfg.output = Some(PathBuf::from("/out"));
+        cfg.fix_extension = FixExtensionMode::Auto;
+
+        let mut header = vec![0u8; 1024];
+        header[0x100..0x108].copy_from_slice(b"SEGA 32X");
+        let path = dir.path().join("Game.32x");
+        fs::write(&path, &header).unwrap();
+        let mut rec = dummy_record("Game.32x");
+        rec.source = path;
+        rec.fixed_extension = sniff_fixed_extension(&rec, &cfg);
+        assert_eq!(
+            resolve_output_path(&rec, &cfg),
+            PathBuf::from("/out/Game.32x")
+        );
+
+        // A DAT name that already fits the content is kept even though the input's didn't.
+        let path = dir.path().join("dump.rom");
+        fs::write(&path, &header).unwrap();
+        let mut rec = dummy_record("dump.rom");
+        rec.source = path;
+        rec.fixed_extension = sniff_fixed_extension(&rec, &cfg);
+        assert_eq!(
+            resolve_output_path(&rec, &cfg),
+            PathBuf::from("/out/dump.bin")
+        );
+        rec.output_name = Some("Game (World).32x".to_string());
+        assert_eq!(
+            resolve_output_path(&rec, &cfg),
+            PathBuf::from("/out/Game (World).32x")
+        );
+    }
+
     #[test]
     fn genre_token_falls_back_when_absent() {
         let mut cfg = test_config(None, None);
@@ -3,6 +3,9 @@ use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
+use crate::config::Config;
+use crate::types::{Checksum, ChecksumSet};
+
 // If the "libchd" feature is enabled we will attempt to route parsing
 // through an external CHD parsing crate or FFI binding. For now that
 // integration is not provided; keep a lightweight fallback implementation
@@ -67,6 +70,9 @@ pub fn parse_chd_header(path: &Path) -> anyhow::Result<Option<ChdInfo>> {
     let mut f = File::open(path).with_context(|| format!("opening CHD file: {:?}", path))?;
     let mut buf = [0u8; 512];
     let n = f.read(&mut buf)?;
+    if let Some(info) = parse_versioned_header(&buf[..n]) {
+        return Ok(Some(info));
+    }
     let s = String::from_utf8_lossy(&buf[..n]).to_string();
 
     // Look for common CHD header markers
@@ -107,6 +113,56 @@ pub fn parse_chd_header(path: &Path) -> anyhow::Result<Option<ChdInfo>> {
     Ok(None)
 }
 
+fn hex_digest(bytes: &[u8]) -> Option<String> {
+    if bytes.iter().all(|b| *b == 0) {
+        return None;
+    }
+    Some(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Reads the fixed-layout `MComprHD` header of CHD v3, v4 and v5 files. All fields are
+/// big-endian; digests that are all zero are treated as absent.
+fn parse_versioned_header(buf: &[u8]) -> Option<ChdInfo> {
+    if buf.len() < 16 || &buf[..8] != b"MComprHD" {
+        return None;
+    }
+    let header_len = u32::from_be_bytes(buf[8..12].try_into().ok()?) as usize;
+    let version = u32::from_be_bytes(buf[12..16].try_into().ok()?);
+    let header = buf.get(..header_len)?;
+    let read_u64 = |at: usize| Some(u64::from_be_bytes(header.get(at..at + 8)?.try_into().ok()?));
+    let digest = |at: usize, len: usize| header.get(at..at + len).and_then(hex_digest);
+    // (logical bytes, md5, sha1, raw sha1) offsets per header version.
+    let (logical, md5, sha1, raw_sha1) = match version {
+        3 => (read_u64(0x1C), digest(0x2C, 16), digest(0x50, 20), None),
+        4 => (read_u64(0x1C), None, digest(0x30, 20), digest(0x58, 20)),
+        5 => (read_u64(0x20), None, digest(0x54, 20), digest(0x40, 20)),
+        _ => return None,
+    };
+    Some(ChdInfo {
+        tag: format!("chd-v{version}"),
+        uncompressed_size: logical,
+        sha1,
+        md5,
+        raw_sha1,
+    })
+}
+
+/// SHA1-only fast path for CHDs (`--input-checksum-min sha1` or `--hash-algorithms sha1`): DATs identify disks by the SHA1 stored in
+/// the header, so take it from there instead of reading the whole file. Returns `None` when
+/// the run asks for other checksums or the header carries no SHA1.
+pub fn embedded_checksums(path: &Path, config: &Config) -> Option<ChecksumSet> {
+    if crate::checksum::checksum_targets(config) != [Checksum::Sha1] {
+        return None;
+    }
+    let sha1 = parse_chd_header(path).ok()??.sha1?;
+    Some(ChecksumSet {
+        crc32: None,
+        md5: None,
+        sha1: Some(sha1),
+        sha256: None,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
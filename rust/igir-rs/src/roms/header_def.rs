@@ -0,0 +1,226 @@
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::Context;
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+
+/// A ClrMamePro-style header detector (`<detector>` XML) loaded by `--header`. Each rule
+/// names where the ROM data starts and the byte tests a file must pass for it to apply.
+#[derive(Debug, Clone)]
+pub struct HeaderDetector {
+    pub name: String,
+    pub rules: Vec<HeaderRule>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HeaderRule {
+    /// Offset of the ROM data, i.e. the header size to skip.
+    pub start_offset: u64,
+    pub tests: Vec<DataTest>,
+}
+
+/// `<data offset=".." value=".." result=".."/>`: the bytes at `offset` equal `value`
+/// (or differ from it when `result` is false).
+#[derive(Debug, Clone)]
+pub struct DataTest {
+    pub offset: u64,
+    pub value: Vec<u8>,
+    pub result: bool,
+}
+
+/// Load every header definition file matched by the `--header` glob.
+pub fn load_header_definitions(pattern: &str) -> anyhow::Result<Vec<HeaderDetector>> {
+    let mut detectors = Vec::new();
+    for entry in glob::glob(pattern).with_context(|| format!("invalid --header glob: {pattern}"))? {
+        let path = entry?;
+        if path.is_file() {
+            detectors.push(parse_header_definition(&path)?);
+        }
+    }
+    Ok(detectors)
+}
+
+/// Parse one `<detector>` file. Rules that transform the data (`operation` other than
+/// `none`, e.g. byte swapping) are skipped since only header skipping is supported, as are
+/// rules using tests this parser cannot evaluate (`<file>`, `<and>`/`<or>`/`<xor>`, or
+/// offsets that are not plain hex) and rules without any test.
+pub fn parse_header_definition(path: &Path) -> anyhow::Result<HeaderDetector> {
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("reading header definition: {}", path.display()))?;
+    let mut reader = Reader::from_str(&raw);
+    let mut detector = HeaderDetector {
+        name: path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        rules: Vec::new(),
+    };
+    // The rule being read, or the reason it cannot be used.
+    let mut current: Option<Result<HeaderRule, String>> = None;
+    let mut in_name = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(ref e)) if e.name().as_ref() == b"name" => in_name = true,
+            Ok(Event::Text(e)) if in_name => {
+                detector.name = e.unescape().unwrap_or_default().trim().to_string();
+                in_name = false;
+            }
+            Ok(Event::Start(ref e)) if e.name().as_ref() == b"rule" => {
+                current = parse_rule(e);
+            }
+            Ok(Event::Empty(ref e)) if e.name().as_ref() == b"rule" => {
+                finish_rule(path, &mut detector.rules, parse_rule(e));
+            }
+            Ok(Event::End(ref e)) if e.name().as_ref() == b"rule" => {
+                finish_rule(path, &mut detector.rules, current.take());
+            }
+            Ok(Event::Empty(ref e)) if e.name().as_ref() == b"data" => {
+                if let Some(Ok(rule)) = current.as_mut() {
+                    match parse_data_test(e) {
+                        Some(test) => rule.tests.push(test),
+                        None => current = Some(Err("an unparseable <data> test".to_string())),
+                    }
+                }
+            }
+            Ok(Event::Start(ref e) | Event::Empty(ref e)) if matches!(current, Some(Ok(_))) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                current = Some(Err(format!("an unsupported <{tag}> test")));
+            }
+            Ok(Event::Eof) => break,
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("parsing header definition: {}", path.display()));
+            }
+            _ => {}
+        }
+    }
+    Ok(detector)
+}
+
+/// Keep a fully parsed rule; warn about and drop one that could not be understood or has
+/// no tests, since an empty rule would match every file.
+fn finish_rule(path: &Path, rules: &mut Vec<HeaderRule>, rule: Option<Result<HeaderRule, String>>) {
+    let reason = match rule {
+        None => return,
+        Some(Ok(rule)) if !rule.tests.is_empty() => {
+            rules.push(rule);
+            return;
+        }
+        Some(Ok(_)) => "no tests".to_string(),
+        Some(Err(reason)) => reason,
+    };
+    vwarn!(
+        "skipping a rule in header definition {} with {reason}",
+        path.display()
+    );
+}
+
+fn attribute(e: &BytesStart<'_>, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .filter_map(Result::ok)
+        .find(|a| a.key.as_ref() == name)
+        .and_then(|a| String::from_utf8(a.value.into_owned()).ok())
+}
+
+fn parse_hex_offset(raw: &str) -> Option<u64> {
+    u64::from_str_radix(raw.trim().trim_start_matches("0x"), 16).ok()
+}
+
+/// `None` for rules that transform data, which are ignored without a warning.
+fn parse_rule(e: &BytesStart<'_>) -> Option<Result<HeaderRule, String>> {
+    let operation = attribute(e, b"operation").unwrap_or_else(|| "none".to_string());
+    if !operation.eq_ignore_ascii_case("none") {
+        return None;
+    }
+    let raw = attribute(e, b"start_offset").unwrap_or_default();
+    Some(
+        parse_hex_offset(&raw)
+            .map(|start_offset| HeaderRule {
+                start_offset,
+                tests: Vec::new(),
+            })
+            .ok_or_else(|| format!("an unsupported start_offset {raw:?}")),
+    )
+}
+
+fn parse_data_test(e: &BytesStart<'_>) -> Option<DataTest> {
+    let offset = parse_hex_offset(&attribute(e, b"offset").unwrap_or_else(|| "0".to_string()))?;
+    let hex = attribute(e, b"value")?;
+    let value = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    if value.is_empty() {
+        return None;
+    }
+    let result = attribute(e, b"result").is_none_or(|r| !r.eq_ignore_ascii_case("false"));
+    Some(DataTest {
+        offset,
+        value,
+        result,
+    })
+}
+
+impl HeaderRule {
+    fn matches(&self, head: &[u8]) -> bool {
+        self.tests.iter().all(|test| {
+            let start = test.offset as usize;
+            let found = head.get(start..start + test.value.len()) == Some(test.value.as_slice());
+            found == test.result
+        })
+    }
+}
+
+/// Header size from the first rule of any detector that matches the start of `path`.
+pub fn detect_header_size(detectors: &[HeaderDetector], path: &Path) -> Option<u64> {
+    let needed = detectors
+        .iter()
+        .flat_map(|d| &d.rules)
+        .flat_map(|r| &r.tests)
+        .map(|t| t.offset as usize + t.value.len())
+        .max()?;
+    let mut head = Vec::with_capacity(needed);
+    fs::File::open(path)
+        .ok()?
+        .take(needed as u64)
+        .read_to_end(&mut head)
+        .ok()?;
+    let size = fs::metadata(path).ok()?.len();
+    detectors
+        .iter()
+        .flat_map(|d| &d.rules)
+        .find(|rule| rule.start_offset < size && rule.matches(&head))
+        .map(|rule| rule.start_offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(xml: &str) -> HeaderDetector {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.xml");
+        fs::write(&path, xml).unwrap();
+        parse_header_definition(&path).unwrap()
+    }
+
+    #[test]
+    fn rules_with_unsupported_tests_are_skipped() {
+        let detector = parse(
+            r#"<detector><name>Test</name>
+                <rule start_offset="10"><data offset="0" value="4E45531A"/></rule>
+                <rule start_offset="20"><file size="PO2" operator="equal"/></rule>
+                <rule start_offset="30"><and><data offset="0" value="00"/></and></rule>
+                <rule start_offset="40"><data offset="-1" value="00"/></rule>
+                <rule start_offset="50"><data offset="zz" value="00"/></rule>
+                <rule start_offset="60"></rule>
+                <rule start_offset="70"/>
+            </detector>"#,
+        );
+        assert_eq!(detector.name, "Test");
+        let offsets: Vec<u64> = detector.rules.iter().map(|r| r.start_offset).collect();
+        assert_eq!(offsets, vec![0x10]);
+    }
+}
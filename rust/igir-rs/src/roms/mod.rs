@@ -1,2 +1,4 @@
 pub mod chd;
+pub mod header_def;
+pub mod nds;
 pub mod rom_scanner;
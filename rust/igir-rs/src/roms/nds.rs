@@ -0,0 +1,43 @@
+use std::io::{self, Read};
+
+/// Bytes of the NDS header needed to recognise a ROM and read its capacity.
+pub const HEADER_LEN: usize = 0x160;
+
+/// Byte the trimmed tail of an NDS ROM is padded with.
+pub const PAD_BYTE: u8 = 0xFF;
+
+const CAPACITY_OFFSET: usize = 0x14;
+const LOGO_CRC_OFFSET: usize = 0x15C;
+/// CRC16 of the Nintendo logo every retail header carries (little-endian 0xCF56).
+const LOGO_CRC: [u8; 2] = [0x56, 0xCF];
+
+/// Cartridge capacity (the untrimmed dump size) declared by an NDS `header`, or
+/// `None` when the bytes don't carry the NDS logo checksum. Trimming tools cut a
+/// ROM after its last used byte, while DATs list the full `0xFF`-padded dump.
+pub fn cartridge_size(header: &[u8]) -> Option<u64> {
+    if header.len() < HEADER_LEN || header[LOGO_CRC_OFFSET..LOGO_CRC_OFFSET + 2] != LOGO_CRC {
+        return None;
+    }
+    // Capacity is 128 KiB << n; real cartridges stop well before 2^(17+15) bytes.
+    let shift = header[CAPACITY_OFFSET];
+    (shift <= 15).then(|| (128 * 1024u64) << shift)
+}
+
+/// Size of the untrimmed dump when `header` is an NDS ROM of `size` bytes that
+/// was trimmed below its cartridge capacity.
+pub fn untrimmed_size(header: &[u8], size: u64) -> Option<u64> {
+    cartridge_size(header).filter(|&full| full > size)
+}
+
+/// Wrap the rest of a trimmed ROM so reading it yields the full `untrimmed`
+/// dump: the already-read `header`, the remaining bytes, then `0xFF` padding.
+pub fn padded_reader<'a, R: Read + 'a>(
+    header: &'a [u8],
+    rest: R,
+    size: u64,
+    untrimmed: u64,
+) -> impl Read + 'a {
+    header
+        .chain(rest)
+        .chain(io::repeat(PAD_BYTE).take(untrimmed.saturating_sub(size)))
+}
@@ -8,6 +8,8 @@ pub struct RomInfo {
     pub path: String,
     /// If a header was detected, header size in bytes (e.g., 128 or 512)
     pub header_size: Option<u64>,
+    /// Name of the header recognised by its signature (e.g. `A78`), as opposed to a size guess
+    pub header_name: Option<&'static str>,
     pub is_chd: bool,
     pub is_nkit: bool,
     pub is_iso: bool,
@@ -51,10 +53,13 @@ pub fn scan(path: &Path) -> anyhow::Result<RomInfo> {
         ("SMC", 3, &"00".repeat(509), 512),
         // LNX (Lynx): '4C594E58' at offset 0
         ("LNX", 0, "4C594E58", 64),
+        // A78 (Atari 7800): 'ATARI7800' at offset 1, data offset 128
+        ("A78", 1, "415441524937383030", 128),
     ];
 
     let mut header_size: Option<u64> = None;
-    for (_name, offset, hexpat, data_offset) in known_headers {
+    let mut header_name: Option<&'static str> = None;
+    for (name, offset, hexpat, data_offset) in known_headers {
         let needed = offset + hexpat.len() / 2;
         if buf.len() >= needed {
             let slice = &buf[*offset..(*offset + hexpat.len() / 2)];
@@ -64,6 +69,7 @@ pub fn scan(path: &Path) -> anyhow::Result<RomInfo> {
                 .collect::<String>();
             if hex == hexpat.to_uppercase() {
                 header_size = Some(*data_offset);
+                header_name = Some(*name);
                 break;
             }
         }
@@ -176,6 +182,7 @@ pub fn scan(path: &Path) -> anyhow::Result<RomInfo> {
     Ok(RomInfo {
         path: path.to_string_lossy().to_string(),
         header_size,
+        header_name,
         is_chd,
         is_nkit,
         is_iso,
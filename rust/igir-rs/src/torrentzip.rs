@@ -4,6 +4,7 @@ use std::path::Path;
 
 use anyhow::Context;
 use crc32fast::Hasher as Crc32;
+use sha2::{Digest, Sha256};
 use zip::write::FileOptions;
 
 use crate::actions::ActionProgressHandle;
@@ -64,6 +65,34 @@ fn encode_cp437(s: &str) -> Option<Vec<u8>> {
     Some(out)
 }
 
+/// Replace the end-of-central-directory comment of the zip at `path` (`--zip-comment`).
+pub fn set_zip_comment(path: &Path, comment: &str) -> anyhow::Result<()> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("opening {:?}", path))?;
+    let len = file.metadata()?.len();
+    // The EOCD record is 22 bytes followed by a comment of at most 64 KiB.
+    let tail_start = len.saturating_sub(22 + u64::from(u16::MAX));
+    file.seek(SeekFrom::Start(tail_start))?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail)?;
+    let pos = tail
+        .windows(4)
+        .rposition(|w| w == b"PK\x05\x06")
+        .context("EOCD not found")?;
+    let comment_len = u16::try_from(comment.len()).context("zip comment too long")?;
+
+    let comment_len_at = tail_start + pos as u64 + 20;
+    file.set_len(comment_len_at)?;
+    file.seek(SeekFrom::Start(comment_len_at))?;
+    file.write_all(&comment_len.to_le_bytes())?;
+    file.write_all(comment.as_bytes())?;
+    file.flush()?;
+    Ok(())
+}
+
 /// Create a TorrentZip/RVZSTD archive for a single file. This is a minimal, pragmatic
 /// implementation: use the zip crate to write the archive, then compute the CRC32 of the
 /// central directory and patch the EOCD comment to match TZWriter behavior.
@@ -73,6 +102,17 @@ pub fn write_torrentzip(
     filename_in_zip: &str,
     format: ZipFormat,
     progress: Option<&ActionProgressHandle>,
+) -> anyhow::Result<()> {
+    write_torrentzip_teed(src, dest, filename_in_zip, format, progress, None)
+}
+
+pub(crate) fn write_torrentzip_teed(
+    src: &Path,
+    dest: &Path,
+    filename_in_zip: &str,
+    format: ZipFormat,
+    progress: Option<&ActionProgressHandle>,
+    mut sha256: Option<&mut Sha256>,
 ) -> anyhow::Result<()> {
     // If this is a Torrentzip (stored) and the filename is CP437-encodable,
     // write a manual single-file Stored ZIP so we can control the filename bytes
@@ -236,6 +276,9 @@ pub fn write_torrentzip(
             break;
         }
         zip.write_all(&buf[..n])?;
+        if let Some(hasher) = sha256.as_deref_mut() {
+            hasher.update(&buf[..n]);
+        }
         written = written.saturating_add(n as u64);
         if let Some(handle) = progress {
             handle.report_bytes(written, total);
@@ -243,39 +286,42 @@ pub fn write_torrentzip(
     }
     zip.finish()?;
 
-    // Now compute CRC32 of the central directory and patch EOCD comment.
-    // Read file back to compute central directory CRC: seek to start and read contents.
-    out.seek(SeekFrom::Start(0))?;
-    let mut data = Vec::new();
-    out.read_to_end(&mut data)?;
+    // Now compute CRC32 of the central directory and patch EOCD comment. Only the EOCD
+    // tail and the central directory are read back, so large ROMs are never buffered whole.
+    let len = out.metadata()?.len();
+    let tail_start = len.saturating_sub(22 + u64::from(u16::MAX));
+    out.seek(SeekFrom::Start(tail_start))?;
+    let mut tail = Vec::new();
+    out.read_to_end(&mut tail)?;
 
     // Find EOCD signature 0x06054b50 (little endian bytes "PK\x05\x06")
-    let eocd_sig = b"PK\x05\x06";
-    let pos = data
+    let pos = tail
         .windows(4)
-        .rposition(|w| w == eocd_sig)
+        .rposition(|w| w == b"PK\x05\x06")
         .context("EOCD not found")?;
 
-    // EOCD structure: offset 16..20 is size of central directory, 12..16 is offset
-    if data.len() < pos + 22 {
+    // EOCD structure: offset 12..16 is size of central directory, 16..20 is offset
+    if tail.len() < pos + 22 {
         anyhow::bail!("EOCD truncated");
     }
     let cd_size = u32::from_le_bytes([
-        data[pos + 12],
-        data[pos + 13],
-        data[pos + 14],
-        data[pos + 15],
-    ]) as usize;
+        tail[pos + 12],
+        tail[pos + 13],
+        tail[pos + 14],
+        tail[pos + 15],
+    ]);
     let cd_offset = u32::from_le_bytes([
-        data[pos + 16],
-        data[pos + 17],
-        data[pos + 18],
-        data[pos + 19],
-    ]) as usize;
+        tail[pos + 16],
+        tail[pos + 17],
+        tail[pos + 18],
+        tail[pos + 19],
+    ]);
 
-    let central_dir = &data[cd_offset..cd_offset + cd_size];
+    let mut central_dir = vec![0u8; cd_size as usize];
+    out.seek(SeekFrom::Start(u64::from(cd_offset)))?;
+    out.read_exact(&mut central_dir)?;
     let mut hasher = Crc32::new();
-    hasher.update(central_dir);
+    hasher.update(&central_dir);
     let cdfh_crc = hasher.finalize();
     let cdfh_crc_hex = format!("{:08X}", cdfh_crc);
 
@@ -285,33 +331,6 @@ pub fn write_torrentzip(
         ZipFormat::Deflate => format!("TORRENTZIPPED-{}", cdfh_crc_hex),
     };
 
-    // Patch comment length and bytes in EOCD
-    // EOCD structure: comment length at pos+20 (2 bytes), comment starts at pos+22
-    let comment_len = comment.len() as u16;
-    // update in-memory data
-    let mut patched = data;
-    patched[pos + 20] = (comment_len & 0xff) as u8;
-    patched[pos + 21] = ((comment_len >> 8) & 0xff) as u8;
-    // ensure buffer length; truncate or extend as necessary
-    let comment_start = pos + 22;
-    if patched.len() < comment_start {
-        patched.resize(comment_start, 0);
-    }
-    if patched.len() >= comment_start + comment.len() {
-        for (i, b) in comment.as_bytes().iter().enumerate() {
-            patched[comment_start + i] = *b;
-        }
-        patched.truncate(comment_start + comment.len());
-    } else {
-        // append comment
-        patched.extend_from_slice(comment.as_bytes());
-    }
-
-    // rewrite file from start
-    out.seek(SeekFrom::Start(0))?;
-    out.set_len(patched.len() as u64)?;
-    out.write_all(&patched)?;
-    out.flush()?;
-
-    Ok(())
+    drop(out);
+    set_zip_comment(dest, &comment)
 }
@@ -4,6 +4,7 @@ use std::path::Path;
 
 use anyhow::Context;
 use crc32fast::Hasher as Crc32;
+use sha2::{Digest, Sha256};
 
 use crate::actions::ActionProgressHandle;
 use crate::types::ZipFormat;
@@ -98,6 +99,7 @@ fn stream_file_into(
     progress: Option<&ActionProgressHandle>,
     aggregate_total: Option<u64>,
     aggregate_written: &mut u64,
+    mut sha256: Option<&mut Sha256>,
 ) -> anyhow::Result<()> {
     let mut input = File::open(src).with_context(|| format!("opening {:?}", src))?;
     let mut buf = vec![0u8; COPY_BUF_SIZE];
@@ -107,6 +109,9 @@ fn stream_file_into(
             break;
         }
         out.write_all(&buf[..n])?;
+        if let Some(hasher) = sha256.as_deref_mut() {
+            hasher.update(&buf[..n]);
+        }
         *aggregate_written = aggregate_written.saturating_add(n as u64);
         if let Some(handle) = progress {
             handle.report_bytes(*aggregate_written, aggregate_total);
@@ -120,11 +125,34 @@ pub fn write_torrentzip_zip64(
     dest: &Path,
     format: ZipFormat,
     progress: Option<&ActionProgressHandle>,
+) -> anyhow::Result<()> {
+    write_torrentzip_zip64_teed(srcs, dest, format, progress, None)
+}
+
+/// Like [`write_torrentzip_zip64`], also returning the SHA256 of the entries' uncompressed
+/// bytes in order, taken from the same reads that feed the archive.
+pub fn write_torrentzip_zip64_with_sha256(
+    srcs: &[(&Path, &str)],
+    dest: &Path,
+    format: ZipFormat,
+    progress: Option<&ActionProgressHandle>,
+) -> anyhow::Result<String> {
+    let mut hasher = Sha256::new();
+    write_torrentzip_zip64_teed(srcs, dest, format, progress, Some(&mut hasher))?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn write_torrentzip_zip64_teed(
+    srcs: &[(&Path, &str)],
+    dest: &Path,
+    format: ZipFormat,
+    progress: Option<&ActionProgressHandle>,
+    mut sha256: Option<&mut Sha256>,
 ) -> anyhow::Result<()> {
     // If single entry, delegate to existing torrentzip writer for parity.
     if srcs.len() == 1 {
         let (src, name) = srcs[0];
-        return crate::torrentzip::write_torrentzip(src, dest, name, format, progress);
+        return crate::torrentzip::write_torrentzip_teed(src, dest, name, format, progress, sha256);
     }
 
     // Multi-file stored writer (initial implementation without Zip64 extras).
@@ -189,7 +217,14 @@ pub fn write_torrentzip_zip64(
         // record local header offset (before writing)
         let local_header_offset = out.seek(SeekFrom::Current(0))?;
         out.write_all(&lh)?;
-        stream_file_into(src, &mut out, progress, aggregate_total, &mut aggregate_written)?;
+        stream_file_into(
+            src,
+            &mut out,
+            progress,
+            aggregate_total,
+            &mut aggregate_written,
+            sha256.as_deref_mut(),
+        )?;
 
         entries.push(Entry {
             name: raw_name,
@@ -7,15 +7,19 @@ use std::path::PathBuf;
 pub enum Action {
     Copy,
     Move,
+    Rename,
     Link,
     Extract,
     Zip,
     Playlist,
     Test,
+    VerifyZip,
     Dir2dat,
     Fixdat,
     Clean,
+    CleanEmptyDirs,
     Report,
+    Verify,
 }
 
 #[derive(Debug, Clone, Serialize, ValueEnum, PartialEq, Eq, Hash, Copy)]
@@ -62,6 +66,39 @@ pub enum FixExtensionMode {
     Always,
 }
 
+/// How candidates with equal match scores are ordered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ValueEnum)]
+pub enum MtimeTiebreak {
+    /// By source path (deterministic across runs)
+    #[default]
+    Path,
+    /// Most recently modified file first
+    Newest,
+    /// Least recently modified file first
+    Oldest,
+}
+
+/// Layout of the end-of-run summary.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ValueEnum)]
+pub enum LogStyle {
+    /// igir's own summary
+    #[default]
+    Default,
+    /// RomVault-style report of complete/partial DATs and missing/fixed ROMs
+    Romvault,
+}
+
+/// How to handle inputs whose planned output paths collide, compared case-insensitively.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ValueEnum)]
+pub enum CollisionPolicy {
+    /// Keep the first input and skip the others
+    Skip,
+    /// Append ` (N)` to the file name of later inputs
+    Rename,
+    /// Abort before writing anything
+    Error,
+}
+
 #[derive(Debug, Clone, Serialize, ValueEnum)]
 pub enum MoveDeleteDirsMode {
     Never,
@@ -91,6 +128,19 @@ pub enum MergeMode {
     Merged,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, ValueEnum, PartialEq, Eq)]
+pub enum FixdatFormat {
+    Logiqx,
+    Clrmamepro,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, ValueEnum, PartialEq, Eq)]
+pub enum OutputStructure {
+    Flat,
+    Mirror,
+    Dat,
+}
+
 #[derive(Debug, Clone, Serialize, ValueEnum, PartialEq, Eq)]
 pub enum IgdbLookupMode {
     BestEffort,
@@ -131,6 +181,36 @@ pub struct FileRecord {
     pub derived_languages: Vec<String>,
     #[serde(skip)]
     pub scan_info: Option<crate::roms::rom_scanner::RomInfo>,
+    // Collision-free file name assigned for `--output-structure flat` or `--on-collision rename`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_name: Option<String>,
+    // Stem of the `.m3u` input that listed this file; members of one playlist form a multi-disc set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub set_name: Option<String>,
+    // The `-i` directory this file was found under; move cleanup never removes it or its parents.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_root: Option<PathBuf>,
+    // Modification time read while scanning; `--tiebreak-mtime` orders equal-score matches by it.
+    #[serde(skip)]
+    pub modified: Option<std::time::SystemTime>,
+    // Position of the `-i` input this file was found under; `--priority-input` prefers lower.
+    #[serde(skip)]
+    pub input_index: Option<usize>,
+    // Extension the file's content calls for under `--fix-extension`, sniffed once while scanning.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixed_extension: Option<String>,
+}
+
+impl FileRecord {
+    /// Key identifying the physical file a record stands for: its source path
+    /// joined with its path inside that source (distinct per archive entry).
+    pub fn identity(&self) -> String {
+        format!(
+            "{}::{}",
+            self.source.to_string_lossy(),
+            self.relative.to_string_lossy()
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -141,6 +221,14 @@ pub enum SkipReason {
     RegexExclude,
     #[serde(rename = "region_language")]
     RegionLanguage,
+    #[serde(rename = "metadata")]
+    Metadata,
+    #[serde(rename = "quarantined")]
+    Quarantined,
+    #[serde(rename = "quarantine_conflict")]
+    QuarantineConflict,
+    #[serde(rename = "unreadable")]
+    Unreadable,
 }
 
 impl fmt::Display for SkipReason {
@@ -149,6 +237,10 @@ impl fmt::Display for SkipReason {
             SkipReason::RegexInclude => write!(f, "failed include regex"),
             SkipReason::RegexExclude => write!(f, "matched exclude regex"),
             SkipReason::RegionLanguage => write!(f, "filtered by region/language"),
+            SkipReason::Metadata => write!(f, "filtered by genre/platform"),
+            SkipReason::Quarantined => write!(f, "moved to quarantine"),
+            SkipReason::QuarantineConflict => write!(f, "left in place, quarantine target exists"),
+            SkipReason::Unreadable => write!(f, "could not be read"),
         }
     }
 }
@@ -187,6 +279,46 @@ pub struct RunSummary {
     pub filters: FilterSummary,
 }
 
+/// One copy in a `--report-duplicates` group.
+#[derive(Debug, Serialize)]
+pub struct DuplicateFile {
+    pub source: PathBuf,
+    pub relative: PathBuf,
+    pub size: u64,
+}
+
+/// Inputs sharing the same strongest checksum, as listed in duplicates.json.
+#[derive(Debug, Serialize)]
+pub struct DuplicateGroup {
+    pub checksum: String,
+    pub files: Vec<DuplicateFile>,
+}
+
+/// Result of the `verify` command: DAT ROMs found and missing, corrupt copies of missing
+/// ROMs, copies that look like over/underdumps of one, and inputs no DAT knows. Written to
+/// verify.json when `--output` is given.
+#[derive(Debug, Default, Serialize)]
+pub struct VerifyReport {
+    pub have: Vec<String>,
+    pub miss: Vec<String>,
+    pub bad: Vec<String>,
+    pub suspect: Vec<String>,
+    pub unknown: Vec<String>,
+}
+
+impl VerifyReport {
+    pub fn summary_line(&self) -> String {
+        format!(
+            "Verify: {} have, {} miss, {} bad, {} suspect, {} unknown",
+            self.have.len(),
+            self.miss.len(),
+            self.bad.len(),
+            self.suspect.len(),
+            self.unknown.len()
+        )
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct FileCollection {
     pub records: Vec<FileRecord>,
@@ -200,6 +332,18 @@ pub struct ActionOutcome {
     pub note: String,
 }
 
+/// Process exit code when DATs were loaded but no input matched any of their games.
+/// Code 1 is used for errors and 2 for command-line usage errors.
+pub const EXIT_NO_MATCHES: i32 = 3;
+
+/// An `--exec` command that failed to start or exited unsuccessfully for a written file.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecFailure {
+    pub path: PathBuf,
+    pub command: Vec<String>,
+    pub error: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ExecutionPlan {
     pub config: crate::config::Config,
@@ -209,5 +353,30 @@ pub struct ExecutionPlan {
     pub dat_unmatched: Vec<crate::dat::DatRom>,
     pub online_matches: Vec<crate::dat::OnlineMatch>,
     pub skipped: Vec<SkippedFile>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exec_failures: Vec<ExecFailure>,
     pub summary: RunSummary,
 }
+
+/// The compact `--summary-only` view of an [`ExecutionPlan`], without the config echo.
+#[derive(Debug, Serialize)]
+pub struct PlanSummary<'a> {
+    pub steps: &'a [ActionOutcome],
+    pub files_processed: usize,
+    pub summary: &'a RunSummary,
+}
+
+impl ExecutionPlan {
+    pub fn summary_view(&self) -> PlanSummary<'_> {
+        PlanSummary {
+            steps: &self.steps,
+            files_processed: self.files_processed,
+            summary: &self.summary,
+        }
+    }
+
+    /// True when DAT entries were loaded but none of them matched an input file.
+    pub fn matched_nothing(&self) -> bool {
+        self.dat_matched.is_empty() && self.summary.dat_unmatched > 0
+    }
+}
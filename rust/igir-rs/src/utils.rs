@@ -1,16 +1,90 @@
 use globset::{Glob, GlobSet, GlobSetBuilder};
-use std::path::PathBuf;
+use std::path::Path;
 
-pub fn build_globset(patterns: &[PathBuf]) -> anyhow::Result<Option<GlobSet>> {
+pub fn build_globset<P: AsRef<Path>>(patterns: &[P]) -> anyhow::Result<Option<GlobSet>> {
     if patterns.is_empty() {
         return Ok(None);
     }
 
     let mut builder = GlobSetBuilder::new();
     for pattern in patterns {
-        let glob = Glob::new(pattern.to_string_lossy().as_ref())?;
+        let glob = Glob::new(pattern.as_ref().to_string_lossy().as_ref())?;
         builder.add(glob);
     }
 
     Ok(Some(builder.build()?))
 }
+
+/// Free space available to this process on the volume holding `path`, or on its
+/// nearest existing ancestor when `path` has not been created yet. Returns `None`
+/// when the filesystem can't report it.
+pub fn available_space(path: &Path) -> Option<u64> {
+    let existing = path.ancestors().find(|p| p.exists())?;
+    fs2::available_space(existing).ok()
+}
+
+/// Produce `target` through a `.tmp` sibling: `write` fills the temp path, which is
+/// renamed over `target` only once it succeeds and removed otherwise, so an interrupted
+/// or failed write never leaves a partial file at `target`.
+pub fn write_atomically<T>(
+    target: &Path,
+    write: impl FnOnce(&Path) -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    let mut tmp_name = target.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp = target.with_file_name(tmp_name);
+    let result = write(&tmp).and_then(|value| {
+        std::fs::rename(&tmp, target)
+            .map_err(|e| anyhow::anyhow!("renaming {tmp:?} to {target:?}: {e}"))?;
+        Ok(value)
+    });
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Split a command line into arguments the way a POSIX shell would for plain words:
+/// whitespace separates arguments, single and double quotes group them, and a backslash
+/// escapes the next character outside single quotes. Nothing else is interpreted.
+pub fn split_command_line(raw: &str) -> anyhow::Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut chars = raw.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\'' | '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some(c) if c == ch => break,
+                        Some('\\') if ch == '"' => {
+                            current.extend(chars.next());
+                        }
+                        Some(c) => current.push(c),
+                        None => anyhow::bail!("unterminated {ch} quote"),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                current.extend(chars.next());
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    args.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_word {
+        args.push(current);
+    }
+    Ok(args)
+}
@@ -9,6 +9,9 @@ pub struct WriteCandidate {
     pub files: Vec<FileRecord>,
     /// mapping of dat part name -> chosen FileRecord
     pub files_map: HashMap<String, FileRecord>,
+    /// dat part names no input satisfied (only with `--allow-incomplete-sets`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing: Vec<String>,
 }
 
 impl WriteCandidate {
@@ -17,6 +20,7 @@ impl WriteCandidate {
             name: name.into(),
             files,
             files_map: HashMap::new(),
+            missing: Vec::new(),
         }
     }
 }
@@ -0,0 +1,129 @@
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::types::{Checksum, ChecksumSet};
+
+// `--xattr-cache` keeps scanned checksums in `user.igir.*` extended attributes on the input
+// file itself, one per checksum kind. `user.igir.stamp` records the size, mtime and stripped
+// header length they were computed for; a stale stamp invalidates them all. Failures (no
+// xattr support, read-only media, other platforms) are silent and the file is just hashed.
+const STAMP: &str = "user.igir.stamp";
+
+fn attribute(kind: Checksum) -> &'static str {
+    match kind {
+        Checksum::Crc32 => "user.igir.crc32",
+        Checksum::Md5 => "user.igir.md5",
+        Checksum::Sha1 => "user.igir.sha1",
+        Checksum::Sha256 => "user.igir.sha256",
+    }
+}
+
+fn stamp(size: u64, modified: Option<SystemTime>, header: Option<u64>) -> Option<String> {
+    let mtime = modified?
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .ok()?
+        .as_nanos();
+    Some(format!("{size}:{mtime}:{}", header.unwrap_or(0)))
+}
+
+/// Checksums cached on `path` for this size/mtime/header, when every kind in `targets`
+/// is present. Kinds outside `targets` are left `None`.
+pub fn read_checksums(
+    path: &Path,
+    size: u64,
+    modified: Option<SystemTime>,
+    header: Option<u64>,
+    targets: &[Checksum],
+) -> Option<ChecksumSet> {
+    let expected = stamp(size, modified, header)?;
+    if sys::get(path, STAMP)? != expected.as_bytes() {
+        return None;
+    }
+    let mut checksums = ChecksumSet {
+        crc32: None,
+        md5: None,
+        sha1: None,
+        sha256: None,
+    };
+    for &kind in targets {
+        let value = String::from_utf8(sys::get(path, attribute(kind))?).ok()?;
+        let slot = match kind {
+            Checksum::Crc32 => &mut checksums.crc32,
+            Checksum::Md5 => &mut checksums.md5,
+            Checksum::Sha1 => &mut checksums.sha1,
+            Checksum::Sha256 => &mut checksums.sha256,
+        };
+        *slot = Some(value);
+    }
+    Some(checksums)
+}
+
+/// Store `checksums` on `path`, stamped with its size/mtime/header, and drop the kinds it
+/// lacks so values from an earlier stamp never survive. Best effort.
+pub fn write_checksums(
+    path: &Path,
+    size: u64,
+    modified: Option<SystemTime>,
+    header: Option<u64>,
+    checksums: &ChecksumSet,
+) {
+    let Some(stamp) = stamp(size, modified, header) else {
+        return;
+    };
+    let values = [
+        (Checksum::Crc32, &checksums.crc32),
+        (Checksum::Md5, &checksums.md5),
+        (Checksum::Sha1, &checksums.sha1),
+        (Checksum::Sha256, &checksums.sha256),
+    ];
+    // Unstamped while rewriting, so an interrupted update is never trusted.
+    sys::remove(path, STAMP);
+    for (kind, value) in values {
+        let stored = match value {
+            Some(value) => sys::set(path, attribute(kind), value.as_bytes()),
+            None => sys::remove(path, attribute(kind)),
+        };
+        if !stored {
+            return;
+        }
+    }
+    sys::set(path, STAMP, stamp.as_bytes());
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::path::Path;
+
+    pub fn get(path: &Path, name: &str) -> Option<Vec<u8>> {
+        xattr::get(path, name).ok().flatten()
+    }
+
+    pub fn set(path: &Path, name: &str, value: &[u8]) -> bool {
+        xattr::set(path, name, value).is_ok()
+    }
+
+    /// Whether `name` is absent from `path` afterwards.
+    pub fn remove(path: &Path, name: &str) -> bool {
+        match xattr::remove(path, name) {
+            Ok(()) => true,
+            Err(_) => matches!(xattr::get(path, name), Ok(None)),
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod sys {
+    use std::path::Path;
+
+    pub fn get(_path: &Path, _name: &str) -> Option<Vec<u8>> {
+        None
+    }
+
+    pub fn set(_path: &Path, _name: &str, _value: &[u8]) -> bool {
+        false
+    }
+
+    pub fn remove(_path: &Path, _name: &str) -> bool {
+        false
+    }
+}
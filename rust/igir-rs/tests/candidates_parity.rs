@@ -33,6 +33,12 @@ fn compare_candidates_with_golden_if_present() {
         derived_region: None,
         derived_languages: Vec::new(),
         scan_info: None,
+        output_name: None,
+        set_name: None,
+        input_root: None,
+        modified: None,
+        input_index: None,
+        fixed_extension: None,
     };
     let got = igir::candidates::generate_candidates(&dats, &[rec_checksum]);
     let got_json = serde_json::to_string_pretty(&got).expect("serialize");
@@ -0,0 +1,64 @@
+use std::fs;
+use tempfile::tempdir;
+
+use igir::dat::{self, DatFile, DatGameChange};
+
+const OLD_DAT: &str = r#"<?xml version="1.0"?>
+<datafile>
+  <game name="Alpha (USA)">
+    <description>Alpha (USA)</description>
+    <rom name="Alpha (USA).nes" size="1024" crc="11111111"/>
+  </game>
+  <game name="Beta (USA)">
+    <description>Beta (USA)</description>
+    <rom name="Beta (USA).nes" size="2048" crc="22222222"/>
+  </game>
+  <game name="Gamma (Europe)">
+    <description>Gamma (Europe)</description>
+    <rom name="Gamma (Europe).nes" size="4096" crc="33333333"/>
+  </game>
+</datafile>
+"#;
+
+const NEW_DAT: &str = r#"<?xml version="1.0"?>
+<datafile>
+  <game name="Alpha (USA)">
+    <description>Alpha (USA)</description>
+    <rom name="Alpha (USA).nes" size="1024" crc="11111111"/>
+  </game>
+  <game name="Beta (USA)">
+    <description>Beta (USA)</description>
+    <rom name="Beta (USA).nes" size="2048" crc="2222AAAA"/>
+  </game>
+  <game name="Gamma (Europe) (Rev 1)">
+    <description>Gamma (Europe) (Rev 1)</description>
+    <rom name="Gamma (Europe) (Rev 1).nes" size="4096" crc="33333333"/>
+  </game>
+</datafile>
+"#;
+
+#[test]
+fn dat_diff_reports_renamed_games_and_changed_checksums() {
+    let dir = tempdir().unwrap();
+    let old_path = dir.path().join("old.dat");
+    let new_path = dir.path().join("new.dat");
+    fs::write(&old_path, OLD_DAT).unwrap();
+    fs::write(&new_path, NEW_DAT).unwrap();
+
+    let old = DatFile::load(&old_path).unwrap();
+    let new = DatFile::load(&new_path).unwrap();
+    let diff = dat::diff(&old, &new);
+
+    assert_eq!(diff.added, vec!["Gamma (Europe) (Rev 1)".to_string()]);
+    assert_eq!(diff.removed, vec!["Gamma (Europe)".to_string()]);
+    assert_eq!(
+        diff.changed,
+        vec![DatGameChange {
+            game: "Beta (USA)".to_string(),
+            roms: vec!["Beta (USA).nes".to_string()],
+        }]
+    );
+
+    // Diffing a DAT against itself reports nothing.
+    assert_eq!(dat::diff(&new, &new), dat::DatDiff::default());
+}
@@ -15,19 +15,28 @@ fn config_with_dats(dat_paths: Vec<PathBuf>, output: Option<PathBuf>) -> Config
         input: vec![],
         input_exclude: vec![],
         input_checksum_quick: false,
+        input_checksum_auto: false,
+        scan_files_only: false,
+        scan_archives_only: false,
         input_checksum_min: Checksum::Crc32,
         input_checksum_max: None,
         input_checksum_archives: ArchiveChecksumMode::Auto,
         dat: dat_paths,
         dat_exclude: vec![],
+        dat_url: vec![],
         dat_name_regex: None,
         dat_name_regex_exclude: None,
+        game_name_regex: None,
+        game_name_regex_exclude: None,
+        system: None,
+        dat_name_rewrite: None,
         dat_description_regex: None,
         dat_description_regex_exclude: None,
         dat_combine: false,
         dat_ignore_parent_clone: false,
         list_unmatched_dats: false,
         print_plan: true,
+        summary_only: false,
         enable_hasheous: false,
         igdb_client_id: None,
         igdb_token: None,
@@ -38,24 +47,37 @@ fn config_with_dats(dat_paths: Vec<PathBuf>, output: Option<PathBuf>) -> Config
         patch_exclude: vec![],
         output,
         dir_mirror: false,
+        dir_mirror_root: false,
         dir_dat_mirror: false,
         dir_dat_name: false,
         dir_dat_description: false,
         dir_letter: false,
+        dir_region: false,
         dir_letter_count: None,
         dir_letter_limit: None,
         dir_letter_group: false,
+        dir_letter_ignore_articles: false,
         dir_game_subdir: DirGameSubdirMode::Multiple,
         fix_extension: FixExtensionMode::Auto,
+        normalize_extension_case: false,
+        shorten_paths: false,
         overwrite: false,
         overwrite_invalid: false,
+        on_collision: igir::types::CollisionPolicy::Skip,
+        quarantine: None,
+        no_space_check: false,
         move_delete_dirs: MoveDeleteDirsMode::Auto,
+        extract_include: None,
+        extract_exclude: None,
         clean_exclude: vec![],
         clean_backup: None,
+        clean_backup_keep: None,
         clean_dry_run: false,
         zip_format: ZipFormat::Torrentzip,
-        zip_exclude: None,
+        zip_exclude: vec![],
         zip_dat_name: false,
+        zip_comment: None,
+        to_chd: false,
         link_mode: LinkMode::Hardlink,
         symlink_relative: false,
         header: None,
@@ -70,8 +92,12 @@ fn config_with_dats(dat_paths: Vec<PathBuf>, output: Option<PathBuf>) -> Config
         filter_regex: None,
         filter_regex_exclude: None,
         filter_language: None,
+        keep_languages: None,
+        keep_top_n: None,
         filter_region: None,
         filter_category_regex: None,
+        only_genre: None,
+        only_platform: None,
         no_bios: false,
         no_device: false,
         no_unlicensed: false,
@@ -83,13 +109,43 @@ fn config_with_dats(dat_paths: Vec<PathBuf>, output: Option<PathBuf>) -> Config
         no_prototype: false,
         no_program: false,
         verbose: 0,
+        no_color: false,
         quiet: 0,
         diag: false,
+        plan_diff: false,
+        region_map: None,
+        profile: false,
+        log_style: igir::types::LogStyle::Default,
+        exec: None,
         cache_only: false,
         cache_db: None,
+        xattr_cache: false,
         hash_threads: None,
         scan_threads: None,
+        archive_threads: None,
         show_match_reasons: false,
+        min_score: None,
+        tiebreak_mtime: igir::types::MtimeTiebreak::Path,
+        priority_input: false,
+        title_strip_regex: vec![],
+        ignore_case: true,
+        output_structure: None,
+        threads: None,
+        strict: false,
+        allow_no_matches: false,
+        dat_best_match: false,
+        dat_priority: Vec::new(),
+        dat_diff: None,
+        fixdat_format: None,
+        hash_algorithms: None,
+        hash_checkpoint_mib: None,
+        expect_crc32: None,
+        expect_md5: None,
+        expect_sha1: None,
+        expect_sha256: None,
+        max_decompressed_size: None,
+        skip_existing_checksum: false,
+        report_duplicates: false,
         online_timeout_secs: Some(5),
         online_max_retries: Some(3),
         online_throttle_ms: None,
@@ -143,3 +199,305 @@ fn missing_explicit_path_errors() {
     let missing_str = missing.to_string_lossy().to_string();
     assert!(err.to_string().contains(&missing_str));
 }
+
+#[test]
+fn sha256_only_dat_warns_when_checksum_max_is_lower() {
+    let dir = tempdir().unwrap();
+    let dat = dir.path().join("sha256-only.dat");
+    let mut f = File::create(&dat).unwrap();
+    f.write_all(br#"<?xml version="1.0"?><datafile><game name="G1"><rom name="rom1.bin" size="5" sha256="9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"/></game></datafile>"#).unwrap();
+
+    let mut cfg = config_with_dats(vec![dat.clone()], Some(dir.path().to_path_buf()));
+    cfg.input_checksum_max = Some(Checksum::Sha1);
+
+    let roms = igir::dat::load_dat_roms(&cfg, None).unwrap();
+    let warnings = igir::dat::dat_checksum_warnings(&roms, &cfg);
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("sha256-only.dat"), "{warnings:?}");
+    assert!(warnings[0].contains("requires sha256"), "{warnings:?}");
+    assert!(igir::dat::check_dat_checksum_coverage(&roms, &cfg).is_ok());
+
+    cfg.strict = true;
+    assert!(igir::dat::check_dat_checksum_coverage(&roms, &cfg).is_err());
+
+    cfg.input_checksum_max = Some(Checksum::Sha256);
+    assert!(igir::dat::dat_checksum_warnings(&roms, &cfg).is_empty());
+}
+
+#[test]
+fn coverage_warnings_are_silenced_by_double_quiet() {
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("roms");
+    std::fs::create_dir_all(&input).unwrap();
+    let dat = dir.path().join("sha256-only.dat");
+    let mut f = File::create(&dat).unwrap();
+    f.write_all(br#"<?xml version="1.0"?><datafile><game name="G1"><rom name="rom1.bin" size="5" sha256="9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"/></game></datafile>"#).unwrap();
+
+    let run = |quiet: &[&str]| {
+        let result = std::process::Command::new(env!("CARGO_BIN_EXE_igir"))
+            .arg("verify")
+            .arg("--input")
+            .arg(&input)
+            .arg("--dat")
+            .arg(&dat)
+            .arg("--input-checksum-max")
+            .arg("sha1")
+            .args(quiet)
+            .arg("--cache-db")
+            .arg(dir.path().join("cache.sqlite"))
+            .output()
+            .expect("igir binary runs");
+        String::from_utf8_lossy(&result.stderr).to_string()
+    };
+    let stderr = run(&[]);
+    assert!(stderr.contains("WARN"), "{stderr}");
+    assert!(stderr.contains("requires sha256"), "{stderr}");
+    let stderr = run(&["-qq"]);
+    assert!(!stderr.contains("requires sha256"), "{stderr}");
+}
+
+#[test]
+fn sha1_only_dat_is_covered_by_the_default_scan() {
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("roms");
+    std::fs::create_dir_all(&input).unwrap();
+    std::fs::write(input.join("rom1.bin"), b"hello").unwrap();
+    let dat = dir.path().join("sha1-only.dat");
+    let mut f = File::create(&dat).unwrap();
+    f.write_all(br#"<?xml version="1.0"?><datafile><game name="G1"><rom name="rom1.bin" size="5" sha1="aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d"/></game></datafile>"#).unwrap();
+
+    let cfg = config_with_dats(vec![dat.clone()], Some(dir.path().to_path_buf()));
+    let roms = igir::dat::load_dat_roms(&cfg, None).unwrap();
+    let scan = igir::dat::effective_scan_config(&roms, &cfg);
+    assert!(igir::dat::dat_checksum_warnings(&roms, &scan).is_empty());
+
+    let result = std::process::Command::new(env!("CARGO_BIN_EXE_igir"))
+        .arg("verify")
+        .arg("--input")
+        .arg(&input)
+        .arg("--dat")
+        .arg(&dat)
+        .arg("--strict")
+        .arg("--cache-db")
+        .arg(dir.path().join("cache.sqlite"))
+        .output()
+        .expect("igir binary runs");
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(result.status.success(), "{stderr}");
+    assert!(!stderr.contains("requires sha1"), "{stderr}");
+    assert!(stderr.contains("Verify: 1 have, 0 miss"), "{stderr}");
+}
+
+#[test]
+fn md5_only_dat_warns_that_quick_mode_cannot_match() {
+    let dir = tempdir().unwrap();
+    let dat = dir.path().join("md5-only.dat");
+    let mut f = File::create(&dat).unwrap();
+    f.write_all(br#"<?xml version="1.0"?><datafile><game name="G1"><rom name="rom1.bin" size="5" md5="5d41402abc4b2a76b9719d911017c592"/></game></datafile>"#).unwrap();
+
+    let mut cfg = config_with_dats(vec![dat.clone()], Some(dir.path().to_path_buf()));
+    cfg.input_checksum_quick = true;
+    let roms = igir::dat::load_dat_roms(&cfg, None).unwrap();
+
+    // The default scan hashes through SHA1, so quick mode never takes the CRC-only path.
+    let scan = igir::dat::effective_scan_config(&roms, &cfg);
+    assert!(igir::dat::dat_checksum_warnings(&roms, &scan).is_empty());
+
+    cfg.input_checksum_max = Some(Checksum::Crc32);
+    let mut scan = igir::dat::effective_scan_config(&roms, &cfg);
+    let warnings = igir::dat::dat_checksum_warnings(&roms, &scan);
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("md5-only.dat"), "{warnings:?}");
+    assert!(
+        warnings[0].contains("drop --input-checksum-quick"),
+        "{warnings:?}"
+    );
+    assert!(igir::dat::check_dat_checksum_coverage(&roms, &scan).is_ok());
+
+    scan.strict = true;
+    assert!(igir::dat::check_dat_checksum_coverage(&roms, &scan).is_err());
+
+    cfg.input_checksum_quick = false;
+    let scan = igir::dat::effective_scan_config(&roms, &cfg);
+    let warnings = igir::dat::dat_checksum_warnings(&roms, &scan);
+    assert!(
+        !warnings[0].contains("--input-checksum-quick"),
+        "{warnings:?}"
+    );
+}
+
+#[test]
+fn redump_game_serial_version_and_region_are_captured() {
+    let dir = tempdir().unwrap();
+    let dat = dir.path().join("redump.dat");
+    let mut f = File::create(&dat).unwrap();
+    f.write_all(br#"<?xml version="1.0"?><datafile><game name="Crash Bandicoot (USA)"><category>Games</category><description>Crash Bandicoot (USA)</description><rom name="Crash Bandicoot (USA).cue" size="99" crc="1A2B3C4D"/><rom name="Crash Bandicoot (USA).bin" size="100" crc="5E6F7A8B"/><serial>SCUS-94900</serial><version>1.1</version><region>USA</region></game><game name="Other (USA)"><rom name="Other (USA).bin" size="10" crc="00000001"/></game></datafile>"#).unwrap();
+
+    let cfg = config_with_dats(vec![dat.clone()], Some(dir.path().to_path_buf()));
+    let roms = igir::dat::load_dat_roms(&cfg, None).unwrap();
+
+    let crash: Vec<_> = roms
+        .iter()
+        .filter(|r| r.name.starts_with("Crash Bandicoot"))
+        .collect();
+    assert_eq!(crash.len(), 2);
+    for rom in crash {
+        assert_eq!(rom.serial.as_deref(), Some("SCUS-94900"));
+        assert_eq!(rom.version.as_deref(), Some("1.1"));
+        assert_eq!(rom.region.as_deref(), Some("USA"));
+    }
+    let other = roms.iter().find(|r| r.name == "Other (USA).bin").unwrap();
+    assert!(other.serial.is_none());
+    assert!(other.version.is_none());
+    assert!(other.region.is_none());
+}
+
+#[test]
+fn tosec_rom_checksum_variants_match_either_crc_with_paired_size() {
+    let dir = tempdir().unwrap();
+    let dat = dir.path().join("tosec.dat");
+    let mut f = File::create(&dat).unwrap();
+    f.write_all(br#"<?xml version="1.0"?><datafile><game name="Game (1990)(Publisher)"><rom name="Game (1990)(Publisher).nes" size="40976" crc="11111111" size2="40960" crc2="22222222"/></game><game name="Demo (1991)"><rom name="Demo (1991).nes" size="16" crc="33333333" crc="44444444"/></game></datafile>"#).unwrap();
+
+    let cfg = config_with_dats(vec![dat.clone()], Some(dir.path().to_path_buf()));
+    let roms = igir::dat::load_dat_roms(&cfg, None).unwrap();
+    assert_eq!(roms.len(), 2);
+    let game = roms.iter().find(|r| r.name.starts_with("Game")).unwrap();
+    let variants = game.variants();
+    assert_eq!(variants.len(), 2);
+    assert_eq!(variants[1].crc32.as_deref(), Some("22222222"));
+    assert_eq!(variants[1].size, Some(40960));
+    let demo = roms.iter().find(|r| r.name.starts_with("Demo")).unwrap();
+    assert_eq!(demo.variants()[1].crc32.as_deref(), Some("44444444"));
+    assert_eq!(demo.variants()[1].size, Some(16));
+
+    let record = |name: &str, size: u64, crc: &str| igir::types::FileRecord {
+        source: PathBuf::from(name),
+        relative: PathBuf::from(name),
+        size,
+        checksums: igir::types::ChecksumSet {
+            crc32: Some(crc.to_string()),
+            md5: None,
+            sha1: None,
+            sha256: None,
+        },
+        letter_dir: None,
+        derived_platform: None,
+        derived_genres: Vec::new(),
+        derived_region: None,
+        derived_languages: Vec::new(),
+        scan_info: None,
+        output_name: None,
+        set_name: None,
+        input_root: None,
+        modified: None,
+        input_index: None,
+        fixed_extension: None,
+    };
+    let headered = record("a.nes", 40976, "11111111");
+    let headerless = record("b.nes", 40960, "22222222");
+    let mismatched_size = record("c.nes", 40976, "22222222");
+
+    for rec in [&headered, &headerless] {
+        let found = igir::dat::find_dat_for_record(rec, &roms).expect("variant should match");
+        assert_eq!(found.name, game.name);
+    }
+    assert!(igir::dat::find_dat_for_record(&mismatched_size, &roms).is_none());
+
+    let variants = igir::candidates::dat_rom_variants(&roms);
+    let candidates = igir::candidates::generate_candidates_for_variants(
+        &variants,
+        &[headered.clone(), headerless.clone(), mismatched_size],
+        &igir::candidates::MatchOptions::default(),
+    );
+    assert_eq!(candidates.len(), 2);
+    let matched: Vec<&str> = candidates[0]
+        .matches
+        .iter()
+        .map(|m| m.relative.to_str().unwrap())
+        .collect();
+    assert_eq!(matched, vec!["a.nes", "b.nes"]);
+}
+
+#[test]
+fn crc32_key_is_the_primary_crc_and_same_named_roms_stay_separate() {
+    let dir = tempdir().unwrap();
+    let dat = dir.path().join("tracks.dat");
+    let mut f = File::create(&dat).unwrap();
+    f.write_all(br#"<?xml version="1.0"?><datafile><game name="Game A"><rom name="Track 01.bin" size="16" crc32="aaaaaaaa"/></game><game name="Game B"><rom name="Track 01.bin" size="32" crc="bbbbbbbb"/></game></datafile>"#).unwrap();
+
+    let cfg = config_with_dats(vec![dat.clone()], Some(dir.path().to_path_buf()));
+    let roms = igir::dat::load_dat_roms(&cfg, None).unwrap();
+    assert_eq!(roms.len(), 2);
+    assert_eq!(roms[0].crc32.as_deref(), Some("AAAAAAAA"));
+    assert!(roms[0].alternates.is_empty());
+
+    let record = |name: &str, size: u64, crc: &str| igir::types::FileRecord {
+        source: PathBuf::from(name),
+        relative: PathBuf::from(name),
+        size,
+        checksums: igir::types::ChecksumSet {
+            crc32: Some(crc.to_string()),
+            md5: None,
+            sha1: None,
+            sha256: None,
+        },
+        letter_dir: None,
+        derived_platform: None,
+        derived_genres: Vec::new(),
+        derived_region: None,
+        derived_languages: Vec::new(),
+        scan_info: None,
+        output_name: None,
+        set_name: None,
+        input_root: None,
+        modified: None,
+        input_index: None,
+        fixed_extension: None,
+    };
+    let candidates = igir::candidates::generate_candidates_for_variants(
+        &igir::candidates::dat_rom_variants(&roms),
+        &[
+            record("a.bin", 16, "AAAAAAAA"),
+            record("b.bin", 32, "BBBBBBBB"),
+        ],
+        &igir::candidates::MatchOptions::default(),
+    );
+    let matched: Vec<Vec<&str>> = candidates
+        .iter()
+        .map(|c| {
+            c.matches
+                .iter()
+                .map(|m| m.relative.to_str().unwrap())
+                .collect()
+        })
+        .collect();
+    assert_eq!(matched, vec![vec!["a.bin"], vec!["b.bin"]]);
+}
+
+#[test]
+fn merged_mode_folds_clone_disks_into_the_parent_set() {
+    let dir = tempdir().unwrap();
+    let dat = dir.path().join("mame.xml");
+    let mut f = File::create(&dat).unwrap();
+    f.write_all(br#"<?xml version="1.0"?><mame><machine name="kinst"><description>Killer Instinct</description><rom name="kinst.u98" size="16" crc="aaaaaaaa"/><disk name="kinst" sha1="1111111111111111111111111111111111111111"/></machine><machine name="kinst2" cloneof="kinst"><description>Killer Instinct (v1.4)</description><rom name="kinst.u98" merge="kinst.u98" size="16" crc="aaaaaaaa"/><disk name="kinst" merge="kinst" sha1="1111111111111111111111111111111111111111"/><disk name="kinst2" sha1="2222222222222222222222222222222222222222"/></machine></mame>"#).unwrap();
+
+    let mut cfg = config_with_dats(vec![dat.clone()], Some(dir.path().to_path_buf()));
+    let roms = igir::dat::load_dat_roms(&cfg, None).unwrap();
+    assert_eq!(roms.len(), 5);
+
+    cfg.merge_roms = MergeMode::Merged;
+    let roms = igir::dat::load_dat_roms(&cfg, None).unwrap();
+    let sets: Vec<(&str, &str)> = roms
+        .iter()
+        .map(|rom| (rom.description.as_deref().unwrap(), rom.name.as_str()))
+        .collect();
+    assert_eq!(
+        sets,
+        vec![
+            ("Killer Instinct", "kinst.u98"),
+            ("Killer Instinct", "kinst.chd"),
+            ("Killer Instinct", "kinst2.chd"),
+        ]
+    );
+}
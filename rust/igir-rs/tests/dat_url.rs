@@ -0,0 +1,47 @@
+use std::env;
+use std::io::Write;
+
+use httpmock::Method::GET;
+use httpmock::MockServer;
+use igir::config::Config;
+use igir::dat::load_dat_roms;
+
+const DAT: &str = r#"<?xml version="1.0"?><datafile><game name="Remote Game"><rom name="Remote Game.bin" size="4" crc="DEADBEEF"/></game></datafile>"#;
+
+#[test]
+fn dat_url_downloads_caches_and_loads_plain_and_gzipped_dats() {
+    let dir = tempfile::tempdir().unwrap();
+    unsafe { env::set_var("IGIR_CONFIG_DIR", dir.path()) };
+
+    let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gz.write_all(DAT.as_bytes()).unwrap();
+    let gzipped = gz.finish().unwrap();
+
+    let server = MockServer::start();
+    let plain_mock = server.mock(|when, then| {
+        when.method(GET).path("/dats/remote.dat");
+        then.status(200).body(DAT);
+    });
+    let gz_mock = server.mock(|when, then| {
+        when.method(GET).path("/dats/remote.dat.gz");
+        then.status(200).body(gzipped.clone());
+    });
+
+    let config = Config {
+        dat_url: vec![
+            server.url("/dats/remote.dat"),
+            server.url("/dats/remote.dat.gz"),
+        ],
+        ..Config::default()
+    };
+    let roms = load_dat_roms(&config, None).unwrap();
+
+    plain_mock.assert();
+    gz_mock.assert();
+    assert_eq!(roms.len(), 2);
+    assert!(roms.iter().all(|rom| rom.name == "Remote Game.bin"));
+    assert!(
+        roms.iter()
+            .all(|rom| rom.source_dat.starts_with(dir.path().join("dats")))
+    );
+}
@@ -0,0 +1,56 @@
+#![cfg(unix)]
+
+use std::fs;
+
+use anyhow::Result;
+use tempfile::tempdir;
+
+use igir::actions::perform_actions;
+use igir::config::Config;
+use igir::types::Action;
+
+#[test]
+fn exec_runs_once_per_written_file_and_records_failures() -> Result<()> {
+    let tmp = tempdir()?;
+    let input_dir = tmp.path().join("input");
+    let output_dir = tmp.path().join("output");
+    fs::create_dir_all(&input_dir)?;
+    fs::write(input_dir.join("First Game.rom"), b"first rom")?;
+    fs::write(input_dir.join("Second; touch pwned.rom"), b"second rom")?;
+
+    let mut cfg = Config {
+        commands: vec![Action::Copy],
+        input: vec![input_dir.clone()],
+        output: Some(output_dir.clone()),
+        enable_hasheous: false,
+        cache_db: Some(tmp.path().join("igir_cache.sqlite")),
+        exec: Some("touch '{path}.{crc32}.seen'".to_string()),
+        ..Config::default()
+    };
+
+    let plan = perform_actions(&cfg)?;
+    assert!(plan.exec_failures.is_empty(), "{:?}", plan.exec_failures);
+    let mut markers: Vec<String> = fs::read_dir(&output_dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".seen"))
+        .collect();
+    markers.sort();
+    assert_eq!(markers.len(), 2, "{markers:?}");
+    assert!(markers[0].starts_with("First Game.rom."));
+    assert!(markers[1].starts_with("Second; touch pwned.rom."));
+    assert!(!tmp.path().join("pwned").exists());
+    assert!(!output_dir.join("pwned").exists());
+
+    cfg.overwrite = true;
+    cfg.exec = Some("false {path}".to_string());
+    let plan = perform_actions(&cfg)?;
+    assert_eq!(plan.exec_failures.len(), 2);
+    assert_eq!(plan.exec_failures[0].command[0], "false");
+
+    // Outputs kept as they are (no --overwrite) were not written, so no hook runs.
+    cfg.overwrite = false;
+    let plan = perform_actions(&cfg)?;
+    assert!(plan.exec_failures.is_empty(), "{:?}", plan.exec_failures);
+    Ok(())
+}
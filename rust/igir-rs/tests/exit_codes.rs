@@ -0,0 +1,56 @@
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+
+fn run_igir(input: &Path, dat: &Path, cache: &Path, extra: &[&str]) -> Option<i32> {
+    Command::new(env!("CARGO_BIN_EXE_igir"))
+        .arg("test")
+        .arg("--input")
+        .arg(input)
+        .arg("--dat")
+        .arg(dat)
+        .arg("--cache-db")
+        .arg(cache)
+        .arg("--cache-only")
+        .args(extra)
+        .output()
+        .expect("igir binary runs")
+        .status
+        .code()
+}
+
+#[test]
+fn no_matching_inputs_exit_with_distinct_code() {
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("roms");
+    std::fs::create_dir_all(&input).unwrap();
+    std::fs::write(input.join("Game.bin"), b"not in the dat").unwrap();
+    let cache = dir.path().join("cache.sqlite");
+
+    let dat = dir.path().join("nomatch.dat");
+    std::fs::write(
+        &dat,
+        r#"<?xml version="1.0"?><datafile><game name="Other"><rom name="Other.bin" size="3" crc="DEADBEEF"/></game></datafile>"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        run_igir(&input, &dat, &cache, &[]),
+        Some(igir::types::EXIT_NO_MATCHES)
+    );
+    assert_eq!(
+        run_igir(&input, &dat, &cache, &["--allow-no-matches"]),
+        Some(0)
+    );
+
+    let crc = format!("{:08X}", crc32fast::hash(b"not in the dat"));
+    let matching = dir.path().join("match.dat");
+    std::fs::write(
+        &matching,
+        format!(
+            r#"<?xml version="1.0"?><datafile><game name="Game"><rom name="Game.bin" size="14" crc="{crc}"/></game></datafile>"#
+        ),
+    )
+    .unwrap();
+    assert_eq!(run_igir(&input, &matching, &cache, &[]), Some(0));
+}
@@ -0,0 +1,31 @@
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::tempdir;
+
+fn run_test(input: &Path, sha1: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_igir"))
+        .arg("test")
+        .arg("--input")
+        .arg(input)
+        .arg("--expect-sha1")
+        .arg(sha1)
+        .output()
+        .expect("igir binary runs")
+}
+
+#[test]
+fn expect_sha1_exits_zero_only_on_a_match() {
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("hello.bin");
+    std::fs::write(&input, b"hello").unwrap();
+
+    let pass = run_test(&input, "AAF4C61DDCC5E8A2DABEDE0F3B482CD9AEA9434D");
+    assert!(pass.status.success());
+    assert!(String::from_utf8_lossy(&pass.stdout).starts_with("PASS "));
+
+    let fail = run_test(&input, "0000000000000000000000000000000000000000");
+    assert_eq!(fail.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&fail.stdout);
+    assert!(stdout.starts_with("FAIL "));
+    assert!(stdout.contains("got aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d"));
+}
@@ -46,13 +46,21 @@ fn saves_and_loads_igdb_credentials() {
         input: vec![PathBuf::from("/tmp/file.bin")],
         input_exclude: vec![],
         input_checksum_quick: false,
+        input_checksum_auto: false,
+        scan_files_only: false,
+        scan_archives_only: false,
         input_checksum_min: Checksum::Crc32,
         input_checksum_max: None,
         input_checksum_archives: ArchiveChecksumMode::Auto,
         dat: vec![],
         dat_exclude: vec![],
+        dat_url: vec![],
         dat_name_regex: None,
         dat_name_regex_exclude: None,
+        game_name_regex: None,
+        game_name_regex_exclude: None,
+        system: None,
+        dat_name_rewrite: None,
         dat_description_regex: None,
         dat_description_regex_exclude: None,
         dat_combine: false,
@@ -65,32 +73,69 @@ fn saves_and_loads_igdb_credentials() {
         igdb_mode: igir::types::IgdbLookupMode::BestEffort,
         cache_only: false,
         cache_db: None,
+        xattr_cache: false,
         hash_threads: None,
         scan_threads: None,
+        archive_threads: None,
         show_match_reasons: false,
+        min_score: None,
+        tiebreak_mtime: igir::types::MtimeTiebreak::Path,
+        priority_input: false,
+        title_strip_regex: vec![],
+        ignore_case: true,
+        output_structure: None,
+        threads: None,
+        strict: false,
+        allow_no_matches: false,
+        dat_best_match: false,
+        dat_priority: Vec::new(),
+        dat_diff: Vec::new(),
+        fixdat_format: None,
+        hash_algorithms: None,
+        hash_checkpoint_mib: None,
+        expect_crc32: None,
+        expect_md5: None,
+        expect_sha1: None,
+        expect_sha256: None,
+        max_decompressed_size: None,
+        skip_existing_checksum: false,
+        report_duplicates: false,
         save_igdb_creds: true,
         patch: vec![],
         patch_exclude: vec![],
         output: Some(PathBuf::from("out")),
         dir_mirror: false,
+        dir_mirror_root: false,
         dir_dat_mirror: false,
         dir_dat_name: false,
         dir_dat_description: false,
         dir_letter: false,
+        dir_region: false,
         dir_letter_count: None,
         dir_letter_limit: None,
         dir_letter_group: false,
+        dir_letter_ignore_articles: false,
         dir_game_subdir: DirGameSubdirMode::Multiple,
         fix_extension: FixExtensionMode::Auto,
+        normalize_extension_case: false,
+        shorten_paths: false,
         overwrite: false,
         overwrite_invalid: false,
+        on_collision: igir::types::CollisionPolicy::Skip,
+        quarantine: None,
+        no_space_check: false,
         move_delete_dirs: MoveDeleteDirsMode::Auto,
+        extract_include: None,
+        extract_exclude: None,
         clean_exclude: vec![],
         clean_backup: None,
+        clean_backup_keep: None,
         clean_dry_run: false,
         zip_format: ZipFormat::Torrentzip,
-        zip_exclude: None,
+        zip_exclude: vec![],
         zip_dat_name: false,
+        zip_comment: None,
+        to_chd: false,
         link_mode: LinkMode::Hardlink,
         symlink_relative: false,
         header: None,
@@ -105,8 +150,12 @@ fn saves_and_loads_igdb_credentials() {
         filter_regex: None,
         filter_regex_exclude: None,
         filter_language: None,
+        keep_languages: None,
+        keep_top_n: None,
         filter_region: None,
         filter_category_regex: None,
+        only_genre: None,
+        only_platform: None,
         no_bios: false,
         no_device: false,
         no_unlicensed: false,
@@ -118,9 +167,17 @@ fn saves_and_loads_igdb_credentials() {
         no_prototype: false,
         no_program: false,
         verbose: 0,
+        no_color: false,
         quiet: 0,
         diag: false,
+        plan_diff: false,
+        region_map: None,
+        profile: false,
+        log_style: igir::types::LogStyle::Default,
+        exec: None,
+        config_file: None,
         print_plan: false,
+        summary_only: false,
     };
 
     // Create and persist config via TryFrom
@@ -160,13 +217,21 @@ fn saves_and_loads_igdb_credentials() {
         input: vec![PathBuf::from("/tmp/file.bin")],
         input_exclude: vec![],
         input_checksum_quick: false,
+        input_checksum_auto: false,
+        scan_files_only: false,
+        scan_archives_only: false,
         input_checksum_min: Checksum::Crc32,
         input_checksum_max: None,
         input_checksum_archives: ArchiveChecksumMode::Auto,
         dat: vec![],
         dat_exclude: vec![],
+        dat_url: vec![],
         dat_name_regex: None,
         dat_name_regex_exclude: None,
+        game_name_regex: None,
+        game_name_regex_exclude: None,
+        system: None,
+        dat_name_rewrite: None,
         dat_description_regex: None,
         dat_description_regex_exclude: None,
         dat_combine: false,
@@ -179,32 +244,69 @@ fn saves_and_loads_igdb_credentials() {
         igdb_mode: igir::types::IgdbLookupMode::BestEffort,
         cache_only: false,
         cache_db: None,
+        xattr_cache: false,
         hash_threads: None,
         scan_threads: None,
+        archive_threads: None,
         show_match_reasons: false,
+        min_score: None,
+        tiebreak_mtime: igir::types::MtimeTiebreak::Path,
+        priority_input: false,
+        title_strip_regex: vec![],
+        ignore_case: true,
+        output_structure: None,
+        threads: None,
+        strict: false,
+        allow_no_matches: false,
+        dat_best_match: false,
+        dat_priority: Vec::new(),
+        dat_diff: Vec::new(),
+        fixdat_format: None,
+        hash_algorithms: None,
+        hash_checkpoint_mib: None,
+        expect_crc32: None,
+        expect_md5: None,
+        expect_sha1: None,
+        expect_sha256: None,
+        max_decompressed_size: None,
+        skip_existing_checksum: false,
+        report_duplicates: false,
         save_igdb_creds: false,
         patch: vec![],
         patch_exclude: vec![],
         output: Some(PathBuf::from("out")),
         dir_mirror: false,
+        dir_mirror_root: false,
         dir_dat_mirror: false,
         dir_dat_name: false,
         dir_dat_description: false,
         dir_letter: false,
+        dir_region: false,
         dir_letter_count: None,
         dir_letter_limit: None,
         dir_letter_group: false,
+        dir_letter_ignore_articles: false,
         dir_game_subdir: DirGameSubdirMode::Multiple,
         fix_extension: FixExtensionMode::Auto,
+        normalize_extension_case: false,
+        shorten_paths: false,
         overwrite: false,
         overwrite_invalid: false,
+        on_collision: igir::types::CollisionPolicy::Skip,
+        quarantine: None,
+        no_space_check: false,
         move_delete_dirs: MoveDeleteDirsMode::Auto,
+        extract_include: None,
+        extract_exclude: None,
         clean_exclude: vec![],
         clean_backup: None,
+        clean_backup_keep: None,
         clean_dry_run: false,
         zip_format: ZipFormat::Torrentzip,
-        zip_exclude: None,
+        zip_exclude: vec![],
         zip_dat_name: false,
+        zip_comment: None,
+        to_chd: false,
         link_mode: LinkMode::Hardlink,
         symlink_relative: false,
         header: None,
@@ -219,8 +321,12 @@ fn saves_and_loads_igdb_credentials() {
         filter_regex: None,
         filter_regex_exclude: None,
         filter_language: None,
+        keep_languages: None,
+        keep_top_n: None,
         filter_region: None,
         filter_category_regex: None,
+        only_genre: None,
+        only_platform: None,
         no_bios: false,
         no_device: false,
         no_unlicensed: false,
@@ -232,9 +338,17 @@ fn saves_and_loads_igdb_credentials() {
         no_prototype: false,
         no_program: false,
         verbose: 0,
+        no_color: false,
         quiet: 0,
         diag: false,
+        plan_diff: false,
+        region_map: None,
+        profile: false,
+        log_style: igir::types::LogStyle::Default,
+        exec: None,
+        config_file: None,
         print_plan: false,
+        summary_only: false,
     };
 
     let cfg2 = Config::try_from(cli_load).expect("should create config with loaded creds");
@@ -270,13 +384,21 @@ fn auto_fetches_token_when_secret_available() {
         input: vec![PathBuf::from("/tmp/file.bin")],
         input_exclude: vec![],
         input_checksum_quick: false,
+        input_checksum_auto: false,
+        scan_files_only: false,
+        scan_archives_only: false,
         input_checksum_min: Checksum::Crc32,
         input_checksum_max: None,
         input_checksum_archives: ArchiveChecksumMode::Auto,
         dat: vec![],
         dat_exclude: vec![],
+        dat_url: vec![],
         dat_name_regex: None,
         dat_name_regex_exclude: None,
+        game_name_regex: None,
+        game_name_regex_exclude: None,
+        system: None,
+        dat_name_rewrite: None,
         dat_description_regex: None,
         dat_description_regex_exclude: None,
         dat_combine: false,
@@ -288,33 +410,70 @@ fn auto_fetches_token_when_secret_available() {
         igdb_token: None,
         igdb_mode: igir::types::IgdbLookupMode::BestEffort,
         cache_db: None,
+        xattr_cache: false,
         hash_threads: None,
         scan_threads: None,
+        archive_threads: None,
         show_match_reasons: false,
+        min_score: None,
+        tiebreak_mtime: igir::types::MtimeTiebreak::Path,
+        priority_input: false,
+        title_strip_regex: vec![],
+        ignore_case: true,
+        output_structure: None,
+        threads: None,
+        strict: false,
+        allow_no_matches: false,
+        dat_best_match: false,
+        dat_priority: Vec::new(),
+        dat_diff: Vec::new(),
+        fixdat_format: None,
+        hash_algorithms: None,
+        hash_checkpoint_mib: None,
+        expect_crc32: None,
+        expect_md5: None,
+        expect_sha1: None,
+        expect_sha256: None,
+        max_decompressed_size: None,
+        skip_existing_checksum: false,
+        report_duplicates: false,
         cache_only: false,
         save_igdb_creds: true,
         patch: vec![],
         patch_exclude: vec![],
         output: Some(PathBuf::from("out")),
         dir_mirror: false,
+        dir_mirror_root: false,
         dir_dat_mirror: false,
         dir_dat_name: false,
         dir_dat_description: false,
         dir_letter: false,
+        dir_region: false,
         dir_letter_count: None,
         dir_letter_limit: None,
         dir_letter_group: false,
+        dir_letter_ignore_articles: false,
         dir_game_subdir: DirGameSubdirMode::Multiple,
         fix_extension: FixExtensionMode::Auto,
+        normalize_extension_case: false,
+        shorten_paths: false,
         overwrite: false,
         overwrite_invalid: false,
+        on_collision: igir::types::CollisionPolicy::Skip,
+        quarantine: None,
+        no_space_check: false,
         move_delete_dirs: MoveDeleteDirsMode::Auto,
+        extract_include: None,
+        extract_exclude: None,
         clean_exclude: vec![],
         clean_backup: None,
+        clean_backup_keep: None,
         clean_dry_run: false,
         zip_format: ZipFormat::Torrentzip,
-        zip_exclude: None,
+        zip_exclude: vec![],
         zip_dat_name: false,
+        zip_comment: None,
+        to_chd: false,
         link_mode: LinkMode::Hardlink,
         symlink_relative: false,
         header: None,
@@ -329,8 +488,12 @@ fn auto_fetches_token_when_secret_available() {
         filter_regex: None,
         filter_regex_exclude: None,
         filter_language: None,
+        keep_languages: None,
+        keep_top_n: None,
         filter_region: None,
         filter_category_regex: None,
+        only_genre: None,
+        only_platform: None,
         no_bios: false,
         no_device: false,
         no_unlicensed: false,
@@ -342,9 +505,17 @@ fn auto_fetches_token_when_secret_available() {
         no_prototype: false,
         no_program: false,
         verbose: 0,
+        no_color: false,
         quiet: 0,
         diag: false,
+        plan_diff: false,
+        region_map: None,
+        profile: false,
+        log_style: igir::types::LogStyle::Default,
+        exec: None,
+        config_file: None,
         print_plan: false,
+        summary_only: false,
     };
 
     let cfg = Config::try_from(cli).expect("config should be created");
@@ -407,13 +578,21 @@ fn refreshes_stale_token_without_expiry() {
         input: vec![PathBuf::from("/tmp/file.bin")],
         input_exclude: vec![],
         input_checksum_quick: false,
+        input_checksum_auto: false,
+        scan_files_only: false,
+        scan_archives_only: false,
         input_checksum_min: Checksum::Crc32,
         input_checksum_max: None,
         input_checksum_archives: ArchiveChecksumMode::Auto,
         dat: vec![],
         dat_exclude: vec![],
+        dat_url: vec![],
         dat_name_regex: None,
         dat_name_regex_exclude: None,
+        game_name_regex: None,
+        game_name_regex_exclude: None,
+        system: None,
+        dat_name_rewrite: None,
         dat_description_regex: None,
         dat_description_regex_exclude: None,
         dat_combine: false,
@@ -425,33 +604,70 @@ fn refreshes_stale_token_without_expiry() {
         igdb_token: None,
         igdb_mode: igir::types::IgdbLookupMode::BestEffort,
         cache_db: None,
+        xattr_cache: false,
         hash_threads: None,
         scan_threads: None,
+        archive_threads: None,
         show_match_reasons: false,
+        min_score: None,
+        tiebreak_mtime: igir::types::MtimeTiebreak::Path,
+        priority_input: false,
+        title_strip_regex: vec![],
+        ignore_case: true,
+        output_structure: None,
+        threads: None,
+        strict: false,
+        allow_no_matches: false,
+        dat_best_match: false,
+        dat_priority: Vec::new(),
+        dat_diff: Vec::new(),
+        fixdat_format: None,
+        hash_algorithms: None,
+        hash_checkpoint_mib: None,
+        expect_crc32: None,
+        expect_md5: None,
+        expect_sha1: None,
+        expect_sha256: None,
+        max_decompressed_size: None,
+        skip_existing_checksum: false,
+        report_duplicates: false,
         cache_only: false,
         save_igdb_creds: false,
         patch: vec![],
         patch_exclude: vec![],
         output: Some(PathBuf::from("out")),
         dir_mirror: false,
+        dir_mirror_root: false,
         dir_dat_mirror: false,
         dir_dat_name: false,
         dir_dat_description: false,
         dir_letter: false,
+        dir_region: false,
         dir_letter_count: None,
         dir_letter_limit: None,
         dir_letter_group: false,
+        dir_letter_ignore_articles: false,
         dir_game_subdir: DirGameSubdirMode::Multiple,
         fix_extension: FixExtensionMode::Auto,
+        normalize_extension_case: false,
+        shorten_paths: false,
         overwrite: false,
         overwrite_invalid: false,
+        on_collision: igir::types::CollisionPolicy::Skip,
+        quarantine: None,
+        no_space_check: false,
         move_delete_dirs: MoveDeleteDirsMode::Auto,
+        extract_include: None,
+        extract_exclude: None,
         clean_exclude: vec![],
         clean_backup: None,
+        clean_backup_keep: None,
         clean_dry_run: false,
         zip_format: ZipFormat::Torrentzip,
-        zip_exclude: None,
+        zip_exclude: vec![],
         zip_dat_name: false,
+        zip_comment: None,
+        to_chd: false,
         link_mode: LinkMode::Hardlink,
         symlink_relative: false,
         header: None,
@@ -466,8 +682,12 @@ fn refreshes_stale_token_without_expiry() {
         filter_regex: None,
         filter_regex_exclude: None,
         filter_language: None,
+        keep_languages: None,
+        keep_top_n: None,
         filter_region: None,
         filter_category_regex: None,
+        only_genre: None,
+        only_platform: None,
         no_bios: false,
         no_device: false,
         no_unlicensed: false,
@@ -479,9 +699,17 @@ fn refreshes_stale_token_without_expiry() {
         no_prototype: false,
         no_program: false,
         verbose: 0,
+        no_color: false,
         quiet: 0,
         diag: false,
+        plan_diff: false,
+        region_map: None,
+        profile: false,
+        log_style: igir::types::LogStyle::Default,
+        exec: None,
+        config_file: None,
         print_plan: false,
+        summary_only: false,
     };
 
     let cfg = Config::try_from(cli).expect("config should load");
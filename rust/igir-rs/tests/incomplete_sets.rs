@@ -0,0 +1,54 @@
+use std::fs;
+
+use anyhow::Result;
+use tempfile::tempdir;
+
+use igir::actions::perform_actions;
+use igir::config::Config;
+use igir::types::Action;
+
+#[test]
+fn allow_incomplete_sets_reports_the_missing_parts() -> Result<()> {
+    let tmp = tempdir()?;
+    let input_dir = tmp.path().join("input");
+    fs::create_dir_all(&input_dir)?;
+    fs::write(input_dir.join("Game (Track 1).bin"), b"track one")?;
+
+    let rom = |name: &str, bytes: &[u8]| {
+        format!(
+            r#"<rom name="{name}" size="{}" crc="{:08x}"/>"#,
+            bytes.len(),
+            crc32fast::hash(bytes)
+        )
+    };
+    let dat = tmp.path().join("sets.dat");
+    fs::write(
+        &dat,
+        format!(
+            r#"<?xml version="1.0"?><datafile><game name="Game"><description>Game</description>{}{}</game></datafile>"#,
+            rom("Game (Track 1).bin", b"track one"),
+            rom("Game (Track 2).bin", b"track two")
+        ),
+    )?;
+
+    let cfg = Config {
+        commands: vec![Action::Copy],
+        input: vec![input_dir],
+        output: Some(tmp.path().join("output")),
+        dat: vec![dat],
+        allow_incomplete_sets: true,
+        enable_hasheous: false,
+        cache_db: Some(tmp.path().join("igir_cache.sqlite")),
+        ..Config::default()
+    };
+
+    let plan = perform_actions(&cfg)?;
+    let incomplete: Vec<&str> = plan
+        .steps
+        .iter()
+        .filter(|step| step.status == "incomplete")
+        .map(|step| step.note.as_str())
+        .collect();
+    assert_eq!(incomplete, vec!["Game: missing Game (Track 2).bin"]);
+    Ok(())
+}
@@ -29,19 +29,28 @@ fn dat_loads_and_parses_roms() {
         input: vec![],
         input_exclude: vec![],
         input_checksum_quick: false,
+        input_checksum_auto: false,
+        scan_files_only: false,
+        scan_archives_only: false,
         input_checksum_min: Checksum::Crc32,
         input_checksum_max: None,
         input_checksum_archives: ArchiveChecksumMode::Auto,
         dat: vec![dat_path.clone()],
         dat_exclude: vec![],
+        dat_url: vec![],
         dat_name_regex: None,
         dat_name_regex_exclude: None,
+        game_name_regex: None,
+        game_name_regex_exclude: None,
+        system: None,
+        dat_name_rewrite: None,
         dat_description_regex: None,
         dat_description_regex_exclude: None,
         dat_combine: false,
         dat_ignore_parent_clone: false,
         list_unmatched_dats: false,
         print_plan: true,
+        summary_only: false,
         enable_hasheous: false,
         igdb_client_id: None,
         igdb_token: None,
@@ -52,24 +61,37 @@ fn dat_loads_and_parses_roms() {
         patch_exclude: vec![],
         output: None,
         dir_mirror: false,
+        dir_mirror_root: false,
         dir_dat_mirror: false,
         dir_dat_name: false,
         dir_dat_description: false,
         dir_letter: false,
+        dir_region: false,
         dir_letter_count: None,
         dir_letter_limit: None,
         dir_letter_group: false,
+        dir_letter_ignore_articles: false,
         dir_game_subdir: DirGameSubdirMode::Multiple,
         fix_extension: FixExtensionMode::Auto,
+        normalize_extension_case: false,
+        shorten_paths: false,
         overwrite: false,
         overwrite_invalid: false,
+        on_collision: igir::types::CollisionPolicy::Skip,
+        quarantine: None,
+        no_space_check: false,
         move_delete_dirs: MoveDeleteDirsMode::Auto,
+        extract_include: None,
+        extract_exclude: None,
         clean_exclude: vec![],
         clean_backup: None,
+        clean_backup_keep: None,
         clean_dry_run: false,
         zip_format: ZipFormat::Torrentzip,
-        zip_exclude: None,
+        zip_exclude: vec![],
         zip_dat_name: false,
+        zip_comment: None,
+        to_chd: false,
         link_mode: LinkMode::Hardlink,
         symlink_relative: false,
         header: None,
@@ -84,8 +106,12 @@ fn dat_loads_and_parses_roms() {
         filter_regex: None,
         filter_regex_exclude: None,
         filter_language: None,
+        keep_languages: None,
+        keep_top_n: None,
         filter_region: None,
         filter_category_regex: None,
+        only_genre: None,
+        only_platform: None,
         no_bios: false,
         no_device: false,
         no_unlicensed: false,
@@ -97,13 +123,43 @@ fn dat_loads_and_parses_roms() {
         no_prototype: false,
         no_program: false,
         verbose: 0,
+        no_color: false,
         quiet: 0,
         diag: false,
+        plan_diff: false,
+        region_map: None,
+        profile: false,
+        log_style: igir::types::LogStyle::Default,
+        exec: None,
         cache_only: false,
         cache_db: None,
+        xattr_cache: false,
         hash_threads: None,
         scan_threads: None,
+        archive_threads: None,
         show_match_reasons: false,
+        min_score: None,
+        tiebreak_mtime: igir::types::MtimeTiebreak::Path,
+        priority_input: false,
+        title_strip_regex: vec![],
+        ignore_case: true,
+        output_structure: None,
+        threads: None,
+        strict: false,
+        allow_no_matches: false,
+        dat_best_match: false,
+        dat_priority: Vec::new(),
+        dat_diff: None,
+        fixdat_format: None,
+        hash_algorithms: None,
+        hash_checkpoint_mib: None,
+        expect_crc32: None,
+        expect_md5: None,
+        expect_sha1: None,
+        expect_sha256: None,
+        max_decompressed_size: None,
+        skip_existing_checksum: false,
+        report_duplicates: false,
         online_timeout_secs: Some(5),
         online_max_retries: Some(3),
         online_throttle_ms: None,
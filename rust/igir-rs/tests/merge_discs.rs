@@ -0,0 +1,99 @@
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn merge_discs_writes_one_folder_with_renamed_discs_and_playlist() {
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("roms");
+    std::fs::create_dir_all(&input).unwrap();
+    std::fs::write(input.join("Game (USA) (Disk 1 of 2).iso"), b"disc one").unwrap();
+    std::fs::write(input.join("Game (USA) (Disk 2 of 2).iso"), b"disc two").unwrap();
+    std::fs::write(input.join("Other (USA).iso"), b"single disc").unwrap();
+
+    let output = dir.path().join("out");
+    let status = Command::new(env!("CARGO_BIN_EXE_igir"))
+        .arg("copy")
+        .arg("clean")
+        .arg("--input")
+        .arg(&input)
+        .arg("--output")
+        .arg(&output)
+        .arg("--merge-discs")
+        .arg("--allow-no-matches")
+        .arg("--cache-db")
+        .arg(dir.path().join("cache.sqlite"))
+        .arg("--cache-only")
+        .status()
+        .expect("igir binary runs");
+    assert!(status.success());
+
+    let folder = output.join("Game (USA)");
+    assert_eq!(
+        std::fs::read(folder.join("Game (USA) (Disc 1).iso")).unwrap(),
+        b"disc one"
+    );
+    assert_eq!(
+        std::fs::read(folder.join("Game (USA) (Disc 2).iso")).unwrap(),
+        b"disc two"
+    );
+    assert_eq!(
+        std::fs::read_to_string(folder.join("Game (USA).m3u")).unwrap(),
+        "Game (USA) (Disc 1).iso\nGame (USA) (Disc 2).iso\n"
+    );
+    assert!(output.join("Other (USA).iso").exists());
+}
+
+#[test]
+fn merge_discs_groups_by_the_matched_dat_game() {
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("roms");
+    std::fs::create_dir_all(&input).unwrap();
+    // Input names carry no disc tags; the DAT games do.
+    std::fs::write(input.join("first.iso"), b"disc one").unwrap();
+    std::fs::write(input.join("second.iso"), b"disc two").unwrap();
+
+    let dat = dir.path().join("discs.dat");
+    let game = |disc: u32, bytes: &[u8]| {
+        format!(
+            r#"<game name="Game (USA) (Disc {disc})"><rom name="Game (USA) (Disc {disc}).iso" size="{}" crc="{:08x}"/></game>"#,
+            bytes.len(),
+            crc32fast::hash(bytes)
+        )
+    };
+    std::fs::write(
+        &dat,
+        format!(
+            r#"<?xml version="1.0"?><datafile>{}{}</datafile>"#,
+            game(1, b"disc one"),
+            game(2, b"disc two")
+        ),
+    )
+    .unwrap();
+
+    let output = dir.path().join("out");
+    let status = Command::new(env!("CARGO_BIN_EXE_igir"))
+        .arg("copy")
+        .arg("--input")
+        .arg(&input)
+        .arg("--dat")
+        .arg(&dat)
+        .arg("--output")
+        .arg(&output)
+        .arg("--merge-discs")
+        .arg("--cache-db")
+        .arg(dir.path().join("cache.sqlite"))
+        .arg("--cache-only")
+        .status()
+        .expect("igir binary runs");
+    assert!(status.success());
+
+    let folder = output.join("Game (USA)");
+    assert_eq!(
+        std::fs::read(folder.join("Game (USA) (Disc 2).iso")).unwrap(),
+        b"disc two"
+    );
+    assert_eq!(
+        std::fs::read_to_string(folder.join("Game (USA).m3u")).unwrap(),
+        "Game (USA) (Disc 1).iso\nGame (USA) (Disc 2).iso\n"
+    );
+}
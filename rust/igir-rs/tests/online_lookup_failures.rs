@@ -25,6 +25,12 @@ fn record_with_checksums(sha1: Option<&str>, md5: Option<&str>) -> FileRecord {
         derived_region: None,
         derived_languages: Vec::new(),
         scan_info: None,
+        output_name: None,
+        set_name: None,
+        input_root: None,
+        modified: None,
+        input_index: None,
+        fixed_extension: None,
     }
 }
 
@@ -22,19 +22,28 @@ fn discovers_patch_files_by_glob_and_detects_type() {
         input: vec![],
         input_exclude: vec![],
         input_checksum_quick: false,
+        input_checksum_auto: false,
+        scan_files_only: false,
+        scan_archives_only: false,
         input_checksum_min: igir::types::Checksum::Crc32,
         input_checksum_max: None,
         input_checksum_archives: igir::types::ArchiveChecksumMode::Auto,
         dat: vec![],
         dat_exclude: vec![],
+        dat_url: vec![],
         dat_name_regex: None,
         dat_name_regex_exclude: None,
+        game_name_regex: None,
+        game_name_regex_exclude: None,
+        system: None,
+        dat_name_rewrite: None,
         dat_description_regex: None,
         dat_description_regex_exclude: None,
         dat_combine: false,
         dat_ignore_parent_clone: false,
         list_unmatched_dats: false,
         print_plan: true,
+        summary_only: false,
         enable_hasheous: false,
         igdb_client_id: None,
         igdb_token: None,
@@ -45,24 +54,37 @@ fn discovers_patch_files_by_glob_and_detects_type() {
         patch_exclude: vec![],
         output: None,
         dir_mirror: false,
+        dir_mirror_root: false,
         dir_dat_mirror: false,
         dir_dat_name: false,
         dir_dat_description: false,
         dir_letter: false,
+        dir_region: false,
         dir_letter_count: None,
         dir_letter_limit: None,
         dir_letter_group: false,
+        dir_letter_ignore_articles: false,
         dir_game_subdir: igir::types::DirGameSubdirMode::Multiple,
         fix_extension: igir::types::FixExtensionMode::Auto,
+        normalize_extension_case: false,
+        shorten_paths: false,
         overwrite: false,
         overwrite_invalid: false,
+        on_collision: igir::types::CollisionPolicy::Skip,
+        quarantine: None,
+        no_space_check: false,
         move_delete_dirs: igir::types::MoveDeleteDirsMode::Auto,
+        extract_include: None,
+        extract_exclude: None,
         clean_exclude: vec![],
         clean_backup: None,
+        clean_backup_keep: None,
         clean_dry_run: false,
         zip_format: igir::types::ZipFormat::Torrentzip,
-        zip_exclude: None,
+        zip_exclude: vec![],
         zip_dat_name: false,
+        zip_comment: None,
+        to_chd: false,
         link_mode: igir::types::LinkMode::Hardlink,
         symlink_relative: false,
         header: None,
@@ -77,8 +99,12 @@ fn discovers_patch_files_by_glob_and_detects_type() {
         filter_regex: None,
         filter_regex_exclude: None,
         filter_language: None,
+        keep_languages: None,
+        keep_top_n: None,
         filter_region: None,
         filter_category_regex: None,
+        only_genre: None,
+        only_platform: None,
         no_bios: false,
         no_device: false,
         no_unlicensed: false,
@@ -90,13 +116,43 @@ fn discovers_patch_files_by_glob_and_detects_type() {
         no_prototype: false,
         no_program: false,
         verbose: 0,
+        no_color: false,
         quiet: 0,
         diag: false,
+        plan_diff: false,
+        region_map: None,
+        profile: false,
+        log_style: igir::types::LogStyle::Default,
+        exec: None,
         cache_only: false,
         cache_db: None,
+        xattr_cache: false,
         hash_threads: None,
         scan_threads: None,
+        archive_threads: None,
         show_match_reasons: false,
+        min_score: None,
+        tiebreak_mtime: igir::types::MtimeTiebreak::Path,
+        priority_input: false,
+        title_strip_regex: vec![],
+        ignore_case: true,
+        output_structure: None,
+        threads: None,
+        strict: false,
+        allow_no_matches: false,
+        dat_best_match: false,
+        dat_priority: Vec::new(),
+        dat_diff: None,
+        fixdat_format: None,
+        hash_algorithms: None,
+        hash_checkpoint_mib: None,
+        expect_crc32: None,
+        expect_md5: None,
+        expect_sha1: None,
+        expect_sha256: None,
+        max_decompressed_size: None,
+        skip_existing_checksum: false,
+        report_duplicates: false,
         online_timeout_secs: Some(5),
         online_max_retries: Some(3),
         online_throttle_ms: None,
@@ -109,3 +165,49 @@ fn discovers_patch_files_by_glob_and_detects_type() {
     assert!(types.contains(&Some("ips")));
     assert!(types.contains(&Some("bps")));
 }
+
+#[test]
+fn copy_writes_a_patched_copy_of_the_targeted_rom() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("in");
+    std::fs::create_dir_all(&input).unwrap();
+    std::fs::write(input.join("Game.gb"), [0u8; 4]).unwrap();
+    std::fs::write(input.join("Other.gb"), [1u8; 4]).unwrap();
+    let crc = format!("{:08x}", crc32fast::hash(&[0u8; 4]));
+    let patch = dir.path().join(format!("Game (Translated) {crc}.ips"));
+    let mut ips = b"PATCH".to_vec();
+    ips.extend_from_slice(&[0x00, 0x00, 0x01, 0x00, 0x02, 0xAA, 0xBB]);
+    ips.extend_from_slice(b"EOF");
+    std::fs::write(&patch, ips).unwrap();
+
+    let output = dir.path().join("out");
+    let result = std::process::Command::new(env!("CARGO_BIN_EXE_igir"))
+        .arg("copy")
+        .arg("--input")
+        .arg(&input)
+        .arg("--output")
+        .arg(&output)
+        .arg("--patch")
+        .arg(&patch)
+        .arg("--cache-db")
+        .arg(dir.path().join("cache.sqlite"))
+        .output()
+        .expect("igir binary runs");
+    assert!(
+        result.status.success(),
+        "{}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+
+    assert_eq!(std::fs::read(output.join("Game.gb")).unwrap(), [0u8; 4]);
+    assert_eq!(
+        std::fs::read(output.join("Game (Translated).gb")).unwrap(),
+        [0x00, 0xAA, 0xBB, 0x00]
+    );
+    let mut names: Vec<_> = std::fs::read_dir(&output)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["Game (Translated).gb", "Game.gb", "Other.gb"]);
+}
@@ -0,0 +1,128 @@
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn plan_diff_classifies_writes_without_touching_the_output() {
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("roms");
+    let output = dir.path().join("out");
+    std::fs::create_dir_all(&input).unwrap();
+    std::fs::create_dir_all(&output).unwrap();
+    std::fs::write(input.join("Same (USA).bin"), b"same bytes").unwrap();
+    std::fs::write(input.join("Changed (USA).bin"), b"new bytes!").unwrap();
+    std::fs::write(input.join("Fresh (USA).bin"), b"fresh").unwrap();
+    std::fs::write(output.join("Same (USA).bin"), b"same bytes").unwrap();
+    std::fs::write(output.join("Changed (USA).bin"), b"old bytes!").unwrap();
+    std::fs::write(output.join("stray.txt"), b"not planned").unwrap();
+    let before = std::fs::metadata(output.join("Same (USA).bin"))
+        .unwrap()
+        .modified()
+        .unwrap();
+
+    let run = Command::new(env!("CARGO_BIN_EXE_igir"))
+        .arg("copy")
+        .arg("zip")
+        .arg("clean")
+        .arg("--input")
+        .arg(&input)
+        .arg("--output")
+        .arg(&output)
+        .arg("--plan-diff")
+        .arg("--allow-no-matches")
+        .arg("--cache-db")
+        .arg(dir.path().join("cache.sqlite"))
+        .arg("--cache-only")
+        .output()
+        .expect("igir binary runs");
+    assert!(run.status.success());
+
+    let stderr = String::from_utf8_lossy(&run.stderr);
+    let line_for = |name: &str| {
+        stderr
+            .lines()
+            .find(|line| line.ends_with(name))
+            .unwrap_or_else(|| panic!("no plan line for {name} in {stderr}"))
+            .split_whitespace()
+            .next()
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(line_for("Same (USA).bin"), "identical");
+    assert_eq!(line_for("Changed (USA).bin"), "overwrite");
+    assert_eq!(line_for("Fresh (USA).bin"), "new");
+
+    let same = output.join("Same (USA).bin");
+    assert_eq!(
+        std::fs::metadata(&same).unwrap().modified().unwrap(),
+        before
+    );
+    assert_eq!(
+        std::fs::read(output.join("Changed (USA).bin")).unwrap(),
+        b"old bytes!"
+    );
+    assert!(!output.join("Fresh (USA).bin").exists());
+    // zip and clean are skipped as well, and no copies are counted
+    assert!(output.join("stray.txt").exists());
+    let mut names: Vec<_> = std::fs::read_dir(&output)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["Changed (USA).bin", "Same (USA).bin", "stray.txt"]);
+    assert!(stderr.contains("copied: n/a"), "{stderr}");
+}
+
+#[test]
+fn plan_diff_lists_the_collision_renamed_targets() {
+    let dir = tempdir().unwrap();
+    let first = dir.path().join("a");
+    let second = dir.path().join("b");
+    let output = dir.path().join("out");
+    std::fs::create_dir_all(&first).unwrap();
+    std::fs::create_dir_all(&second).unwrap();
+    std::fs::create_dir_all(&output).unwrap();
+    std::fs::write(first.join("Game (USA).bin"), b"first game").unwrap();
+    std::fs::write(second.join("Game (USA).bin"), b"other game").unwrap();
+    std::fs::write(output.join("Game (USA).bin"), b"first game").unwrap();
+
+    let run = Command::new(env!("CARGO_BIN_EXE_igir"))
+        .arg("copy")
+        .arg("--input")
+        .arg(&first)
+        .arg("--input")
+        .arg(&second)
+        .arg("--output")
+        .arg(&output)
+        .arg("--on-collision")
+        .arg("rename")
+        .arg("--plan-diff")
+        .arg("--allow-no-matches")
+        .arg("--cache-db")
+        .arg(dir.path().join("cache.sqlite"))
+        .arg("--cache-only")
+        .output()
+        .expect("igir binary runs");
+    assert!(run.status.success());
+
+    let stderr = String::from_utf8_lossy(&run.stderr);
+    let planned: Vec<&str> = stderr
+        .lines()
+        .filter(|line| line.ends_with(".bin"))
+        .collect();
+    assert!(
+        planned
+            .iter()
+            .any(|line| line.starts_with("identical") && line.ends_with("Game (USA).bin")),
+        "{stderr}"
+    );
+    assert!(
+        planned
+            .iter()
+            .any(|line| line.starts_with("new") && line.ends_with("Game (USA) (1).bin")),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("1 new, 1 identical, 0 overwrite"),
+        "{stderr}"
+    );
+}
@@ -0,0 +1,40 @@
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn profile_prints_a_timing_row_for_each_phase_that_ran() {
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("roms");
+    std::fs::create_dir_all(&input).unwrap();
+    std::fs::write(input.join("Game (USA).bin"), b"payload").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_igir"))
+        .arg("copy")
+        .arg("--input")
+        .arg(&input)
+        .arg("--output")
+        .arg(dir.path().join("out"))
+        .arg("--profile")
+        .arg("--allow-no-matches")
+        .arg("--cache-db")
+        .arg(dir.path().join("cache.sqlite"))
+        .arg("--cache-only")
+        .output()
+        .expect("igir binary runs");
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let profile = &stderr[stderr.find("Profile:").expect("profile printed")..];
+    for phase in [
+        "collect_inputs",
+        "load_dats",
+        "partition_matches",
+        "action_copy",
+    ] {
+        assert!(
+            profile.contains(&format!("- {phase} ")),
+            "missing {phase} in {profile}"
+        );
+    }
+    assert!(!profile.contains("action_move"));
+}
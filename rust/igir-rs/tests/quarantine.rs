@@ -0,0 +1,46 @@
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn unmatched_inputs_are_moved_to_quarantine() {
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("roms");
+    std::fs::create_dir_all(input.join("nested")).unwrap();
+    std::fs::write(input.join("Game.bin"), b"known game").unwrap();
+    std::fs::write(input.join("nested").join("Stray.bin"), b"not in the dat").unwrap();
+
+    let crc = format!("{:08X}", crc32fast::hash(b"known game"));
+    let dat = dir.path().join("games.dat");
+    std::fs::write(
+        &dat,
+        format!(
+            r#"<?xml version="1.0"?><datafile><game name="Game"><rom name="Game.bin" size="10" crc="{crc}"/></game></datafile>"#
+        ),
+    )
+    .unwrap();
+
+    let output = dir.path().join("out");
+    let quarantine = dir.path().join("quarantine");
+    let status = Command::new(env!("CARGO_BIN_EXE_igir"))
+        .arg("copy")
+        .arg("--input")
+        .arg(&input)
+        .arg("--dat")
+        .arg(&dat)
+        .arg("--output")
+        .arg(&output)
+        .arg("--quarantine")
+        .arg(&quarantine)
+        .arg("--cache-db")
+        .arg(dir.path().join("cache.sqlite"))
+        .arg("--cache-only")
+        .status()
+        .expect("igir binary runs");
+    assert!(status.success());
+
+    assert!(output.join("Game.bin").exists());
+    assert!(!output.join("Stray.bin").exists());
+    assert!(quarantine.join("nested").join("Stray.bin").exists());
+    assert!(!input.join("nested").join("Stray.bin").exists());
+    assert!(input.join("Game.bin").exists());
+}
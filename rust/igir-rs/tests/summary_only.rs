@@ -0,0 +1,30 @@
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn summary_only_prints_steps_without_config() {
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("roms");
+    std::fs::create_dir_all(&input).unwrap();
+    std::fs::write(input.join("Game.bin"), b"payload").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_igir"))
+        .arg("test")
+        .arg("--input")
+        .arg(&input)
+        .arg("--cache-db")
+        .arg(dir.path().join("cache.sqlite"))
+        .arg("--cache-only")
+        .arg("--summary-only")
+        .output()
+        .expect("igir binary runs");
+    assert!(output.status.success());
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("summary output is JSON");
+    let object = json.as_object().unwrap();
+    assert!(!object.contains_key("config"));
+    assert!(object["steps"].is_array());
+    assert_eq!(object["files_processed"], 1);
+    assert!(object["summary"].is_object());
+}
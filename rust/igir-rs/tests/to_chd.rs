@@ -0,0 +1,52 @@
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn to_chd_converts_cue_bin_sets_with_chdman() {
+    if which::which("chdman").is_err() {
+        eprintln!("chdman not available; skipping");
+        return;
+    }
+
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("roms");
+    std::fs::create_dir_all(&input).unwrap();
+    let cue = b"FILE \"Game.bin\" BINARY\n  TRACK 01 MODE1/2352\n    INDEX 01 00:00:00\n";
+    let bin = vec![0u8; 2352 * 8];
+    std::fs::write(input.join("Game.cue"), cue).unwrap();
+    std::fs::write(input.join("Game.bin"), &bin).unwrap();
+
+    let dat = dir.path().join("discs.dat");
+    std::fs::write(
+        &dat,
+        format!(
+            r#"<?xml version="1.0"?><datafile><game name="Game"><rom name="Game.cue" size="{}" crc="{:08X}"/><rom name="Game.bin" size="{}" crc="{:08X}"/></game></datafile>"#,
+            cue.len(),
+            crc32fast::hash(cue),
+            bin.len(),
+            crc32fast::hash(&bin)
+        ),
+    )
+    .unwrap();
+
+    let output = dir.path().join("out");
+    let status = Command::new(env!("CARGO_BIN_EXE_igir"))
+        .arg("copy")
+        .arg("--input")
+        .arg(&input)
+        .arg("--dat")
+        .arg(&dat)
+        .arg("--output")
+        .arg(&output)
+        .arg("--to-chd")
+        .arg("--cache-db")
+        .arg(dir.path().join("cache.sqlite"))
+        .arg("--cache-only")
+        .status()
+        .expect("igir binary runs");
+    assert!(status.success());
+
+    assert!(output.join("Game.chd").exists());
+    assert!(!output.join("Game.cue").exists());
+    assert!(!output.join("Game.bin").exists());
+}
@@ -0,0 +1,81 @@
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn verify_summary_matches_the_written_report() {
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("roms");
+    std::fs::create_dir_all(&input).unwrap();
+    std::fs::write(input.join("Game.bin"), b"known game").unwrap();
+    // Twice the size of Lost.bin, but not named like it.
+    std::fs::write(input.join("Stray.bin"), b"not in d").unwrap();
+    std::fs::write(input.join("Broken.bin"), b"bad").unwrap();
+    std::fs::write(input.join("Big.bin"), b"overdump").unwrap();
+
+    let crc = format!("{:08X}", crc32fast::hash(b"known game"));
+    let dat = dir.path().join("games.dat");
+    std::fs::write(
+        &dat,
+        format!(
+            r#"<?xml version="1.0"?><datafile><game name="Game"><rom name="Game.bin" size="10" crc="{crc}"/></game><game name="Lost"><rom name="Lost.bin" size="4" crc="DEADBEEF"/></game><game name="Broken"><rom name="Broken.bin" size="4" crc="DEADBEEF"/></game><game name="Big"><rom name="Big.bin" size="4" crc="DEADBEEF"/></game></datafile>"#
+        ),
+    )
+    .unwrap();
+
+    let output = dir.path().join("out");
+    let result = Command::new(env!("CARGO_BIN_EXE_igir"))
+        .arg("verify")
+        .arg("--input")
+        .arg(&input)
+        .arg("--dat")
+        .arg(&dat)
+        .arg("--output")
+        .arg(&output)
+        .arg("--cache-db")
+        .arg(dir.path().join("cache.sqlite"))
+        .arg("--cache-only")
+        .output()
+        .expect("igir binary runs");
+    assert!(result.status.success());
+
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    let summary = stderr
+        .lines()
+        .find(|line| line.starts_with("Verify: "))
+        .expect("verify summary on stderr");
+    assert_eq!(
+        summary,
+        "Verify: 1 have, 3 miss, 1 bad, 1 suspect, 1 unknown"
+    );
+
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(output.join("verify.json")).unwrap())
+            .unwrap();
+    let count = |key: &str| report[key].as_array().unwrap().len();
+    assert_eq!(
+        summary,
+        format!(
+            "Verify: {} have, {} miss, {} bad, {} suspect, {} unknown",
+            count("have"),
+            count("miss"),
+            count("bad"),
+            count("suspect"),
+            count("unknown")
+        )
+    );
+    assert_eq!(report["miss"][2], "Lost.bin");
+    assert!(report["bad"][0].as_str().unwrap().ends_with("Broken.bin"));
+    assert!(
+        report["suspect"][0]
+            .as_str()
+            .unwrap()
+            .ends_with("Big.bin: probable overdump of Big.bin")
+    );
+    assert!(
+        report["unknown"][0]
+            .as_str()
+            .unwrap()
+            .ends_with("Stray.bin")
+    );
+    assert!(!output.join("Game.bin").exists());
+}